@@ -1,6 +1,6 @@
 from __future__ import annotations
 from typing_extensions import Self
-from typing import Dict, List, Tuple, Optional, ByteString
+from typing import Dict, List, Tuple, Optional, ByteString, Union
 from abc import ABC
 
 class RustVec(ABC):
@@ -58,6 +58,49 @@ class SerdeAPI(ABC):
         ...
 
 
+FieldValue = Union[None, bool, int, float, str, List["FieldValue"]]
+
+
+class FieldPathAPI(ABC):
+    """Access to (nested) fields by dot-separated path, e.g.
+    `transmission.gear_ratios.1`, where numeric segments index into lists"""
+    def get_field(self, path: str) -> FieldValue:
+        ...
+
+    def set_field(self, path: str, value: FieldValue) -> None:
+        """Sets the field at `path`.  Raises an error if called on a nested
+        struct, e.g. `sim_drive.veh.set_field(...)`; use
+        `sim_drive.veh = veh` after setting fields on `veh` instead.
+        `RustVehicle.set_derived` must be called after changing vehicle
+        inputs."""
+        ...
+
+    @staticmethod
+    def list_fields() -> List[Tuple[str, str, str]]:
+        """Returns `(path, type, doc)` for every (nested) field"""
+        ...
+
+
+
+class FastSimError(RuntimeError):
+    """Base class for errors raised by fastsim-core simulations"""
+class TraceMissError(FastSimError):
+    """Achieved speed trace deviates from the prescribed cycle beyond tolerance.
+    Only raised when `RustSimDriveParams.error_on_trace_miss` is True."""
+class EnergyAuditError(FastSimError):
+    """Energy audit error exceeds tolerance.
+    Only raised when `RustSimDriveParams.error_on_energy_audit` is True."""
+class InvalidVehicleError(FastSimError):
+    """Vehicle parameters are invalid or inconsistent"""
+class InvalidCycleError(FastSimError):
+    """Drive cycle is invalid"""
+class SimDriveStepError(FastSimError):
+    """Unspecified failure while solving a time step"""
+class SimFaultError(FastSimError):
+    """NaN, infinite, or impossible negative value found by simulation diagnostics.
+    Only raised when `RustSimDriveParams.diagnostics` is True."""
+class FastSimIOError(IOError):
+    """File I/O or (de)serialization failure"""
 
 class Pyo3ArrayI32(SerdeAPI, RustVec):
     """Helper struct to allow Rust to return a Python class that will indicate to the user that it's
@@ -100,7 +143,482 @@ class SimDriveVec(SerdeAPI, RustVec):
         ...
 
 
-class RustPhysicalProperties(SerdeAPI):
+class SweepParam(SerdeAPI):
+    """Range of values of one `RustVehicle` field in a `ParameterSweep`"""
+    path: str
+    min: float
+    max: float
+    levels: int
+
+    def __init__(self, path: str, min: float, max: float, levels: int = 2) -> None:
+        """
+        Arguments:
+        ----------
+        path: dot-separated path to the field, e.g. `drag_coef`, or
+            `fc_pwr_out_perc.3` for an element of an array field
+        min, max: bounds of range
+        levels: number of evenly spaced values from `min` to `max`, inclusive,
+            used by `full_factorial` sweeps
+        """
+        ...
+
+
+class SweepResults(SerdeAPI):
+    """Results of a `ParameterSweep`: one row per sample of input values
+    followed by output values"""
+    inputs: List[str]
+    outputs: List[str]
+    rows: List[List[float]]
+    errors: List[Optional[str]]
+
+    def column(self, name: str) -> List[float]:
+        """Returns the values of input or output column `name`"""
+        ...
+
+    def to_csv(self) -> str:
+        """Returns the results as CSV with a header row"""
+        ...
+
+    def to_dict(self) -> Dict[str, List]:
+        """Returns a dict of column name to values, plus `error`, suitable for
+        e.g. `pandas.DataFrame`"""
+        ...
+
+    def __len__(self) -> int:
+        ...
+
+
+class ParameterSweep(SerdeAPI):
+    """Design of experiments over `RustVehicle` parameters"""
+    params: List[SweepParam]
+    design: str
+    n_samples: int
+    seed: int
+    outputs: List[str]
+
+    def __init__(
+        self,
+        params: List[SweepParam],
+        outputs: List[str],
+        design: str = "full_factorial",
+        n_samples: int = 0,
+        seed: int = 0,
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        params: parameters to vary
+        outputs: dot-separated paths to scalar `RustSimDrive` fields to report,
+            e.g. `mpgge`, `electric_kwh_per_mi`, or `trace_miss`, or the
+            computed outputs `range_mi` (on-cycle range) and `val0_to60_mph`
+            (simulated 0-60 mph time, s)
+        design: one of `full_factorial`, `latin_hypercube`, or `sobol`
+        n_samples: number of samples for `latin_hypercube` and `sobol`
+        seed: random seed for `latin_hypercube`
+        """
+        ...
+
+    def samples(self) -> List[List[float]]:
+        """Returns the sampled parameter values, one row per vehicle"""
+        ...
+
+    def run(
+        self, veh: RustVehicle, cyc: RustCycle, parallelize: bool = True
+    ) -> SweepResults:
+        """Simulates `veh` over `cyc` with each sampled set of parameter
+        values.  Vehicles that are invalid or fail to simulate get NaN outputs
+        and an entry in `SweepResults.errors`."""
+        ...
+
+
+class ParamSensitivity(SerdeAPI):
+    """Sensitivity of one output to one parameter"""
+    param: str
+    nominal: float
+    gradient: float
+    elasticity: float
+    first_order: float
+    total_order: float
+
+
+class OutputSensitivity(SerdeAPI):
+    """Sensitivity of one output to every parameter, most influential first"""
+    output: str
+    nominal: float
+    variance: float
+    params: List[ParamSensitivity]
+
+    def ranking(self) -> List[str]:
+        """Parameter paths, most influential first"""
+        ...
+
+
+class SensitivityReport(SerdeAPI):
+    """Outcome of `SensitivityAnalysis.run`"""
+    outputs: List[OutputSensitivity]
+    n_simulations: int
+    n_failed: int
+
+
+class SensitivityAnalysis(SerdeAPI):
+    """Sensitivity of scalar simulation outputs to `RustVehicle` parameters:
+    local elasticities by central differences about the vehicle's own values,
+    and Sobol indices over the parameter ranges"""
+    params: List[SweepParam]
+    outputs: List[str]
+    rel_step: float
+    n_samples: int
+
+    def __init__(
+        self,
+        params: List[SweepParam],
+        outputs: List[str],
+        rel_step: float = 0.01,
+        n_samples: int = 64,
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        params: parameters, with the ranges sampled for Sobol indices
+        outputs: outputs as in `ParameterSweep`, e.g. `mpgge`, `range_mi`,
+            or `val0_to60_mph`
+        rel_step: central difference step as a fraction of each parameter's
+            value, or of its range if the value is zero
+        n_samples: number of base samples for Sobol indices, or 0 to skip
+            them; costs `n_samples * (len(params) + 2)` simulations
+        """
+        ...
+
+    def run(
+        self, veh: RustVehicle, cyc: RustCycle, parallelize: bool = True
+    ) -> SensitivityReport:
+        """Computes the sensitivity of each output to each parameter for `veh`
+        driving `cyc`"""
+        ...
+
+
+class PlatoonMember(SerdeAPI):
+    """Energy use, gaps, and string stability metrics of one `Platoon`
+    vehicle"""
+    position: int
+    scenario_name: str
+    dist_mi: float
+    fuel_kj: float
+    ess_dischg_kj: float
+    mpgge: float
+    electric_kwh_per_mi: float
+    gap_m: List[float]
+    min_gap_m: float
+    mean_gap_m: float
+    mean_time_headway_s: float
+    collision: bool
+    rms_accel_m_per_s2: float
+    peak_accel_m_per_s2: float
+    rms_accel_ratio: float
+    peak_accel_ratio: float
+
+
+class PlatoonResults(SerdeAPI):
+    """Outcome of `Platoon.run`"""
+    members: List[PlatoonMember]
+    string_stable: bool
+    max_rms_accel_ratio: float
+    collision: bool
+    sds: List[RustSimDrive]
+
+
+class Platoon(SerdeAPI):
+    """Vehicles driving in one lane, the first following `cyc` and each of
+    the rest following the achieved speed of the vehicle ahead of it with IDM
+    car following"""
+    cyc: RustCycle
+    vehs: List[RustVehicle]
+    sim_params: List[RustSimDriveParams]
+
+    def __init__(
+        self,
+        cyc: RustCycle,
+        vehs: List[RustVehicle],
+        sim_params: Optional[List[RustSimDriveParams]] = None,
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        cyc: reference cycle driven by the lead vehicle
+        vehs: vehicles, from the leader back
+        sim_params: simulation parameters for each vehicle, whose `idm_*`
+            parameters set follower driver or ACC behavior
+        """
+        ...
+
+    def run(self) -> PlatoonResults:
+        """Simulates the leader over `cyc`, then each follower behind the
+        vehicle ahead of it"""
+        ...
+
+
+class EcoCruiseResults(SerdeAPI):
+    """Comparison of `PredictiveEcoCruise` against the original cycle"""
+    baseline_energy_kj: float
+    eco_energy_kj: float
+    energy_savings_frac: float
+    baseline_time_s: float
+    eco_time_s: float
+    time_penalty_s: float
+    time_penalty_frac: float
+    baseline_dist_m: float
+    eco_dist_m: float
+    sd_baseline: RustSimDrive
+    sd_eco: RustSimDrive
+
+
+class PredictiveEcoCruise(SerdeAPI):
+    """Planner that replans the speed trace between each pair of stops by
+    dynamic programming over distance, using upcoming grade, stop locations,
+    and speed limits"""
+    max_time_penalty_frac: float
+    dist_step_m: float
+    speed_step_mps: float
+    accel_max_m_per_s2: float
+    decel_max_m_per_s2: float
+    speed_limits_by_distance_m: Optional[List[Tuple[float, float]]]
+    dt_s: float
+
+    def __init__(
+        self,
+        max_time_penalty_frac: float = 0.05,
+        dist_step_m: float = 25.0,
+        speed_step_mps: float = 0.5,
+        accel_max_m_per_s2: float = 1.0,
+        decel_max_m_per_s2: float = 1.5,
+        speed_limits_by_distance_m: Optional[List[Tuple[float, float]]] = None,
+        dt_s: float = 1.0,
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        max_time_penalty_frac: allowed increase in travel time between each
+            pair of stops, as a fraction of the original travel time
+        dist_step_m: distance step of the planning grid
+        speed_step_mps: speed step of the planning grid
+        accel_max_m_per_s2: maximum acceleration
+        decel_max_m_per_s2: maximum deceleration, positive
+        speed_limits_by_distance_m: (distance from start, speed limit) pairs,
+            each applying until the next; defaults to the highest original
+            speed between each pair of stops
+        dt_s: time step of the planned cycle
+        """
+        ...
+
+    def plan(self, veh: RustVehicle, cyc: RustCycle) -> RustCycle:
+        """Returns `cyc` with the speed trace between each pair of stops
+        replanned to minimize the energy use of `veh`"""
+        ...
+
+    def run(self, veh: RustVehicle, cyc: RustCycle) -> EcoCruiseResults:
+        """Plans an eco-cruise trace for `veh` over `cyc`, then simulates
+        `veh` over both traces"""
+        ...
+
+
+class TrafficSignal(SerdeAPI):
+    """Signalized intersection along a cycle, green for `green_s` at the
+    start of each signal cycle and red for the rest, with signal cycles
+    starting at `offset_s` plus multiples of `cycle_s`"""
+    dist_m: float
+    cycle_s: float
+    green_s: float
+    offset_s: float
+
+    def __init__(
+        self, dist_m: float, cycle_s: float, green_s: float, offset_s: float = 0.0
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        dist_m: distance of the stop line from the start of the cycle
+        cycle_s: signal cycle length
+        green_s: green phase duration
+        offset_s: time at which a green phase starts
+        """
+        ...
+
+    def is_green(self, time_s: float) -> bool:
+        """Whether the signal is green at `time_s`"""
+        ...
+
+    def next_green_s(self, time_s: float) -> float:
+        """Earliest time at or after `time_s` at which the signal is green"""
+        ...
+
+
+class SignalPassage(SerdeAPI):
+    """How the planned cycle passes one `TrafficSignal`"""
+    dist_m: float
+    crossing_time_s: float
+    stopped_in_cycle: bool
+    stopped: bool
+    wait_s: float
+    on_green: bool
+
+
+class SpatPlan(SerdeAPI):
+    """Outcome of `SpatPlanner.plan`"""
+    cyc: RustCycle
+    passages: List[SignalPassage]
+    n_stops_in_cycle: int
+    n_stops: int
+
+
+class SpatResults(SerdeAPI):
+    """Outcome of `SpatPlanner.run`"""
+    plan: SpatPlan
+    sd: RustSimDrive
+
+
+class SpatPlanner(SerdeAPI):
+    """Approach speed planner for a cycle with signalized intersections,
+    which replans the speed trace around each signal with constant-jerk
+    trajectories to pass on green, without stopping when possible"""
+    signals: List[TrafficSignal]
+    anticipate: bool
+    approach_dist_m: float
+    horizon_s: float
+    accel_max_m_per_s2: float
+    decel_max_m_per_s2: float
+    stop_window_m: float
+
+    def __init__(
+        self,
+        signals: List[TrafficSignal],
+        anticipate: bool = True,
+        approach_dist_m: float = 300.0,
+        horizon_s: float = 60.0,
+        accel_max_m_per_s2: float = 1.5,
+        decel_max_m_per_s2: float = 2.0,
+        stop_window_m: float = 10.0,
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        signals: signalized intersections
+        anticipate: if True, the driver knows the signal timing and avoids
+            stopping when possible; if False, the driver follows the cycle and
+            only stops, or waits longer, when the signal is red
+        approach_dist_m: distance before a signal over which the approach may
+            be replanned
+        horizon_s: time after passing a signal within which the replanned
+            trace must rejoin the cycle
+        accel_max_m_per_s2: maximum acceleration
+        decel_max_m_per_s2: maximum deceleration, positive
+        stop_window_m: distance from a signal within which a stop in the
+            cycle is a stop at the signal
+        """
+        ...
+
+    def plan(self, cyc: RustCycle) -> SpatPlan:
+        """Returns `cyc` replanned to pass each signal on green"""
+        ...
+
+    def run(self, veh: RustVehicle, cyc: RustCycle) -> SpatResults:
+        """Plans `cyc` around the signals, then simulates `veh` over the
+        planned cycle"""
+        ...
+
+
+class CalibrationParam(SerdeAPI):
+    """Bounded `RustVehicle` parameter fitted by a `Calibration`"""
+    name: str
+    min: float
+    max: float
+    initial: Optional[float]
+
+    def __init__(
+        self, name: str, min: float, max: float, initial: Optional[float] = None
+    ) -> None:
+        """
+        Arguments:
+        ----------
+        name: dot-separated path to the field, e.g. `drag_coef`,
+            `wheel_rr_coef`, `aux_kw`, or `ess_round_trip_eff`, or one of
+            `fc_peak_eff`, `mc_peak_eff`, `fc_eff_range`, or `mc_eff_range`
+        min, max: bounds
+        initial: starting value, defaults to the vehicle's value clamped to
+            the bounds
+        """
+        ...
+
+
+class CalibrationObjective(SerdeAPI):
+    """Measured time series that a `Calibration` fits the model to"""
+    signal: str
+    measured: List[float]
+    weight: float
+
+    def __init__(self, signal: str, measured: List[float], weight: float = 1.0) -> None:
+        """
+        Arguments:
+        ----------
+        signal: name of the `RustSimDrive` time series, e.g. `mps_ach`,
+            `fs_kw_out_ach` for fuel power, or `soc`
+        measured: measured values at each time step of the cycle, with NaN
+            for missing samples
+        weight: weight of this term in the total cost
+        """
+        ...
+
+
+class ObjectiveFit(SerdeAPI):
+    """Goodness of fit of one `CalibrationObjective`"""
+    signal: str
+    rmse: float
+    nrmse: float
+    r_squared: float
+    bias: float
+    residuals: List[float]
+
+
+class CalibrationResult(SerdeAPI):
+    """Outcome of `Calibration.run`"""
+    names: List[str]
+    values: List[float]
+    initial_values: List[float]
+    cost: float
+    initial_cost: float
+    iters: int
+    fits: List[ObjectiveFit]
+    veh: RustVehicle
+
+
+class Calibration(SerdeAPI):
+    """Fits bounded `RustVehicle` parameters to measured time series by
+    minimizing the weighted sum of squared normalized RMSEs of the objectives"""
+    params: List[CalibrationParam]
+    objectives: List[CalibrationObjective]
+    max_iters: int
+
+    def __init__(
+        self,
+        params: List[CalibrationParam],
+        objectives: List[CalibrationObjective],
+        max_iters: int = 100,
+    ) -> None:
+        ...
+
+    def evaluate(
+        self, veh: RustVehicle, cyc: RustCycle, values: List[float]
+    ) -> Tuple[float, List[ObjectiveFit]]:
+        """Returns the total cost and the fit of each objective for `values`
+        of `params`"""
+        ...
+
+    def run(self, veh: RustVehicle, cyc: RustCycle) -> CalibrationResult:
+        """Fits `params` of `veh` so that simulating `cyc` matches
+        `objectives`"""
+        ...
+
+
+class RustPhysicalProperties(SerdeAPI, FieldPathAPI):
     """Struct containing time trace data"""
     a_grav_mps2: float
     air_density_kg_per_m3: float
@@ -113,7 +631,7 @@ class RustPhysicalProperties(SerdeAPI):
         """Reset the orphaned flag to false."""
         ...
 
-class RustCycle(SerdeAPI):
+class RustCycle(SerdeAPI, FieldPathAPI):
     """Struct for containing:
     * time_s, cycle time, $s$  
     * mps, vehicle speed, $\\frac{m}{s}$  
@@ -176,7 +694,202 @@ class RustCycle(SerdeAPI):
         """Reset the orphaned flag to false."""
         ...
 
-class RustVehicle(SerdeAPI):
+class RustRoute(SerdeAPI):
+    """Distance-indexed route containing speed limits, stop locations, and
+    road grade and/or elevation.  Use `to_cycle` to generate a drivable cycle."""
+    dist_m: Pyo3ArrayF64
+    grade: Pyo3ArrayF64
+    elevation_m: Pyo3ArrayF64
+    speed_limit_dist_m: Pyo3ArrayF64
+    speed_limit_mps: Pyo3ArrayF64
+    stop_dist_m: Pyo3ArrayF64
+    stop_dwell_s: Pyo3ArrayF64
+    name: str
+    length_m: float
+
+    def __init__(
+        self,
+        dist_m: List[float],
+        speed_limit_dist_m: List[float],
+        speed_limit_mps: List[float],
+        grade: Optional[List[float]] = None,
+        elevation_m: Optional[List[float]] = None,
+        stop_dist_m: Optional[List[float]] = None,
+        stop_dwell_s: Optional[List[float]] = None,
+        name: Optional[str] = None,
+    ) -> None: ...
+    def to_cycle(
+        self,
+        dt_s: float = 1.0,
+        accel_m_per_s2: float = 1.0,
+        brake_accel_m_per_s2: float = -2.0,
+    ) -> RustCycle: ...
+
+class WeatherProfile(SerdeAPI):
+    """Ambient weather indexed by either time or distance, providing
+    temperature, wind, solar irradiance, humidity, and pressure.  Assign to
+    `RustSimDrive.weather` or pass as `weather` to `SimDriveHot`."""
+    time_s: Pyo3ArrayF64
+    dist_m: Pyo3ArrayF64
+    amb_te_deg_c: Pyo3ArrayF64
+    wind_speed_mps: Pyo3ArrayF64
+    wind_from_deg: Pyo3ArrayF64
+    heading_deg: Pyo3ArrayF64
+    solar_w_per_m2: Pyo3ArrayF64
+    rel_humidity: Pyo3ArrayF64
+    pressure_kpa: Pyo3ArrayF64
+    elevation_m: Pyo3ArrayF64
+
+    def __init__(
+        self,
+        amb_te_deg_c: List[float],
+        time_s: Optional[List[float]] = None,
+        dist_m: Optional[List[float]] = None,
+        wind_speed_mps: Optional[List[float]] = None,
+        wind_from_deg: Optional[List[float]] = None,
+        heading_deg: Optional[List[float]] = None,
+        solar_w_per_m2: Optional[List[float]] = None,
+        rel_humidity: Optional[List[float]] = None,
+        pressure_kpa: Optional[List[float]] = None,
+        elevation_m: Optional[List[float]] = None,
+    ) -> None: ...
+    @staticmethod
+    def from_csv(filepath: str) -> WeatherProfile:
+        """Load from a CSV file whose column headers are field names"""
+        ...
+    @staticmethod
+    def from_tmy(
+        filepath: str, start_hour: int = 0, n_hours: Optional[int] = None
+    ) -> WeatherProfile:
+        """Load hourly weather from a TMY3 or NSRDB-style CSV file"""
+        ...
+    def amb_te_deg_c_at(self, time_s: float = 0.0, dist_m: float = 0.0) -> float: ...
+    def headwind_mps_at(self, time_s: float = 0.0, dist_m: float = 0.0) -> float: ...
+    def solar_w_per_m2_at(self, time_s: float = 0.0, dist_m: float = 0.0) -> float: ...
+    def air_density_kg_per_m3_at(self, time_s: float = 0.0, dist_m: float = 0.0) -> float: ...
+
+class EssCircuitModel(SerdeAPI, FieldPathAPI):
+    """Equivalent-circuit (Rint or Thevenin) traction battery model with
+    state-of-charge-dependent open circuit voltage and resistance"""
+    soc_bins: List[float]
+    ocv_cell_v: List[float]
+    r0_cell_ohm: List[float]
+    r1_cell_ohm: List[float]
+    c1_cell_f: float
+    n_series: int
+    n_parallel: int
+    v_cell_min: float
+    v_cell_max: float
+    te_ref_deg_c: float
+    res_arrhenius_te_k: float
+    is_thevenin: bool
+
+    @classmethod
+    def default(cls) -> Self: ...
+
+class Transmission(SerdeAPI, FieldPathAPI):
+    """Multi-gear transmission with final drive and speed- and load-based
+    shift schedule.  Gears are numbered from 1 (lowest)."""
+    gear_ratios: List[float]
+    final_drive_ratio: float
+    gear_effs: List[float]
+    min_in_rpm: float
+    max_in_rpm: float
+    upshift_rpm_low_load: float
+    upshift_rpm_full_load: float
+    downshift_rpm_low_load: float
+    downshift_rpm_full_load: float
+    num_gears: int
+
+    @classmethod
+    def default(cls) -> Self: ...
+    def overall_ratio(self, gear: int) -> float: ...
+    def select_gear(
+        self, prev_gear: int, out_rad_per_sec: float, load_frac: float
+    ) -> int: ...
+
+class SpeedTorqueEffMap(SerdeAPI, FieldPathAPI):
+    """Efficiency map over shaft speed and torque"""
+    speed_rpm: List[float]
+    torque_nm: List[float]
+    eff: List[List[float]]
+
+    @classmethod
+    def from_csv_file(
+        cls, filepath: str, fuel_lhv_kj_per_kg: Optional[float] = None
+    ) -> Self: ...
+    @classmethod
+    def from_csv_str(
+        cls, csv_str: str, fuel_lhv_kj_per_kg: Optional[float] = None
+    ) -> Self: ...
+    def eff_at(self, speed_rad_per_sec: float, torque_nm: float) -> float: ...
+    def eff_at_kw(self, speed_rad_per_sec: float, kw: float) -> float: ...
+
+class EmissionsModel(SerdeAPI, FieldPathAPI):
+    """Tailpipe emissions model for CO2 and criteria pollutants (NOx, CO,
+    HC, PM) with aftertreatment light-off"""
+    fc_pwr_out_frac: List[float]
+    eo_nox_g_per_kg_fuel: List[float]
+    eo_co_g_per_kg_fuel: List[float]
+    eo_hc_g_per_kg_fuel: List[float]
+    eo_pm_g_per_kg_fuel: List[float]
+    cat_nox_eff: float
+    cat_co_eff: float
+    cat_hc_eff: float
+    cat_pm_eff: float
+    cat_lightoff_width_deg_c: float
+
+    @classmethod
+    def default(cls) -> Self: ...
+    def cat_conv_frac(self, cat_te_deg_c: float, cat_te_lightoff_deg_c: float) -> float: ...
+
+class Fuel(SerdeAPI, FieldPathAPI):
+    """Fuel properties, with library values for each of `list_fuel_types()`"""
+    fuel_type: str
+    lhv_kj_per_kg: float
+    density_kg_per_l: float
+    carbon_frac: float
+    afr_stoich: float
+    native_unit: str
+    kwh_per_native_unit: float
+
+    def __init__(self, fuel_type: str = "Gasoline") -> None: ...
+    @staticmethod
+    def list_fuel_types() -> List[str]: ...
+    def gge_factor(self, kwh_per_gge: float) -> float: ...
+
+class SecondaryFuel(SerdeAPI, FieldPathAPI):
+    """Second fuel burned by a dual-fuel or blended-fuel converter, with
+    energy share constant or interpolated over fuel converter output power
+    fraction"""
+    fuel: Fuel
+    fc_pwr_out_frac: List[float]
+    energy_share: List[float]
+
+    def __init__(
+        self, fuel: Fuel, fc_pwr_out_frac: List[float], energy_share: List[float]
+    ) -> None: ...
+    @staticmethod
+    def constant(fuel: Fuel, energy_share: float) -> SecondaryFuel: ...
+    def share(self, fc_pwr_out_frac: float) -> float: ...
+
+class ValidationIssue(SerdeAPI):
+    """Single problem found by `RustVehicle.validate`"""
+    path: str
+    severity: str
+    message: str
+
+class ValidationReport(SerdeAPI):
+    """All problems found by `RustVehicle.validate`"""
+    issues: List[ValidationIssue]
+
+    def is_valid(self) -> bool:
+        """Returns True if there are no issues with severity "error""""
+        ...
+    def errors(self) -> List[ValidationIssue]: ...
+    def warnings(self) -> List[ValidationIssue]: ...
+
+class RustVehicle(SerdeAPI, FieldPathAPI):
     """Struct containing vehicle attributes
 
     # Python Examples
@@ -294,6 +1007,21 @@ class RustVehicle(SerdeAPI):
     mc_peak_eff: float
     mc_peak_eff_override: Optional[float]
     'Motor efficiency peak override, scales entire curve'
+    ess_circuit: Optional[EssCircuitModel]
+    'Optional equivalent-circuit traction battery model'
+    transmission: Optional[Transmission]
+    'Optional multi-gear transmission'
+    fc_eff_map_2d: Optional[SpeedTorqueEffMap]
+    'Optional fuel converter efficiency map over speed and torque'
+    mc_eff_map_2d: Optional[SpeedTorqueEffMap]
+    'Optional electric motor efficiency map over speed and torque'
+    emissions: Optional[EmissionsModel]
+    'Optional tailpipe emissions model'
+    fuel: Optional[Fuel]
+    'Optional fuel properties, gasoline per `props` if not provided'
+    fuel_props: Fuel
+    secondary_fuel: Optional[SecondaryFuel]
+    'Optional second fuel burned by the fuel converter alongside `fuel`'
     mc_perc_out_array: Pyo3VecF64
     mc_pwr_out_perc: Pyo3ArrayF64
     'Electric motor output power percentage map, x-values of [mc_eff_map](RustVehicle::mc_eff_map)'
@@ -372,6 +1100,10 @@ class RustVehicle(SerdeAPI):
     def set_derived(self) -> None:
         ...
 
+    def validate(self) -> ValidationReport:
+        """Checks vehicle inputs and returns a `ValidationReport` of every problem found"""
+        ...
+
     def set_veh_mass(self) -> None:
         ...
 
@@ -387,7 +1119,59 @@ class RustVehicle(SerdeAPI):
         """Reset the orphaned flag to false."""
         ...
 
-class RustSimDrive(SerdeAPI):
+class SimFault(SerdeAPI):
+    """First invalid value found by simulation diagnostics"""
+    i: int
+    component: str
+    variable: str
+    value: float
+    kind: str
+
+class SimDiagnostics(SerdeAPI):
+    """Report produced by `RustSimDrive` when `RustSimDriveParams.diagnostics` is True"""
+    steps_checked: int
+    fault: Optional[SimFault]
+    newton_iters_total: int
+    newton_iters_max: int
+    newton_iters_hotspots: List[Tuple[int, int]]
+    trace_miss_iters_total: int
+    trace_miss_iters_max: int
+    trace_miss_iters_hotspots: List[Tuple[int, int]]
+
+class EnergyFlow(SerdeAPI, FieldPathAPI):
+    """Energy [kJ] flowing from node `source` to node `sink` of an `EnergyBreakdown`"""
+    source: str
+    sink: str
+    kj: float
+
+class EnergyBreakdown(SerdeAPI, FieldPathAPI):
+    """Source-to-sink energy breakdown [kJ] over time steps `i_start..=i_end` of a
+    `RustSimDrive`, e.g. for Sankey diagrams"""
+    i_start: int
+    i_end: int
+    dist_m: float
+    fuel_kj: float
+    roadway_chg_kj: float
+    ess_dischg_kj: float
+    ke_kj: float
+    fc_out_kj: float
+    ess_out_kj: float
+    ess_in_kj: float
+    wheel_out_kj: float
+    regen_kj: float
+    fc_loss_kj: float
+    mc_loss_kj: float
+    ess_loss_kj: float
+    trans_loss_kj: float
+    aux_kj: float
+    drag_kj: float
+    rr_kj: float
+    ascent_kj: float
+    brake_kj: float
+    flows: List[EnergyFlow]
+    audit_error: float
+
+class RustSimDrive(SerdeAPI, FieldPathAPI):
     accel_buff_soc: Pyo3ArrayF64
     accel_kw: Pyo3ArrayF64
     add_kwh: Pyo3ArrayF64
@@ -398,6 +1182,9 @@ class RustSimDrive(SerdeAPI):
     battery_kwh_per_mi: float
     brake_kj: float
     can_pwr_all_elec: Pyo3ArrayBool
+    cat_conv_frac: Pyo3ArrayF64
+    co2_g: float
+    co2_g_per_s: Pyo3ArrayF64
     coast_delay_index: Pyo3ArrayI32
     cur_ess_max_kw_out: Pyo3ArrayF64
     cur_max_avail_elec_kw: Pyo3ArrayF64
@@ -422,6 +1209,7 @@ class RustSimDrive(SerdeAPI):
     cyc_whl_kw_req: Pyo3ArrayF64
     cyc_whl_rad_per_sec: Pyo3ArrayF64
     desired_ess_kw_out_for_ae: Pyo3ArrayF64
+    diagnostics: Optional[SimDiagnostics]
     dist_m: Pyo3ArrayF64
     dist_mi: Pyo3ArrayF64
     dod_cycs: Pyo3ArrayF64
@@ -430,6 +1218,15 @@ class RustSimDrive(SerdeAPI):
     elec_kw_req_4ae: Pyo3ArrayF64
     electric_kwh_per_mi: float
     energy_audit_error: float
+    energy_breakdown: Optional[EnergyBreakdown]
+    eo_co_g: float
+    eo_co_g_per_s: Pyo3ArrayF64
+    eo_hc_g: float
+    eo_hc_g_per_s: Pyo3ArrayF64
+    eo_nox_g: float
+    eo_nox_g_per_s: Pyo3ArrayF64
+    eo_pm_g: float
+    eo_pm_g_per_s: Pyo3ArrayF64
     er_ae_kw_out: Pyo3ArrayF64
     er_kw_if_fc_req: Pyo3ArrayF64
     ess2fuel_kwh: float
@@ -447,6 +1244,16 @@ class RustSimDrive(SerdeAPI):
     cur_max_mech_mc_kw_in: Pyo3ArrayF64
     ess_lim_mc_regen_perc_kw: Pyo3ArrayF64
     ess_loss_kw: Pyo3ArrayF64
+    ess_voltage_v: Pyo3ArrayF64
+    'Battery terminal voltage [V], only calculated if `veh.ess_circuit` is provided'
+    ess_current_a: Pyo3ArrayF64
+    'Battery current [A], positive when discharging'
+    ess_c_rate: Pyo3ArrayF64
+    'Battery C-rate, positive when discharging'
+    ess_v_rc_v: Pyo3ArrayF64
+    'Battery RC branch voltage [V]'
+    ess_te_deg_c: Pyo3ArrayF64
+    'Battery temperature [°C] used by `veh.ess_circuit`'
     ess_perc_dead: Pyo3ArrayF64
     ess_regen_buff_dischg_kw: Pyo3ArrayF64
     fc_forced_on: Pyo3ArrayBool
@@ -461,7 +1268,16 @@ class RustSimDrive(SerdeAPI):
     fs_cumu_mj_out_ach: Pyo3ArrayF64
     fs_kw_out_ach: Pyo3ArrayF64
     fs_kwh_out_ach: Pyo3ArrayF64
+    fuel1_kj: float
+    fuel1_kw_out_ach: Pyo3ArrayF64
+    fuel2_kg: float
+    fuel2_kj: float
+    fuel2_kw_out_ach: Pyo3ArrayF64
+    fuel2_native: float
+    fuel2_share: Pyo3ArrayF64
+    fuel_kg: float
     fuel_kj: float
+    fuel_native: float
     hev_sim_count: int
     high_acc_fc_on_tag: Pyo3ArrayBool
     i: int
@@ -480,6 +1296,8 @@ class RustSimDrive(SerdeAPI):
     mc_mech_kw_4forced_fc: Pyo3ArrayF64
     mc_mech_kw_out_ach: Pyo3ArrayF64
     mc_transi_lim_kw: Pyo3ArrayF64
+    mi_per_fuel2_native: float
+    mi_per_fuel_native: float
     min_ess_kw_2help_fc: Pyo3ArrayF64
     min_mc_kw_2help_fc: Pyo3ArrayF64
     mpgge: float
@@ -499,20 +1317,38 @@ class RustSimDrive(SerdeAPI):
     sim_params: RustSimDriveParams
     soc: Pyo3ArrayF64
     spare_trac_kw: Pyo3ArrayF64
+    tp_co_g: float
+    tp_co_g_per_s: Pyo3ArrayF64
+    tp_hc_g: float
+    tp_hc_g_per_s: Pyo3ArrayF64
+    tp_nox_g: float
+    tp_nox_g_per_s: Pyo3ArrayF64
+    tp_pm_g: float
+    tp_pm_g_per_s: Pyo3ArrayF64
     trace_miss: bool
     trace_miss_dist_frac: float
     trace_miss_iters: Pyo3ArrayU32
     trace_miss_speed_mps: float
     trace_miss_time_frac: float
     trans_kj: float
+    trans_eff_ach: Pyo3ArrayF64
+    trans_gear: Pyo3ArrayU32
+    trans_in_rad_per_sec: Pyo3ArrayF64
     trans_kw_in_ach: Pyo3ArrayF64
     trans_kw_out_ach: Pyo3ArrayF64
     use_ess_for_mpgge: bool
     veh: RustVehicle
+    weather: Optional[WeatherProfile]
 
     def __init__(self, cyc: RustCycle, veh: RustVehicle) -> Self:
         ...
 
+    def energy_breakdown_by_microtrip(
+        self, stop_speed_m_per_s: Optional[float] = None
+    ) -> List[EnergyBreakdown]:
+        """Returns the energy breakdown for each microtrip of `cyc0`"""
+        ...
+
     def gap_to_lead_vehicle_m(self) -> List[float]:
         """Provides the gap-with lead vehicle from start to finish"""
         ...
@@ -556,6 +1392,20 @@ class RustSimDrive(SerdeAPI):
         i: index of time step"""
         ...
 
+    def set_fuel_split(self, i: int) -> None:
+        """Splits fuel power between primary and secondary fuels.
+        Arguments
+        ------------
+        i: index of time step"""
+        ...
+
+    def set_emissions(self, i: int) -> None:
+        """Sets emission rates for the current time step.
+        Arguments
+        ------------
+        i: index of time step"""
+        ...
+
     def set_hybrid_cont_calcs(self, i: int) -> None:
         """Hybrid control calculations.
         Arguments
@@ -582,6 +1432,10 @@ class RustSimDrive(SerdeAPI):
         This includes mpgge, various energy metrics, and others"""
         ...
 
+    def set_battery_wear(self) -> None:
+        """Sets battery wear variables: `add_kwh`, `dod_cycs`, and `ess_perc_dead`"""
+        ...
+
     def set_power_calcs(self, i: int) -> None:
         """Calculate power requirements to meet cycle and determine if
         cycle can be met.
@@ -628,10 +1482,69 @@ class RustSimDrive(SerdeAPI):
         """Perform all the calculations to solve 1 time step."""
         ...
 
+    def timeseries(self, columns: Optional[List[str]] = None) -> Dict[str, List[float]]:
+        """Returns dict of `time_s` and `columns`, or all time series if None,
+        e.g. for `polars.DataFrame(sd.timeseries())`.  Booleans are 0.0/1.0."""
+        ...
+
+    def timeseries_units(self) -> Dict[str, str]:
+        """Returns dict of time series column name to unit, for columns with a known unit"""
+        ...
+
+    def to_timeseries_file(self, filepath: str, columns: Optional[List[str]] = None) -> None:
+        """Writes `time_s` and `columns`, or all time series if None, to `filepath` with
+        format determined by its extension: csv, parquet, or arrow/ipc/feather.  Units are
+        stored as `unit` field metadata in parquet and arrow files."""
+        ...
+
     def copy(self) -> Self:
         ...
 
-class RustSimDriveParams(SerdeAPI):
+class SimDriveStreamState(SerdeAPI):
+    """Outputs of the most recent time step and running totals of
+    `SimDriveStream`"""
+    i: int
+    time_s: float
+    mps: float
+    mps_ach: float
+    trace_met: bool
+    soc: float
+    fs_kw_out_ach: float
+    fc_kw_out_ach: float
+    mc_mech_kw_out_ach: float
+    ess_kw_out_ach: float
+    co2_g_per_s: float
+    cumu_dist_mi: float
+    cumu_fuel_kj: float
+    cumu_ess_dischg_kj: float
+    cumu_roadway_chg_kj: float
+    cumu_co2_g: float
+    mpgge: float
+    electric_kwh_per_mi: float
+
+class SimDriveStream(SerdeAPI):
+    """Streaming simulator that takes one (dt, speed, grade) sample at a
+    time, starting from rest at time zero"""
+    sd: RustSimDrive
+    init_soc: float
+    state: SimDriveStreamState
+
+    def __init__(
+        self,
+        veh: RustVehicle,
+        init_soc: Optional[float] = None,
+        sim_params: Optional[RustSimDriveParams] = None,
+    ) -> None: ...
+    def step(self, dt_s: float, mps: float, grade: float) -> SimDriveStreamState:
+        """Simulates one sample and returns step outputs and running totals."""
+        ...
+    def to_sim_drive(self) -> RustSimDrive:
+        """Returns `RustSimDrive` with arrays for all received samples and
+        post scalars set"""
+        ...
+    def len(self) -> int: ...
+
+class RustSimDriveParams(SerdeAPI, FieldPathAPI):
     """Struct containing time trace data"""
     coast_allow: bool
     coast_allow_passing: bool
@@ -640,7 +1553,10 @@ class RustSimDriveParams(SerdeAPI):
     coast_max_speed_m_per_s: float
     coast_start_speed_m_per_s: float
     coast_time_horizon_for_adjustment_s: float
+    diagnostics: bool
     energy_audit_error_tol: float
+    error_on_energy_audit: bool
+    error_on_trace_miss: bool
     idm_allow: bool
     idm_accel_m_per_s2: float
     idm_decel_m_per_s2: float
@@ -662,11 +1578,52 @@ class RustSimDriveParams(SerdeAPI):
     trace_miss_dist_tol: float
     trace_miss_speed_mps_tol: float
     trace_miss_time_tol: float
+    energy_management_type: str
 
     def reset_orphaned(self) -> None:
         """Reset the orphaned flag to false."""
         ...
 
+    def set_energy_management_classic(self) -> None: ...
+    def set_energy_management_ecms(self, ecms: EcmsEnergyManagement) -> None: ...
+    def get_energy_management_ecms(self) -> EcmsEnergyManagement: ...
+    def set_energy_management_thermostat(self, thermostat: ThermostatEnergyManagement) -> None: ...
+    def get_energy_management_thermostat(self) -> ThermostatEnergyManagement: ...
+
+class EcmsEnergyManagement(SerdeAPI):
+    """Equivalent consumption minimization strategy for hybrid energy management"""
+    equiv_factor: float
+    soc_gain: float
+    soc_target: Optional[float]
+    n_candidates: int
+
+    def __init__(
+        self,
+        equiv_factor: Optional[float] = None,
+        soc_gain: Optional[float] = None,
+        soc_target: Optional[float] = None,
+        n_candidates: Optional[int] = None,
+    ) -> None: ...
+    @classmethod
+    def default(cls) -> Self: ...
+
+class ThermostatEnergyManagement(SerdeAPI):
+    """Rule-based SOC thermostat strategy for hybrid energy management"""
+    soc_frac_fc_on: float
+    soc_frac_fc_off: float
+    fc_setpoint_kw: Optional[float]
+    n_candidates: int
+
+    def __init__(
+        self,
+        soc_frac_fc_on: Optional[float] = None,
+        soc_frac_fc_off: Optional[float] = None,
+        fc_setpoint_kw: Optional[float] = None,
+        n_candidates: Optional[int] = None,
+    ) -> None: ...
+    @classmethod
+    def default(cls) -> Self: ...
+
 class ThermalState:
     """Struct containing thermal state variables for all thermal components"""
     fc_te_deg_c: float
@@ -697,6 +1654,12 @@ class ThermalState:
     'heat transfer to cabin from hvac system'
     cab_hvac_pwr_aux_kw: float
     'aux load from hvac'
+    cab_heat_pump_pwr_kw: float
+    'heat pump electric power [kW], included in `cab_hvac_pwr_aux_kw`'
+    cab_ptc_heater_pwr_kw: float
+    'PTC heater electric power [kW], included in `cab_hvac_pwr_aux_kw`'
+    cab_ac_compressor_pwr_kw: float
+    'AC compressor electric power [kW], included in `cab_hvac_pwr_aux_kw`'
     exh_mdot: float
     'exhaust mass flow rate [kg/s]'
     exh_hdot_kw: float
@@ -727,6 +1690,12 @@ class ThermalState:
     'convection from exhaust to cat [W]\n\n    positive means cat is receiving heat'
     cat_qdot_net: float
     'net heat generation in cat [W]'
+    ess_te_deg_c: float
+    'battery temperature [°C]'
+    ess_qdot_kw: float
+    'battery heat generation from equivalent circuit losses [kW]'
+    ess_qdot_to_amb_kw: float
+    'battery heat transfer to ambient [kW]'
     amb_te_deg_c: float
     'ambient temperature'
     orphaned: bool
@@ -756,6 +1725,8 @@ class VehicleThermal:
     ess_c_kj_k: float
     cat_te_lightoff_deg_c: float
     cab_htc_to_amb_stop: float
+    cab_solar_area_m2: float
+    cabin_hvac_components: Optional[HvacComponents]
     exhport_c_kj__k: float
     rad_eps: float
     ess_htc_to_amb: float
@@ -784,9 +1755,127 @@ class VehicleThermal:
     def from_file(cls, filename: str) -> Self:
         ...
 
+class PtcHeater(SerdeAPI):
+    """Resistive positive temperature coefficient (PTC) cabin heater"""
+    max_kw: float
+    eff: float
+
+    def __init__(self, max_kw: float = 6.0, eff: float = 0.95) -> None: ...
+
+class HeatPump(SerdeAPI):
+    """Air-source heat pump for cabin heating with COP and heating capacity
+    that degrade with falling ambient temperature"""
+    te_amb_deg_c: List[float]
+    cop: List[float]
+    max_heat_kw: List[float]
+    te_amb_min_deg_c: float
+
+    def __init__(
+        self,
+        te_amb_deg_c: List[float],
+        cop: List[float],
+        max_heat_kw: List[float],
+        te_amb_min_deg_c: float,
+    ) -> None: ...
+    def cop_at(self, te_amb_deg_c: float) -> float: ...
+    def max_heat_kw_at(self, te_amb_deg_c: float) -> float: ...
+
+class AcCompressor(SerdeAPI):
+    """Electrically driven air conditioning compressor for cabin cooling"""
+    te_amb_deg_c: List[float]
+    cop: List[float]
+    max_cool_kw: float
+
+    def __init__(
+        self, te_amb_deg_c: List[float], cop: List[float], max_cool_kw: float
+    ) -> None: ...
+    def cop_at(self, te_amb_deg_c: float) -> float: ...
+
+class HvacComponents(SerdeAPI):
+    """Electric cabin HVAC hardware delivering the heating or cooling demanded
+    by the cabin HVAC controller.  Assign to
+    `VehicleThermal.cabin_hvac_components`."""
+    ptc_heater: Optional[PtcHeater]
+    heat_pump: Optional[HeatPump]
+    ac_compressor: Optional[AcCompressor]
+
+    def __init__(
+        self,
+        ptc_heater: Optional[PtcHeater] = None,
+        heat_pump: Optional[HeatPump] = None,
+        ac_compressor: Optional[AcCompressor] = None,
+    ) -> None: ...
+
 class ThermalStateHistoryVec(SerdeAPI, RustVec):
     ...
 
+class BatteryAgingModel(SerdeAPI):
+    """Battery aging model parameters for calendar and cycle capacity fade
+    and resistance growth"""
+    cal_fade_coef_per_sqrt_day: float
+    cal_arrhenius_te_k: float
+    cal_ref_te_deg_c: float
+    te_deg_c: float
+    cyc_fade_at_eol: float
+    res_growth_per_fade: float
+    eol_soh: float
+
+    def __init__(
+        self,
+        cal_fade_coef_per_sqrt_day: Optional[float] = None,
+        cal_arrhenius_te_k: Optional[float] = None,
+        cal_ref_te_deg_c: Optional[float] = None,
+        te_deg_c: Optional[float] = None,
+        cyc_fade_at_eol: Optional[float] = None,
+        res_growth_per_fade: Optional[float] = None,
+        eol_soh: Optional[float] = None,
+    ) -> None: ...
+    @classmethod
+    def default(cls) -> Self: ...
+
+class BatteryAgingState(SerdeAPI):
+    """Battery aging state variables"""
+    age_days: float
+    dist_mi: float
+    cyc_life_frac: float
+    cal_fade_frac: float
+    cyc_fade_frac: float
+    soh: float
+    res_factor: float
+
+    def apply(self, veh_fresh: RustVehicle) -> RustVehicle:
+        """Returns copy of `veh_fresh` with usable capacity and round trip
+        efficiency degraded per this state"""
+        ...
+
+class BatteryAgingStateHistoryVec(SerdeAPI, RustVec):
+    ...
+
+class FleetLife(SerdeAPI):
+    """Multi-year driver that repeatedly runs `sim_drive` over representative
+    cycles with a progressively aged battery"""
+    veh: RustVehicle
+    cycs: List[RustCycle]
+    aging_model: BatteryAgingModel
+    miles_per_year: float
+    years: float
+    steps_per_year: int
+    state: BatteryAgingState
+    history: BatteryAgingStateHistoryVec
+
+    def __init__(
+        self,
+        veh: RustVehicle,
+        cycs: List[RustCycle],
+        aging_model: Optional[BatteryAgingModel] = None,
+        miles_per_year: Optional[float] = None,
+        years: Optional[float] = None,
+        steps_per_year: Optional[int] = None,
+    ) -> None: ...
+    def walk(self) -> None:
+        """Simulates battery aging over `years` and sets `veh.val_ess_life_miles`"""
+        ...
+
 class SimDriveHot(SerdeAPI):
     sd: RustSimDrive
     vehthrm: VehicleThermal
@@ -800,7 +1889,8 @@ class SimDriveHot(SerdeAPI):
         veh: RustVehicle, 
         vehthrm: VehicleThermal, 
         init_state: Optional[ThermalState], 
-        amb_te_deg_c: Optional[List[float]]
+        amb_te_deg_c: Optional[List[float]],
+        weather: Optional[WeatherProfile] = None,
     ) -> Self:
         ...
 
@@ -936,6 +2026,21 @@ class SimDriveHot(SerdeAPI):
         """Perform all the calculations to solve 1 time step."""
         ...
 
+    def timeseries(self, columns: Optional[List[str]] = None) -> Dict[str, List[float]]:
+        """Returns dict of `time_s` and `columns`, or all time series if None,
+        e.g. for `polars.DataFrame(sdh.timeseries())`.  Booleans are 0.0/1.0."""
+        ...
+
+    def timeseries_units(self) -> Dict[str, str]:
+        """Returns dict of time series column name to unit, for columns with a known unit"""
+        ...
+
+    def to_timeseries_file(self, filepath: str, columns: Optional[List[str]] = None) -> None:
+        """Writes `time_s` and `columns`, or all time series if None, to `filepath` with
+        format determined by its extension: csv, parquet, or arrow/ipc/feather.  Units are
+        stored as `unit` field metadata in parquet and arrow files."""
+        ...
+
 def abc_to_drag_coeffs(
     veh: RustVehicle,
     a_lbf: float,
@@ -70,6 +70,13 @@ class SimDriveParams(object):
         self.newton_xtol = 1e-9  # newton solver tolerance
         # tolerance for energy audit error warning, i.e. 0.1%
         self.energy_audit_error_tol = 0.002
+        # if True, trace miss beyond tolerance raises `TraceMissError` rather than warning
+        self.error_on_trace_miss = False
+        # if True, energy audit error beyond tolerance raises `EnergyAuditError` rather than warning
+        self.error_on_energy_audit = False
+        # if True, each time step is checked for NaN/Inf/impossible negative values and
+        # `SimFaultError` is raised at the first one; see `RustSimDrive.diagnostics`
+        self.diagnostics = False
         self.coast_allow = False  # if True, coasting to stops are allowed
         # if True, coasting vehicle can eclipse the shadow trace
         self.coast_allow_passing = False
@@ -6,9 +6,11 @@ use serde_json::{json, Value};
 use std::fs;
 
 use fastsim_core::{
-    cycle::RustCycle, params::MPH_PER_MPS, simdrive::RustSimDrive, simdrivelabel::get_label_fe,
-    simdrivelabel::get_net_accel, simdrivelabel::make_accel_trace, traits::SerdeAPI,
-    utils::interpolate_vectors as interp, vehicle::RustVehicle, vehicle_utils::abc_to_drag_coeffs,
+    cycle::RustCycle, field_path::FieldPath, field_path::FieldValue, fuel::Fuel, fuel::FuelType,
+    fuel::SecondaryFuel, params::MPH_PER_MPS, simdrive::sweep::ParameterSweep,
+    simdrive::RustSimDrive, simdrivelabel::get_label_fe, simdrivelabel::get_net_accel,
+    simdrivelabel::make_accel_trace, traits::SerdeAPI, vehicle::FcEffType, vehicle::PowertrainType,
+    vehicle::RustVehicle, vehicle_utils::abc_to_drag_coeffs,
 };
 
 /// Wrapper for fastsim.
@@ -25,7 +27,7 @@ use fastsim_core::{
 #[clap(group(
     ArgGroup::new("cycle")
     .required(true)
-    .args(&["cyc", "cyc-file", "adopt", "adopt-hd"])
+    .args(&["cyc", "cyc-file", "adopt", "adopt-hd", "validate"])
 ))]
 #[clap(group(
     ArgGroup::new("vehicle")
@@ -55,6 +57,9 @@ struct FastSimApi {
     #[clap(value_parser, long)]
     //adopt HD flag
     adopt_hd: Option<String>,
+    #[clap(value_parser, long)]
+    /// Check vehicle inputs and print every problem found instead of simulating
+    validate: Option<bool>,
     /// Vehicle as json string
     #[clap(value_parser, long)]
     veh: Option<String>,
@@ -62,6 +67,14 @@ struct FastSimApi {
     /// Path to vehicle file (yaml)
     veh_file: Option<String>,
     #[clap(long, value_parser)]
+    /// Vehicle input override as `path=value`, e.g. `drag_coef=0.3` or
+    /// `transmission.final_drive_ratio=3.5`; may be repeated
+    set: Vec<String>,
+    #[clap(long, value_parser)]
+    /// Path to parameter sweep file (yaml or json) to run over the vehicle and
+    /// cycle, printing inputs and outputs of each sample as CSV
+    sweep: Option<String>,
+    #[clap(long, value_parser)]
     /// How to return results: `adopt_json`, `mpgge`, ... TBD
     res_fmt: Option<String>,
     #[clap(long, value_parser)]
@@ -113,59 +126,24 @@ pub struct H2AndDieselResults {
     pub diesel_mpg: f64,
 }
 
-pub fn calculate_mpgge_for_h2_diesel_ice(
-    dist_mi: f64,
-    max_fc_power_kw: f64,
-    kwh_per_gge: f64,
-    fc_kw_out_ach: &[f64],
-    fs_kwh_out_ach: &[f64],
-    fc_pwr_out_perc: &Vec<f64>,
-    h2share: &Vec<f64>,
-) -> anyhow::Result<H2AndDieselResults> {
-    anyhow::ensure!(fc_kw_out_ach.len() == fs_kwh_out_ach.len());
-    anyhow::ensure!(fc_pwr_out_perc.len() == h2share.len());
-    let kwh_per_gallon_diesel = 37.95;
-    let gge_per_kwh = 1.0 / kwh_per_gge;
-    let mut total_diesel_kwh = 0.0;
-    let mut total_diesel_gals = 0.0;
-    let mut total_diesel_gge = 0.0;
-    let mut total_h2_kwh = 0.0;
-    let mut total_h2_gge = 0.0;
-    for idx in 0..fs_kwh_out_ach.len() {
-        let fc_kw_out = fc_kw_out_ach[idx];
-        let fs_kwh_out = fs_kwh_out_ach[idx];
-        let fc_perc_pwr = fc_kw_out / max_fc_power_kw;
-        let h2_perc = interp(&fc_perc_pwr, fc_pwr_out_perc, h2share, false);
-        let h2_kwh = h2_perc * fs_kwh_out;
-        let h2_gge = gge_per_kwh * h2_kwh;
-        total_h2_kwh += h2_kwh;
-        total_h2_gge += h2_gge;
-
-        let diesel_perc = 1.0 - h2_perc;
-        let diesel_kwh = diesel_perc * fs_kwh_out;
-        let diesel_gals = diesel_kwh / kwh_per_gallon_diesel;
-        let diesel_gge = diesel_kwh * gge_per_kwh;
-        total_diesel_kwh += diesel_kwh;
-        total_diesel_gals += diesel_gals;
-        total_diesel_gge += diesel_gge;
+impl H2AndDieselResults {
+    /// Results for a vehicle with diesel as primary fuel and H2 as
+    /// `secondary_fuel` from totals calculated by `set_post_scalars`
+    pub fn from_sim_drive(sd: &RustSimDrive) -> Self {
+        let dist_mi = sd.dist_mi.sum();
+        let h2_kwh = sd.fuel2_kj / 3.6e3;
+        let h2_gge = h2_kwh / sd.props.kwh_per_gge;
+        let diesel_kwh = sd.fuel1_kj / 3.6e3;
+        Self {
+            h2_kwh,
+            h2_gge,
+            h2_mpgge: if h2_gge > 0.0 { dist_mi / h2_gge } else { 0.0 },
+            diesel_kwh,
+            diesel_gals: sd.fuel_native,
+            diesel_gge: diesel_kwh / sd.props.kwh_per_gge,
+            diesel_mpg: sd.mi_per_fuel_native,
+        }
     }
-    Ok(H2AndDieselResults {
-        h2_kwh: total_h2_kwh,
-        h2_gge: total_h2_gge,
-        h2_mpgge: if total_h2_gge > 0.0 {
-            dist_mi / total_h2_gge
-        } else {
-            0.0
-        },
-        diesel_kwh: total_diesel_kwh,
-        diesel_gals: total_diesel_gals,
-        diesel_gge: total_diesel_gge,
-        diesel_mpg: if total_diesel_gals > 0.0 {
-            dist_mi / total_diesel_gals
-        } else {
-            0.0
-        },
-    })
 }
 
 pub fn integrate_power_to_kwh(dts_s: &[f64], ps_kw: &[f64]) -> anyhow::Result<Vec<f64>> {
@@ -182,6 +160,19 @@ pub fn integrate_power_to_kwh(dts_s: &[f64], ps_kw: &[f64]) -> anyhow::Result<Ve
 pub fn main() -> anyhow::Result<()> {
     let fastsim_api = FastSimApi::parse();
 
+    if fastsim_api.validate.unwrap_or_default() {
+        let veh = if let Some(veh_string) = &fastsim_api.veh {
+            RustVehicle::from_json(veh_string, true)?
+        } else if let Some(veh_file_path) = &fastsim_api.veh_file {
+            RustVehicle::from_file(veh_file_path, true)?
+        } else {
+            anyhow::bail!("`--validate` requires a vehicle, given with `--veh` or `--veh-file`");
+        };
+        let report = veh.validate();
+        println!("{report}");
+        return report.to_result();
+    }
+
     if let Some(_cyc_json_str) = fastsim_api.cyc {
         // TODO: this probably could be filled out...
         anyhow::bail!("Need to implement: let cyc = RustCycle::from_json(cyc_json_str)");
@@ -241,14 +232,11 @@ pub fn main() -> anyhow::Result<()> {
     // TODO: put in logic here for loading vehicle for adopt-hd
     // with same file format as regular adopt and same outputs retured
     let is_adopt = fastsim_api.adopt.is_some() && fastsim_api.adopt.unwrap();
-    let mut fc_pwr_out_perc = None;
-    let mut hd_h2_diesel_ice_h2share = None;
-    let veh = if let Some(veh_string) = fastsim_api.veh {
+    let mut veh = if let Some(veh_string) = fastsim_api.veh {
         if is_adopt || is_adopt_hd {
-            let (veh_string, pwr_out_perc, h2share) = json_rewrite(veh_string)?;
-            hd_h2_diesel_ice_h2share = h2share;
-            fc_pwr_out_perc = pwr_out_perc;
+            let (veh_string, secondary_fuel) = json_rewrite(veh_string)?;
             let mut veh = RustVehicle::from_json(&veh_string, false)?;
+            set_h2_diesel_fuels(&mut veh, secondary_fuel);
             veh.set_derived()?;
             Ok(veh)
         } else {
@@ -259,10 +247,9 @@ pub fn main() -> anyhow::Result<()> {
     } else if let Some(veh_file_path) = fastsim_api.veh_file {
         if is_adopt || is_adopt_hd {
             let veh_string = fs::read_to_string(veh_file_path)?;
-            let (veh_string, pwr_out_perc, h2share) = json_rewrite(veh_string)?;
-            hd_h2_diesel_ice_h2share = h2share;
-            fc_pwr_out_perc = pwr_out_perc;
+            let (veh_string, secondary_fuel) = json_rewrite(veh_string)?;
             let mut veh = RustVehicle::from_json(&veh_string, false)?;
+            set_h2_diesel_fuels(&mut veh, secondary_fuel);
             veh.set_derived()?;
             Ok(veh)
         } else {
@@ -272,6 +259,23 @@ pub fn main() -> anyhow::Result<()> {
         Ok(RustVehicle::mock_vehicle())
     }?;
 
+    for veh_override in &fastsim_api.set {
+        let (path, value) = veh_override
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("expected `path=value`, got `{veh_override}`"))?;
+        veh.set_field(path, value.parse::<FieldValue>()?)?;
+    }
+    if !fastsim_api.set.is_empty() {
+        veh.set_derived()?;
+    }
+
+    if let Some(sweep_file_path) = fastsim_api.sweep {
+        let sweep = ParameterSweep::from_file(sweep_file_path, false)?;
+        let res = sweep.run(&veh, &cyc, None)?;
+        print!("{}", res.to_csv_string()?);
+        return Ok(());
+    }
+
     if is_adopt {
         let sdl = get_label_fe(&veh, Some(false), Some(false))?;
         let res = AdoptResults {
@@ -294,27 +298,12 @@ pub fn main() -> anyhow::Result<()> {
         sim_drive.sim_drive(None, None)?;
         let mut sim_drive_accel = RustSimDrive::new(make_accel_trace(), veh.clone());
         let net_accel = get_net_accel(&mut sim_drive_accel, &veh.scenario_name)?;
-        let mut mpgge = sim_drive.mpgge;
-        let h2_diesel_results = if let Some(hd_h2_diesel_ice_h2share) = hd_h2_diesel_ice_h2share {
-            if let Some(fc_pwr_out_perc) = fc_pwr_out_perc {
-                let dist_mi = sim_drive.dist_mi.sum();
-                let r = calculate_mpgge_for_h2_diesel_ice(
-                    dist_mi,
-                    sim_drive.veh.fc_max_kw,
-                    sim_drive.props.kwh_per_gge,
-                    &sim_drive.fc_kw_out_ach.to_vec(),
-                    &sim_drive.fs_kwh_out_ach.to_vec(),
-                    &fc_pwr_out_perc,
-                    &hd_h2_diesel_ice_h2share,
-                )?;
-                mpgge = dist_mi / (r.diesel_gge + r.h2_gge);
-                Some(r)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let mpgge = sim_drive.mpgge;
+        let h2_diesel_results = sim_drive
+            .veh
+            .secondary_fuel
+            .as_ref()
+            .map(|_| H2AndDieselResults::from_sim_drive(&sim_drive));
 
         let res = AdoptResults {
             adjCombMpgge: mpgge,
@@ -345,16 +334,12 @@ pub fn main() -> anyhow::Result<()> {
 }
 
 fn translate_veh_pt_type(x: i64) -> String {
-    if x == 1 {
-        String::from("Conv")
-    } else if x == 2 {
-        String::from("HEV")
-    } else if x == 3 {
-        String::from("PHEV")
-    } else if x == 4 {
-        String::from("BEV")
-    } else {
-        x.to_string()
+    match x {
+        1 => PowertrainType::Conv.to_string(),
+        2 => PowertrainType::HEV.to_string(),
+        3 => PowertrainType::PHEV.to_string(),
+        4 => PowertrainType::BEV.to_string(),
+        _ => x.to_string(),
     }
 }
 
@@ -396,9 +381,18 @@ struct ParsedValue(Value);
 
 impl SerdeAPI for ParsedValue {}
 
+/// Sets diesel as the primary fuel and H2 as `secondary_fuel` of an H2/diesel
+/// dual-fuel ICE, if `secondary_fuel` is provided.
+fn set_h2_diesel_fuels(veh: &mut RustVehicle, secondary_fuel: Option<SecondaryFuel>) {
+    if let Some(secondary_fuel) = secondary_fuel {
+        veh.fuel = Some(Fuel::new(FuelType::Diesel));
+        veh.secondary_fuel = Some(secondary_fuel);
+    }
+}
+
 /// Rewrites the ADOPT JSON string to be in compliance with what FASTSim expects for JSON input.
-#[allow(clippy::type_complexity)]
-fn json_rewrite(x: String) -> anyhow::Result<(String, Option<Vec<f64>>, Option<Vec<f64>>)> {
+/// Also returns the H2 `SecondaryFuel` of an H2/diesel dual-fuel ICE if specified.
+fn json_rewrite(x: String) -> anyhow::Result<(String, Option<SecondaryFuel>)> {
     let adoptstring = x;
 
     let mut fc_pwr_out_perc = None;
@@ -419,6 +413,8 @@ fn json_rewrite(x: String) -> anyhow::Result<(String, Option<Vec<f64>>, Option<V
         let fc_eff_type = String::from(fc_eff_type_value);
         parsed_data["fcEffType"] = Value::String(fc_eff_type.clone());
         if fc_eff_type == *"HDH2DieselIce" {
+            // H2 share is applied via `secondary_fuel`, so simulate as HD diesel
+            parsed_data["fcEffType"] = json!(FcEffType::HDDiesel.to_string());
             let fc_pwr_out_perc_raw = &parsed_data["fuelConverter"]["fcPwrOutPerc"];
             if fc_pwr_out_perc_raw.is_array() {
                 fc_pwr_out_perc = Some(transform_array_of_value_to_vec_of_f64(
@@ -498,5 +494,15 @@ fn json_rewrite(x: String) -> anyhow::Result<(String, Option<Vec<f64>>, Option<V
 
     let adoptstring = ParsedValue(parsed_data).to_json()?;
 
-    Ok((adoptstring, fc_pwr_out_perc, hd_h2_diesel_ice_h2share))
+    let secondary_fuel = match (fc_pwr_out_perc, hd_h2_diesel_ice_h2share) {
+        (Some(fc_pwr_out_frac), Some(energy_share)) => Some(SecondaryFuel {
+            fuel: Fuel::new(FuelType::H2),
+            fc_pwr_out_frac,
+            energy_share,
+            orphaned: false,
+        }),
+        _ => None,
+    };
+
+    Ok((adoptstring, secondary_fuel))
 }
@@ -2,7 +2,7 @@ use std::process::Command;
 use std::{path::Path, str::FromStr};
 
 use assert_cmd::prelude::{CommandCargoExt, OutputAssertExt};
-use predicates::prelude::predicate;
+use predicates::prelude::{predicate, PredicateBooleanExt};
 
 #[test]
 fn test_that_cli_app_produces_result() {
@@ -59,3 +59,120 @@ fn test_that_adopt_hd_option_works_as_expected() {
             .stdout(predicate::str::contains(expected_mpg));
     }
 }
+
+#[test]
+fn test_that_validate_option_reports_vehicle_problems() {
+    let mut veh_file = project_root::get_project_root().unwrap();
+    veh_file.push(Path::new(
+        "../python/fastsim/resources/vehdb/2012_Ford_Fusion.yaml",
+    ));
+    veh_file = veh_file.canonicalize().unwrap();
+    assert!(veh_file.exists());
+
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args([
+        "--validate",
+        "true",
+        "--veh-file",
+        veh_file.to_str().unwrap(),
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[error]").not());
+
+    // same vehicle with a zero wheel radius
+    let veh_yaml = std::fs::read_to_string(&veh_file)
+        .unwrap()
+        .replace("wheel_radius_m: 0.326", "wheel_radius_m: 0.0");
+    let bad_veh_file = std::env::temp_dir().join("fastsim_cli_invalid_vehicle.yaml");
+    std::fs::write(&bad_veh_file, veh_yaml).unwrap();
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args([
+        "--validate",
+        "true",
+        "--veh-file",
+        bad_veh_file.to_str().unwrap(),
+    ]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("[error] wheel_radius_m"));
+
+    // no vehicle to validate
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args(["--validate", "true"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--veh"));
+}
+
+#[test]
+fn test_that_sweep_option_prints_results_table() {
+    let mut cyc_file = project_root::get_project_root().unwrap();
+    cyc_file.push(Path::new("../python/fastsim/resources/cycles/udds.csv"));
+    let mut veh_file = project_root::get_project_root().unwrap();
+    veh_file.push(Path::new(
+        "../python/fastsim/resources/vehdb/2012_Ford_Fusion.yaml",
+    ));
+    let mut sweep_file = project_root::get_project_root().unwrap();
+    sweep_file.push(Path::new("../rust/fastsim-cli/tests/assets/sweep.yaml"));
+    sweep_file = sweep_file.canonicalize().unwrap();
+    assert!(sweep_file.exists());
+
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args([
+        "--cyc-file",
+        cyc_file.canonicalize().unwrap().to_str().unwrap(),
+        "--veh-file",
+        veh_file.canonicalize().unwrap().to_str().unwrap(),
+        "--sweep",
+        sweep_file.to_str().unwrap(),
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "drag_coef,fc_max_kw,mpgge,trace_miss,error");
+    // header plus 3 drag levels times 2 engine sizes
+    assert_eq!(lines.len(), 7);
+    assert!(lines[1].starts_with("0.25,100,"));
+}
+
+#[test]
+fn test_that_set_option_overrides_vehicle_inputs() {
+    let mut cyc_file = project_root::get_project_root().unwrap();
+    cyc_file.push(Path::new("../python/fastsim/resources/cycles/udds.csv"));
+    let mut veh_file = project_root::get_project_root().unwrap();
+    veh_file.push(Path::new(
+        "../python/fastsim/resources/vehdb/2012_Ford_Fusion.yaml",
+    ));
+    let cyc_file = cyc_file.canonicalize().unwrap();
+    let veh_file = veh_file.canonicalize().unwrap();
+
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args([
+        "--cyc-file",
+        cyc_file.to_str().unwrap(),
+        "--veh-file",
+        veh_file.to_str().unwrap(),
+        "--set",
+        "drag_coef=0.2",
+        "--set",
+        "glider_kg=800",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let mpgge: f64 = String::from_utf8(output).unwrap().trim().parse().unwrap();
+    // lighter and more aerodynamic than the baseline of 33.8 mpgge
+    assert!(mpgge > 34.0, "{mpgge}");
+
+    let mut cmd = Command::cargo_bin("fastsim-cli").unwrap();
+    cmd.args([
+        "--cyc-file",
+        cyc_file.to_str().unwrap(),
+        "--veh-file",
+        veh_file.to_str().unwrap(),
+        "--set",
+        "not_a_field=1",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not_a_field"));
+}
@@ -193,6 +193,10 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         abort_call_site!("`add_pyo3_api` works only on named and tuple structs.");
     };
 
+    if derives_field_path(&ast.attrs) {
+        py_impl_block.extend::<TokenStream2>(field_path_methods(&ast.fields));
+    }
+
     // py_impl_block.extend::<TokenStream2>(quote! {
     //     #[staticmethod]
     //     #[pyo3(name = "default")]
@@ -220,7 +224,7 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
             Self::from_resource(
                 PathBuf::extract_bound(filepath)?, 
                 skip_init.unwrap_or_default()
-            ).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            ).map_err(to_py_io_err)
         }
 
         /// Write (serialize) an object to a file.
@@ -235,7 +239,7 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         pub fn to_file_py(&self, filepath: &Bound<PyAny>) -> PyResult<()> {
            self.to_file(
                PathBuf::extract_bound(filepath)?
-           ).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+           ).map_err(to_py_io_err)
         }
 
         /// Read (deserialize) an object from a file.
@@ -253,7 +257,7 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
                 PathBuf::extract_bound(filepath)?, 
                 skip_init.unwrap_or_default()
             )
-            .map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            .map_err(to_py_io_err)
         }
 
         /// Write (serialize) an object into a string
@@ -264,7 +268,7 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         ///
         #[pyo3(name = "to_str")]
         pub fn to_str_py(&self, format: &str) -> PyResult<String> {
-            self.to_str(format).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            self.to_str(format).map_err(to_py_io_err)
         }
 
         /// Read (deserialize) an object from a string
@@ -278,13 +282,13 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[pyo3(name = "from_str")]
         #[pyo3(signature = (contents, format, skip_init=None))]
         pub fn from_str_py(contents: &str, format: &str, skip_init: Option<bool>) -> PyResult<Self> {
-            Self::from_str(contents, format, skip_init.unwrap_or_default()).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            Self::from_str(contents, format, skip_init.unwrap_or_default()).map_err(to_py_io_err)
         }
 
         /// Write (serialize) an object to a JSON string
         #[pyo3(name = "to_json")]
         pub fn to_json_py(&self) -> PyResult<String> {
-            self.to_json().map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            self.to_json().map_err(to_py_io_err)
         }
 
         /// Read (deserialize) an object to a JSON string
@@ -297,13 +301,13 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[pyo3(name = "from_json")]
         #[pyo3(signature = (json_str, skip_init=None))]
         pub fn from_json_py(json_str: &str, skip_init: Option<bool>) -> PyResult<Self> {
-            Self::from_json(json_str, skip_init.unwrap_or_default()).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            Self::from_json(json_str, skip_init.unwrap_or_default()).map_err(to_py_io_err)
         }
 
         /// Write (serialize) an object to a YAML string
         #[pyo3(name = "to_yaml")]
         pub fn to_yaml_py(&self) -> PyResult<String> {
-            self.to_yaml().map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            self.to_yaml().map_err(to_py_io_err)
         }
 
         /// Read (deserialize) an object from a YAML string
@@ -316,14 +320,14 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[pyo3(name = "from_yaml")]
         #[pyo3(signature = (yaml_str, skip_init=None))]
         pub fn from_yaml_py(yaml_str: &str, skip_init: Option<bool>) -> PyResult<Self> {
-            Self::from_yaml(yaml_str, skip_init.unwrap_or_default()).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+            Self::from_yaml(yaml_str, skip_init.unwrap_or_default()).map_err(to_py_io_err)
         }
 
         // /// Write (serialize) an object to bincode-encoded `bytes`
         // #[cfg(feature = "bincode")]
         // #[pyo3(name = "to_bincode")]
         // pub fn to_bincode_py<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
-        //     PyResult::Ok(PyBytes::new(py, &self.to_bincode()?)).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+        //     PyResult::Ok(PyBytes::new(py, &self.to_bincode()?)).map_err(to_py_io_err)
         // }
 
         // /// Read (deserialize) an object from bincode-encoded `bytes`
@@ -336,7 +340,7 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
         // #[staticmethod]
         // #[pyo3(name = "from_bincode")]
         // pub fn from_bincode_py(encoded: &PyBytes, skip_init: Option<bool>) -> PyResult<Self> {
-        //     Self::from_bincode(encoded.as_bytes(), skip_init.unwrap_or_default()).map_err(|e| PyIOError::new_err(format!("{:?}", e)))
+        //     Self::from_bincode(encoded.as_bytes(), skip_init.unwrap_or_default()).map_err(to_py_io_err)
         // }
     });
 
@@ -367,6 +371,65 @@ pub fn add_pyo3_api(attr: TokenStream, item: TokenStream) -> TokenStream {
     final_output.into()
 }
 
+/// Whether the struct has `#[derive(FieldPath)]`, which requires
+/// `add_pyo3_api` to come before `derive`
+fn derives_field_path(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().filter(|a| a.path.is_ident("derive")).any(|a| {
+        match a.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().any(|nested| {
+                matches!(nested, syn::NestedMeta::Meta(meta) if meta.path().is_ident("FieldPath"))
+            }),
+            _ => false,
+        }
+    })
+}
+
+/// Python methods wrapping [FieldPath](../fastsim_core/field_path/trait.FieldPath.html)
+fn field_path_methods(fields: &syn::Fields) -> TokenStream2 {
+    let has_orphaned = fields
+        .iter()
+        .any(|f| f.ident.as_ref().is_some_and(|ident| ident == "orphaned"));
+    let check_orphaned = if has_orphaned {
+        quote! {
+            if self.orphaned {
+                bail!(PyAttributeError::new_err(crate::utils::NESTED_STRUCT_ERR))
+            }
+        }
+    } else {
+        TokenStream2::default()
+    };
+    quote! {
+        #[pyo3(name = "get_field")]
+        /// Returns the value of the (nested) field at dot-separated `path`,
+        /// where numeric segments index into lists, e.g. `mps.10`
+        pub fn get_field_py(&self, path: &str) -> anyhow::Result<crate::field_path::FieldValue> {
+            crate::field_path::FieldPath::get_field(self, path)
+        }
+
+        #[pyo3(name = "set_field")]
+        /// Sets the value of the (nested) field at dot-separated `path`,
+        /// where numeric segments index into lists, e.g. `mps.10`
+        pub fn set_field_py(
+            &mut self,
+            path: &str,
+            value: crate::field_path::FieldValue,
+        ) -> anyhow::Result<()> {
+            #check_orphaned
+            crate::field_path::FieldPath::set_field(self, path, value)
+        }
+
+        #[staticmethod]
+        #[pyo3(name = "list_fields")]
+        /// Returns `(path, type, doc)` for every (nested) field
+        pub fn list_fields_py() -> Vec<(String, String, String)> {
+            <Self as crate::field_path::FieldPath>::list_fields()
+                .into_iter()
+                .map(|f| (f.path, f.type_name, f.doc))
+                .collect()
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct FieldOptions {
     /// if true, getters are not generated for a field
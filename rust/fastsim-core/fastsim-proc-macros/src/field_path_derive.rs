@@ -0,0 +1,117 @@
+//! Module that implements [super::field_path_derive]
+
+use crate::imports::*;
+
+pub fn field_path_derive(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+
+    let fields = match ast.data {
+        syn::Data::Struct(s) => s.fields,
+        _ => abort_call_site!("#[derive(FieldPath)] only works on structs"),
+    };
+
+    // `orphaned` is bookkeeping for the pyo3 API, not a model field
+    let fields = fields
+        .iter()
+        .filter(|f| f.ident.as_ref().unwrap() != "orphaned" && !has_skip_attr(f))
+        .collect::<Vec<_>>();
+
+    let field_names = fields
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let field_strs = field_names
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>();
+    let field_types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let type_strs = field_types
+        .iter()
+        .map(|ty| ty.to_token_stream().to_string().replace(' ', ""))
+        .collect::<Vec<_>>();
+    let docs = fields.iter().map(|f| doc_string(f)).collect::<Vec<_>>();
+
+    let mut generated = TokenStream2::new();
+    generated.append_all(quote! {
+        impl FieldPath for #name {
+            fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+                match path.split_first() {
+                    #(Some((&#field_strs, rest)) => self.#field_names.get_path(rest),)*
+                    Some((key, _)) => anyhow::bail!("`{}` has no field `{}`", stringify!(#name), key),
+                    None => anyhow::bail!(
+                        "`{}` is a struct, specify one of its fields",
+                        stringify!(#name)
+                    ),
+                }
+            }
+
+            fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+                match path.split_first() {
+                    #(Some((&#field_strs, rest)) => self.#field_names.set_path(rest, value),)*
+                    Some((key, _)) => anyhow::bail!("`{}` has no field `{}`", stringify!(#name), key),
+                    None => anyhow::bail!(
+                        "`{}` is a struct, set its fields individually",
+                        stringify!(#name)
+                    ),
+                }
+            }
+
+            fn from_field_value(_value: FieldValue) -> anyhow::Result<Self> {
+                anyhow::bail!(
+                    "cannot create `{}` from a value, set its fields individually",
+                    stringify!(#name)
+                )
+            }
+
+            fn list_paths(prefix: &str, out: &mut Vec<FieldInfo>) {
+                #(
+                    out.push(FieldInfo {
+                        path: format!("{}{}", prefix, #field_strs),
+                        type_name: #type_strs.into(),
+                        doc: #docs.into(),
+                    });
+                    <#field_types as FieldPath>::list_paths(&format!("{}{}.", prefix, #field_strs), out);
+                )*
+            }
+        }
+    });
+    generated.into()
+}
+
+/// Whether field has `#[field_path(skip)]`
+fn has_skip_attr(field: &syn::Field) -> bool {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("field_path")) {
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                match nested {
+                    syn::NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => skip = true,
+                    _ => abort!(
+                        attr.span(),
+                        "Invalid field_path option.\nValid option is: `skip`."
+                    ),
+                }
+            }
+        }
+    }
+    skip
+}
+
+/// Doc comment of field, with lines joined by spaces
+fn doc_string(field: &syn::Field) -> String {
+    field
+        .attrs
+        .iter()
+        .filter(|a| a.path.is_ident("doc"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(s),
+                ..
+            })) => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
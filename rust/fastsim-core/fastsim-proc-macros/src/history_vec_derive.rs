@@ -1,6 +1,7 @@
 //! Module that implements [super::history_vec_derive]
 
 use crate::imports::*;
+use crate::timeseries_derive::timeseries_impl;
 use crate::utilities::*;
 
 pub fn history_vec_derive(input: TokenStream) -> TokenStream {
@@ -118,5 +119,10 @@ pub fn history_vec_derive(input: TokenStream) -> TokenStream {
             }
         }
     });
+    let series_names = field_names_no_orphaned
+        .iter()
+        .map(|f| (**f).clone())
+        .collect::<Vec<_>>();
+    generated.append_all(timeseries_impl(&new_name, &series_names));
     generated.into()
 }
@@ -6,7 +6,9 @@ mod imports;
 mod add_pyo3_api;
 mod approx_eq_derive;
 mod doc_field;
+mod field_path_derive;
 mod history_vec_derive;
+mod timeseries_derive;
 
 // modules - other
 mod utilities;
@@ -35,8 +37,27 @@ pub fn history_vec_derive(input: TokenStream) -> TokenStream {
     history_vec_derive::history_vec_derive(input)
 }
 
+/// Derive implementation of TimeSeries trait, which adds a column to a
+/// `TimeSeriesTable` for each `Array1` and `Vec` field.  `*HistoryVec` structs
+/// created by `#[derive(HistoryVec)]` implement TimeSeries already.
+#[proc_macro_error]
+#[proc_macro_derive(TimeSeries)]
+pub fn timeseries_derive(input: TokenStream) -> TokenStream {
+    timeseries_derive::timeseries_derive(input)
+}
+
 /// Derive implementation of ApproxEq trait
 #[proc_macro_derive(ApproxEq)]
 pub fn approx_eq_derive(input: TokenStream) -> TokenStream {
     approx_eq_derive::approx_eq_derive(input)
 }
+
+/// Derive implementation of FieldPath trait for getting, setting, and listing
+/// (nested) fields by dot-separated path.  Skips `orphaned` and fields with
+/// `#[field_path(skip)]`.  [macro@add_pyo3_api], if placed before the derive,
+/// exposes these to Python as `get_field`, `set_field`, and `list_fields`.
+#[proc_macro_error]
+#[proc_macro_derive(FieldPath, attributes(field_path))]
+pub fn field_path_derive(input: TokenStream) -> TokenStream {
+    field_path_derive::field_path_derive(input)
+}
@@ -0,0 +1,68 @@
+//! Module that implements [super::timeseries_derive]
+
+use crate::imports::*;
+
+pub fn timeseries_derive(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+
+    let fields = match ast.data {
+        syn::Data::Struct(s) => s.fields,
+        _ => abort_call_site!("#[derive(TimeSeries)] only works on structs"),
+    };
+
+    // only `Array1` and `Vec` fields are time series
+    let field_names = fields
+        .iter()
+        .filter(|f| is_series_type(&f.ty))
+        .map(|f| f.ident.clone().unwrap())
+        .collect::<Vec<_>>();
+
+    timeseries_impl(name, &field_names).into()
+}
+
+/// Implementation of `TimeSeries` for struct `name`, pushing a column for
+/// each of `field_names`, which must be `Array1`s or `Vec`s of elements that
+/// convert to `f64`
+pub(crate) fn timeseries_impl(name: &Ident, field_names: &[Ident]) -> TokenStream2 {
+    let field_strs = field_names
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>();
+    quote! {
+        impl crate::timeseries::TimeSeries for #name {
+            fn push_columns(
+                &self,
+                table: &mut crate::timeseries::TimeSeriesTable,
+                prefix: &str,
+            ) -> anyhow::Result<()> {
+                #(
+                    table.push(
+                        format!("{}{}", prefix, #field_strs),
+                        self.#field_names.iter().map(|x| f64::from(*x)).collect(),
+                    )?;
+                )*
+                Ok(())
+            }
+
+            fn series(&self, name: &str) -> Option<Vec<f64>> {
+                match name {
+                    #(#field_strs => Some(self.#field_names.iter().map(|x| f64::from(*x)).collect()),)*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Whether `ty` is `Array1<_>` or `Vec<_>`
+fn is_series_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == "Array1" || seg.ident == "Vec"),
+        _ => false,
+    }
+}
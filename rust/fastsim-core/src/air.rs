@@ -5,6 +5,14 @@ use ndarray::Array1;
 
 pub const R_AIR: f64 = 287.0; // J/(kg*K)
 
+/// Returns standard atmospheric pressure \[Pa\] at elevation `h` \[m\] above
+/// sea level, as used by [AirProperties::get_rho]  
+/// Source: <https://www.grc.nasa.gov/WWW/K-12/rocket/atmosmet.html>
+pub fn get_std_pressure(h: f64) -> f64 {
+    let te_standard = 15.04 - 0.00649 * h; // \[degC\]
+    101.29e3 * ((te_standard + 273.1) / 288.08).powf(5.256)
+}
+
 /// Fluid Properties for calculations.  
 ///
 /// Values obtained via (in Python):
@@ -64,9 +72,7 @@ impl AirProperties {
     /// * `te_air` - ambient temperature \[°C\] of air   
     /// * `h` - evelation \[m\] above sea level, defaults to 180 m
     pub fn get_rho(&self, te_air: f64, h: Option<f64>) -> f64 {
-        let h = h.unwrap_or(180.0);
-        let te_standard = 15.04 - 0.00649 * h; // \[degC\]
-        let p = 101.29e3 * ((te_standard + 273.1) / 288.08).powf(5.256); // \[Pa\]
+        let p = get_std_pressure(h.unwrap_or(180.0)); // \[Pa\]
         p / (R_AIR * (te_air + 273.15)) // [kg/m**3]
     }
 
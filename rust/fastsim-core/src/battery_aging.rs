@@ -0,0 +1,436 @@
+//! Module for simulating traction battery aging (calendar and cycle capacity
+//! fade and resistance growth) over the life of a vehicle
+
+use crate::proc_macros::{add_pyo3_api, HistoryVec};
+
+use crate::cycle::RustCycle;
+use crate::imports::*;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::simdrive::RustSimDrive;
+use crate::vehicle::{PowertrainType, RustVehicle};
+
+/// days per year
+const DAYS_PER_YEAR: f64 = 365.25;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (
+        cal_fade_coef_per_sqrt_day=None,
+        cal_arrhenius_te_k=None,
+        cal_ref_te_deg_c=None,
+        te_deg_c=None,
+        cyc_fade_at_eol=None,
+        res_growth_per_fade=None,
+        eol_soh=None,
+    ))]
+    pub fn __new__(
+        cal_fade_coef_per_sqrt_day: Option<f64>,
+        cal_arrhenius_te_k: Option<f64>,
+        cal_ref_te_deg_c: Option<f64>,
+        te_deg_c: Option<f64>,
+        cyc_fade_at_eol: Option<f64>,
+        res_growth_per_fade: Option<f64>,
+        eol_soh: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        let default = Self::default();
+        let mut model = Self {
+            cal_fade_coef_per_sqrt_day: cal_fade_coef_per_sqrt_day
+                .unwrap_or(default.cal_fade_coef_per_sqrt_day),
+            cal_arrhenius_te_k: cal_arrhenius_te_k.unwrap_or(default.cal_arrhenius_te_k),
+            cal_ref_te_deg_c: cal_ref_te_deg_c.unwrap_or(default.cal_ref_te_deg_c),
+            te_deg_c: te_deg_c.unwrap_or(default.te_deg_c),
+            cyc_fade_at_eol: cyc_fade_at_eol.unwrap_or(default.cyc_fade_at_eol),
+            res_growth_per_fade: res_growth_per_fade.unwrap_or(default.res_growth_per_fade),
+            eol_soh: eol_soh.unwrap_or(default.eol_soh),
+            orphaned: false,
+        };
+        model.init()?;
+        Ok(model)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Battery aging model parameters.  Calendar fade follows a square-root-of-time
+/// law with an Arrhenius temperature dependence, and cycle fade is linear in the
+/// fraction of cycle life consumed per `RustVehicle::ess_life_coef_a` and
+/// `RustVehicle::ess_life_coef_b`.
+pub struct BatteryAgingModel {
+    /// calendar capacity fade coefficient at `cal_ref_te_deg_c` \[1 / sqrt(day)\]
+    pub cal_fade_coef_per_sqrt_day: f64,
+    /// calendar fade activation energy divided by universal gas constant \[K\]
+    pub cal_arrhenius_te_k: f64,
+    /// reference temperature for `cal_fade_coef_per_sqrt_day` \[°C\]
+    pub cal_ref_te_deg_c: f64,
+    /// average battery temperature \[°C\]
+    pub te_deg_c: f64,
+    /// capacity fade fraction when cycle life is fully consumed
+    pub cyc_fade_at_eol: f64,
+    /// fractional resistance growth per fractional capacity fade
+    pub res_growth_per_fade: f64,
+    /// state of health (usable capacity fraction) at end of life
+    pub eol_soh: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for BatteryAgingModel {
+    fn default() -> Self {
+        Self {
+            cal_fade_coef_per_sqrt_day: 1.4e-3,
+            cal_arrhenius_te_k: 3.6e3,
+            cal_ref_te_deg_c: 25.0,
+            te_deg_c: 25.0,
+            cyc_fade_at_eol: 0.2,
+            res_growth_per_fade: 2.0,
+            eol_soh: 0.8,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for BatteryAgingModel {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.cal_fade_coef_per_sqrt_day >= 0.0,
+            "{}",
+            format_dbg!(self.cal_fade_coef_per_sqrt_day)
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.cyc_fade_at_eol),
+            "{}",
+            format_dbg!(self.cyc_fade_at_eol)
+        );
+        ensure!(
+            self.res_growth_per_fade >= 0.0,
+            "{}",
+            format_dbg!(self.res_growth_per_fade)
+        );
+        ensure!(
+            0.0 < self.eol_soh && self.eol_soh < 1.0,
+            "{}",
+            format_dbg!(self.eol_soh)
+        );
+        Ok(())
+    }
+}
+
+impl BatteryAgingModel {
+    /// Calendar fade coefficient \[1 / sqrt(day)\] at `te_deg_c`
+    pub fn cal_fade_coef(&self) -> f64 {
+        self.cal_fade_coef_per_sqrt_day
+            * (-self.cal_arrhenius_te_k
+                * (1.0 / (self.te_deg_c + 273.15) - 1.0 / (self.cal_ref_te_deg_c + 273.15)))
+                .exp()
+    }
+
+    /// Fraction of cycle life consumed by a completed `sim_drive`, including a
+    /// full recharge at the end of the cycle for plug-in vehicles
+    pub fn cyc_life_frac(&self, sd: &mut RustSimDrive) -> f64 {
+        sd.set_battery_wear();
+        let recharge_frac =
+            if sd.veh.veh_pt_type.is_electrified() && sd.veh.veh_pt_type != PowertrainType::HEV {
+                (sd.soc[0] - sd.soc.last().unwrap()).max(0.0)
+            } else {
+                0.0
+            };
+        let recharge_life_frac = if recharge_frac > 0.0 {
+            (sd.veh.ess_life_coef_a / recharge_frac).powf(1.0 / sd.veh.ess_life_coef_b)
+        } else {
+            0.0
+        };
+        sd.ess_perc_dead.sum() + recharge_life_frac
+    }
+
+    /// Advances `state` by `days` of calendar aging and `cyc_life_frac` of
+    /// consumed cycle life
+    pub fn step(&self, state: &mut BatteryAgingState, days: f64, cyc_life_frac: f64) {
+        let k_cal = self.cal_fade_coef();
+        // equivalent aging time at current temperature allows temperature to
+        // vary between steps
+        let eq_days = if k_cal > 0.0 {
+            (state.cal_fade_frac / k_cal).powi(2)
+        } else {
+            0.0
+        };
+        state.age_days += days;
+        state.cal_fade_frac = k_cal * (eq_days + days).sqrt();
+        state.cyc_life_frac += cyc_life_frac;
+        state.cyc_fade_frac = self.cyc_fade_at_eol * state.cyc_life_frac;
+        state.soh = (1.0 - state.cal_fade_frac - state.cyc_fade_frac).max(0.0);
+        state.res_factor = 1.0 + self.res_growth_per_fade * (1.0 - state.soh);
+    }
+}
+
+#[add_pyo3_api(
+    #[pyo3(name = "apply")]
+    /// Returns copy of `veh_fresh` with usable capacity and round trip
+    /// efficiency degraded per this state
+    pub fn apply_py(&self, veh_fresh: &RustVehicle) -> anyhow::Result<RustVehicle> {
+        self.apply(veh_fresh)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, HistoryVec)]
+/// Battery aging state variables
+pub struct BatteryAgingState {
+    /// battery age \[day\]
+    pub age_days: f64,
+    /// vehicle distance traveled \[mi\]
+    pub dist_mi: f64,
+    /// fraction of cycle life consumed
+    pub cyc_life_frac: f64,
+    /// capacity fade fraction due to calendar aging
+    pub cal_fade_frac: f64,
+    /// capacity fade fraction due to cycling
+    pub cyc_fade_frac: f64,
+    /// state of health, usable capacity relative to fresh battery
+    pub soh: f64,
+    /// resistance relative to fresh battery
+    pub res_factor: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for BatteryAgingState {
+    fn default() -> Self {
+        Self {
+            age_days: 0.0,
+            dist_mi: 0.0,
+            cyc_life_frac: 0.0,
+            cal_fade_frac: 0.0,
+            cyc_fade_frac: 0.0,
+            soh: 1.0,
+            res_factor: 1.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for BatteryAgingState {}
+
+impl BatteryAgingState {
+    /// Returns copy of `veh_fresh` with `ess_max_kwh` scaled by state of health
+    /// and battery losses scaled by resistance growth.  Battery mass is unchanged.
+    pub fn apply(&self, veh_fresh: &RustVehicle) -> anyhow::Result<RustVehicle> {
+        let mut veh = veh_fresh.clone();
+        veh.ess_max_kwh = veh_fresh.ess_max_kwh * self.soh;
+        veh.ess_round_trip_eff = 1.0 - (1.0 - veh_fresh.ess_round_trip_eff) * self.res_factor;
+        ensure!(
+            veh.ess_round_trip_eff > 0.0,
+            "{}\nbattery resistance growth exceeds round trip efficiency",
+            format_dbg!((veh.ess_round_trip_eff, self.res_factor))
+        );
+        Ok(veh)
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (
+        veh,
+        cycs,
+        aging_model=None,
+        miles_per_year=None,
+        years=None,
+        steps_per_year=None,
+    ))]
+    pub fn __new__(
+        veh: RustVehicle,
+        cycs: Vec<RustCycle>,
+        aging_model: Option<BatteryAgingModel>,
+        miles_per_year: Option<f64>,
+        years: Option<f64>,
+        steps_per_year: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        Self::new(veh, cycs, aging_model, miles_per_year, years, steps_per_year)
+    }
+
+    #[pyo3(name = "walk")]
+    /// Simulates battery aging over `years`
+    pub fn walk_py(&mut self) -> anyhow::Result<()> {
+        self.walk()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Multi-year "fleet life" driver that repeatedly runs `sim_drive` over
+/// representative cycles with a progressively aged battery.  Each cycle in
+/// `cycs` is treated as a trip that, for plug-in vehicles, is followed by a
+/// full recharge.
+pub struct FleetLife {
+    /// fresh vehicle, `val_ess_life_miles` is set by `walk`
+    #[api(has_orphaned)]
+    pub veh: RustVehicle,
+    /// representative cycles driven repeatedly over vehicle life
+    #[api(skip_set)]
+    pub cycs: Vec<RustCycle>,
+    #[api(has_orphaned)]
+    pub aging_model: BatteryAgingModel,
+    /// annual vehicle distance traveled \[mi\]
+    pub miles_per_year: f64,
+    /// simulated vehicle life \[year\]
+    pub years: f64,
+    /// number of aging steps per year, battery state is updated between steps
+    pub steps_per_year: usize,
+    #[api(has_orphaned)]
+    pub state: BatteryAgingState,
+    pub history: BatteryAgingStateHistoryVec,
+}
+
+impl SerdeAPI for FleetLife {
+    fn init(&mut self) -> anyhow::Result<()> {
+        self.veh.init()?;
+        self.aging_model.init()?;
+        ensure!(
+            self.veh.ess_max_kwh > 0.0,
+            "{}\nvehicle must have a traction battery",
+            format_dbg!(self.veh.ess_max_kwh)
+        );
+        ensure!(!self.cycs.is_empty(), "{}", format_dbg!(self.cycs.len()));
+        ensure!(
+            self.miles_per_year > 0.0,
+            "{}",
+            format_dbg!(self.miles_per_year)
+        );
+        ensure!(self.years > 0.0, "{}", format_dbg!(self.years));
+        ensure!(
+            self.steps_per_year > 0,
+            "{}",
+            format_dbg!(self.steps_per_year)
+        );
+        Ok(())
+    }
+}
+
+impl FleetLife {
+    pub fn new(
+        veh: RustVehicle,
+        cycs: Vec<RustCycle>,
+        aging_model: Option<BatteryAgingModel>,
+        miles_per_year: Option<f64>,
+        years: Option<f64>,
+        steps_per_year: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        let mut fleet_life = Self {
+            veh,
+            cycs,
+            aging_model: aging_model.unwrap_or_default(),
+            miles_per_year: miles_per_year.unwrap_or(12e3),
+            years: years.unwrap_or(15.0),
+            steps_per_year: steps_per_year.unwrap_or(4),
+            state: Default::default(),
+            history: Default::default(),
+        };
+        fleet_life.init()?;
+        Ok(fleet_life)
+    }
+
+    /// Runs `sim_drive` for each of `cycs` with the currently aged battery and
+    /// returns (distance \[mi\], fraction of cycle life consumed)
+    pub fn sim_cycs(&self) -> anyhow::Result<(f64, f64)> {
+        let veh = self.state.apply(&self.veh)?;
+        let mut dist_mi = 0.0;
+        let mut cyc_life_frac = 0.0;
+        for (i, cyc) in self.cycs.iter().enumerate() {
+            let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+            sd.sim_drive(None, None)
+                .with_context(|| format!("{}\ncycle idx: {}", format_dbg!(), i))?;
+            dist_mi += sd.dist_mi.sum();
+            cyc_life_frac += self.aging_model.cyc_life_frac(&mut sd);
+        }
+        Ok((dist_mi, cyc_life_frac))
+    }
+
+    /// Simulates battery aging over `years`, recording the battery state at
+    /// each step in `history` and setting `veh.val_ess_life_miles` to the
+    /// distance at which `aging_model.eol_soh` is reached, or NaN if end of
+    /// life is not reached within `years`
+    pub fn walk(&mut self) -> anyhow::Result<()> {
+        self.state = Default::default();
+        self.history.clear();
+        self.history.push(self.state.clone());
+        let days_per_step = DAYS_PER_YEAR / self.steps_per_year as f64;
+        let miles_per_step = self.miles_per_year / self.steps_per_year as f64;
+        let n_steps = (self.years * self.steps_per_year as f64).ceil() as usize;
+        for _ in 0..n_steps {
+            let (dist_mi, cyc_life_frac) = self.sim_cycs()?;
+            ensure!(dist_mi > 0.0, "{}", format_dbg!(dist_mi));
+            self.aging_model.step(
+                &mut self.state,
+                days_per_step,
+                cyc_life_frac * miles_per_step / dist_mi,
+            );
+            self.state.dist_mi += miles_per_step;
+            self.history.push(self.state.clone());
+        }
+        self.veh.val_ess_life_miles = self.ess_life_miles();
+        Ok(())
+    }
+
+    /// Distance \[mi\] at which `aging_model.eol_soh` is reached in `history`,
+    /// linearly interpolated between steps, or NaN if not reached
+    pub fn ess_life_miles(&self) -> f64 {
+        let eol_soh = self.aging_model.eol_soh;
+        (1..self.history.len())
+            .find(|i| self.history.soh[*i] <= eol_soh)
+            .map_or(f64::NAN, |i| {
+                let (soh0, soh1) = (self.history.soh[i - 1], self.history.soh[i]);
+                let (mi0, mi1) = (self.history.dist_mi[i - 1], self.history.dist_mi[i]);
+                mi0 + (mi1 - mi0) * (soh0 - eol_soh) / (soh0 - soh1)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar_fade() {
+        let model = BatteryAgingModel::default();
+        let mut state = BatteryAgingState::default();
+        model.step(&mut state, 2.0 * DAYS_PER_YEAR, 0.0);
+        let mut state_stepped = BatteryAgingState::default();
+        for _ in 0..8 {
+            model.step(&mut state_stepped, DAYS_PER_YEAR / 4.0, 0.0);
+        }
+        assert!((state.soh - state_stepped.soh).abs() < 1e-12);
+        assert!(state.soh < 1.0);
+        assert!(state.res_factor > 1.0);
+
+        // aging is faster when hotter
+        let hot_model = BatteryAgingModel {
+            te_deg_c: 35.0,
+            ..Default::default()
+        };
+        let mut hot_state = BatteryAgingState::default();
+        hot_model.step(&mut hot_state, 2.0 * DAYS_PER_YEAR, 0.0);
+        assert!(hot_state.soh < state.soh);
+    }
+
+    #[test]
+    fn test_fleet_life() {
+        let veh = RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut fleet_life =
+            FleetLife::new(veh.clone(), vec![cyc], None, None, Some(10.0), Some(2)).unwrap();
+        fleet_life.walk().unwrap();
+
+        assert_eq!(fleet_life.history.len(), 21);
+        assert!(fleet_life.history.soh.windows(2).all(|w| w[1] <= w[0]));
+        assert!(fleet_life.state.cyc_life_frac > 0.0);
+        assert!(fleet_life.veh.val_ess_life_miles > 0.0);
+        assert!(fleet_life.veh.val_ess_life_miles < 10.0 * 12e3);
+        let veh_aged = fleet_life.state.apply(&veh).unwrap();
+        assert!(veh_aged.ess_max_kwh < veh.ess_max_kwh);
+        assert!(veh_aged.ess_round_trip_eff < veh.ess_round_trip_eff);
+    }
+}
@@ -1,4 +1,18 @@
+//! Module for calibrating vehicle model parameters against measured data
+
+#[cfg(feature = "default")]
+use argmin::core::{CostFunction, Executor, State};
+#[cfg(feature = "default")]
+use argmin::solver::neldermead::NelderMead;
+
+use crate::cycle::RustCycle;
 use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::simdrive::RustSimDrive;
+use crate::timeseries::TimeSeries;
+use crate::vehicle::RustVehicle;
 
 /// Skews the peak of a curve to a specified new x-value, redistributing other
 /// x-values linearly, preserving relative distances between peak and endpoints.
@@ -116,3 +130,558 @@ fn get_index_for_value(array: &Array1<f64>, value: f64) -> anyhow::Result<usize>
         .with_context(|| "Value not found in array.")?
         .to_owned())
 }
+
+/// Names of [CalibrationParam]s that are applied with a [RustVehicle] setter,
+/// after `set_derived`, rather than by [FieldPath]
+pub const SETTER_PARAMS: [&str; 4] = ["fc_peak_eff", "mc_peak_eff", "fc_eff_range", "mc_eff_range"];
+
+/// Cost of a parameter set for which the simulation fails, large enough to
+/// steer the optimizer away but finite so the simplex stays well defined
+const FAILED_SIM_COST: f64 = 1e12;
+
+/// Weight of the squared distance outside the bounds, in normalized
+/// coordinates, added to the cost
+const BOUNDS_PENALTY: f64 = 1e2;
+
+/// Initial simplex step, in normalized coordinates
+const SIMPLEX_STEP: f64 = 0.1;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (name, min, max, initial=None))]
+    pub fn __new__(name: String, min: f64, max: f64, initial: Option<f64>) -> anyhow::Result<Self> {
+        let mut param = Self {
+            name,
+            min,
+            max,
+            initial,
+            orphaned: false,
+        };
+        param.init()?;
+        Ok(param)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Bounded [RustVehicle] parameter fitted by a [Calibration]
+pub struct CalibrationParam {
+    /// [FieldPath] to the field, e.g. `drag_coef`, `wheel_rr_coef`, `aux_kw`,
+    /// or `ess_round_trip_eff`, or one of [SETTER_PARAMS], e.g. `fc_peak_eff`
+    pub name: String,
+    /// lower bound
+    pub min: f64,
+    /// upper bound
+    pub max: f64,
+    /// starting value, defaults to the vehicle's value clamped to the bounds
+    #[serde(default)]
+    pub initial: Option<f64>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl CalibrationParam {
+    fn is_setter(&self) -> bool {
+        SETTER_PARAMS.contains(&self.name.as_str())
+    }
+
+    /// Current value of the parameter in `veh`
+    pub fn value(&self, veh: &RustVehicle) -> anyhow::Result<f64> {
+        match self.name.as_str() {
+            "fc_peak_eff" => Ok(veh.fc_peak_eff()),
+            "mc_peak_eff" => Ok(veh.mc_peak_eff()),
+            "fc_eff_range" => veh.get_fc_eff_range(),
+            "mc_eff_range" => veh.get_mc_eff_range(),
+            name => veh
+                .get_field(name)?
+                .as_f64()
+                .with_context(|| format!("calibration parameter `{name}` is not numeric")),
+        }
+    }
+
+    fn set_value(&self, veh: &mut RustVehicle, value: f64) -> anyhow::Result<()> {
+        match self.name.as_str() {
+            "fc_peak_eff" => veh.set_fc_peak_eff(value),
+            "mc_peak_eff" => veh.set_mc_peak_eff(value),
+            "fc_eff_range" => veh.set_fc_eff_range(value)?,
+            "mc_eff_range" => veh.set_mc_eff_range(value)?,
+            name => veh.set_field(name, value)?,
+        }
+        Ok(())
+    }
+
+    /// Starting value for `veh`
+    pub fn initial_value(&self, veh: &RustVehicle) -> anyhow::Result<f64> {
+        match self.initial {
+            Some(initial) => Ok(initial),
+            None => Ok(self.value(veh)?.clamp(self.min, self.max)),
+        }
+    }
+
+    fn normalize(&self, value: f64) -> f64 {
+        (value - self.min) / (self.max - self.min)
+    }
+
+    fn denormalize(&self, u: f64) -> f64 {
+        self.min + u * (self.max - self.min)
+    }
+}
+
+impl SerdeAPI for CalibrationParam {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(!self.name.is_empty(), "calibration parameter name is empty");
+        ensure!(
+            self.min.is_finite() && self.max.is_finite() && self.min < self.max,
+            "calibration parameter `{}` has invalid bounds [{}, {}]",
+            self.name,
+            self.min,
+            self.max
+        );
+        if let Some(initial) = self.initial {
+            ensure!(
+                (self.min..=self.max).contains(&initial),
+                "calibration parameter `{}` initial value {} is outside bounds [{}, {}]",
+                self.name,
+                initial,
+                self.min,
+                self.max
+            );
+        }
+        Ok(())
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (signal, measured, weight=1.0))]
+    pub fn __new__(signal: String, measured: Vec<f64>, weight: f64) -> anyhow::Result<Self> {
+        let mut objective = Self {
+            signal,
+            measured,
+            weight,
+            orphaned: false,
+        };
+        objective.init()?;
+        Ok(objective)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Measured time series that a [Calibration] fits the model to
+pub struct CalibrationObjective {
+    /// name of the [RustSimDrive] time series, e.g. `mps_ach`, `fs_kw_out_ach`
+    /// for fuel power, or `soc`
+    pub signal: String,
+    /// measured values at each time step of the cycle, with NaN for missing
+    /// samples, which are ignored
+    pub measured: Vec<f64>,
+    /// weight of this term in the total cost
+    #[serde(default = "CalibrationObjective::default_weight")]
+    pub weight: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl CalibrationObjective {
+    fn default_weight() -> f64 {
+        1.0
+    }
+
+    /// Compares `modeled` to the measured values
+    pub fn fit(&self, modeled: &[f64]) -> anyhow::Result<ObjectiveFit> {
+        ensure!(
+            modeled.len() == self.measured.len(),
+            "signal `{}` has {} modeled values but {} measured values",
+            self.signal,
+            modeled.len(),
+            self.measured.len()
+        );
+        let residuals: Vec<f64> = modeled
+            .iter()
+            .zip(&self.measured)
+            .map(|(model, meas)| model - meas)
+            .collect();
+        let (meas, res): (Vec<f64>, Vec<f64>) = self
+            .measured
+            .iter()
+            .zip(&residuals)
+            .filter(|(meas, _)| meas.is_finite())
+            .unzip();
+        ensure!(
+            !meas.is_empty(),
+            "signal `{}` has no finite measured values",
+            self.signal
+        );
+        let n = meas.len() as f64;
+        let sse: f64 = res.iter().map(|r| r * r).sum();
+        let mean = meas.iter().sum::<f64>() / n;
+        let sst: f64 = meas.iter().map(|m| (m - mean).powi(2)).sum();
+        let range = meas.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            - meas.iter().copied().fold(f64::INFINITY, f64::min);
+        let rmse = (sse / n).sqrt();
+        Ok(ObjectiveFit {
+            signal: self.signal.clone(),
+            rmse,
+            nrmse: if range > 0.0 { rmse / range } else { rmse },
+            r_squared: if sst > 0.0 { 1.0 - sse / sst } else { f64::NAN },
+            bias: res.iter().sum::<f64>() / n,
+            residuals,
+            orphaned: false,
+        })
+    }
+}
+
+impl SerdeAPI for CalibrationObjective {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(!self.signal.is_empty(), "calibration signal name is empty");
+        ensure!(
+            self.weight.is_finite() && self.weight >= 0.0,
+            "calibration signal `{}` has invalid weight {}",
+            self.signal,
+            self.weight
+        );
+        Ok(())
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Goodness of fit of one [CalibrationObjective]
+pub struct ObjectiveFit {
+    pub signal: String,
+    /// root mean square error
+    pub rmse: f64,
+    /// `rmse` divided by the range of the measured values
+    pub nrmse: f64,
+    /// coefficient of determination, NaN if the measured values are constant
+    pub r_squared: f64,
+    /// mean of modeled minus measured
+    pub bias: f64,
+    /// modeled minus measured at each time step
+    pub residuals: Vec<f64>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for ObjectiveFit {}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (params, objectives, max_iters=100))]
+    pub fn __new__(
+        params: Vec<CalibrationParam>,
+        objectives: Vec<CalibrationObjective>,
+        max_iters: u64,
+    ) -> anyhow::Result<Self> {
+        let mut calibration = Self {
+            params,
+            objectives,
+            max_iters,
+            orphaned: false,
+        };
+        calibration.init()?;
+        Ok(calibration)
+    }
+
+    #[pyo3(name = "evaluate")]
+    /// Returns the total cost and the fit of each objective for `values` of
+    /// `params`
+    pub fn evaluate_py(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        values: Vec<f64>,
+    ) -> anyhow::Result<(f64, Vec<ObjectiveFit>)> {
+        self.evaluate(veh, cyc, &values)
+    }
+
+    #[cfg(feature = "default")]
+    #[pyo3(name = "run")]
+    /// Fits `params` of `veh` so that simulating `cyc` matches `objectives`
+    pub fn run_py(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<CalibrationResult> {
+        self.run(veh, cyc)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Fits bounded [RustVehicle] parameters to measured time series by
+/// minimizing the weighted sum of squared normalized RMSEs of the objectives
+/// with Nelder-Mead
+pub struct Calibration {
+    pub params: Vec<CalibrationParam>,
+    pub objectives: Vec<CalibrationObjective>,
+    /// maximum number of optimizer iterations
+    #[serde(default = "Calibration::default_max_iters")]
+    pub max_iters: u64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Calibration {
+    fn default_max_iters() -> u64 {
+        100
+    }
+
+    /// Returns a copy of `veh` with `values` of `params` applied
+    pub fn apply(&self, veh: &RustVehicle, values: &[f64]) -> anyhow::Result<RustVehicle> {
+        ensure!(
+            values.len() == self.params.len(),
+            "got {} values for {} calibration parameters",
+            values.len(),
+            self.params.len()
+        );
+        let mut veh = veh.clone();
+        let pairs = || self.params.iter().zip(values);
+        for (param, value) in pairs().filter(|(param, _)| !param.is_setter()) {
+            param
+                .set_value(&mut veh, *value)
+                .with_context(|| format!("failed to set `{}`", param.name))?;
+        }
+        veh.set_derived()?;
+        for (param, value) in pairs().filter(|(param, _)| param.is_setter()) {
+            param
+                .set_value(&mut veh, *value)
+                .with_context(|| format!("failed to set `{}`", param.name))?;
+        }
+        Ok(veh)
+    }
+
+    /// Returns the total cost and the fit of each objective for `values` of
+    /// `params`
+    pub fn evaluate(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        values: &[f64],
+    ) -> anyhow::Result<(f64, Vec<ObjectiveFit>)> {
+        let veh = self.apply(veh, values)?;
+        let mut sd = RustSimDrive::new(cyc.clone(), veh);
+        sd.sim_drive(None, None)?;
+        let fits = self
+            .objectives
+            .iter()
+            .map(|obj| {
+                let modeled = sd
+                    .series(&obj.signal)
+                    .with_context(|| format!("no time series `{}`", obj.signal))?;
+                obj.fit(&modeled)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let cost = self
+            .objectives
+            .iter()
+            .zip(&fits)
+            .map(|(obj, fit)| obj.weight * fit.nrmse.powi(2))
+            .sum();
+        Ok((cost, fits))
+    }
+
+    /// Fits `params` of `veh` so that simulating `cyc` matches `objectives`
+    #[cfg(feature = "default")]
+    pub fn run(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<CalibrationResult> {
+        self.validate(cyc)?;
+        let initial_values = self
+            .params
+            .iter()
+            .map(|param| param.initial_value(veh))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let (initial_cost, _) = self
+            .evaluate(veh, cyc, &initial_values)
+            .with_context(|| "simulation failed at initial parameter values")?;
+
+        let u0: Vec<f64> = self
+            .params
+            .iter()
+            .zip(&initial_values)
+            .map(|(param, value)| param.normalize(*value))
+            .collect();
+        let mut simplex = vec![Array1::from(u0.clone())];
+        for i in 0..u0.len() {
+            let mut vertex = u0.clone();
+            // step toward the interior so every vertex starts within bounds
+            vertex[i] += if u0[i] + SIMPLEX_STEP <= 1.0 {
+                SIMPLEX_STEP
+            } else {
+                -SIMPLEX_STEP
+            };
+            simplex.push(Array1::from(vertex));
+        }
+
+        let problem = CalibrationCost {
+            calibration: self,
+            veh,
+            cyc,
+        };
+        let solver = NelderMead::new(simplex);
+        let res = Executor::new(problem, solver)
+            .configure(|state| state.max_iters(self.max_iters))
+            .run()?;
+        let u_best = res
+            .state()
+            .get_best_param()
+            .with_context(|| format_dbg!("optimizer returned no parameters"))?;
+        let iters = res.state().get_iter();
+
+        let values = self.denormalize(u_best);
+        let (cost, fits) = self.evaluate(veh, cyc, &values)?;
+        Ok(CalibrationResult {
+            names: self.params.iter().map(|param| param.name.clone()).collect(),
+            veh: self.apply(veh, &values)?,
+            values,
+            initial_values,
+            cost,
+            initial_cost,
+            iters,
+            fits,
+            orphaned: false,
+        })
+    }
+
+    /// Parameter values for normalized coordinates `u`, clamped to the bounds
+    fn denormalize(&self, u: &Array1<f64>) -> Vec<f64> {
+        self.params
+            .iter()
+            .zip(u)
+            .map(|(param, u)| param.denormalize(u.clamp(0.0, 1.0)))
+            .collect()
+    }
+
+    /// Checks that every objective matches the length of `cyc`
+    pub fn validate(&self, cyc: &RustCycle) -> anyhow::Result<()> {
+        ensure!(!self.params.is_empty(), "no calibration parameters");
+        ensure!(!self.objectives.is_empty(), "no calibration objectives");
+        for obj in &self.objectives {
+            ensure!(
+                obj.measured.len() == cyc.len(),
+                "signal `{}` has {} measured values but cycle has {} time steps",
+                obj.signal,
+                obj.measured.len(),
+                cyc.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl SerdeAPI for Calibration {
+    fn init(&mut self) -> anyhow::Result<()> {
+        for param in self.params.iter_mut() {
+            param.init()?;
+        }
+        for obj in self.objectives.iter_mut() {
+            obj.init()?;
+        }
+        Ok(())
+    }
+}
+
+/// Cost of a [Calibration] in coordinates normalized to \[0, 1\] over the
+/// parameter bounds
+#[cfg(feature = "default")]
+struct CalibrationCost<'a> {
+    calibration: &'a Calibration,
+    veh: &'a RustVehicle,
+    cyc: &'a RustCycle,
+}
+
+#[cfg(feature = "default")]
+impl CostFunction for CalibrationCost<'_> {
+    type Param = Array1<f64>;
+    type Output = f64;
+
+    fn cost(&self, u: &Self::Param) -> anyhow::Result<Self::Output> {
+        // Nelder-Mead is unbounded, so evaluate at the nearest point within
+        // bounds and penalize the distance outside them
+        let outside: f64 = u.iter().map(|u| (u - u.clamp(0.0, 1.0)).powi(2)).sum();
+        let values = self.calibration.denormalize(u);
+        let cost = match self.calibration.evaluate(self.veh, self.cyc, &values) {
+            Ok((cost, _)) if cost.is_finite() => cost,
+            _ => FAILED_SIM_COST,
+        };
+        Ok(cost + BOUNDS_PENALTY * outside)
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Outcome of [Calibration::run]
+pub struct CalibrationResult {
+    /// names of the fitted parameters
+    pub names: Vec<String>,
+    /// fitted parameter values
+    pub values: Vec<f64>,
+    /// parameter values the optimizer started from
+    pub initial_values: Vec<f64>,
+    /// total cost at `values`
+    pub cost: f64,
+    /// total cost at `initial_values`
+    pub initial_cost: f64,
+    /// number of optimizer iterations
+    pub iters: u64,
+    /// goodness of fit of each objective at `values`
+    pub fits: Vec<ObjectiveFit>,
+    /// vehicle with the fitted parameter values
+    #[api(has_orphaned)]
+    pub veh: RustVehicle,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for CalibrationResult {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drag_calibration(measured: Vec<f64>) -> Calibration {
+        let mut calibration = Calibration {
+            params: vec![CalibrationParam {
+                name: "drag_coef".into(),
+                min: 0.2,
+                max: 0.5,
+                initial: None,
+                orphaned: false,
+            }],
+            objectives: vec![CalibrationObjective {
+                signal: "fs_kw_out_ach".into(),
+                measured,
+                weight: 1.0,
+                orphaned: false,
+            }],
+            max_iters: 100,
+            orphaned: false,
+        };
+        calibration.init().unwrap();
+        calibration
+    }
+
+    #[test]
+    fn test_calibration_recovers_drag_coef() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("hwfet.csv", false).unwrap();
+        let true_drag = 0.37;
+        let mut truth = veh.clone();
+        truth.drag_coef = true_drag;
+        truth.set_derived().unwrap();
+        let mut sd = RustSimDrive::new(cyc.clone(), truth);
+        sd.sim_drive(None, None).unwrap();
+
+        let calibration = drag_calibration(sd.fs_kw_out_ach.to_vec());
+        let res = calibration.run(&veh, &cyc).unwrap();
+        assert!(
+            res.values[0].approx_eq(&true_drag, 0.01 * true_drag),
+            "{}",
+            res.values[0]
+        );
+        assert!(res.cost < res.initial_cost);
+        assert!(res.fits[0].r_squared > 0.999);
+        assert!(res.veh.drag_coef.approx_eq(&res.values[0], 1e-12));
+    }
+
+    #[test]
+    fn test_calibration_rejects_wrong_length() {
+        let cyc = RustCycle::from_resource("hwfet.csv", false).unwrap();
+        let calibration = drag_calibration(vec![0.0; cyc.len() - 1]);
+        assert!(calibration.run(&RustVehicle::mock_vehicle(), &cyc).is_err());
+
+        let mut inverted = calibration.params[0].clone();
+        (inverted.min, inverted.max) = (0.5, 0.2);
+        assert!(inverted.init().is_err());
+    }
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 // local
 use crate::imports::*;
 use crate::params::*;
-use crate::proc_macros::add_pyo3_api;
+use crate::proc_macros::{add_pyo3_api, FieldPath};
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
 use crate::utils::*;
@@ -204,7 +204,7 @@ pub fn to_microtrips(cycle: &RustCycle, stop_speed_m_per_s: Option<f64>) -> Vec<
     let vs = cycle.mps.to_vec();
     let gs = cycle.grade.to_vec();
     let rs = cycle.road_type.to_vec();
-    let mut mt_ts = Vec::new();
+    let mut mt_ts: Vec<f64> = Vec::new();
     let mut mt_vs = Vec::new();
     let mut mt_gs = Vec::new();
     let mut mt_rs = Vec::new();
@@ -474,7 +474,6 @@ impl RustCycleCache {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[add_pyo3_api(
     pub fn __len__(&self) -> usize {
         self.len()
@@ -629,6 +628,7 @@ impl RustCycleCache {
         RustCycle::list_resources()
     }
 )]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, FieldPath)]
 /// Struct for containing:
 /// * time_s, cycle time, $s$
 /// * mps, vehicle speed, $\frac{m}{s}$
@@ -668,11 +668,13 @@ impl SerdeAPI for RustCycle {
 
     fn to_writer<W: std::io::Write>(&self, mut wtr: W, format: &str) -> anyhow::Result<()> {
         match format.trim_start_matches('.').to_lowercase().as_str() {
-            "yaml" | "yml" => serde_yaml::to_writer(wtr, self)?,
-            "json" => serde_json::to_writer(wtr, self)?,
-            "toml" => wtr.write_all(self.to_toml()?.as_bytes())?,
+            "yaml" | "yml" => serde_yaml::to_writer(wtr, self).map_err(FastSimError::format)?,
+            "json" => serde_json::to_writer(wtr, self).map_err(FastSimError::format)?,
+            "toml" => wtr
+                .write_all(self.to_toml()?.as_bytes())
+                .map_err(FastSimError::Io)?,
             #[cfg(feature = "bincode")]
-            "bin" => bincode::serialize_into(wtr, self)?,
+            "bin" => bincode::serialize_into(wtr, self).map_err(FastSimError::format)?,
             "csv" => {
                 let mut wtr = csv::Writer::from_writer(wtr);
                 for i in 0..self.len() {
@@ -681,14 +683,15 @@ impl SerdeAPI for RustCycle {
                         mps: self.mps[i],
                         grade: Some(self.grade[i]),
                         road_type: Some(self.road_type[i]),
-                    })?;
+                    })
+                    .map_err(FastSimError::format)?;
                 }
-                wtr.flush()?
+                wtr.flush().map_err(FastSimError::Io)?
             }
-            _ => bail!(
+            _ => bail!(FastSimError::Format(format!(
                 "Unsupported format {format:?}, must be one of {:?}",
                 Self::ACCEPTED_BYTE_FORMATS
-            ),
+            ))),
         }
         Ok(())
     }
@@ -701,10 +704,10 @@ impl SerdeAPI for RustCycle {
                 "toml" => self.to_toml()?,
                 "csv" => self.to_csv()?,
                 _ => {
-                    bail!(
+                    bail!(FastSimError::Format(format!(
                         "Unsupported format {format:?}, must be one of {:?}",
                         Self::ACCEPTED_STR_FORMATS
-                    )
+                    )))
                 }
             },
         )
@@ -719,10 +722,10 @@ impl SerdeAPI for RustCycle {
                 "json" => Self::from_json(contents, skip_init)?,
                 "toml" => Self::from_toml(contents, skip_init)?,
                 "csv" => Self::from_reader(contents.as_ref().as_bytes(), "csv", skip_init)?,
-                _ => bail!(
+                _ => bail!(FastSimError::Format(format!(
                     "Unsupported format {format:?}, must be one of {:?}",
                     Self::ACCEPTED_STR_FORMATS
-                ),
+                ))),
             },
         )
     }
@@ -733,29 +736,29 @@ impl SerdeAPI for RustCycle {
         skip_init: bool,
     ) -> anyhow::Result<Self> {
         let mut deserialized = match format.trim_start_matches('.').to_lowercase().as_str() {
-            "yaml" | "yml" => serde_yaml::from_reader(rdr)?,
-            "json" => serde_json::from_reader(rdr)?,
+            "yaml" | "yml" => serde_yaml::from_reader(rdr).map_err(FastSimError::format)?,
+            "json" => serde_json::from_reader(rdr).map_err(FastSimError::format)?,
             "toml" => {
                 let mut buf = String::new();
-                rdr.read_to_string(&mut buf)?;
+                rdr.read_to_string(&mut buf).map_err(FastSimError::Io)?;
                 Self::from_toml(buf, skip_init)?
             }
             #[cfg(feature = "bincode")]
-            "bin" => bincode::deserialize_from(rdr)?,
+            "bin" => bincode::deserialize_from(rdr).map_err(FastSimError::format)?,
             "csv" => {
                 // Create empty cycle to be populated
                 let mut cyc = Self::default();
                 let mut rdr = csv::Reader::from_reader(rdr);
                 for result in rdr.deserialize() {
-                    cyc.push(result?);
+                    cyc.push(result.map_err(FastSimError::format)?);
                 }
                 cyc
             }
             _ => {
-                bail!(
+                bail!(FastSimError::Format(format!(
                     "Unsupported format {format:?}, must be one of {:?}",
                     Self::ACCEPTED_BYTE_FORMATS
-                )
+                )))
             }
         };
         if !skip_init {
@@ -818,15 +821,23 @@ impl From<RustCycle> for HashMap<String, Vec<f64>> {
 /// pure Rust methods that need to be separate due to pymethods incompatibility
 impl RustCycle {
     fn init_checks(&self) -> anyhow::Result<()> {
-        ensure!(!self.is_empty(), "Deserialized cycle is empty");
-        ensure!(self.is_sorted(), "Deserialized cycle is not sorted in time");
+        ensure!(
+            !self.is_empty(),
+            FastSimError::InvalidCycle("Deserialized cycle is empty".into())
+        );
+        ensure!(
+            self.is_sorted(),
+            FastSimError::InvalidCycle("Deserialized cycle is not sorted in time".into())
+        );
         ensure!(
             self.are_fields_equal_length(),
-            "Deserialized cycle has unequal field lengths\ntime_s: {}\nmps: {}\ngrade: {}\nroad_type: {}",
-            self.time_s.len(),
-            self.mps.len(),
-            self.grade.len(),
-            self.road_type.len(),
+            FastSimError::InvalidCycle(format!(
+                "Deserialized cycle has unequal field lengths\ntime_s: {}\nmps: {}\ngrade: {}\nroad_type: {}",
+                self.time_s.len(),
+                self.mps.len(),
+                self.grade.len(),
+                self.road_type.len(),
+            ))
         );
         Ok(())
     }
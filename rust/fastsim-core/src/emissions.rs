@@ -0,0 +1,248 @@
+//! Module containing a tailpipe emissions model for CO2 and criteria
+//! pollutants (NOx, CO, HC, PM)
+
+use crate::fuel::Fuel;
+use crate::imports::*;
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+/// Ratio of CO2 molar mass to carbon molar mass
+pub const CO2_PER_C: f64 = 44.009 / 12.011;
+
+#[add_pyo3_api(
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+
+    #[pyo3(name = "cat_conv_frac")]
+    pub fn cat_conv_frac_py(&self, cat_te_deg_c: f64, cat_te_lightoff_deg_c: f64) -> f64 {
+        self.cat_conv_frac(cat_te_deg_c, cat_te_lightoff_deg_c)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq, FieldPath)]
+/// Tailpipe emissions model.  CO2 is calculated from fuel consumption and
+/// carbon content of `RustVehicle::fuel_props`.  Engine-out NOx, CO, HC, and PM are calculated from
+/// emission indices (mass of pollutant per mass of fuel) that depend on
+/// fuel converter output power fraction, and tailpipe emissions are
+/// engine-out emissions reduced by the aftertreatment conversion efficiency.
+///
+/// `RustSimDrive` assumes fully lit-off aftertreatment.  `SimDriveHot` scales
+/// conversion efficiency by a logistic light-off curve of catalyst
+/// temperature if `VehicleThermal::cat_model` is internal, capturing
+/// cold-start emissions.
+pub struct EmissionsModel {
+    /// fuel converter output power fraction breakpoints for emission indices
+    pub fc_pwr_out_frac: Vec<f64>,
+    /// engine-out NOx emission index \[g/kg fuel\] at each of `fc_pwr_out_frac`
+    pub eo_nox_g_per_kg_fuel: Vec<f64>,
+    /// engine-out CO emission index \[g/kg fuel\] at each of `fc_pwr_out_frac`
+    pub eo_co_g_per_kg_fuel: Vec<f64>,
+    /// engine-out HC emission index \[g/kg fuel\] at each of `fc_pwr_out_frac`
+    pub eo_hc_g_per_kg_fuel: Vec<f64>,
+    /// engine-out PM emission index \[g/kg fuel\] at each of `fc_pwr_out_frac`
+    pub eo_pm_g_per_kg_fuel: Vec<f64>,
+    /// NOx conversion efficiency of lit-off aftertreatment
+    pub cat_nox_eff: f64,
+    /// CO conversion efficiency of lit-off aftertreatment
+    pub cat_co_eff: f64,
+    /// HC conversion efficiency of lit-off aftertreatment
+    pub cat_hc_eff: f64,
+    /// PM filtration efficiency of lit-off aftertreatment
+    pub cat_pm_eff: f64,
+    /// temperature \[°C\] over which conversion efficiency rises from ~27% to
+    /// ~73% of lit-off value, centered on `VehicleThermal::cat_te_lightoff_deg_c`
+    pub cat_lightoff_width_deg_c: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for EmissionsModel {
+    /// Gasoline spark-ignition engine with three-way catalyst
+    fn default() -> Self {
+        Self {
+            fc_pwr_out_frac: vec![0.0, 0.25, 0.5, 0.75, 1.0],
+            eo_nox_g_per_kg_fuel: vec![5.0, 12.0, 20.0, 25.0, 20.0],
+            eo_co_g_per_kg_fuel: vec![40.0, 20.0, 20.0, 40.0, 150.0],
+            eo_hc_g_per_kg_fuel: vec![8.0, 4.0, 3.0, 3.0, 6.0],
+            eo_pm_g_per_kg_fuel: vec![0.02, 0.01, 0.01, 0.02, 0.08],
+            cat_nox_eff: 0.97,
+            cat_co_eff: 0.98,
+            cat_hc_eff: 0.97,
+            cat_pm_eff: 0.0,
+            cat_lightoff_width_deg_c: 25.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for EmissionsModel {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.fc_pwr_out_frac.len() >= 2 && self.fc_pwr_out_frac.windows(2).all(|w| w[0] < w[1]),
+            "{}\n`fc_pwr_out_frac` must have at least 2 elements and be strictly increasing",
+            format_dbg!(&self.fc_pwr_out_frac)
+        );
+        for (name, eis) in [
+            ("eo_nox_g_per_kg_fuel", &self.eo_nox_g_per_kg_fuel),
+            ("eo_co_g_per_kg_fuel", &self.eo_co_g_per_kg_fuel),
+            ("eo_hc_g_per_kg_fuel", &self.eo_hc_g_per_kg_fuel),
+            ("eo_pm_g_per_kg_fuel", &self.eo_pm_g_per_kg_fuel),
+        ] {
+            ensure!(
+                eis.len() == self.fc_pwr_out_frac.len(),
+                "{}\n`{name}` must have the same length as `fc_pwr_out_frac`",
+                format_dbg!((eis.len(), self.fc_pwr_out_frac.len()))
+            );
+            ensure!(
+                eis.iter().all(|ei| *ei >= 0.0),
+                "{}\n`{name}` must be non-negative",
+                format_dbg!(eis)
+            );
+        }
+        for (name, eff) in [
+            ("cat_nox_eff", self.cat_nox_eff),
+            ("cat_co_eff", self.cat_co_eff),
+            ("cat_hc_eff", self.cat_hc_eff),
+            ("cat_pm_eff", self.cat_pm_eff),
+        ] {
+            ensure!(
+                (0.0..=1.0).contains(&eff),
+                "{}\n`{name}` must be between 0 and 1",
+                format_dbg!(eff)
+            );
+        }
+        ensure!(
+            self.cat_lightoff_width_deg_c > 0.0,
+            "{}",
+            format_dbg!(self.cat_lightoff_width_deg_c)
+        );
+        Ok(())
+    }
+}
+
+/// Emission rates at a single time step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EmissionsStep {
+    /// CO2 emission rate \[g/s\]
+    pub co2_g_per_s: f64,
+    /// engine-out NOx, CO, HC, and PM emission rates \[g/s\]
+    pub eo_g_per_s: [f64; 4],
+    /// tailpipe NOx, CO, HC, and PM emission rates \[g/s\]
+    pub tp_g_per_s: [f64; 4],
+}
+
+impl std::ops::AddAssign for EmissionsStep {
+    fn add_assign(&mut self, other: Self) {
+        self.co2_g_per_s += other.co2_g_per_s;
+        for k in 0..4 {
+            self.eo_g_per_s[k] += other.eo_g_per_s[k];
+            self.tp_g_per_s[k] += other.tp_g_per_s[k];
+        }
+    }
+}
+
+impl EmissionsModel {
+    /// Fraction of lit-off conversion efficiency achieved at `cat_te_deg_c`
+    pub fn cat_conv_frac(&self, cat_te_deg_c: f64, cat_te_lightoff_deg_c: f64) -> f64 {
+        1.0 / (1.0
+            + (-(cat_te_deg_c - cat_te_lightoff_deg_c) * 2.0 / self.cat_lightoff_width_deg_c).exp())
+    }
+
+    /// Emission rates for fuel `fuel` consumed at `fuel_kw` at fuel converter
+    /// output power fraction `fc_pwr_out_frac` and aftertreatment conversion
+    /// fraction `cat_conv_frac`
+    pub fn solve(
+        &self,
+        fuel: &Fuel,
+        fuel_kw: f64,
+        fc_pwr_out_frac: f64,
+        cat_conv_frac: f64,
+    ) -> EmissionsStep {
+        if fuel_kw <= 0.0 {
+            return EmissionsStep::default();
+        }
+        let fuel_kg_per_s = fuel.kg_from_kj(fuel_kw);
+        let mut step = EmissionsStep {
+            co2_g_per_s: fuel_kg_per_s * 1e3 * fuel.carbon_frac * CO2_PER_C,
+            ..Default::default()
+        };
+        for (k, (eis, cat_eff)) in [
+            (&self.eo_nox_g_per_kg_fuel, self.cat_nox_eff),
+            (&self.eo_co_g_per_kg_fuel, self.cat_co_eff),
+            (&self.eo_hc_g_per_kg_fuel, self.cat_hc_eff),
+            (&self.eo_pm_g_per_kg_fuel, self.cat_pm_eff),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let ei = interpolate_clamped(fc_pwr_out_frac, &self.fc_pwr_out_frac, eis);
+            step.eo_g_per_s[k] = ei * fuel_kg_per_s;
+            step.tp_g_per_s[k] = step.eo_g_per_s[k] * (1.0 - cat_eff * cat_conv_frac);
+        }
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::simdrive::RustSimDrive;
+    use crate::thermal::{SimDriveHot, ThermalState};
+    use crate::vehicle::RustVehicle;
+    use crate::vehicle_thermal::{ComponentModelTypes, VehicleThermal};
+
+    #[test]
+    fn test_emissions_step() {
+        let em = EmissionsModel::default();
+        let fuel = Fuel::default();
+        // 1 g/s of fuel
+        let fuel_kw = fuel.lhv_kj_per_kg * 1e-3;
+        let step = em.solve(&fuel, fuel_kw, 0.5, 1.0);
+        assert!((step.co2_g_per_s - 0.866 * CO2_PER_C).abs() < 1e-9);
+        assert!((step.eo_g_per_s[0] - 20e-3).abs() < 1e-12);
+        assert!((step.tp_g_per_s[0] - 20e-3 * 0.03).abs() < 1e-12);
+        // no conversion when catalyst is cold
+        let step_cold = em.solve(&fuel, fuel_kw, 0.5, 0.0);
+        assert_eq!(step_cold.tp_g_per_s, step_cold.eo_g_per_s);
+        assert_eq!(em.solve(&fuel, 0.0, 0.5, 1.0), EmissionsStep::default());
+        assert!((em.cat_conv_frac(400.0, 400.0) - 0.5).abs() < 1e-12);
+        assert!(em.cat_conv_frac(25.0, 400.0) < 1e-6);
+    }
+
+    #[test]
+    fn test_sim_drive_emissions() {
+        let mut veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+
+        let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.sim_drive(None, None).unwrap();
+        assert_eq!(sd.co2_g, 0.0);
+
+        veh.emissions = Some(EmissionsModel::default());
+        let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.sim_drive(None, None).unwrap();
+        // CO2 from fuel mass and carbon content
+        assert!((sd.co2_g - sd.fuel_kg * 1e3 * 0.866 * CO2_PER_C).abs() / sd.co2_g < 1e-9);
+        assert!(sd.tp_nox_g > 0.0 && sd.tp_co_g > 0.0 && sd.tp_hc_g > 0.0);
+        assert!((sd.eo_nox_g - (&sd.eo_nox_g_per_s * &sd.cyc.dt_s()).sum()).abs() < 1e-9);
+        assert!(sd.tp_nox_g < sd.eo_nox_g && sd.tp_co_g < sd.eo_co_g);
+        assert!(sd.tp_hc_g < sd.eo_hc_g);
+        // no particulate filter by default
+        assert_eq!(sd.tp_pm_g, sd.eo_pm_g);
+
+        // cold start with simulated catalyst temperature
+        let vehthrm = VehicleThermal {
+            cat_model: ComponentModelTypes::Internal,
+            ..Default::default()
+        };
+        let mut sdh = SimDriveHot::new(cyc, veh, vehthrm, Some(ThermalState::default()), None);
+        sdh.sim_drive(None, None).unwrap();
+        assert!(sdh.sd.cat_conv_frac[1] < 0.01);
+        assert!(sdh.sd.tp_hc_g > sd.tp_hc_g);
+        assert!(sdh.sd.tp_co_g > sd.tp_co_g);
+    }
+}
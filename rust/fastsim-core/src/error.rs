@@ -0,0 +1,270 @@
+//! Structured error types for fastsim-core.
+//!
+//! Public functions still return [`anyhow::Result`], but failures that callers
+//! may want to handle differently are raised as [`FastSimError`] variants so
+//! that they can be recovered with [`anyhow::Error::downcast_ref`], even after
+//! context has been attached.  The pyo3 layer maps each variant to a distinct
+//! Python exception class (see [`pyerr`]).
+
+use std::fmt::Display;
+
+/// Categories of errors raised by fastsim-core
+#[derive(Debug, thiserror::Error)]
+pub enum FastSimError {
+    /// Achieved speed trace deviates from the prescribed cycle by more than the
+    /// `trace_miss_*_tol` tolerances in [RustSimDriveParams](crate::simdrive::RustSimDriveParams).
+    /// Only raised when `sim_params.error_on_trace_miss` is true.
+    #[error(
+        "trace miss, worst at time step {i}: speed error {speed_mps:.5} m/s, \
+        distance fraction {dist_frac:.5}, time fraction {time_frac:.5}"
+    )]
+    TraceMiss {
+        /// time step with the largest speed deviation
+        i: usize,
+        /// maximum absolute speed deviation [m/s]
+        speed_mps: f64,
+        /// fractional error in distance traveled
+        dist_frac: f64,
+        /// fractional error in elapsed time
+        time_frac: f64,
+    },
+    /// Energy audit error exceeds `sim_params.energy_audit_error_tol`.  Only
+    /// raised when `sim_params.error_on_energy_audit` is true.
+    #[error("energy audit failed: error of {error:.5} exceeds tolerance of {tol:.5}")]
+    EnergyAudit {
+        /// fractional energy audit error
+        error: f64,
+        /// allowed tolerance
+        tol: f64,
+    },
+    /// Vehicle parameters are invalid or inconsistent
+    #[error("invalid vehicle: {0}")]
+    InvalidVehicle(String),
+    /// Drive cycle is invalid
+    #[error("invalid cycle: {0}")]
+    InvalidCycle(String),
+    /// Unspecified failure while solving time step `i`
+    #[error("simulation failed at time step {i}")]
+    SimDriveStep {
+        /// index of time step
+        i: usize,
+        /// underlying error
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    /// NaN, infinite, or impossible negative value found by the diagnostics
+    /// enabled with `sim_params.diagnostics`
+    #[error("diagnostics fault: {0}")]
+    SimFault(crate::simdrive::diagnostics::SimFault),
+    /// File system or other I/O failure
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Failure to (de)serialize, or unsupported format
+    #[error("format error: {0}")]
+    Format(String),
+}
+
+impl FastSimError {
+    /// Wraps an error raised while solving time step `i`, keeping it as-is if
+    /// it is already a [`FastSimError`]
+    pub fn at_step(i: usize, err: anyhow::Error) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => err,
+            Err(err) => Self::SimDriveStep {
+                i,
+                source: err.into(),
+            },
+        }
+    }
+
+    /// Converts any displayable (de)serialization error into [`FastSimError::Format`]
+    pub fn format<E: Display>(err: E) -> Self {
+        Self::Format(err.to_string())
+    }
+
+    /// Converts any error raised while validating a vehicle into
+    /// [`FastSimError::InvalidVehicle`], keeping it as-is if it is already a
+    /// [`FastSimError`]
+    pub fn invalid_vehicle(err: anyhow::Error) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => err,
+            Err(err) => Self::InvalidVehicle(format!("{err:#}")),
+        }
+    }
+}
+
+/// Python exception classes corresponding to [`FastSimError`] variants
+#[cfg(feature = "pyo3")]
+pub mod pyerr {
+    use super::FastSimError as RustFastSimError;
+    use crate::pyo3imports::*;
+    use pyo3::create_exception;
+
+    create_exception!(
+        fastsimrust,
+        FastSimError,
+        PyRuntimeError,
+        "Base class for errors raised by fastsim-core simulations"
+    );
+    create_exception!(
+        fastsimrust,
+        TraceMissError,
+        FastSimError,
+        "Achieved speed trace deviates from the prescribed cycle beyond tolerance"
+    );
+    create_exception!(
+        fastsimrust,
+        EnergyAuditError,
+        FastSimError,
+        "Energy audit error exceeds tolerance"
+    );
+    create_exception!(
+        fastsimrust,
+        InvalidVehicleError,
+        FastSimError,
+        "Vehicle parameters are invalid or inconsistent"
+    );
+    create_exception!(
+        fastsimrust,
+        InvalidCycleError,
+        FastSimError,
+        "Drive cycle is invalid"
+    );
+    create_exception!(
+        fastsimrust,
+        SimDriveStepError,
+        FastSimError,
+        "Unspecified failure while solving a time step"
+    );
+    create_exception!(
+        fastsimrust,
+        SimFaultError,
+        FastSimError,
+        "NaN, infinite, or impossible negative value found by simulation diagnostics"
+    );
+    create_exception!(
+        fastsimrust,
+        FastSimIOError,
+        PyIOError,
+        "File I/O or (de)serialization failure"
+    );
+
+    fn to_pyerr_or(err: anyhow::Error, fallback: fn(String) -> PyErr) -> PyErr {
+        let msg = format!("{err:?}");
+        match err.downcast_ref::<RustFastSimError>() {
+            Some(RustFastSimError::TraceMiss { .. }) => TraceMissError::new_err(msg),
+            Some(RustFastSimError::EnergyAudit { .. }) => EnergyAuditError::new_err(msg),
+            Some(RustFastSimError::InvalidVehicle(_)) => InvalidVehicleError::new_err(msg),
+            Some(RustFastSimError::InvalidCycle(_)) => InvalidCycleError::new_err(msg),
+            Some(RustFastSimError::SimDriveStep { .. }) => SimDriveStepError::new_err(msg),
+            Some(RustFastSimError::SimFault(_)) => SimFaultError::new_err(msg),
+            Some(RustFastSimError::Io(_) | RustFastSimError::Format(_)) => {
+                FastSimIOError::new_err(msg)
+            }
+            None => match err.downcast::<PyErr>() {
+                Ok(err) => err,
+                Err(_) => fallback(msg),
+            },
+        }
+    }
+
+    /// Maps an error to the matching Python exception class, falling back to
+    /// `FastSimError` for uncategorized errors
+    pub fn to_pyerr(err: anyhow::Error) -> PyErr {
+        to_pyerr_or(err, FastSimError::new_err)
+    }
+
+    /// Maps an error to the matching Python exception class, falling back to
+    /// `FastSimIOError` for uncategorized errors
+    pub fn to_py_io_err(err: anyhow::Error) -> PyErr {
+        to_pyerr_or(err, FastSimIOError::new_err)
+    }
+
+    /// Adds the exception classes to the Python module
+    pub fn register(py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
+        m.add("FastSimError", py.get_type::<FastSimError>())?;
+        m.add("TraceMissError", py.get_type::<TraceMissError>())?;
+        m.add("EnergyAuditError", py.get_type::<EnergyAuditError>())?;
+        m.add("InvalidVehicleError", py.get_type::<InvalidVehicleError>())?;
+        m.add("InvalidCycleError", py.get_type::<InvalidCycleError>())?;
+        m.add("SimDriveStepError", py.get_type::<SimDriveStepError>())?;
+        m.add("SimFaultError", py.get_type::<SimFaultError>())?;
+        m.add("FastSimIOError", py.get_type::<FastSimIOError>())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::imports::*;
+    use crate::simdrive::RustSimDrive;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_trace_miss_and_energy_audit_errors() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut veh = RustVehicle::mock_vehicle();
+        // too little power to follow the trace
+        veh.fc_max_kw = 20.0;
+        veh.set_derived().unwrap();
+
+        // default behavior only flags the trace miss
+        let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.sim_drive(None, None).unwrap();
+        assert!(sd.trace_miss);
+
+        sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.sim_params.error_on_trace_miss = true;
+        let err = sd.sim_drive(None, None).unwrap_err();
+        match err.downcast_ref::<FastSimError>() {
+            Some(FastSimError::TraceMiss { i, speed_mps, .. }) => {
+                assert!(*i > 0 && *i < cyc.len());
+                assert!(*speed_mps > sd.sim_params.trace_miss_speed_mps_tol);
+            }
+            _ => panic!("expected `FastSimError::TraceMiss`, got {err:?}"),
+        }
+
+        sd = RustSimDrive::new(cyc, veh);
+        sd.sim_params.error_on_energy_audit = true;
+        sd.sim_params.energy_audit_error_tol = 0.0;
+        let err = sd.sim_drive(None, None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::EnergyAudit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_input_errors() {
+        let mut veh = RustVehicle::mock_vehicle();
+        // conventional vehicles without stop/start must not have a motor
+        veh.mc_max_kw = 10.0;
+        let err = veh.set_derived().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::InvalidVehicle(_))
+        ));
+
+        let err = RustCycle::from_json(r#"{"name": "empty"}"#, false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::Format(_))
+        ));
+        let err = RustCycle::from_file("does_not_exist.csv", false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::Io(_))
+        ));
+        let err = RustCycle::from_str(
+            "time_s,mps,grade,road_type\n1,0,0,0\n0,0,0,0\n",
+            "csv",
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::InvalidCycle(_))
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+//! Module containing an equivalent-circuit (Rint or Thevenin) traction battery
+//! model with state-of-charge-dependent open circuit voltage and resistance
+
+use crate::imports::*;
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+#[add_pyo3_api(
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+
+    #[getter]
+    pub fn get_is_thevenin(&self) -> bool {
+        self.is_thevenin()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq, FieldPath)]
+/// Equivalent-circuit traction battery model.  Cell parameters are scaled to
+/// the pack by `n_series` and `n_parallel`.  If `r1_cell_ohm` is empty, the
+/// model is a simple internal resistance (Rint) model; otherwise, a single RC
+/// branch is added in series (Thevenin model).
+///
+/// When assigned to `RustVehicle::ess_circuit`, battery losses are computed
+/// from the circuit rather than `ess_round_trip_eff`, and battery power limits
+/// are derived from `v_cell_min` and `v_cell_max` rather than `ess_max_kw`.
+pub struct EssCircuitModel {
+    /// state of charge breakpoints for `ocv_cell_v`, `r0_cell_ohm`, and `r1_cell_ohm`
+    pub soc_bins: Vec<f64>,
+    /// cell open circuit voltage \[V\] at each of `soc_bins`
+    pub ocv_cell_v: Vec<f64>,
+    /// cell series resistance \[Ω\] at `te_ref_deg_c` at each of `soc_bins`
+    pub r0_cell_ohm: Vec<f64>,
+    /// cell RC branch resistance \[Ω\] at `te_ref_deg_c` at each of
+    /// `soc_bins`, empty for Rint model
+    #[serde(default)]
+    pub r1_cell_ohm: Vec<f64>,
+    /// cell RC branch capacitance \[F\]
+    #[serde(default)]
+    pub c1_cell_f: f64,
+    /// number of cells in series
+    pub n_series: u32,
+    /// number of cells in parallel
+    pub n_parallel: u32,
+    /// minimum cell terminal voltage \[V\]
+    pub v_cell_min: f64,
+    /// maximum cell terminal voltage \[V\]
+    pub v_cell_max: f64,
+    /// reference temperature for resistances \[°C\], also used as battery
+    /// temperature when not simulated by `SimDriveHot`
+    pub te_ref_deg_c: f64,
+    /// Arrhenius coefficient \[K\] for temperature dependence of resistances,
+    /// `R = R_ref * exp(res_arrhenius_te_k * (1 / T - 1 / T_ref))`
+    pub res_arrhenius_te_k: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for EssCircuitModel {
+    /// Generic lithium-ion NMC cell in a 96s1p pack
+    fn default() -> Self {
+        Self {
+            soc_bins: vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0],
+            ocv_cell_v: vec![
+                3.0, 3.45, 3.55, 3.6, 3.65, 3.7, 3.78, 3.87, 3.95, 4.05, 4.15,
+            ],
+            r0_cell_ohm: vec![
+                3.0e-3, 2.4e-3, 2.1e-3, 2.0e-3, 2.0e-3, 2.0e-3, 2.0e-3, 2.0e-3, 2.0e-3, 2.1e-3,
+                2.2e-3,
+            ],
+            r1_cell_ohm: vec![
+                1.5e-3, 1.2e-3, 1.0e-3, 1.0e-3, 1.0e-3, 1.0e-3, 1.0e-3, 1.0e-3, 1.0e-3, 1.0e-3,
+                1.1e-3,
+            ],
+            c1_cell_f: 2.0e4,
+            n_series: 96,
+            n_parallel: 1,
+            v_cell_min: 2.8,
+            v_cell_max: 4.2,
+            te_ref_deg_c: 25.0,
+            res_arrhenius_te_k: 3.0e3,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for EssCircuitModel {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.soc_bins.len() >= 2,
+            "{}\n`soc_bins` must have at least 2 elements",
+            format_dbg!(self.soc_bins.len())
+        );
+        ensure!(
+            self.soc_bins.windows(2).all(|w| w[0] < w[1]),
+            "{}\n`soc_bins` must be strictly increasing",
+            format_dbg!(&self.soc_bins)
+        );
+        ensure!(
+            self.ocv_cell_v.len() == self.soc_bins.len()
+                && self.r0_cell_ohm.len() == self.soc_bins.len(),
+            "{}\n`ocv_cell_v` and `r0_cell_ohm` must have the same length as `soc_bins`",
+            format_dbg!((
+                self.ocv_cell_v.len(),
+                self.r0_cell_ohm.len(),
+                self.soc_bins.len()
+            ))
+        );
+        ensure!(
+            self.r0_cell_ohm.iter().all(|r| *r > 0.0),
+            "{}\n`r0_cell_ohm` must be positive",
+            format_dbg!(&self.r0_cell_ohm)
+        );
+        if self.is_thevenin() {
+            ensure!(
+                self.r1_cell_ohm.len() == self.soc_bins.len(),
+                "{}\n`r1_cell_ohm` must be empty or have the same length as `soc_bins`",
+                format_dbg!((self.r1_cell_ohm.len(), self.soc_bins.len()))
+            );
+            ensure!(
+                self.r1_cell_ohm.iter().all(|r| *r > 0.0) && self.c1_cell_f > 0.0,
+                "{}\n`r1_cell_ohm` and `c1_cell_f` must be positive",
+                format_dbg!((&self.r1_cell_ohm, self.c1_cell_f))
+            );
+        }
+        ensure!(
+            self.n_series > 0 && self.n_parallel > 0,
+            "{}",
+            format_dbg!((self.n_series, self.n_parallel))
+        );
+        ensure!(
+            self.v_cell_min < self.v_cell_max,
+            "{}",
+            format_dbg!((self.v_cell_min, self.v_cell_max))
+        );
+        Ok(())
+    }
+}
+
+/// Result of solving the equivalent circuit for one time step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EssCircuitStep {
+    /// pack current \[A\], positive when discharging
+    pub current_a: f64,
+    /// pack terminal voltage \[V\]
+    pub voltage_v: f64,
+    /// pack RC branch voltage \[V\] at end of time step
+    pub v_rc_v: f64,
+    /// power drawn from (positive) or stored in (negative) battery chemistry \[kW\]
+    pub chem_kw: f64,
+    /// power dissipated in battery resistances plus power stored in RC branch
+    /// capacitance \[kW\], may be briefly negative as RC branch discharges
+    pub loss_kw: f64,
+}
+
+impl EssCircuitModel {
+    /// Returns true if model has an RC branch
+    pub fn is_thevenin(&self) -> bool {
+        !self.r1_cell_ohm.is_empty()
+    }
+
+    /// Linearly interpolates `y` over `soc_bins`, holding the end values
+    /// outside of them
+    fn interp(&self, soc: f64, y: &[f64]) -> f64 {
+        let bins = &self.soc_bins;
+        let soc = soc.clamp(bins[0], bins[bins.len() - 1]);
+        // `soc_bins` is strictly increasing, as checked by `init`
+        let i = bins
+            .partition_point(|bin| *bin <= soc)
+            .clamp(1, bins.len() - 1)
+            - 1;
+        y[i] + (soc - bins[i]) / (bins[i + 1] - bins[i]) * (y[i + 1] - y[i])
+    }
+
+    /// resistance temperature correction factor
+    fn res_te_factor(&self, te_deg_c: f64) -> f64 {
+        (self.res_arrhenius_te_k * (1.0 / (te_deg_c + 273.15) - 1.0 / (self.te_ref_deg_c + 273.15)))
+            .exp()
+    }
+
+    /// Pack open circuit voltage \[V\]
+    pub fn ocv_v(&self, soc: f64) -> f64 {
+        self.interp(soc, &self.ocv_cell_v) * self.n_series as f64
+    }
+
+    /// Pack series resistance \[Ω\]
+    pub fn r0_ohm(&self, soc: f64, te_deg_c: f64) -> f64 {
+        self.interp(soc, &self.r0_cell_ohm) * self.res_te_factor(te_deg_c) * self.n_series as f64
+            / self.n_parallel as f64
+    }
+
+    /// Pack RC branch resistance \[Ω\], zero for Rint model
+    pub fn r1_ohm(&self, soc: f64, te_deg_c: f64) -> f64 {
+        if self.is_thevenin() {
+            self.interp(soc, &self.r1_cell_ohm)
+                * self.res_te_factor(te_deg_c)
+                * self.n_series as f64
+                / self.n_parallel as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Pack RC branch capacitance \[F\]
+    pub fn c1_f(&self) -> f64 {
+        self.c1_cell_f * self.n_parallel as f64 / self.n_series as f64
+    }
+
+    /// Pack capacity \[A·h\] corresponding to `ess_max_kwh` at the pack
+    /// open circuit voltage at 50% state of charge
+    pub fn cap_ah(&self, ess_max_kwh: f64) -> f64 {
+        ess_max_kwh * 1e3 / self.ocv_v(0.5)
+    }
+
+    /// Returns (max discharge power \[kW\], max charge power \[kW\]) at the
+    /// battery terminals such that the terminal voltage stays within
+    /// `v_cell_min` and `v_cell_max`
+    pub fn pwr_lims_kw(&self, soc: f64, v_rc_v: f64, te_deg_c: f64) -> (f64, f64) {
+        let emf_v = self.ocv_v(soc) - v_rc_v;
+        let r0_ohm = self.r0_ohm(soc, te_deg_c);
+        let v_min = self.v_cell_min * self.n_series as f64;
+        let v_max = self.v_cell_max * self.n_series as f64;
+        // terminal power peaks at half of emf
+        let dischg_kw = if v_min >= 0.5 * emf_v {
+            v_min * (emf_v - v_min) / r0_ohm
+        } else {
+            emf_v * emf_v / (4.0 * r0_ohm)
+        } / 1e3;
+        let chg_kw = v_max * (v_max - emf_v) / r0_ohm / 1e3;
+        (dischg_kw.max(0.0), chg_kw.max(0.0))
+    }
+
+    /// Solves circuit for terminal power `pwr_kw` (positive when discharging)
+    /// over a time step of `dt_s`, given state of charge, RC branch voltage,
+    /// and temperature at the start of the step
+    pub fn solve(
+        &self,
+        pwr_kw: f64,
+        soc: f64,
+        v_rc_v: f64,
+        te_deg_c: f64,
+        dt_s: f64,
+    ) -> EssCircuitStep {
+        let ocv_v = self.ocv_v(soc);
+        let emf_v = ocv_v - v_rc_v;
+        let r0_ohm = self.r0_ohm(soc, te_deg_c);
+        // terminal power: P = (emf - I * R0) * I
+        let discriminant = (emf_v * emf_v - 4.0 * r0_ohm * pwr_kw * 1e3).max(0.0);
+        let current_a = (emf_v - discriminant.sqrt()) / (2.0 * r0_ohm);
+        let voltage_v = emf_v - current_a * r0_ohm;
+        let r1_ohm = self.r1_ohm(soc, te_deg_c);
+        let v_rc_v = if r1_ohm > 0.0 {
+            let decay = (-dt_s / (r1_ohm * self.c1_f())).exp();
+            v_rc_v * decay + current_a * r1_ohm * (1.0 - decay)
+        } else {
+            0.0
+        };
+        let chem_kw = ocv_v * current_a / 1e3;
+        EssCircuitStep {
+            current_a,
+            voltage_v,
+            v_rc_v,
+            chem_kw,
+            loss_kw: chem_kw - voltage_v * current_a / 1e3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ocv_interp() {
+        let ess = EssCircuitModel::default();
+        let n_series = ess.n_series as f64;
+        assert!((ess.ocv_v(0.0) - 3.0 * n_series).abs() < 1e-9);
+        assert!((ess.ocv_v(0.55) - 3.74 * n_series).abs() < 1e-9);
+        assert!((ess.ocv_v(1.0) - 4.15 * n_series).abs() < 1e-9);
+        // held constant outside of `soc_bins`
+        assert_eq!(ess.ocv_v(-0.1), ess.ocv_v(0.0));
+        assert_eq!(ess.ocv_v(1.1), ess.ocv_v(1.0));
+    }
+
+    #[test]
+    fn test_solve_power_balance() {
+        let ess = EssCircuitModel::default();
+        for pwr_kw in [-30.0, -5.0, 0.0, 5.0, 30.0] {
+            let step = ess.solve(pwr_kw, 0.6, 0.0, 25.0, 1.0);
+            assert!((step.voltage_v * step.current_a / 1e3 - pwr_kw).abs() < 1e-9);
+            assert!(step.loss_kw >= 0.0);
+            assert!((step.chem_kw - pwr_kw - step.loss_kw).abs() < 1e-9);
+        }
+        // RC branch voltage relaxes toward I * R1
+        let step = ess.solve(30.0, 0.6, 0.0, 25.0, 1.0);
+        assert!(step.v_rc_v > 0.0 && step.v_rc_v < step.current_a * ess.r1_ohm(0.6, 25.0));
+    }
+
+    #[test]
+    fn test_pwr_lims() {
+        let ess = EssCircuitModel::default();
+        let (dischg_kw, chg_kw) = ess.pwr_lims_kw(0.5, 0.0, 25.0);
+        let step = ess.solve(dischg_kw, 0.5, 0.0, 25.0, 1.0);
+        assert!((step.voltage_v - ess.v_cell_min * ess.n_series as f64).abs() < 1e-6);
+        let step = ess.solve(-chg_kw, 0.5, 0.0, 25.0, 1.0);
+        assert!((step.voltage_v - ess.v_cell_max * ess.n_series as f64).abs() < 1e-6);
+        // colder battery has higher resistance and less power
+        let (dischg_cold_kw, chg_cold_kw) = ess.pwr_lims_kw(0.5, 0.0, -10.0);
+        assert!(dischg_cold_kw < dischg_kw);
+        assert!(chg_cold_kw < chg_kw);
+    }
+
+    #[test]
+    fn test_sim_drive_with_ess_circuit() {
+        let mut veh = crate::vehicle::RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        let ess = EssCircuitModel::default();
+        veh.ess_circuit = Some(ess.clone());
+        veh.init().unwrap();
+        let cyc = crate::cycle::RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = crate::simdrive::RustSimDrive::new(cyc, veh);
+        sd.sim_drive(None, None).unwrap();
+
+        assert!(sd.energy_audit_error.abs() < sd.sim_params.energy_audit_error_tol);
+        assert!(sd.ess_current_a.iter().any(|i| *i > 0.0));
+        assert!(sd.ess_current_a.iter().any(|i| *i < 0.0));
+        assert!(sd
+            .ess_voltage_v
+            .iter()
+            .all(|v| *v >= ess.v_cell_min * ess.n_series as f64 - 1e-6
+                && *v <= ess.v_cell_max * ess.n_series as f64 + 1e-6));
+        assert!(sd.ess_eff_kj > 0.0);
+        assert!(sd.mpgge > 0.0);
+    }
+
+    #[test]
+    fn test_sim_drive_hot_ess_temperature() {
+        use crate::thermal::{SimDriveHot, ThermalState};
+        use crate::vehicle_thermal::VehicleThermal;
+
+        let mut veh = crate::vehicle::RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        veh.ess_circuit = Some(EssCircuitModel::default());
+        veh.init().unwrap();
+        let cyc = crate::cycle::RustCycle::from_resource("udds.csv", false).unwrap();
+        let run = |amb_te_deg_c: f64| {
+            let mut sdh = SimDriveHot::new(
+                cyc.clone(),
+                veh.clone(),
+                VehicleThermal::default(),
+                Some(ThermalState::new(
+                    Some(amb_te_deg_c),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )),
+                None,
+            );
+            sdh.walk(0.6, None);
+            sdh
+        };
+        let sdh_cold = run(-10.0);
+        let sdh_warm = run(25.0);
+        // battery warms up due to its own losses
+        assert!(sdh_cold.state.ess_te_deg_c > -10.0);
+        assert_eq!(sdh_cold.sd.ess_te_deg_c[1], -10.0);
+        // cold battery has higher resistance and therefore higher losses
+        assert!(sdh_cold.sd.ess_loss_kw.sum() > sdh_warm.sd.ess_loss_kw.sum());
+    }
+}
@@ -0,0 +1,574 @@
+//! Module for getting, setting, and listing struct fields by dot-separated
+//! path, e.g. `transmission.gear_ratios.1`, via the [FieldPath] trait, which
+//! is implemented for structs with `#[derive(FieldPath)]`
+
+use std::fmt;
+
+use crate::imports::*;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+/// Value of a field accessed with [FieldPath]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    /// unset optional field
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// elements of an array, `Vec`, or tuple field
+    List(Vec<FieldValue>),
+}
+
+impl FieldValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Bool(_) => "bool",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::Str(_) => "str",
+            Self::List(_) => "list",
+        }
+    }
+
+    /// Returns the value as a float, converting integers
+    pub fn as_f64(&self) -> anyhow::Result<f64> {
+        match self {
+            Self::Float(x) => Ok(*x),
+            Self::Int(i) => Ok(*i as f64),
+            _ => bail!("expected float, got {}", self.type_name()),
+        }
+    }
+
+    /// Returns the value as an integer, converting floats with no fractional part
+    pub fn as_i64(&self) -> anyhow::Result<i64> {
+        match self {
+            Self::Int(i) => Ok(*i),
+            Self::Float(x) if x.fract() == 0.0 && x.abs() < i64::MAX as f64 => Ok(*x as i64),
+            _ => bail!("expected int, got {self}"),
+        }
+    }
+
+    pub fn into_list(self) -> anyhow::Result<Vec<FieldValue>> {
+        match self {
+            Self::List(values) => Ok(values),
+            _ => bail!("expected list, got {}", self.type_name()),
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(x) => write!(f, "{x}"),
+            Self::Str(s) => write!(f, "{s}"),
+            Self::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Parses JSON, e.g. `0.3`, `true`, or `[1, 2]`, falling back to a string,
+/// e.g. for command line overrides like `fc_eff_type=HDDiesel`
+impl std::str::FromStr for FieldValue {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(s).unwrap_or_else(|_| Self::Str(s.to_string())))
+    }
+}
+
+#[cfg(feature = "pyo3")]
+impl<'py> FromPyObject<'py> for FieldValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if ob.is_none() {
+            Ok(Self::None)
+        } else if ob.is_instance_of::<pyo3::types::PyBool>() {
+            Ok(Self::Bool(ob.extract()?))
+        } else if let Ok(i) = ob.extract::<i64>() {
+            Ok(Self::Int(i))
+        } else if let Ok(x) = ob.extract::<f64>() {
+            Ok(Self::Float(x))
+        } else if let Ok(s) = ob.extract::<String>() {
+            Ok(Self::Str(s))
+        } else if let Ok(values) = ob.extract::<Vec<Self>>() {
+            Ok(Self::List(values))
+        } else {
+            Err(PyTypeError::new_err(format!(
+                "cannot convert {} to a field value",
+                ob.get_type().name()?
+            )))
+        }
+    }
+}
+
+#[cfg(feature = "pyo3")]
+impl<'py> IntoPyObject<'py> for FieldValue {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(match self {
+            Self::None => py.None().into_bound(py),
+            Self::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+            Self::Int(i) => i.into_pyobject(py)?.into_any(),
+            Self::Float(x) => x.into_pyobject(py)?.into_any(),
+            Self::Str(s) => s.into_pyobject(py)?.into_any(),
+            Self::List(values) => values.into_pyobject(py)?.into_any(),
+        })
+    }
+}
+
+/// Path, type, and documentation of a field reachable with [FieldPath]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldInfo {
+    /// dot-separated path, e.g. `transmission.final_drive_ratio`
+    pub path: String,
+    /// Rust type, e.g. `Option<Transmission>`
+    pub type_name: String,
+    /// doc comment of the field
+    pub doc: String,
+}
+
+/// Dynamic access to (nested) fields by dot-separated path, where numeric
+/// path segments index into arrays, `Vec`s, and tuples.  Implement for
+/// structs with `#[derive(FieldPath)]`, which skips `orphaned` and fields
+/// marked `#[field_path(skip)]`.
+pub trait FieldPath {
+    /// Returns the value at `path`, relative to `self`
+    fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue>;
+
+    /// Sets the value at `path`, relative to `self`
+    fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()>;
+
+    /// Creates a new instance from a value, which is only possible for types
+    /// that are not structs
+    fn from_field_value(value: FieldValue) -> anyhow::Result<Self>
+    where
+        Self: Sized;
+
+    /// Appends info for each (nested) field, with paths prefixed by `prefix`
+    fn list_paths(_prefix: &str, _out: &mut Vec<FieldInfo>) {}
+
+    /// Returns the value of the field at dot-separated `path`
+    fn get_field(&self, path: &str) -> anyhow::Result<FieldValue> {
+        self.get_path(&split_path(path))
+            .with_context(|| format!("failed to get field `{path}`"))
+    }
+
+    /// Sets the value of the field at dot-separated `path`.  Note that this
+    /// does not update derived fields, e.g. [RustVehicle::set_derived](crate::vehicle::RustVehicle::set_derived)
+    /// must be called after changing vehicle inputs.
+    fn set_field<V: Into<FieldValue>>(&mut self, path: &str, value: V) -> anyhow::Result<()> {
+        self.set_path(&split_path(path), value.into())
+            .with_context(|| format!("failed to set field `{path}`"))
+    }
+
+    /// Returns info for every (nested) field
+    fn list_fields() -> Vec<FieldInfo>
+    where
+        Self: Sized,
+    {
+        let mut out = Vec::new();
+        Self::list_paths("", &mut out);
+        out
+    }
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    if path.is_empty() {
+        vec![]
+    } else {
+        path.split('.').collect()
+    }
+}
+
+fn ensure_leaf(path: &[&str]) -> anyhow::Result<()> {
+    ensure!(
+        path.is_empty(),
+        "cannot access `{}` of a value that is not a struct or list",
+        path.join(".")
+    );
+    Ok(())
+}
+
+fn parse_index<'a, 'b>(path: &'a [&'b str], len: usize) -> anyhow::Result<(usize, &'a [&'b str])> {
+    let (key, rest) = path.split_first().unwrap();
+    let idx: usize = key
+        .parse()
+        .with_context(|| format!("expected index, got `{key}`"))?;
+    ensure!(idx < len, "index {idx} out of bounds for length {len}");
+    Ok((idx, rest))
+}
+
+macro_rules! impl_field_path_for_leaves {
+    ($($leaf_type: ty),*) => {
+        $(
+            impl FieldPath for $leaf_type {
+                fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+                    ensure_leaf(path)?;
+                    Ok(self.clone().into())
+                }
+
+                fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+                    ensure_leaf(path)?;
+                    *self = Self::from_field_value(value)?;
+                    Ok(())
+                }
+
+                fn from_field_value(value: FieldValue) -> anyhow::Result<Self> {
+                    value.try_into()
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! impl_field_value_for_ints {
+    ($($int_type: ty),*) => {
+        $(
+            impl From<$int_type> for FieldValue {
+                fn from(value: $int_type) -> Self {
+                    Self::Int(value as i64)
+                }
+            }
+
+            impl TryFrom<FieldValue> for $int_type {
+                type Error = anyhow::Error;
+                fn try_from(value: FieldValue) -> anyhow::Result<Self> {
+                    let i = value.as_i64()?;
+                    Self::try_from(i).with_context(|| {
+                        format!("{i} is out of range for {}", stringify!($int_type))
+                    })
+                }
+            }
+        )*
+        impl_field_path_for_leaves!($($int_type),*);
+    }
+}
+
+impl_field_value_for_ints!(u8, u32, u64, usize, i32, i64);
+
+/// Implements [FieldPath] for enums that convert to and from strings with
+/// `Display` and `FromStr`
+macro_rules! impl_field_value_for_str_enums {
+    ($($enum_type: ty),*) => {
+        $(
+            impl From<$enum_type> for FieldValue {
+                fn from(value: $enum_type) -> Self {
+                    Self::Str(value.to_string())
+                }
+            }
+
+            impl TryFrom<FieldValue> for $enum_type {
+                type Error = anyhow::Error;
+                fn try_from(value: FieldValue) -> anyhow::Result<Self> {
+                    match value {
+                        FieldValue::Str(s) => s.parse(),
+                        _ => bail!("expected str, got {}", value.type_name()),
+                    }
+                }
+            }
+        )*
+        impl_field_path_for_leaves!($($enum_type),*);
+    }
+}
+
+impl_field_value_for_str_enums!(crate::vehicle::PowertrainType, crate::vehicle::FcEffType);
+impl_field_value_for_str_enums!(crate::fuel::FuelType, crate::fuel::FuelUnit);
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl TryFrom<FieldValue> for f64 {
+    type Error = anyhow::Error;
+    fn try_from(value: FieldValue) -> anyhow::Result<Self> {
+        value.as_f64()
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl TryFrom<FieldValue> for bool {
+    type Error = anyhow::Error;
+    fn try_from(value: FieldValue) -> anyhow::Result<Self> {
+        match value {
+            FieldValue::Bool(b) => Ok(b),
+            _ => bail!("expected bool, got {}", value.type_name()),
+        }
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
+impl TryFrom<FieldValue> for String {
+    type Error = anyhow::Error;
+    fn try_from(value: FieldValue) -> anyhow::Result<Self> {
+        match value {
+            FieldValue::Str(s) => Ok(s),
+            _ => bail!("expected str, got {}", value.type_name()),
+        }
+    }
+}
+
+impl_field_path_for_leaves!(f64, bool, String);
+
+impl<T: Into<FieldValue>> From<Vec<T>> for FieldValue {
+    fn from(values: Vec<T>) -> Self {
+        Self::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<FieldValue>> From<Option<T>> for FieldValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::None, Into::into)
+    }
+}
+
+impl<T: FieldPath> FieldPath for Option<T> {
+    fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+        match self {
+            Some(value) => value.get_path(path),
+            None if path.is_empty() => Ok(FieldValue::None),
+            None => bail!("cannot access `{}` of None", path.join(".")),
+        }
+    }
+
+    fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+        match (self.as_mut(), path.is_empty()) {
+            (Some(inner), false) => inner.set_path(path, value),
+            (None, false) => bail!("cannot set `{}` of None", path.join(".")),
+            (_, true) => {
+                *self = Self::from_field_value(value)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn from_field_value(value: FieldValue) -> anyhow::Result<Self> {
+        match value {
+            FieldValue::None => Ok(None),
+            value => Ok(Some(T::from_field_value(value)?)),
+        }
+    }
+
+    fn list_paths(prefix: &str, out: &mut Vec<FieldInfo>) {
+        T::list_paths(prefix, out)
+    }
+}
+
+impl<T: FieldPath> FieldPath for Vec<T> {
+    fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+        if path.is_empty() {
+            return Ok(FieldValue::List(
+                self.iter()
+                    .map(|x| x.get_path(path))
+                    .collect::<anyhow::Result<_>>()?,
+            ));
+        }
+        let (idx, rest) = parse_index(path, self.len())?;
+        self[idx].get_path(rest)
+    }
+
+    fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+        if path.is_empty() {
+            *self = Self::from_field_value(value)?;
+            return Ok(());
+        }
+        let (idx, rest) = parse_index(path, self.len())?;
+        self[idx].set_path(rest, value)
+    }
+
+    fn from_field_value(value: FieldValue) -> anyhow::Result<Self> {
+        value
+            .into_list()?
+            .into_iter()
+            .map(T::from_field_value)
+            .collect()
+    }
+}
+
+impl<T: FieldPath + Clone> FieldPath for Array1<T> {
+    fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+        if path.is_empty() {
+            return Ok(FieldValue::List(
+                self.iter()
+                    .map(|x| x.get_path(path))
+                    .collect::<anyhow::Result<_>>()?,
+            ));
+        }
+        let (idx, rest) = parse_index(path, self.len())?;
+        self[idx].get_path(rest)
+    }
+
+    fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+        if path.is_empty() {
+            *self = Self::from_field_value(value)?;
+            return Ok(());
+        }
+        let (idx, rest) = parse_index(path, self.len())?;
+        self[idx].set_path(rest, value)
+    }
+
+    fn from_field_value(value: FieldValue) -> anyhow::Result<Self> {
+        Ok(Array1::from_vec(Vec::<T>::from_field_value(value)?))
+    }
+}
+
+impl<A: FieldPath, B: FieldPath> FieldPath for (A, B) {
+    fn get_path(&self, path: &[&str]) -> anyhow::Result<FieldValue> {
+        if path.is_empty() {
+            return Ok(FieldValue::List(vec![
+                self.0.get_path(path)?,
+                self.1.get_path(path)?,
+            ]));
+        }
+        match parse_index(path, 2)? {
+            (0, rest) => self.0.get_path(rest),
+            (_, rest) => self.1.get_path(rest),
+        }
+    }
+
+    fn set_path(&mut self, path: &[&str], value: FieldValue) -> anyhow::Result<()> {
+        if path.is_empty() {
+            *self = Self::from_field_value(value)?;
+            return Ok(());
+        }
+        match parse_index(path, 2)? {
+            (0, rest) => self.0.set_path(rest, value),
+            (_, rest) => self.1.set_path(rest, value),
+        }
+    }
+
+    fn from_field_value(value: FieldValue) -> anyhow::Result<Self> {
+        let values = value.into_list()?;
+        ensure!(
+            values.len() == 2,
+            "expected list of length 2, got length {}",
+            values.len()
+        );
+        let mut values = values.into_iter();
+        Ok((
+            A::from_field_value(values.next().unwrap())?,
+            B::from_field_value(values.next().unwrap())?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simdrive::RustSimDriveParams;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_get_set_vehicle_fields() {
+        let mut veh = RustVehicle::mock_vehicle();
+        assert_eq!(
+            veh.get_field("drag_coef").unwrap(),
+            FieldValue::Float(0.355)
+        );
+        veh.set_field("drag_coef", 0.3).unwrap();
+        assert_eq!(veh.drag_coef, 0.3);
+        veh.set_field("veh_year", 2020).unwrap();
+        assert_eq!(veh.veh_year, 2020);
+        veh.set_field("fc_eff_map.3", 0.31).unwrap();
+        assert_eq!(veh.fc_eff_map[3], 0.31);
+        veh.set_field("fc_eff_type", "HDDiesel").unwrap();
+        assert_eq!(veh.get_field("fc_eff_type").unwrap(), "HD_Diesel".into());
+        veh.set_field("props.a_grav_mps2", 9.8).unwrap();
+        assert_eq!(veh.props.a_grav_mps2, 9.8);
+
+        // nested optional struct
+        assert!(veh.set_field("fuel.lhv_kj_per_kg", 4.0e4).is_err());
+        veh.fuel = Some(Default::default());
+        veh.set_field("fuel.lhv_kj_per_kg", 4.0e4).unwrap();
+        assert_eq!(veh.fuel.as_ref().unwrap().lhv_kj_per_kg, 4.0e4);
+        veh.set_field("fuel", FieldValue::None).unwrap();
+        assert!(veh.fuel.is_none());
+
+        for (path, value) in [
+            ("not_a_field", FieldValue::Float(1.0)),
+            ("drag_coef", FieldValue::Str("big".into())),
+            ("veh_year", FieldValue::Float(2020.5)),
+            ("fc_eff_map.1000", FieldValue::Float(0.3)),
+            ("drag_coef.1", FieldValue::Float(0.3)),
+            ("props", FieldValue::Float(0.3)),
+            ("fc_eff_type", FieldValue::Str("not_a_type".into())),
+        ] {
+            assert!(veh.set_field(path, value).is_err(), "{path}");
+        }
+        assert!(veh.get_field("props").is_err());
+    }
+
+    #[test]
+    fn test_list_fields() {
+        let fields = RustVehicle::list_fields();
+        let drag_coef = fields.iter().find(|f| f.path == "drag_coef").unwrap();
+        assert_eq!(drag_coef.type_name, "f64");
+        assert!(!drag_coef.doc.is_empty());
+        assert!(fields
+            .iter()
+            .any(|f| f.path == "transmission.final_drive_ratio"));
+        assert!(fields.iter().all(|f| !f.path.ends_with("orphaned")));
+        // every listed field can be read
+        let veh = RustVehicle::mock_vehicle();
+        for field in fields.iter().filter(|f| !f.path.contains('.')) {
+            let value = veh.get_field(&field.path);
+            assert!(
+                value.is_ok() || field.type_name.starts_with("RustPhysical"),
+                "{}",
+                field.path
+            );
+        }
+
+        let mut params = RustSimDriveParams::default();
+        params
+            .set_field(
+                "idm_v_desired_in_m_per_s_by_distance_m",
+                "[[0, 10.0], [100, 20.0]]".parse::<FieldValue>().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            params.idm_v_desired_in_m_per_s_by_distance_m,
+            Some(vec![(0.0, 10.0), (100.0, 20.0)])
+        );
+        assert_eq!(
+            params
+                .get_field("idm_v_desired_in_m_per_s_by_distance_m.1.0")
+                .unwrap(),
+            FieldValue::Float(100.0)
+        );
+    }
+}
@@ -0,0 +1,486 @@
+//! Module containing fuel properties and a library of common fuels
+
+use crate::imports::*;
+use crate::params::{RustPhysicalProperties, L_PER_GAL};
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+#[cfg(feature = "pyo3")]
+use crate::utils;
+
+/// Fuel type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum FuelType {
+    /// Gasoline with no ethanol, the reference fuel for gallons of gasoline
+    /// equivalent
+    #[default]
+    Gasoline,
+    /// Gasoline with 10% ethanol by volume
+    E10,
+    /// Gasoline with 85% ethanol by volume
+    E85,
+    /// Diesel
+    Diesel,
+    /// Compressed natural gas
+    CNG,
+    /// Liquefied natural gas
+    LNG,
+    /// Hydrogen
+    H2,
+    /// Propane (liquefied petroleum gas)
+    Propane,
+}
+
+impl FuelType {
+    /// All valid fuel types
+    pub const ALL: [Self; 8] = [
+        Self::Gasoline,
+        Self::E10,
+        Self::E85,
+        Self::Diesel,
+        Self::CNG,
+        Self::LNG,
+        Self::H2,
+        Self::Propane,
+    ];
+
+    /// String representation used in vehicle files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gasoline => "Gasoline",
+            Self::E10 => "E10",
+            Self::E85 => "E85",
+            Self::Diesel => "Diesel",
+            Self::CNG => "CNG",
+            Self::LNG => "LNG",
+            Self::H2 => "H2",
+            Self::Propane => "Propane",
+        }
+    }
+
+    /// Maps a fueleconomy.gov `fuelType` to the primary fuel, returning `None`
+    /// for electricity
+    pub fn from_fueleconomy_gov(fuel_type: &str) -> Option<Self> {
+        match fuel_type {
+            "Electricity" => None,
+            "Diesel" => Some(Self::Diesel),
+            "CNG" => Some(Self::CNG),
+            "Hydrogen" => Some(Self::H2),
+            // "Regular", "Premium", "Midgrade", "Gasoline or E85",
+            // "Regular Gas and Electricity", etc.
+            _ => Some(Self::Gasoline),
+        }
+    }
+}
+
+impl std::fmt::Display for FuelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for FuelType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|fuel_type| fuel_type.as_str() == s)
+            .with_context(|| {
+                format!(
+                    "invalid fuel type `{s}`, must be one of {:?}",
+                    Self::ALL.map(|fuel_type| fuel_type.as_str())
+                )
+            })
+    }
+}
+
+/// Unit in which fuel is typically sold and reported
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FuelUnit {
+    /// US gallons
+    #[default]
+    #[serde(rename = "gal")]
+    Gal,
+    /// kilograms
+    #[serde(rename = "kg")]
+    Kg,
+}
+
+impl FuelUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gal => "gal",
+            Self::Kg => "kg",
+        }
+    }
+}
+
+impl std::fmt::Display for FuelUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for FuelUnit {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "gal" => Ok(Self::Gal),
+            "kg" => Ok(Self::Kg),
+            _ => bail!("invalid fuel unit `{s}`, must be one of [\"gal\", \"kg\"]"),
+        }
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (fuel_type="Gasoline"))]
+    pub fn __new__(fuel_type: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(fuel_type.parse()?))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "list_fuel_types")]
+    pub fn list_fuel_types_py() -> Vec<&'static str> {
+        FuelType::ALL.map(|fuel_type| fuel_type.as_str()).to_vec()
+    }
+
+    #[getter]
+    pub fn get_fuel_type(&self) -> String {
+        self.fuel_type.to_string()
+    }
+
+    #[setter]
+    pub fn set_fuel_type(&mut self, new_value: &str) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, fuel_type, new_value.parse()?)
+    }
+
+    #[getter]
+    pub fn get_native_unit(&self) -> String {
+        self.native_unit.to_string()
+    }
+
+    #[setter]
+    pub fn set_native_unit(&mut self, new_value: &str) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, native_unit, new_value.parse()?)
+    }
+
+    #[getter]
+    pub fn get_kwh_per_native_unit(&self) -> f64 {
+        self.kwh_per_native_unit()
+    }
+
+    #[pyo3(name = "gge_factor")]
+    pub fn gge_factor_py(&self, kwh_per_gge: f64) -> f64 {
+        self.gge_factor(kwh_per_gge)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq, FieldPath)]
+/// Fuel properties.  [Fuel::new] provides properties for each of
+/// [FuelType::ALL], which can then be modified.
+pub struct Fuel {
+    #[api(skip_get, skip_set)]
+    pub fuel_type: FuelType,
+    /// lower heating value \[kJ/kg\]
+    pub lhv_kj_per_kg: f64,
+    /// density \[kg/L\], liquid density or gas density at typical storage
+    /// pressure
+    pub density_kg_per_l: f64,
+    /// carbon mass fraction
+    pub carbon_frac: f64,
+    /// stoichiometric air-fuel ratio (mass basis)
+    pub afr_stoich: f64,
+    /// unit in which consumption is reported
+    #[api(skip_get, skip_set)]
+    pub native_unit: FuelUnit,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for Fuel {
+    fn default() -> Self {
+        Self::new(FuelType::default())
+    }
+}
+
+impl SerdeAPI for Fuel {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.lhv_kj_per_kg > 0.0 && self.density_kg_per_l > 0.0,
+            "{}",
+            format_dbg!((self.lhv_kj_per_kg, self.density_kg_per_l))
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.carbon_frac),
+            "{}",
+            format_dbg!(self.carbon_frac)
+        );
+        ensure!(self.afr_stoich > 0.0, "{}", format_dbg!(self.afr_stoich));
+        Ok(())
+    }
+}
+
+impl Fuel {
+    /// Properties of `fuel_type` from the fuel library
+    pub fn new(fuel_type: FuelType) -> Self {
+        let (lhv_kj_per_kg, density_kg_per_l, carbon_frac, afr_stoich, native_unit) =
+            match fuel_type {
+                // LHV such that one gallon is exactly one gallon of gasoline
+                // equivalent per `RustPhysicalProperties::kwh_per_gge`
+                FuelType::Gasoline => (
+                    33.7 * 3.6e3 / (0.75 * L_PER_GAL),
+                    0.75,
+                    0.866,
+                    14.7,
+                    FuelUnit::Gal,
+                ),
+                FuelType::E10 => (41_070.0, 0.754, 0.830, 14.1, FuelUnit::Gal),
+                FuelType::E85 => (29_090.0, 0.783, 0.571, 9.8, FuelUnit::Gal),
+                FuelType::Diesel => (42_660.0, 0.846, 0.870, 14.5, FuelUnit::Gal),
+                FuelType::CNG => (47_100.0, 0.19, 0.730, 17.2, FuelUnit::Kg),
+                FuelType::LNG => (48_600.0, 0.45, 0.750, 17.2, FuelUnit::Gal),
+                FuelType::H2 => (120_000.0, 0.040, 0.0, 34.3, FuelUnit::Kg),
+                FuelType::Propane => (46_350.0, 0.508, 0.817, 15.7, FuelUnit::Gal),
+            };
+        Self {
+            fuel_type,
+            lhv_kj_per_kg,
+            density_kg_per_l,
+            carbon_frac,
+            afr_stoich,
+            native_unit,
+            orphaned: false,
+        }
+    }
+
+    /// Gasoline with properties from `props`, for vehicles that do not
+    /// specify a fuel
+    pub fn from_props(props: &RustPhysicalProperties) -> Self {
+        Self {
+            lhv_kj_per_kg: props.get_fuel_lhv_kj_per_kg(),
+            density_kg_per_l: props.fuel_rho_kg__L,
+            afr_stoich: props.fuel_afr_stoich,
+            ..Self::new(FuelType::Gasoline)
+        }
+    }
+
+    /// Fuel mass \[kg\] with energy content `kj`
+    pub fn kg_from_kj(&self, kj: f64) -> f64 {
+        kj / self.lhv_kj_per_kg
+    }
+
+    /// Energy content \[kWh\] of one `native_unit` of fuel
+    pub fn kwh_per_native_unit(&self) -> f64 {
+        match self.native_unit {
+            FuelUnit::Gal => self.lhv_kj_per_kg * self.density_kg_per_l * L_PER_GAL / 3.6e3,
+            FuelUnit::Kg => self.lhv_kj_per_kg / 3.6e3,
+        }
+    }
+
+    /// Amount of fuel in `native_unit` with energy content `kj`
+    pub fn native_units_from_kj(&self, kj: f64) -> f64 {
+        kj / 3.6e3 / self.kwh_per_native_unit()
+    }
+
+    /// Gallons of gasoline equivalent per `native_unit` of fuel
+    pub fn gge_factor(&self, kwh_per_gge: f64) -> f64 {
+        self.kwh_per_native_unit() / kwh_per_gge
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    pub fn __new__(fuel: Fuel, fc_pwr_out_frac: Vec<f64>, energy_share: Vec<f64>) -> anyhow::Result<Self> {
+        let mut secondary_fuel = Self {
+            fuel,
+            fc_pwr_out_frac,
+            energy_share,
+            orphaned: false,
+        };
+        secondary_fuel.init()?;
+        Ok(secondary_fuel)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "constant")]
+    pub fn constant_py(fuel: Fuel, energy_share: f64) -> Self {
+        Self::constant(fuel, energy_share)
+    }
+
+    #[pyo3(name = "share")]
+    pub fn share_py(&self, fc_pwr_out_frac: f64) -> f64 {
+        self.share(fc_pwr_out_frac)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ApproxEq, FieldPath)]
+/// Second fuel burned by a dual-fuel or blended-fuel converter alongside
+/// `RustVehicle::fuel_props`.  The share of fuel converter input energy from
+/// the second fuel is either constant, if `fc_pwr_out_frac` has a single
+/// element, or interpolated over fuel converter output power fraction.
+pub struct SecondaryFuel {
+    pub fuel: Fuel,
+    /// fuel converter output power fraction breakpoints for `energy_share`
+    pub fc_pwr_out_frac: Vec<f64>,
+    /// share of fuel converter input energy from `fuel` at each of
+    /// `fc_pwr_out_frac`
+    pub energy_share: Vec<f64>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SecondaryFuel {
+    fn init(&mut self) -> anyhow::Result<()> {
+        self.fuel.init()?;
+        ensure!(
+            !self.fc_pwr_out_frac.is_empty()
+                && self.fc_pwr_out_frac.len() == self.energy_share.len(),
+            "{}\n`fc_pwr_out_frac` and `energy_share` must be non-empty and have the same length",
+            format_dbg!((self.fc_pwr_out_frac.len(), self.energy_share.len()))
+        );
+        ensure!(
+            self.fc_pwr_out_frac.windows(2).all(|w| w[0] < w[1]),
+            "{}\n`fc_pwr_out_frac` must be strictly increasing",
+            format_dbg!(&self.fc_pwr_out_frac)
+        );
+        ensure!(
+            self.energy_share.iter().all(|s| (0.0..=1.0).contains(s)),
+            "{}\n`energy_share` must be between 0 and 1",
+            format_dbg!(&self.energy_share)
+        );
+        Ok(())
+    }
+}
+
+impl SecondaryFuel {
+    /// Secondary fuel with constant `energy_share`
+    pub fn constant(fuel: Fuel, energy_share: f64) -> Self {
+        Self {
+            fuel,
+            fc_pwr_out_frac: vec![0.0],
+            energy_share: vec![energy_share],
+            orphaned: false,
+        }
+    }
+
+    /// Share of fuel converter input energy from `fuel` at fuel converter
+    /// output power fraction `fc_pwr_out_frac`
+    pub fn share(&self, fc_pwr_out_frac: f64) -> f64 {
+        if self.energy_share.len() == 1 {
+            self.energy_share[0]
+        } else {
+            interpolate_vectors(
+                &fc_pwr_out_frac,
+                &self.fc_pwr_out_frac,
+                &self.energy_share,
+                false,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::simdrive::RustSimDrive;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_fuel_library() {
+        let props = RustPhysicalProperties::default();
+        let gasoline = Fuel::new(FuelType::Gasoline);
+        assert!((gasoline.gge_factor(props.kwh_per_gge) - 1.0).abs() < 1e-12);
+        // consistent with hard-coded diesel energy content previously used by the CLI
+        let diesel = Fuel::new(FuelType::Diesel);
+        assert!((diesel.kwh_per_native_unit() - 37.95).abs() < 0.01);
+        // 1 kg H2 is approximately 1 GGE
+        let h2 = Fuel::new(FuelType::H2);
+        assert_eq!(h2.native_unit, FuelUnit::Kg);
+        assert!((h2.gge_factor(props.kwh_per_gge) - 0.99).abs() < 0.01);
+        for fuel_type in FuelType::ALL {
+            let mut fuel = Fuel::new(fuel_type);
+            assert!(fuel.init().is_ok());
+            assert_eq!(fuel_type.as_str().parse::<FuelType>().unwrap(), fuel_type);
+            let kj = 1e5;
+            assert!(
+                (fuel.native_units_from_kj(kj) * fuel.kwh_per_native_unit() * 3.6e3 - kj).abs()
+                    < 1e-6
+            );
+        }
+        assert!("Kerosene".parse::<FuelType>().is_err());
+        assert_eq!(
+            FuelType::from_fueleconomy_gov("Premium"),
+            Some(FuelType::Gasoline)
+        );
+        assert_eq!(FuelType::from_fueleconomy_gov("Electricity"), None);
+    }
+
+    #[test]
+    fn test_sim_drive_native_fuel_units() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/Regional_Delivery_Class_8_Truck.yaml"),
+            false,
+        )
+        .unwrap();
+        let cyc = RustCycle::from_resource("HHDDTCruiseSmooth.csv", false).unwrap();
+        veh.fuel = Some(Fuel::new(FuelType::Diesel));
+        let mut sd = RustSimDrive::new(cyc, veh);
+        sd.sim_drive(None, None).unwrap();
+        let diesel_gal = sd.fuel_kj / 3.6e3 / 37.95;
+        assert!((sd.fuel_native - diesel_gal).abs() / diesel_gal < 1e-3);
+        assert!((sd.fuel_kg - sd.fuel_kj / 42_660.0).abs() < 1e-9);
+        // diesel gallon has more energy than a gallon of gasoline equivalent
+        assert!(sd.mi_per_fuel_native > sd.mpgge);
+    }
+
+    #[test]
+    fn test_sim_drive_dual_fuel() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/Regional_Delivery_Class_8_Truck.yaml"),
+            false,
+        )
+        .unwrap();
+        let cyc = RustCycle::from_resource("HHDDTCruiseSmooth.csv", false).unwrap();
+        veh.fuel = Some(Fuel::new(FuelType::Diesel));
+        let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.sim_drive(None, None).unwrap();
+        assert_eq!(sd.fuel2_kj, 0.0);
+        assert_eq!(sd.fuel1_kj, sd.fuel_kj);
+
+        // constant share does not affect total fuel energy
+        veh.secondary_fuel = Some(SecondaryFuel::constant(Fuel::new(FuelType::H2), 0.3));
+        let mut sd_dual = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_dual.sim_drive(None, None).unwrap();
+        assert!((sd_dual.mpgge - sd.mpgge).abs() < 1e-9);
+        assert!((sd_dual.fuel2_kj - 0.3 * sd_dual.fuel_kj).abs() < 1e-6);
+        assert!((sd_dual.fuel_native - 0.7 * sd.fuel_native).abs() < 1e-9);
+        assert!((sd_dual.fuel2_native - sd_dual.fuel2_kj / 120_000.0).abs() < 1e-9);
+        assert!(
+            (sd_dual.fuel_kg - (sd_dual.fuel1_kj / 42_660.0 + sd_dual.fuel2_kj / 120_000.0)).abs()
+                < 1e-9
+        );
+
+        // power-dependent share
+        veh.secondary_fuel = Some(SecondaryFuel {
+            fuel: Fuel::new(FuelType::H2),
+            fc_pwr_out_frac: vec![0.0, 0.5, 1.0],
+            energy_share: vec![0.0, 0.8, 0.8],
+            orphaned: false,
+        });
+        let mut sd_dual = RustSimDrive::new(cyc, veh.clone());
+        sd_dual.sim_drive(None, None).unwrap();
+        for i in 0..sd_dual.len() {
+            let share = veh
+                .secondary_fuel
+                .as_ref()
+                .unwrap()
+                .share(sd_dual.fc_kw_out_ach[i] / veh.fc_max_kw);
+            assert!((sd_dual.fuel2_share[i] - share).abs() < 1e-12);
+        }
+        assert!(sd_dual.fuel2_kj > 0.0 && sd_dual.fuel2_kj < 0.8 * sd_dual.fuel_kj);
+
+        let mut bad = SecondaryFuel::constant(Fuel::new(FuelType::H2), 1.5);
+        assert!(bad.init().is_err());
+    }
+}
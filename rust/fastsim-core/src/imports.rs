@@ -12,5 +12,7 @@ pub(crate) use std::path::Path;
 #[allow(unused_imports)]
 pub(crate) use std::path::PathBuf;
 
+pub(crate) use crate::error::FastSimError;
+pub(crate) use crate::field_path::*;
 pub(crate) use crate::traits::*;
 pub(crate) use crate::utils::*;
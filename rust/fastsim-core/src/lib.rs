@@ -38,21 +38,33 @@ extern crate ndarray;
 #[macro_use]
 pub mod macros;
 pub mod air;
+pub mod battery_aging;
 pub mod cycle;
+pub mod emissions;
+pub mod error;
+pub mod ess_circuit;
+pub mod field_path;
+pub mod fuel;
 pub mod imports;
 pub mod params;
 pub mod pyo3imports;
+pub mod route;
 pub mod simdrive;
-mod calibration;
+pub mod calibration;
 pub use simdrive::simdrive_impl;
 pub mod simdrivelabel;
+pub mod speed_torque_map;
 pub mod thermal;
+pub mod timeseries;
 pub mod traits;
+pub mod transmission;
 pub mod utils;
 pub mod vehicle;
 pub mod vehicle_import;
 pub mod vehicle_thermal;
 pub mod vehicle_utils;
+pub mod vehicle_validation;
+pub mod weather;
 
 pub use fastsim_proc_macros as proc_macros;
 
@@ -65,6 +77,9 @@ pub fn enabled_features() -> Vec<String> {
     #[cfg(feature = "default")]
     enabled.push("default".into());
 
+    #[cfg(feature = "arrow")]
+    enabled.push("arrow".into());
+
     #[cfg(feature = "bincode")]
     enabled.push("bincode".into());
 
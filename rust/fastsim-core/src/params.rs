@@ -1,7 +1,7 @@
 //! Module containing FASTSim parameters.
 
 use crate::imports::*;
-use crate::proc_macros::{add_pyo3_api, ApproxEq};
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
 
@@ -16,13 +16,12 @@ pub const L_PER_GAL: f64 = 3.78541;
 pub const HP_PER_KW: f64 = 1.34102;
 pub const IN_PER_M: f64 = 39.3701;
 pub const LBS_PER_KG: f64 = 2.20462;
+pub const RAD_PER_SEC_PER_RPM: f64 = std::f64::consts::PI / 30.0;
 
 /// Misc Constants
 pub const MODERN_MAX: f64 = 0.95;
 
 /// Struct containing time trace data
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ApproxEq)]
-#[allow(non_snake_case)]
 #[add_pyo3_api(
     #[allow(non_snake_case)]
     #[new]
@@ -44,6 +43,8 @@ pub const MODERN_MAX: f64 = 0.95;
         todo!();
     }
 )]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ApproxEq, FieldPath)]
+#[allow(non_snake_case)]
 pub struct RustPhysicalProperties {
     pub air_density_kg_per_m3: f64, // = 1.2, Sea level air density at approximately 20C
     pub a_grav_mps2: f64,           // = 9.81
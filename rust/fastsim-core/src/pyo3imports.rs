@@ -2,3 +2,5 @@
 pub use pyo3::exceptions::*;
 pub use pyo3::prelude::*;
 pub use pyo3::types::{PyAny, PyBytes, PyDict, PyType};
+
+pub use crate::error::pyerr::{to_py_io_err, to_pyerr};
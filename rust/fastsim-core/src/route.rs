@@ -0,0 +1,447 @@
+//! Module containing a distance-indexed route representation that can be
+//! converted into a drivable, time-indexed [RustCycle](crate::cycle::RustCycle).
+
+use crate::cycle::{calc_constant_jerk_trajectory, trapz_step_distances, RustCycle};
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+/// distance tolerance for considering a rendezvous point (e.g. stop) reached \[m\]
+const ROUTE_DIST_TOL_M: f64 = 0.5;
+/// speed below which the vehicle is considered stopped \[m/s\]
+const ROUTE_STOPPED_MPS: f64 = 1e-6;
+/// safety limit on the number of time steps in a generated cycle
+const ROUTE_MAX_STEPS: usize = 10_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[add_pyo3_api(
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        dist_m,
+        speed_limit_dist_m,
+        speed_limit_mps,
+        grade=None,
+        elevation_m=None,
+        stop_dist_m=None,
+        stop_dwell_s=None,
+        name=None,
+    ))]
+    pub fn __new__(
+        dist_m: Vec<f64>,
+        speed_limit_dist_m: Vec<f64>,
+        speed_limit_mps: Vec<f64>,
+        grade: Option<Vec<f64>>,
+        elevation_m: Option<Vec<f64>>,
+        stop_dist_m: Option<Vec<f64>>,
+        stop_dwell_s: Option<Vec<f64>>,
+        name: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let mut route = Self {
+            dist_m: Array::from_vec(dist_m),
+            grade: Array::from_vec(grade.unwrap_or_default()),
+            elevation_m: Array::from_vec(elevation_m.unwrap_or_default()),
+            speed_limit_dist_m: Array::from_vec(speed_limit_dist_m),
+            speed_limit_mps: Array::from_vec(speed_limit_mps),
+            stop_dist_m: Array::from_vec(stop_dist_m.unwrap_or_default()),
+            stop_dwell_s: Array::from_vec(stop_dwell_s.unwrap_or_default()),
+            name: name.unwrap_or_default(),
+            orphaned: false,
+        };
+        route.init()?;
+        Ok(route)
+    }
+
+    #[getter("length_m")]
+    pub fn length_m_py(&self) -> f64 {
+        self.length_m()
+    }
+
+    #[pyo3(name = "to_cycle")]
+    #[pyo3(signature = (dt_s=1.0, accel_m_per_s2=1.0, brake_accel_m_per_s2=-2.0))]
+    pub fn to_cycle_py(
+        &self,
+        dt_s: f64,
+        accel_m_per_s2: f64,
+        brake_accel_m_per_s2: f64,
+    ) -> anyhow::Result<RustCycle> {
+        self.to_cycle(dt_s, accel_m_per_s2, brake_accel_m_per_s2)
+    }
+)]
+/// Distance-indexed route, e.g. as exported from a mapping tool, containing
+/// speed limits, stop locations, and road grade and/or elevation.  Use
+/// `to_cycle` to generate a drivable, time-indexed cycle.
+pub struct RustRoute {
+    /// distances along route at which `grade` and/or `elevation_m` are
+    /// specified \[m\], last value is the route length
+    pub dist_m: Array1<f64>,
+    /// road grade \[rise/run\] at each of `dist_m`
+    #[serde(default)]
+    pub grade: Array1<f64>,
+    /// elevation \[m\] at each of `dist_m`, used to derive grade if `grade` is empty
+    #[serde(default)]
+    pub elevation_m: Array1<f64>,
+    /// distances along route at which each of `speed_limit_mps` takes effect \[m\]
+    pub speed_limit_dist_m: Array1<f64>,
+    /// speed limit \[m/s\], applies until the next speed limit takes effect
+    pub speed_limit_mps: Array1<f64>,
+    /// distances along route of stops \[m\], the vehicle also always stops at
+    /// the end of the route
+    #[serde(default)]
+    pub stop_dist_m: Array1<f64>,
+    /// dwell time at each of `stop_dist_m` \[s\], no dwell if empty
+    #[serde(default)]
+    pub stop_dwell_s: Array1<f64>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for RustRoute {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.dist_m.len() >= 2,
+            "{}\n`dist_m` must have at least 2 elements",
+            format_dbg!(self.dist_m.len())
+        );
+        ensure!(
+            self.dist_m.windows(2).into_iter().all(|w| w[0] < w[1]),
+            "{}\n`dist_m` must be strictly increasing",
+            format_dbg!()
+        );
+        ensure!(
+            self.grade.is_empty() || self.grade.len() == self.dist_m.len(),
+            "{}\n`grade` must be empty or have the same length as `dist_m`",
+            format_dbg!((self.grade.len(), self.dist_m.len()))
+        );
+        ensure!(
+            self.elevation_m.is_empty() || self.elevation_m.len() == self.dist_m.len(),
+            "{}\n`elevation_m` must be empty or have the same length as `dist_m`",
+            format_dbg!((self.elevation_m.len(), self.dist_m.len()))
+        );
+        ensure!(
+            !self.speed_limit_mps.is_empty()
+                && self.speed_limit_mps.len() == self.speed_limit_dist_m.len(),
+            "{}\n`speed_limit_mps` must be non-empty and have the same length as `speed_limit_dist_m`",
+            format_dbg!((self.speed_limit_mps.len(), self.speed_limit_dist_m.len()))
+        );
+        ensure!(
+            self.speed_limit_dist_m[0] <= self.dist_m[0],
+            "{}\nfirst speed limit must take effect at or before the start of the route",
+            format_dbg!((self.speed_limit_dist_m[0], self.dist_m[0]))
+        );
+        ensure!(
+            self.speed_limit_dist_m
+                .windows(2)
+                .into_iter()
+                .all(|w| w[0] < w[1]),
+            "{}\n`speed_limit_dist_m` must be strictly increasing",
+            format_dbg!()
+        );
+        ensure!(
+            self.speed_limit_mps.iter().all(|v| *v > 0.0),
+            "{}\n`speed_limit_mps` must be positive",
+            format_dbg!()
+        );
+        ensure!(
+            self.stop_dwell_s.is_empty() || self.stop_dwell_s.len() == self.stop_dist_m.len(),
+            "{}\n`stop_dwell_s` must be empty or have the same length as `stop_dist_m`",
+            format_dbg!((self.stop_dwell_s.len(), self.stop_dist_m.len()))
+        );
+        ensure!(
+            self.stop_dwell_s.iter().all(|t| *t >= 0.0),
+            "{}\n`stop_dwell_s` must be non-negative",
+            format_dbg!()
+        );
+        ensure!(
+            self.stop_dist_m
+                .iter()
+                .all(|d| *d >= self.dist_m[0] && *d <= self.length_m()),
+            "{}\n`stop_dist_m` must lie within the route",
+            format_dbg!()
+        );
+        Ok(())
+    }
+}
+
+impl RustRoute {
+    /// Total route length \[m\]
+    pub fn length_m(&self) -> f64 {
+        *self.dist_m.last().unwrap()
+    }
+
+    /// Speed limit \[m/s\] in effect at the given distance along the route
+    pub fn speed_limit_at(&self, dist_m: f64) -> f64 {
+        self.speed_limit_dist_m
+            .iter()
+            .zip(self.speed_limit_mps.iter())
+            .take_while(|(d, _)| **d <= dist_m)
+            .last()
+            .map_or(self.speed_limit_mps[0], |(_, v)| *v)
+    }
+
+    /// Road grade \[rise/run\] at the given distance along the route.  If `grade`
+    /// is empty, grade is derived from the slope of `elevation_m`.  If both are
+    /// empty, the route is flat.
+    pub fn grade_at(&self, dist_m: f64) -> f64 {
+        if !self.grade.is_empty() {
+            interpolate(&dist_m, &self.dist_m, &self.grade, false)
+        } else if !self.elevation_m.is_empty() {
+            let idx = self
+                .dist_m
+                .iter()
+                .skip(1)
+                .position(|d| *d > dist_m)
+                .unwrap_or(self.dist_m.len() - 2);
+            (self.elevation_m[idx + 1] - self.elevation_m[idx])
+                / (self.dist_m[idx + 1] - self.dist_m[idx])
+        } else {
+            0.0
+        }
+    }
+
+    /// Stops along the route as (distance \[m\], dwell time \[s\]), sorted by
+    /// distance and ending with the end of the route
+    fn stops(&self) -> Vec<(f64, f64)> {
+        let mut stops: Vec<(f64, f64)> = self
+            .stop_dist_m
+            .iter()
+            .enumerate()
+            .map(|(j, d)| (*d, self.stop_dwell_s.get(j).copied().unwrap_or_default()))
+            .filter(|(d, _)| *d > self.dist_m[0] && *d < self.length_m())
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        stops.push((self.length_m(), 0.0));
+        stops
+    }
+
+    /// Generates a drivable cycle from the route.  The vehicle accelerates at
+    /// constant acceleration up to the speed limit and uses constant-jerk
+    /// trajectories to slow down for lower speed limits and to stop at each
+    /// stop and at the end of the route.
+    /// Arguments:
+    /// - dt_s: time step duration \[s\]
+    /// - accel_m_per_s2: positive acceleration used to reach the speed limit \[m/s^2\]
+    /// - brake_accel_m_per_s2: negative acceleration used to plan for slowing
+    ///   down and stopping \[m/s^2\]
+    pub fn to_cycle(
+        &self,
+        dt_s: f64,
+        accel_m_per_s2: f64,
+        brake_accel_m_per_s2: f64,
+    ) -> anyhow::Result<RustCycle> {
+        ensure!(dt_s > 0.0, "{}", format_dbg!(dt_s));
+        ensure!(accel_m_per_s2 > 0.0, "{}", format_dbg!(accel_m_per_s2));
+        ensure!(
+            brake_accel_m_per_s2 < 0.0,
+            "{}",
+            format_dbg!(brake_accel_m_per_s2)
+        );
+        // time steps are collected here and the cycle is built once at the end
+        let mut time_s = vec![0.0];
+        let mut mps = vec![0.0];
+        // distance traveled \[m\], relative to the start of the route
+        let mut d = 0.0;
+        let d0 = self.dist_m[0];
+
+        for (stop_dist_m, dwell_s) in self.stops() {
+            let stop_d = stop_dist_m - d0;
+            loop {
+                let i = mps.len();
+                ensure!(
+                    i < ROUTE_MAX_STEPS,
+                    "{}\nexceeded maximum number of time steps",
+                    format_dbg!(i)
+                );
+                let v = mps[i - 1];
+                if stop_d - d <= ROUTE_DIST_TOL_M && v <= ROUTE_STOPPED_MPS {
+                    break;
+                }
+                let v_next = (v + accel_m_per_s2 * dt_s).min(self.speed_limit_at(d + d0));
+                let d_next = d + 0.5 * (v + v_next) * dt_s;
+                // upcoming speed reductions and the stop, in order of distance
+                let rendezvous = self
+                    .speed_limit_dist_m
+                    .iter()
+                    .zip(self.speed_limit_mps.iter())
+                    .map(|(dl, vl)| (dl - d0, *vl))
+                    .filter(|(dl, vl)| *dl > d + ROUTE_DIST_TOL_M && *dl < stop_d && *vl < v_next)
+                    .chain(std::iter::once((stop_d, 0.0)))
+                    .find(|(dr, vr)| {
+                        d_next + (v_next * v_next - vr * vr) / (-2.0 * brake_accel_m_per_s2) >= *dr
+                    });
+
+                match rendezvous {
+                    Some((dr, vr)) if dr - d > ROUTE_DIST_TOL_M => {
+                        let dd = dr - d;
+                        let t_s = if v + vr > accel_m_per_s2 * dt_s {
+                            2.0 * dd / (v + vr)
+                        } else {
+                            // creeping from (nearly) stopped
+                            2.0 * (dd / accel_m_per_s2).sqrt()
+                        };
+                        let n = ((t_s / dt_s).round() as usize).max(2);
+                        let mut speeds = if vr == 0.0 && v > accel_m_per_s2 * dt_s {
+                            trajectory_speeds(v, n, dt_s, |cyc| {
+                                cyc.modify_with_braking_trajectory(-0.5 * v * v / dd, 1, Some(dd))
+                                    .map(|_| ())
+                            })?
+                        } else {
+                            let (jerk_m_per_s3, accel0_m_per_s2) =
+                                calc_constant_jerk_trajectory(n, 0.0, v, dd, vr, dt_s)?;
+                            trajectory_speeds(v, n, dt_s, |cyc| {
+                                cyc.modify_by_const_jerk_trajectory(
+                                    1,
+                                    n,
+                                    jerk_m_per_s3,
+                                    accel0_m_per_s2,
+                                );
+                                Ok(())
+                            })?
+                        };
+                        if vr == 0.0 {
+                            speeds[n - 1] = 0.0;
+                        }
+                        d += push_steps(&mut time_s, &mut mps, &speeds, dt_s);
+                    }
+                    Some(_) => {
+                        // already at the stop but still moving, only the stop
+                        // can be this close since passed speed limits are filtered out
+                        let n = ((v / -brake_accel_m_per_s2 / dt_s).round() as usize).max(2);
+                        let mut speeds = trajectory_speeds(v, n, dt_s, |cyc| {
+                            cyc.modify_with_braking_trajectory(brake_accel_m_per_s2, 1, None)
+                                .map(|_| ())
+                        })?;
+                        speeds[n - 1] = 0.0;
+                        d += push_steps(&mut time_s, &mut mps, &speeds, dt_s);
+                    }
+                    _ => {
+                        push_steps(&mut time_s, &mut mps, &[v_next], dt_s);
+                        d = d_next;
+                    }
+                }
+            }
+            let n_dwell = (dwell_s / dt_s).ceil() as usize;
+            push_steps(&mut time_s, &mut mps, &vec![0.0; n_dwell], dt_s);
+        }
+
+        let len = mps.len();
+        let mut cyc = RustCycle {
+            time_s: Array::from_vec(time_s),
+            mps: Array::from_vec(mps),
+            grade: Array::zeros(len),
+            road_type: Array::zeros(len),
+            name: self.name.clone(),
+            orphaned: false,
+        };
+        let dists_m = ndarrcumsum(&trapz_step_distances(&cyc));
+        cyc.grade = Array::from_iter((0..cyc.len()).map(|i| {
+            if i == 0 {
+                self.grade_at(d0)
+            } else {
+                self.grade_at(d0 + 0.5 * (dists_m[i - 1] + dists_m[i]))
+            }
+        }));
+        cyc.init()?;
+        Ok(cyc)
+    }
+}
+
+/// Speeds for the `n` time steps of duration `dt_s` that follow a step at
+/// speed `v0_mps`, as set by `modify`, which is applied at index 1 of a
+/// cycle made of the preceding step and `n` stationary steps
+fn trajectory_speeds<F>(v0_mps: f64, n: usize, dt_s: f64, modify: F) -> anyhow::Result<Vec<f64>>
+where
+    F: FnOnce(&mut RustCycle) -> anyhow::Result<()>,
+{
+    let mut cyc = RustCycle {
+        time_s: Array::range(0.0, n as f64 + 0.5, 1.0) * dt_s,
+        mps: Array::zeros(n + 1),
+        grade: Array::zeros(n + 1),
+        road_type: Array::zeros(n + 1),
+        name: String::new(),
+        orphaned: false,
+    };
+    cyc.mps[0] = v0_mps;
+    modify(&mut cyc)?;
+    Ok(cyc.mps.iter().skip(1).copied().collect())
+}
+
+/// Appends time steps of duration `dt_s` at `speeds` to `time_s` and `mps`
+/// and returns the distance traveled over the appended steps \[m\]
+fn push_steps(time_s: &mut Vec<f64>, mps: &mut Vec<f64>, speeds: &[f64], dt_s: f64) -> f64 {
+    let t0 = *time_s.last().unwrap();
+    let mut dist_m = 0.0;
+    for (k, v) in speeds.iter().enumerate() {
+        dist_m += 0.5 * (mps.last().unwrap() + v) * dt_s;
+        time_s.push(t0 + (k + 1) as f64 * dt_s);
+        mps.push(*v);
+    }
+    dist_m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_route() -> RustRoute {
+        let mut route = RustRoute {
+            dist_m: array![0.0, 500.0, 1000.0],
+            elevation_m: array![0.0, 10.0, 10.0],
+            speed_limit_dist_m: array![0.0, 600.0],
+            speed_limit_mps: array![15.0, 10.0],
+            stop_dist_m: array![300.0],
+            stop_dwell_s: array![5.0],
+            name: "test_route".into(),
+            ..Default::default()
+        };
+        route.init().unwrap();
+        route
+    }
+
+    #[test]
+    fn test_route_validation() {
+        let mut route = test_route();
+        route.stop_dwell_s = array![5.0, 5.0];
+        assert!(route.init().is_err());
+        let mut route = test_route();
+        route.speed_limit_dist_m = array![100.0, 600.0];
+        assert!(route.init().is_err());
+    }
+
+    #[test]
+    fn test_route_to_cycle() {
+        let route = test_route();
+        let cyc = route.to_cycle(1.0, 1.0, -2.0).unwrap();
+        let dists_m = ndarrcumsum(&trapz_step_distances(&cyc));
+
+        assert_eq!(cyc.mps[0], 0.0);
+        assert_eq!(*cyc.mps.last().unwrap(), 0.0);
+        assert!(
+            (dists_m.last().unwrap() - route.length_m()).abs() < 1.0,
+            "{}",
+            format_dbg!(dists_m.last().unwrap())
+        );
+        for (d, v) in dists_m.iter().zip(cyc.mps.iter()) {
+            assert!(
+                *v <= route.speed_limit_at(*d) + 1e-6,
+                "{}",
+                format_dbg!((d, v))
+            );
+        }
+        // vehicle dwells at the intermediate stop
+        let stopped: Vec<f64> = dists_m
+            .iter()
+            .zip(cyc.mps.iter())
+            .filter(|(d, v)| **v == 0.0 && **d > 1.0 && **d < route.length_m() - 1.0)
+            .map(|(d, _)| *d)
+            .collect();
+        assert!(stopped.len() >= 5, "{}", format_dbg!(stopped.len()));
+        assert!(stopped.iter().all(|d| (d - 300.0).abs() < 1.0));
+        // grade is derived from elevation
+        assert!(cyc.grade.iter().any(|g| (g - 0.02).abs() < 1e-9));
+        assert!(cyc.grade.iter().any(|g| g.abs() < 1e-9));
+    }
+}
@@ -3,20 +3,85 @@
 use crate::cycle::{RustCycle, RustCycleCache};
 use crate::imports::*;
 use crate::params::RustPhysicalProperties;
-use crate::proc_macros::add_pyo3_api;
+use crate::proc_macros::{add_pyo3_api, FieldPath, TimeSeries};
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
+#[cfg(feature = "pyo3")]
+use crate::utils;
 use crate::vehicle::*;
+use crate::weather::WeatherProfile;
 pub mod cyc_mods;
+pub mod diagnostics;
+pub mod eco_cruise;
+pub mod energy_breakdown;
+pub mod energy_management;
+pub mod platoon;
+pub mod sensitivity;
 pub mod simdrive_impl;
 pub mod simdrive_iter;
+pub mod simdrive_stream;
+pub mod spat;
+pub mod sweep;
+use diagnostics::SimDiagnostics;
+use energy_breakdown::EnergyBreakdown;
+use energy_management::*;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[add_pyo3_api(
     pub fn __getnewargs__(&self) {
         todo!();
     }
+
+    #[getter]
+    pub fn get_energy_management_type(&self) -> String {
+        self.energy_management.name().into()
+    }
+
+    pub fn set_energy_management_classic(&mut self) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, energy_management, EnergyManagementTypes::Classic)
+    }
+
+    pub fn set_energy_management_ecms(
+        &mut self,
+        ecms: EcmsEnergyManagement
+    ) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, energy_management, EnergyManagementTypes::Ecms(ecms))
+    }
+
+    pub fn get_energy_management_ecms(&self) -> anyhow::Result<EcmsEnergyManagement> {
+        if let EnergyManagementTypes::Ecms(ecms) = &self.energy_management {
+            Ok(ecms.clone())
+        } else {
+            bail!(PyAttributeError::new_err(format!(
+                "EnergyManagementTypes::{} variant currently used.",
+                self.energy_management.name()
+            )))
+        }
+    }
+
+    pub fn set_energy_management_thermostat(
+        &mut self,
+        thermostat: ThermostatEnergyManagement
+    ) -> anyhow::Result<()> {
+        thermostat.clone().init()?;
+        check_orphaned_and_set!(
+            self,
+            energy_management,
+            EnergyManagementTypes::Thermostat(thermostat)
+        )
+    }
+
+    pub fn get_energy_management_thermostat(&self) -> anyhow::Result<ThermostatEnergyManagement> {
+        if let EnergyManagementTypes::Thermostat(thermostat) = &self.energy_management {
+            Ok(thermostat.clone())
+        } else {
+            bail!(PyAttributeError::new_err(format!(
+                "EnergyManagementTypes::{} variant currently used.",
+                self.energy_management.name()
+            )))
+        }
+    }
 )]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FieldPath)]
 /// Struct containing time trace data
 pub struct RustSimDriveParams {
     /// if true, accuracy will be favored over performance for grade per step estimates
@@ -55,6 +120,19 @@ pub struct RustSimDriveParams {
     pub newton_xtol: f64,
     /// tolerance for energy audit error warning, i.e. 0.1%
     pub energy_audit_error_tol: f64,
+    /// if true, a trace miss beyond the `trace_miss_*_tol` tolerances is returned as
+    /// [FastSimError::TraceMiss] rather than only logged as a warning
+    #[serde(default)]
+    pub error_on_trace_miss: bool,
+    /// if true, an energy audit error beyond `energy_audit_error_tol` is returned as
+    /// [FastSimError::EnergyAudit] rather than only logged as a warning
+    #[serde(default)]
+    pub error_on_energy_audit: bool,
+    /// if true, arrays written at each time step are checked for NaN, infinite,
+    /// and impossible negative values, and a [SimDiagnostics] report is kept
+    /// in `RustSimDrive::diagnostics`
+    #[serde(default)]
+    pub diagnostics: bool,
     // Eco-Coasting Maneuver Parameters
     /// if true, coasting to stops are allowed
     pub coast_allow: bool,
@@ -92,11 +170,23 @@ pub struct RustSimDriveParams {
     // Other, Misc.
     /// EPA fuel economy adjustment parameters; maximum EPA adjustment factor
     pub max_epa_adj: f64,
+    /// hybrid energy management strategy, see [EnergyManagementTypes](EnergyManagementTypes)
+    #[serde(default)]
+    #[api(skip_get, skip_set)]
+    #[field_path(skip)]
+    pub energy_management: EnergyManagementTypes,
     #[serde(skip)]
     pub orphaned: bool,
 }
 
-impl SerdeAPI for RustSimDriveParams {}
+impl SerdeAPI for RustSimDriveParams {
+    fn init(&mut self) -> anyhow::Result<()> {
+        if let EnergyManagementTypes::Thermostat(thermostat) = &mut self.energy_management {
+            thermostat.init()?;
+        }
+        Ok(())
+    }
+}
 
 impl Default for RustSimDriveParams {
     fn default() -> Self {
@@ -121,7 +211,10 @@ impl Default for RustSimDriveParams {
         let newton_max_iter = 100; // newton solver max iterations
         let newton_xtol = 1e-9; // newton solver tolerance
         let energy_audit_error_tol = 0.002; // tolerance for energy audit error warning, i.e. 0.1%
-                                            // Coasting
+        let diagnostics = false; // if true, check each time step for NaN/Inf/negative values
+        let error_on_trace_miss = false; // if true, trace miss is an error rather than a warning
+        let error_on_energy_audit = false; // if true, energy audit failure is an error rather than a warning
+                                           // Coasting
         let coast_allow = false;
         let coast_allow_passing = false;
         let coast_max_speed_m_per_s = 40.0;
@@ -141,6 +234,7 @@ impl Default for RustSimDriveParams {
         let idm_v_desired_in_m_per_s_by_distance_m = None;
         // EPA fuel economy adjustment parameters
         let max_epa_adj = 0.3; // maximum EPA adjustment factor
+        let energy_management = EnergyManagementTypes::default(); // hybrid energy management strategy
         Self {
             favor_grade_accuracy,
             missed_trace_correction,
@@ -156,6 +250,9 @@ impl Default for RustSimDriveParams {
             newton_max_iter,
             newton_xtol,
             energy_audit_error_tol,
+            error_on_trace_miss,
+            error_on_energy_audit,
+            diagnostics,
             coast_allow,
             coast_allow_passing,
             coast_max_speed_m_per_s,
@@ -172,12 +269,12 @@ impl Default for RustSimDriveParams {
             idm_decel_m_per_s2,
             idm_v_desired_in_m_per_s_by_distance_m,
             max_epa_adj,
+            energy_management,
             orphaned: false,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[add_pyo3_api(
     /// method for instantiating SimDriveRust
     #[new]
@@ -209,9 +306,9 @@ impl Default for RustSimDriveParams {
         &mut self,
         init_soc: Option<f64>,
         aux_in_kw_override: Option<Vec<f64>>,
-    ) -> anyhow::Result<()> {
+    ) -> PyResult<()> {
         let aux_in_kw_override = aux_in_kw_override.map(Array1::from);
-        self.sim_drive(init_soc, aux_in_kw_override)
+        self.sim_drive(init_soc, aux_in_kw_override).map_err(to_pyerr)
     }
 
     #[pyo3(signature = (init_soc, aux_in_kw_override=None))]
@@ -230,9 +327,9 @@ impl Default for RustSimDriveParams {
         &mut self,
         init_soc: f64,
         aux_in_kw_override: Option<Vec<f64>>,
-    ) -> anyhow::Result<()> {
+    ) -> PyResult<()> {
         let aux_in_kw_override = aux_in_kw_override.map(Array1::from);
-        self.walk(init_soc, aux_in_kw_override)
+        self.walk(init_soc, aux_in_kw_override).map_err(to_pyerr)
     }
 
     #[pyo3(signature = (by_microtrip=None, extend_fraction=None, blend_factor=None, min_target_speed_m_per_s=None))]
@@ -278,14 +375,14 @@ impl Default for RustSimDriveParams {
     }
 
     /// Step through 1 time step.
-    pub fn sim_drive_step(&mut self) -> anyhow::Result<()> {
-        self.step()
+    pub fn sim_drive_step(&mut self) -> PyResult<()> {
+        self.step().map_err(to_pyerr)
     }
 
     #[pyo3(name = "solve_step")]
     /// Perform all the calculations to solve 1 time step.
-    pub fn solve_step_py(&mut self, i: usize) -> anyhow::Result<()> {
-        self.solve_step(i)
+    pub fn solve_step_py(&mut self, i: usize) -> PyResult<()> {
+        self.solve_step(i).map_err(to_pyerr)
     }
 
     #[pyo3(name = "set_misc_calcs")]
@@ -362,6 +459,24 @@ impl Default for RustSimDriveParams {
         self.set_fc_power(i)
     }
 
+    #[pyo3(name = "set_fuel_split")]
+    /// Splits fuel power between primary and secondary fuels.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_fuel_split_py(&mut self, i: usize) {
+        self.set_fuel_split(i)
+    }
+
+    #[pyo3(name = "set_emissions")]
+    /// Sets emission rates for the current time step.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_emissions_py(&mut self, i: usize) -> anyhow::Result<()> {
+        self.set_emissions(i)
+    }
+
     #[pyo3(name = "set_time_dilation")]
     /// Sets the time dilation for the current step.
     /// Arguments
@@ -374,8 +489,14 @@ impl Default for RustSimDriveParams {
     #[pyo3(name = "set_post_scalars")]
     /// Sets scalar variables that can be calculated after a cycle is run.
     /// This includes mpgge, various energy metrics, and others
-    pub fn set_post_scalars_py(&mut self) -> anyhow::Result<()> {
-        self.set_post_scalars()
+    pub fn set_post_scalars_py(&mut self) -> PyResult<()> {
+        self.set_post_scalars().map_err(to_pyerr)
+    }
+
+    #[pyo3(name = "set_battery_wear")]
+    /// Sets battery wear variables: `add_kwh`, `dod_cycs`, and `ess_perc_dead`
+    pub fn set_battery_wear_py(&mut self) {
+        self.set_battery_wear()
     }
 
     #[pyo3(name = "len")]
@@ -388,6 +509,69 @@ impl Default for RustSimDriveParams {
         self.is_empty()
     }
 
+    #[pyo3(name = "to_timeseries_file", signature = (filepath, columns=None))]
+    /// Writes `time_s` and `columns`, or all time series if `None`, to `filepath`
+    /// with format determined by its extension: `csv`, `parquet`, or
+    /// `arrow`/`ipc`/`feather`
+    pub fn to_timeseries_file_py(
+        &self,
+        filepath: &Bound<PyAny>,
+        columns: Option<Vec<String>>
+    ) -> PyResult<()> {
+        self.to_timeseries_file(PathBuf::extract_bound(filepath)?, columns.as_deref())
+            .map_err(to_py_io_err)
+    }
+
+    #[pyo3(name = "timeseries", signature = (columns=None))]
+    /// Returns dict of `time_s` and `columns`, or all time series if `None`,
+    /// e.g. for `polars.DataFrame(sd.timeseries())`
+    pub fn timeseries_py<'py>(
+        &self,
+        py: Python<'py>,
+        columns: Option<Vec<String>>
+    ) -> PyResult<Bound<'py, PyDict>> {
+        self.to_timeseries()
+            .and_then(|table| table.select_opt(columns.as_deref()))
+            .map_err(to_pyerr)?
+            .to_pydict(py)
+    }
+
+    #[pyo3(name = "timeseries_units")]
+    /// Returns dict of time series column name to unit, for columns with a known unit
+    pub fn timeseries_units_py(&self) -> PyResult<std::collections::BTreeMap<String, String>> {
+        Ok(self.to_timeseries().map_err(to_pyerr)?.units())
+    }
+
+    #[getter]
+    pub fn get_energy_breakdown(&self) -> Option<EnergyBreakdown> {
+        self.energy_breakdown.clone()
+    }
+
+    #[pyo3(name = "energy_breakdown_by_microtrip", signature = (stop_speed_m_per_s=None))]
+    /// Returns the energy breakdown for each microtrip of `cyc0`
+    pub fn energy_breakdown_by_microtrip_py(
+        &self,
+        stop_speed_m_per_s: Option<f64>
+    ) -> PyResult<Vec<EnergyBreakdown>> {
+        self.energy_breakdown_by_microtrip(stop_speed_m_per_s)
+            .map_err(to_pyerr)
+    }
+
+    #[getter]
+    pub fn get_diagnostics(&self) -> Option<SimDiagnostics> {
+        self.diagnostics.clone()
+    }
+
+    #[getter]
+    pub fn get_weather(&self) -> Option<WeatherProfile> {
+        self.weather.clone()
+    }
+
+    #[setter]
+    pub fn set_weather(&mut self, weather: Option<WeatherProfile>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, weather, weather)
+    }
+
     #[getter]
     pub fn get_fs_cumu_mj_out_ach(&self) -> Pyo3ArrayF64 {
         Pyo3ArrayF64::new(ndarrcumsum(&(&self.fs_kw_out_ach * self.cyc.dt_s() * 1e-3)))
@@ -397,6 +581,7 @@ impl Default for RustSimDriveParams {
         Pyo3ArrayF64::new(ndarrcumsum(&(&self.fc_kw_out_ach * self.cyc.dt_s() * 1e-3)))
     }
 )]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FieldPath, TimeSeries)]
 pub struct RustSimDrive {
     pub hev_sim_count: usize,
     #[api(has_orphaned)]
@@ -476,8 +661,19 @@ pub struct RustSimDrive {
     /// either `cyc_trans_kw_out_req` if cycle is met,
     /// or `cur_max_trans_kw_out` if it is not
     pub trans_kw_out_ach: Array1<f64>,
-    /// Achieved transmission input power, accounting for `veh.trans_eff`
+    /// Achieved transmission input power, accounting for `trans_eff_ach`
     pub trans_kw_in_ach: Array1<f64>,
+    /// Transmission efficiency, either `veh.trans_eff` or the efficiency of
+    /// `trans_gear` if `veh.transmission` is provided
+    #[serde(default)]
+    pub trans_eff_ach: Array1<f64>,
+    /// Selected gear, only calculated if `veh.transmission` is provided
+    #[serde(default)]
+    pub trans_gear: Array1<u32>,
+    /// Transmission input (engine and/or motor) speed \[rad/s\], only
+    /// calculated if `veh.transmission` is provided
+    #[serde(default)]
+    pub trans_in_rad_per_sec: Array1<f64>,
     pub cur_soc_target: Array1<f64>,
     pub min_mc_kw_2help_fc: Array1<f64>,
     /// Achieved electric motor mechanical output power to transmission
@@ -549,6 +745,65 @@ pub struct RustSimDrive {
     /// Power lost to aerodynamic drag according to the drag equation, `1/2 * rho * Cd * A * v_avg³ / 1000`
     pub drag_kw: Array1<f64>,
     pub ess_loss_kw: Array1<f64>,
+    /// Battery terminal voltage \[V\], only calculated if `veh.ess_circuit` is provided
+    #[serde(default)]
+    pub ess_voltage_v: Array1<f64>,
+    /// Battery current \[A\], positive when discharging, only calculated if
+    /// `veh.ess_circuit` is provided
+    #[serde(default)]
+    pub ess_current_a: Array1<f64>,
+    /// Battery C-rate, positive when discharging, only calculated if
+    /// `veh.ess_circuit` is provided
+    #[serde(default)]
+    pub ess_c_rate: Array1<f64>,
+    /// Battery RC branch voltage \[V\], only calculated if `veh.ess_circuit`
+    /// is provided
+    #[serde(default)]
+    pub ess_v_rc_v: Array1<f64>,
+    /// Battery temperature \[°C\] used by `veh.ess_circuit`, set by
+    /// `SimDriveHot` if battery thermal behavior is simulated
+    #[serde(default)]
+    pub ess_te_deg_c: Array1<f64>,
+    /// Fraction of lit-off aftertreatment conversion efficiency achieved,
+    /// less than 1 only when `SimDriveHot` simulates catalyst temperature
+    #[serde(default)]
+    pub cat_conv_frac: Array1<f64>,
+    /// Share of fuel converter input energy from `veh.secondary_fuel`
+    #[serde(default)]
+    pub fuel2_share: Array1<f64>,
+    /// Power \[kW\] from `veh.fuel_props()`
+    #[serde(default)]
+    pub fuel1_kw_out_ach: Array1<f64>,
+    /// Power \[kW\] from `veh.secondary_fuel`
+    #[serde(default)]
+    pub fuel2_kw_out_ach: Array1<f64>,
+    /// CO2 emission rate \[g/s\], only calculated if `veh.emissions` is provided
+    #[serde(default)]
+    pub co2_g_per_s: Array1<f64>,
+    /// Engine-out NOx emission rate \[g/s\]
+    #[serde(default)]
+    pub eo_nox_g_per_s: Array1<f64>,
+    /// Engine-out CO emission rate \[g/s\]
+    #[serde(default)]
+    pub eo_co_g_per_s: Array1<f64>,
+    /// Engine-out HC emission rate \[g/s\]
+    #[serde(default)]
+    pub eo_hc_g_per_s: Array1<f64>,
+    /// Engine-out PM emission rate \[g/s\]
+    #[serde(default)]
+    pub eo_pm_g_per_s: Array1<f64>,
+    /// Tailpipe NOx emission rate \[g/s\]
+    #[serde(default)]
+    pub tp_nox_g_per_s: Array1<f64>,
+    /// Tailpipe CO emission rate \[g/s\]
+    #[serde(default)]
+    pub tp_co_g_per_s: Array1<f64>,
+    /// Tailpipe HC emission rate \[g/s\]
+    #[serde(default)]
+    pub tp_hc_g_per_s: Array1<f64>,
+    /// Tailpipe PM emission rate \[g/s\]
+    #[serde(default)]
+    pub tp_pm_g_per_s: Array1<f64>,
     /// Power to accelerate, `veh.veh_kg * (v_current² - v_prev²)/2 / dt / 1000`
     pub accel_kw: Array1<f64>,
     /// Power expended to ascend a grade, `sin(atan(grade)) * props.a_grav_mps2 * veh.veh_kg * v_avg / 1000`
@@ -560,9 +815,61 @@ pub struct RustSimDrive {
     pub trace_miss_iters: Array1<u32>,
     pub newton_iters: Array1<u32>,
     pub fuel_kj: f64,
+    /// Total CO2 emissions \[g\], only calculated if `veh.emissions` is provided
+    #[serde(default)]
+    pub co2_g: f64,
+    /// Total engine-out NOx emissions \[g\]
+    #[serde(default)]
+    pub eo_nox_g: f64,
+    /// Total engine-out CO emissions \[g\]
+    #[serde(default)]
+    pub eo_co_g: f64,
+    /// Total engine-out HC emissions \[g\]
+    #[serde(default)]
+    pub eo_hc_g: f64,
+    /// Total engine-out PM emissions \[g\]
+    #[serde(default)]
+    pub eo_pm_g: f64,
+    /// Total tailpipe NOx emissions \[g\]
+    #[serde(default)]
+    pub tp_nox_g: f64,
+    /// Total tailpipe CO emissions \[g\]
+    #[serde(default)]
+    pub tp_co_g: f64,
+    /// Total tailpipe HC emissions \[g\]
+    #[serde(default)]
+    pub tp_hc_g: f64,
+    /// Total tailpipe PM emissions \[g\]
+    #[serde(default)]
+    pub tp_pm_g: f64,
     pub ess_dischg_kj: f64,
     pub energy_audit_error: f64,
     pub mpgge: f64,
+    /// Total fuel consumed \[kg\], including `veh.secondary_fuel`
+    #[serde(default)]
+    pub fuel_kg: f64,
+    /// Total `veh.fuel_props()` consumed in its `native_unit`, e.g. gallons of
+    /// diesel or kg of H2
+    #[serde(default)]
+    pub fuel_native: f64,
+    /// Fuel economy in miles per `native_unit` of `veh.fuel_props()`
+    #[serde(default)]
+    pub mi_per_fuel_native: f64,
+    /// Total energy \[kJ\] from `veh.fuel_props()`
+    #[serde(default)]
+    pub fuel1_kj: f64,
+    /// Total energy \[kJ\] from `veh.secondary_fuel`
+    #[serde(default)]
+    pub fuel2_kj: f64,
+    /// Total `veh.secondary_fuel` consumed \[kg\]
+    #[serde(default)]
+    pub fuel2_kg: f64,
+    /// Total `veh.secondary_fuel` consumed in its `native_unit`
+    #[serde(default)]
+    pub fuel2_native: f64,
+    /// Fuel economy in miles per `native_unit` of `veh.secondary_fuel`
+    #[serde(default)]
+    pub mi_per_fuel2_native: f64,
     pub roadway_chg_kj: f64,
     pub battery_kwh_per_mi: f64,
     pub electric_kwh_per_mi: f64,
@@ -594,15 +901,42 @@ pub struct RustSimDrive {
     pub coast_delay_index: Array1<i32>,
     pub idm_target_speed_m_per_s: Array1<f64>,
     #[serde(skip)]
+    #[field_path(skip)]
     pub cyc0_cache: RustCycleCache,
     #[api(skip_get, skip_set)]
     #[serde(skip)]
+    #[field_path(skip)]
     aux_in_kw_override: Option<Vec<f64>>,
+    /// per-step diagnostics report, only populated if `sim_params.diagnostics` is true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    #[field_path(skip)]
+    pub diagnostics: Option<SimDiagnostics>,
+    /// source-to-sink energy breakdown over the full cycle, set by `set_post_scalars`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub energy_breakdown: Option<EnergyBreakdown>,
+    /// ambient weather affecting air density and aerodynamic drag, still air
+    /// with density from `props` if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    #[field_path(skip)]
+    pub weather: Option<WeatherProfile>,
+    /// time step index and sum of `dist_m` before it, kept so that distance
+    /// need not be re-summed at every step
+    #[serde(skip)]
+    #[api(skip_get, skip_set)]
+    #[field_path(skip)]
+    dist_m_before_step: (usize, f64),
 }
 
 impl SerdeAPI for RustSimDrive {
     fn init(&mut self) -> anyhow::Result<()> {
         self.veh.init()?;
+        self.sim_params.init()?;
+        if let Some(weather) = &mut self.weather {
+            weather.init()?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,379 @@
+//! Opt-in per-step diagnostics for [RustSimDrive], enabled with
+//! `sim_params.diagnostics`.  After each time step is solved, every array
+//! written by the step is checked for NaN, infinite, and physically impossible
+//! negative values, and the simulation stops at the first offending step.
+
+use std::fmt;
+
+use ndarray::ArrayView1;
+
+use super::RustSimDrive;
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+/// Tolerance below zero allowed for quantities that cannot be negative, to
+/// avoid flagging floating point round-off
+pub const NEGATIVE_TOL: f64 = 1e-6;
+/// Number of time steps reported in each hotspot list of [SimDiagnostics]
+pub const HOTSPOT_COUNT: usize = 10;
+
+/// Kind of invalid value found by diagnostics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FaultKind {
+    #[default]
+    NaN,
+    Inf,
+    /// negative value for a quantity that cannot be negative
+    Negative,
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NaN => write!(f, "NaN"),
+            Self::Inf => write!(f, "infinite"),
+            Self::Negative => write!(f, "negative"),
+        }
+    }
+}
+
+#[add_pyo3_api(
+    #[getter]
+    pub fn get_kind(&self) -> String {
+        self.kind.to_string()
+    }
+
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// First invalid value found by diagnostics
+pub struct SimFault {
+    /// index of time step
+    #[api(skip_set)]
+    pub i: usize,
+    /// component that owns `variable`, e.g. `fuel_converter` or `ess`
+    #[api(skip_set)]
+    pub component: String,
+    /// name of offending [RustSimDrive] array
+    #[api(skip_set)]
+    pub variable: String,
+    #[api(skip_set)]
+    pub value: f64,
+    #[api(skip_get, skip_set)]
+    pub kind: FaultKind,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SimFault {}
+
+impl fmt::Display for SimFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` ({}) is {} ({}) at time step {}",
+            self.variable, self.component, self.kind, self.value, self.i
+        )
+    }
+}
+
+#[add_pyo3_api(
+    #[getter]
+    pub fn get_fault(&self) -> Option<SimFault> {
+        self.fault.clone()
+    }
+
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// Report produced by [RustSimDrive] when `sim_params.diagnostics` is true
+pub struct SimDiagnostics {
+    /// number of time step solutions checked, including repeated solutions of
+    /// the same step while iterating on time dilation
+    #[api(skip_set)]
+    pub steps_checked: usize,
+    /// first invalid value found, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub fault: Option<SimFault>,
+    /// sum of `newton_iters` over all time steps
+    #[api(skip_set)]
+    pub newton_iters_total: u64,
+    #[api(skip_set)]
+    pub newton_iters_max: u32,
+    /// `(i, newton_iters[i])` for the time steps with the most newton
+    /// iterations, in descending order
+    #[api(skip_set)]
+    pub newton_iters_hotspots: Vec<(usize, u32)>,
+    /// sum of `trace_miss_iters` over all time steps
+    #[api(skip_set)]
+    pub trace_miss_iters_total: u64,
+    #[api(skip_set)]
+    pub trace_miss_iters_max: u32,
+    /// `(i, trace_miss_iters[i])` for the time steps with the most trace miss
+    /// iterations, in descending order
+    #[api(skip_set)]
+    pub trace_miss_iters_hotspots: Vec<(usize, u32)>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SimDiagnostics {}
+
+impl fmt::Display for SimDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.fault {
+            Some(fault) => writeln!(f, "fault: {fault}")?,
+            None => writeln!(f, "no faults in {} step(s) checked", self.steps_checked)?,
+        }
+        writeln!(
+            f,
+            "newton iterations: {} total, {} max, hotspots {:?}",
+            self.newton_iters_total, self.newton_iters_max, self.newton_iters_hotspots
+        )?;
+        write!(
+            f,
+            "trace miss iterations: {} total, {} max, hotspots {:?}",
+            self.trace_miss_iters_total, self.trace_miss_iters_max, self.trace_miss_iters_hotspots
+        )
+    }
+}
+
+/// Returns totals, maximum, and the [HOTSPOT_COUNT] nonzero entries with the
+/// most iterations
+fn hotspots(iters: ArrayView1<u32>) -> (u64, u32, Vec<(usize, u32)>) {
+    let total = iters.iter().map(|&n| n as u64).sum();
+    let max = iters.iter().copied().max().unwrap_or_default();
+    let mut hotspots: Vec<(usize, u32)> = iters
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, n)| n > 0)
+        .collect();
+    // stable sort keeps earlier time steps first among ties
+    hotspots.sort_by_key(|&(_, n)| cmp::Reverse(n));
+    hotspots.truncate(HOTSPOT_COUNT);
+    (total, max, hotspots)
+}
+
+impl SimDiagnostics {
+    /// Summarizes iteration counts for time steps `0..=i`
+    pub fn set_hotspots(
+        &mut self,
+        newton_iters: &Array1<u32>,
+        trace_miss_iters: &Array1<u32>,
+        i: usize,
+    ) {
+        (
+            self.newton_iters_total,
+            self.newton_iters_max,
+            self.newton_iters_hotspots,
+        ) = hotspots(newton_iters.slice(s![..=i]));
+        (
+            self.trace_miss_iters_total,
+            self.trace_miss_iters_max,
+            self.trace_miss_iters_hotspots,
+        ) = hotspots(trace_miss_iters.slice(s![..=i]));
+    }
+}
+
+/// Builds `(component, variable, value, non_negative)` for every array
+/// written while solving a time step
+macro_rules! step_values {
+    ($sd:expr, $i:expr, {
+        $($component:literal => [$($var:ident),* $(,)?] non_negative [$($nn_var:ident),* $(,)?]),* $(,)?
+    }) => {
+        [$(
+            $(($component, stringify!($var), $sd.$var[$i], false),)*
+            $(($component, stringify!($nn_var), $sd.$nn_var[$i], true),)*
+        )*]
+    };
+}
+
+impl RustSimDrive {
+    /// `(component, variable, value, non_negative)` of the arrays written at
+    /// time step `i`.  Keep in sync with the `Array1<f64>` fields of
+    /// [RustSimDrive]; `test_step_values_cover_arrays` lists the exceptions.
+    fn step_values(
+        &self,
+        i: usize,
+    ) -> impl Iterator<Item = (&'static str, &'static str, f64, bool)> {
+        step_values!(self, i, {
+            "vehicle" => [accel_kw, ascent_kw, max_trac_mps]
+                non_negative [mps_ach, mph_ach, dist_m, dist_mi, idm_target_speed_m_per_s],
+            "road_load" => [
+                cyc_trac_kw_req, cur_max_trac_kw, spare_trac_kw, cyc_tire_inertia_kw,
+                cyc_whl_kw_req, regen_contrl_lim_kw_perc, cyc_regen_brake_kw,
+                cyc_trans_kw_out_req,
+            ] non_negative [drag_kw, rr_kw, cyc_whl_rad_per_sec, cyc_fric_brake_kw],
+            "transmission" => [cur_max_trans_kw_out, trans_kw_out_ach, trans_kw_in_ach]
+                non_negative [trans_eff_ach, trans_in_rad_per_sec],
+            "aux" => [] non_negative [aux_in_kw],
+            "fuel_converter" => [fc_trans_lim_kw, fc_kw_out_ach_pct, fc_kw_gap_fr_eff]
+                non_negative [
+                    cur_max_fc_kw_out, fc_kw_out_ach, fc_kw_in_ach, fc_time_on,
+                    prev_fc_time_on,
+                ],
+            "fuel_storage" => [fuel2_share]
+                non_negative [
+                    cur_max_fs_kw_out, fs_kw_out_ach, fs_kwh_out_ach, fuel1_kw_out_ach,
+                    fuel2_kw_out_ach,
+                ],
+            "motor" => [
+                cur_max_elec_kw, mc_elec_in_lim_kw, mc_transi_lim_kw, cur_max_mc_kw_out,
+                cur_max_mech_mc_kw_in, mc_mech_kw_out_ach, mc_elec_kw_in_ach,
+                cur_max_mc_elec_kw_in, mc_elec_in_kw_for_max_fc_eff,
+            ] non_negative [],
+            "ess" => [
+                ess_cap_lim_dischg_kw, cur_ess_max_kw_out, cur_max_avail_elec_kw,
+                ess_cap_lim_chg_kw, cur_max_ess_chg_kw, ess_lim_mc_regen_perc_kw,
+                ess_kw_out_ach, ess_loss_kw, ess_voltage_v, ess_current_a, ess_c_rate,
+                ess_v_rc_v, ess_te_deg_c, regen_buff_soc, accel_buff_soc,
+                ess_regen_buff_dischg_kw, max_ess_regen_buff_chg_kw, ess_accel_buff_chg_kw,
+                max_ess_accell_buff_dischg_kw, ess_accel_regen_dischg_kw,
+            ] non_negative [ess_cur_kwh, soc],
+            "roadway_charging" => [roadway_chg_kw_out_ach] non_negative [],
+            "hybrid_controls" => [
+                cur_soc_target, min_mc_kw_2help_fc, min_ess_kw_2help_fc, elec_kw_req_4ae,
+                desired_ess_kw_out_for_ae, ess_ae_kw_out, er_ae_kw_out, ess_desired_kw_4fc_eff,
+                ess_kw_if_fc_req, er_kw_if_fc_req, mc_elec_kw_in_if_fc_req, mc_kw_if_fc_req,
+                mc_mech_kw_4forced_fc,
+            ] non_negative [],
+            "emissions" => [] non_negative [
+                cat_conv_frac, co2_g_per_s, eo_nox_g_per_s, eo_co_g_per_s, eo_hc_g_per_s,
+                eo_pm_g_per_s, tp_nox_g_per_s, tp_co_g_per_s, tp_hc_g_per_s, tp_pm_g_per_s,
+            ],
+        })
+        .into_iter()
+    }
+
+    /// Returns the first NaN, infinite, or impossible negative value in the
+    /// arrays written at time step `i`
+    pub fn find_fault(&self, i: usize) -> Option<SimFault> {
+        self.step_values(i)
+            .find_map(|(component, variable, value, non_negative)| {
+                let kind = if value.is_nan() {
+                    FaultKind::NaN
+                } else if value.is_infinite() {
+                    FaultKind::Inf
+                } else if non_negative && value < -NEGATIVE_TOL {
+                    FaultKind::Negative
+                } else {
+                    return None;
+                };
+                Some(SimFault {
+                    i,
+                    component: component.into(),
+                    variable: variable.into(),
+                    value,
+                    kind,
+                    orphaned: false,
+                })
+            })
+    }
+
+    /// Checks the arrays written at time step `i`, recording the result in
+    /// `self.diagnostics` and returning [FastSimError::SimFault] at the first
+    /// invalid value
+    pub fn check_step(&mut self, i: usize) -> anyhow::Result<()> {
+        let fault = self.find_fault(i);
+        let diagnostics = self.diagnostics.get_or_insert_with(Default::default);
+        diagnostics.steps_checked += 1;
+        if let Some(fault) = fault {
+            diagnostics.set_hotspots(&self.newton_iters, &self.trace_miss_iters, i);
+            diagnostics.fault = Some(fault.clone());
+            bail!(FastSimError::SimFault(fault));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_step_values_cover_arrays() {
+        // arrays that are zeroed by `init_arrays` and never written afterward
+        let not_step_values = [
+            "add_kwh",
+            "cur_max_roadway_chg_kw",
+            "dod_cycs",
+            "ess_perc_dead",
+        ];
+        let sd = RustSimDrive::new(RustCycle::test_cyc(), RustVehicle::mock_vehicle());
+        let step_values: Vec<&str> = sd.step_values(0).map(|(_, var, ..)| var).collect();
+        let mut missing: Vec<String> = RustSimDrive::list_fields()
+            .into_iter()
+            .filter(|f| f.type_name == "Array1<f64>" && !f.path.contains('.'))
+            .map(|f| f.path)
+            .filter(|path| {
+                !step_values.contains(&path.as_str()) && !not_step_values.contains(&path.as_str())
+            })
+            .collect();
+        missing.sort();
+        assert!(
+            missing.is_empty(),
+            "not checked by `find_fault`: {missing:?}"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_clean_run() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        for veh_file in ["2012_Ford_Fusion.yaml", "2016_TOYOTA_Prius_Two.yaml"] {
+            let veh = RustVehicle::from_file(resources_path().join("vehdb").join(veh_file), false)
+                .unwrap();
+            let mut sd = RustSimDrive::new(cyc.clone(), veh);
+            sd.sim_params.diagnostics = true;
+            sd.sim_drive(None, None).unwrap();
+            let diagnostics = sd.diagnostics.as_ref().unwrap();
+            assert!(diagnostics.fault.is_none(), "{veh_file}: {diagnostics}");
+            assert_eq!(diagnostics.steps_checked, cyc.len() - 1);
+            assert_eq!(
+                diagnostics.newton_iters_total,
+                sd.newton_iters.iter().map(|&n| n as u64).sum::<u64>()
+            );
+            assert!(diagnostics.newton_iters_hotspots.len() <= HOTSPOT_COUNT);
+            assert!(diagnostics
+                .newton_iters_hotspots
+                .windows(2)
+                .all(|w| w[0].1 >= w[1].1));
+        }
+
+        // disabled by default
+        let mut sd = RustSimDrive::new(cyc, RustVehicle::mock_vehicle());
+        sd.sim_drive(None, None).unwrap();
+        assert!(sd.diagnostics.is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_stops_at_first_fault() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut veh = RustVehicle::mock_vehicle();
+        // corrupt the fuel converter efficiency map after derived values are set
+        veh.fc_eff_array.fill(f64::NAN);
+        let mut sd = RustSimDrive::new(cyc, veh);
+        sd.sim_params.diagnostics = true;
+        let err = sd.sim_drive(None, None).unwrap_err();
+        let fault = match err.downcast_ref::<FastSimError>() {
+            Some(FastSimError::SimFault(fault)) => fault.clone(),
+            _ => panic!("expected `FastSimError::SimFault`, got {err:?}"),
+        };
+        assert_eq!(fault.component, "fuel_converter");
+        assert_eq!(fault.variable, "fc_kw_in_ach");
+        assert_eq!(fault.kind, FaultKind::NaN);
+        // simulation stopped at the offending step
+        assert_eq!(sd.i, fault.i);
+        let recorded = sd.diagnostics.as_ref().unwrap().fault.as_ref().unwrap();
+        assert_eq!((recorded.i, &recorded.variable), (fault.i, &fault.variable));
+    }
+}
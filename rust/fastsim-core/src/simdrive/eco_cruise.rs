@@ -0,0 +1,642 @@
+//! Module for predictive eco-cruise: replanning the speed trace between each
+//! pair of stops by dynamic programming over distance, using the upcoming
+//! grade, stop locations, and speed limits, and comparing the energy use and
+//! trip time of the replanned trace against the original cycle
+
+use super::RustSimDrive;
+use crate::cycle::{RustCycle, RustCycleCache};
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::{PowertrainType, RustVehicle};
+
+/// Speed at or below which the vehicle is considered stopped \[m/s\], as in
+/// [crate::cycle::to_microtrips]
+pub(super) const STOP_SPEED_MPS: f64 = 1e-6;
+
+/// Number of bisection iterations on the weight of travel time
+const TIME_WEIGHT_ITERS: usize = 24;
+
+/// Largest weight of travel time tried \[kJ/s\]; beyond this the fastest
+/// feasible trajectory is used
+const MAX_TIME_WEIGHT_KJ_PER_S: f64 = 1e4;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (
+        max_time_penalty_frac=0.05,
+        dist_step_m=25.0,
+        speed_step_mps=0.5,
+        accel_max_m_per_s2=1.0,
+        decel_max_m_per_s2=1.5,
+        speed_limits_by_distance_m=None,
+        dt_s=1.0,
+    ))]
+    pub fn __new__(
+        max_time_penalty_frac: f64,
+        dist_step_m: f64,
+        speed_step_mps: f64,
+        accel_max_m_per_s2: f64,
+        decel_max_m_per_s2: f64,
+        speed_limits_by_distance_m: Option<Vec<(f64, f64)>>,
+        dt_s: f64,
+    ) -> anyhow::Result<Self> {
+        let mut eco = Self {
+            max_time_penalty_frac,
+            dist_step_m,
+            speed_step_mps,
+            accel_max_m_per_s2,
+            decel_max_m_per_s2,
+            speed_limits_by_distance_m,
+            dt_s,
+            orphaned: false,
+        };
+        eco.init()?;
+        Ok(eco)
+    }
+
+    #[pyo3(name = "plan")]
+    /// Returns `cyc` with the speed trace between each pair of stops replanned
+    /// to minimize the energy use of `veh`
+    pub fn plan_py(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<RustCycle> {
+        self.plan(veh, cyc)
+    }
+
+    #[pyo3(name = "run")]
+    /// Plans an eco-cruise trace for `veh` over `cyc`, then simulates `veh`
+    /// over both traces
+    pub fn run_py(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<EcoCruiseResults> {
+        self.run(veh, cyc)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+/// Predictive eco-cruise planner.  Between each pair of stops in the original
+/// cycle, finds the speed trajectory over distance that minimizes estimated
+/// energy use plus a weight on travel time, with the weight chosen so that
+/// travel time grows by at most `max_time_penalty_frac`.  Stops and the time
+/// spent stopped are kept as in the original cycle, as is any portion before
+/// the first or after the last stop.
+pub struct PredictiveEcoCruise {
+    /// allowed increase in travel time between each pair of stops, as a
+    /// fraction of the original travel time
+    pub max_time_penalty_frac: f64,
+    /// distance step of the planning grid \[m\]
+    pub dist_step_m: f64,
+    /// speed step of the planning grid \[m/s\]
+    pub speed_step_mps: f64,
+    /// maximum acceleration \[m/s^2\]
+    pub accel_max_m_per_s2: f64,
+    /// maximum deceleration, positive \[m/s^2\]
+    pub decel_max_m_per_s2: f64,
+    /// speed limits as (distance from start \[m\], speed limit \[m/s\]) pairs,
+    /// each applying until the next, in the style of
+    /// `idm_v_desired_in_m_per_s_by_distance_m`.  Defaults to the highest
+    /// speed of the original cycle between each pair of stops.
+    pub speed_limits_by_distance_m: Option<Vec<(f64, f64)>>,
+    /// time step of the planned cycle \[s\]
+    pub dt_s: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for PredictiveEcoCruise {
+    fn default() -> Self {
+        Self {
+            max_time_penalty_frac: 0.05,
+            dist_step_m: 25.0,
+            speed_step_mps: 0.5,
+            accel_max_m_per_s2: 1.0,
+            decel_max_m_per_s2: 1.5,
+            speed_limits_by_distance_m: None,
+            dt_s: 1.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for PredictiveEcoCruise {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_time_penalty_frac >= 0.0,
+            "`max_time_penalty_frac` must be non-negative, got {}",
+            self.max_time_penalty_frac
+        );
+        for (name, value) in [
+            ("dist_step_m", self.dist_step_m),
+            ("speed_step_mps", self.speed_step_mps),
+            ("accel_max_m_per_s2", self.accel_max_m_per_s2),
+            ("decel_max_m_per_s2", self.decel_max_m_per_s2),
+            ("dt_s", self.dt_s),
+        ] {
+            ensure!(
+                value.is_finite() && value > 0.0,
+                "`{name}` must be positive, got {value}"
+            );
+        }
+        if let Some(limits) = &self.speed_limits_by_distance_m {
+            ensure!(!limits.is_empty(), "`speed_limits_by_distance_m` is empty");
+            ensure!(
+                limits.windows(2).all(|w| w[0].0 <= w[1].0),
+                "`speed_limits_by_distance_m` must be sorted by distance"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Stop-to-stop portion of a cycle, from the last stopped sample before
+/// departure to the first stopped sample after arrival
+#[derive(Clone, Debug)]
+struct Segment {
+    i_depart: usize,
+    i_arrive: usize,
+    start_m: f64,
+    length_m: f64,
+    time_s: f64,
+    max_speed_mps: f64,
+}
+
+/// Speeds at evenly spaced distances from one stop to the next
+#[derive(Clone, Debug)]
+struct SegmentPlan {
+    step_m: f64,
+    speeds_mps: Vec<f64>,
+}
+
+impl SegmentPlan {
+    /// Travel time \[s\]
+    fn time_s(&self) -> f64 {
+        self.speeds_mps
+            .windows(2)
+            .map(|v| 2.0 * self.step_m / (v[0] + v[1]))
+            .sum()
+    }
+
+    /// Speeds every `dt_s` after departure and before arrival, with constant
+    /// acceleration over each distance step
+    fn resample(&self, dt_s: f64) -> Vec<f64> {
+        let mut samples = vec![];
+        let mut t_step_start = 0.0;
+        let mut t = dt_s;
+        for v in self.speeds_mps.windows(2) {
+            let step_s = 2.0 * self.step_m / (v[0] + v[1]);
+            let accel = (v[1] - v[0]) / step_s;
+            while t < t_step_start + step_s {
+                samples.push(v[0] + accel * (t - t_step_start));
+                t += dt_s;
+            }
+            t_step_start += step_s;
+        }
+        samples
+    }
+}
+
+/// Simplified vehicle model used to estimate energy use while planning
+struct RoadLoad {
+    mass_kg: f64,
+    /// aerodynamic drag force per speed squared \[N/(m/s)^2\]
+    drag_n_per_mps2: f64,
+    wheel_rr_coef: f64,
+    a_grav_mps2: f64,
+    aux_kw: f64,
+    /// efficiency from stored energy to the wheels
+    drive_eff: f64,
+    /// fraction of braking energy at the wheels recovered to storage
+    regen_eff: f64,
+}
+
+impl RoadLoad {
+    fn new(veh: &RustVehicle) -> Self {
+        let (drive_eff, regen_eff) = match veh.veh_pt_type {
+            PowertrainType::Conv => (veh.fc_peak_eff() * veh.trans_eff, 0.0),
+            PowertrainType::HEV => (
+                veh.fc_peak_eff() * veh.trans_eff,
+                veh.max_regen * veh.mc_peak_eff() * veh.trans_eff,
+            ),
+            PowertrainType::PHEV | PowertrainType::BEV => (
+                veh.mc_peak_eff() * veh.trans_eff,
+                veh.max_regen * veh.mc_peak_eff() * veh.trans_eff,
+            ),
+        };
+        Self {
+            mass_kg: veh.veh_kg,
+            drag_n_per_mps2: 0.5
+                * veh.props.air_density_kg_per_m3
+                * veh.drag_coef
+                * veh.frontal_area_m2,
+            wheel_rr_coef: veh.wheel_rr_coef,
+            a_grav_mps2: veh.props.a_grav_mps2,
+            aux_kw: veh.aux_kw,
+            drive_eff,
+            regen_eff,
+        }
+    }
+
+    /// Estimated energy drawn from storage \[kJ\] to go from `v0` to `v1`
+    /// over `dist_m` on `grade` in `dt_s`, with constant acceleration
+    fn energy_kj(&self, v0: f64, v1: f64, dist_m: f64, grade: f64, dt_s: f64) -> f64 {
+        let theta = grade.atan();
+        let wheel_kj = (0.5 * self.mass_kg * (v1 * v1 - v0 * v0)
+            + self.mass_kg * self.a_grav_mps2 * (theta.sin() + self.wheel_rr_coef * theta.cos())
+                * dist_m
+            // speed squared varies linearly with distance at constant acceleration
+            + self.drag_n_per_mps2 * 0.5 * (v0 * v0 + v1 * v1) * dist_m)
+            / 1e3;
+        let wheel_kj = if wheel_kj > 0.0 {
+            wheel_kj / self.drive_eff
+        } else {
+            wheel_kj * self.regen_eff
+        };
+        wheel_kj + self.aux_kw * dt_s / self.drive_eff
+    }
+}
+
+impl PredictiveEcoCruise {
+    /// Stop-to-stop segments of `cyc`
+    fn segments(&self, cyc: &RustCycle, cache: &RustCycleCache) -> Vec<Segment> {
+        let mut segments = vec![];
+        let mut i_depart = None;
+        for i in 1..cyc.len() {
+            let stopped = cyc.mps[i] <= STOP_SPEED_MPS;
+            match i_depart {
+                None if !stopped && cyc.mps[i - 1] <= STOP_SPEED_MPS => i_depart = Some(i - 1),
+                Some(i0) if stopped => {
+                    let start_m = cache.trapz_distances_m[i0];
+                    segments.push(Segment {
+                        i_depart: i0,
+                        i_arrive: i,
+                        start_m,
+                        length_m: cyc.calc_distance_to_next_stop_from(start_m, Some(cache)),
+                        time_s: cyc.time_s[i] - cyc.time_s[i0],
+                        max_speed_mps: cyc
+                            .mps
+                            .slice(s![i0..=i])
+                            .fold(0.0, |max, v| f64::max(max, *v)),
+                    });
+                    i_depart = None;
+                }
+                _ => {}
+            }
+        }
+        segments
+    }
+
+    /// Speed limit at `dist_m` within `seg`, from `speed_limits_by_distance_m`
+    /// if provided, with the first limit also applying before its distance
+    fn speed_limit(&self, seg: &Segment, dist_m: f64) -> f64 {
+        match &self.speed_limits_by_distance_m {
+            Some(limits) => {
+                let k = limits.partition_point(|(d, _)| *d <= dist_m);
+                limits[k.saturating_sub(1)].1
+            }
+            None => seg.max_speed_mps,
+        }
+    }
+
+    /// Trajectory over `seg` minimizing estimated energy plus `time_weight`
+    /// times travel time, or `None` if no trajectory satisfies the
+    /// acceleration limits
+    fn optimize_segment(
+        &self,
+        model: &RoadLoad,
+        cache: &RustCycleCache,
+        seg: &Segment,
+        time_weight: f64,
+    ) -> Option<SegmentPlan> {
+        let n = ((seg.length_m / self.dist_step_m).ceil() as usize).max(2);
+        let step_m = seg.length_m / n as f64;
+        let v_max = self
+            .speed_limits_by_distance_m
+            .as_ref()
+            .map_or(seg.max_speed_mps, |limits| {
+                limits.iter().fold(0.0, |max, (_, v)| f64::max(max, *v))
+            });
+        let speeds: Vec<f64> = (0..=(v_max / self.speed_step_mps).floor() as usize)
+            .map(|k| k as f64 * self.speed_step_mps)
+            .collect();
+        let allowed = |j: usize| -> usize {
+            if j == 0 || j == n {
+                // stopped at both ends
+                1
+            } else {
+                let limit = self.speed_limit(seg, seg.start_m + j as f64 * step_m);
+                speeds.partition_point(|v| *v <= limit + 1e-9)
+            }
+        };
+
+        // cost to reach each speed at each distance, and the previous speed
+        let mut cost = vec![f64::INFINITY; speeds.len()];
+        cost[0] = 0.0;
+        let mut prev: Vec<Vec<usize>> = Vec::with_capacity(n);
+        for j in 0..n {
+            let grade = cache.interp_grade(seg.start_m + (j as f64 + 0.5) * step_m);
+            let mut next_cost = vec![f64::INFINITY; speeds.len()];
+            let mut next_prev = vec![0; speeds.len()];
+            for (k1, v1) in speeds.iter().enumerate().take(allowed(j + 1)) {
+                for (k0, v0) in speeds.iter().enumerate().take(allowed(j)) {
+                    if !cost[k0].is_finite() || v0 + v1 <= 0.0 {
+                        continue;
+                    }
+                    let accel = (v1 * v1 - v0 * v0) / (2.0 * step_m);
+                    if accel > self.accel_max_m_per_s2 || -accel > self.decel_max_m_per_s2 {
+                        continue;
+                    }
+                    let dt_s = 2.0 * step_m / (v0 + v1);
+                    let c = cost[k0]
+                        + model.energy_kj(*v0, *v1, step_m, grade, dt_s)
+                        + time_weight * dt_s;
+                    if c < next_cost[k1] {
+                        next_cost[k1] = c;
+                        next_prev[k1] = k0;
+                    }
+                }
+            }
+            cost = next_cost;
+            prev.push(next_prev);
+        }
+        if !cost[0].is_finite() {
+            return None;
+        }
+
+        let mut k = 0;
+        let mut speeds_mps = vec![0.0; n + 1];
+        for j in (0..n).rev() {
+            k = prev[j][k];
+            speeds_mps[j] = speeds[k];
+        }
+        Some(SegmentPlan { step_m, speeds_mps })
+    }
+
+    /// Lowest-energy trajectory over `seg` whose travel time is within
+    /// `max_time_penalty_frac` of the original, or the fastest trajectory if
+    /// none is, or `None` if no trajectory satisfies the acceleration limits
+    fn plan_segment(
+        &self,
+        model: &RoadLoad,
+        cache: &RustCycleCache,
+        seg: &Segment,
+    ) -> Option<SegmentPlan> {
+        let max_time_s = seg.time_s * (1.0 + self.max_time_penalty_frac);
+        let plan = self.optimize_segment(model, cache, seg, 0.0)?;
+        if plan.time_s() <= max_time_s {
+            return Some(plan);
+        }
+        // find a weight on time that is large enough
+        let mut lo = 0.0;
+        let mut hi = 1.0;
+        let mut best = loop {
+            let plan = self.optimize_segment(model, cache, seg, hi)?;
+            if plan.time_s() <= max_time_s || hi >= MAX_TIME_WEIGHT_KJ_PER_S {
+                break plan;
+            }
+            lo = hi;
+            hi *= 4.0;
+        };
+        // then the smallest such weight
+        for _ in 0..TIME_WEIGHT_ITERS {
+            let mid = 0.5 * (lo + hi);
+            let plan = self.optimize_segment(model, cache, seg, mid)?;
+            if plan.time_s() <= max_time_s {
+                hi = mid;
+                best = plan;
+            } else {
+                lo = mid;
+            }
+        }
+        Some(best)
+    }
+
+    /// Returns `cyc` with the speed trace between each pair of stops replanned
+    /// to minimize the energy use of `veh`.  Grade in the returned cycle is
+    /// looked up by distance, so it matches `cyc` along the route.  `cyc`
+    /// must have a constant time step of `dt_s`, as its samples outside the
+    /// replanned portions are copied unchanged.
+    pub fn plan(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<RustCycle> {
+        ensure!(cyc.len() >= 2, "cycle must have at least 2 samples");
+        ensure!(
+            cyc.dt_s()
+                .iter()
+                .skip(1)
+                .all(|dt| (dt - self.dt_s).abs() < 1e-9),
+            "{}\ncycle time step must equal `dt_s` everywhere; resample the cycle first",
+            format_dbg!(self.dt_s)
+        );
+        let cache = cyc.build_cache();
+        let model = RoadLoad::new(veh);
+
+        let mut mps: Vec<f64> = vec![];
+        let mut i_next = 0;
+        for seg in self.segments(cyc, &cache) {
+            mps.extend(cyc.mps.slice(s![i_next..=seg.i_depart]));
+            match self.plan_segment(&model, &cache, &seg) {
+                Some(plan) => mps.extend(plan.resample(self.dt_s)),
+                None => mps.extend(cyc.mps.slice(s![seg.i_depart + 1..seg.i_arrive])),
+            }
+            i_next = seg.i_arrive;
+        }
+        mps.extend(cyc.mps.slice(s![i_next..]));
+
+        Ok(cycle_from_speeds(
+            cyc,
+            &cache,
+            mps,
+            self.dt_s,
+            format!("{} eco-cruise", cyc.name),
+        ))
+    }
+
+    /// Plans an eco-cruise trace for `veh` over `cyc`, then simulates `veh`
+    /// over both traces
+    pub fn run(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<EcoCruiseResults> {
+        let eco_cyc = self.plan(veh, cyc)?;
+        let mut sd_baseline = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_baseline
+            .sim_drive(None, None)
+            .context("baseline simulation failed")?;
+        let mut sd_eco = RustSimDrive::new(eco_cyc, veh.clone());
+        sd_eco
+            .sim_drive(None, None)
+            .context("eco-cruise simulation failed")?;
+        Ok(EcoCruiseResults::new(sd_baseline, sd_eco))
+    }
+}
+
+/// Cycle with speeds `mps` every `dt_s` from the start of `cyc`, and with
+/// grade and road type looked up by distance along `cyc`
+pub(super) fn cycle_from_speeds(
+    cyc: &RustCycle,
+    cache: &RustCycleCache,
+    mps: Vec<f64>,
+    dt_s: f64,
+    name: String,
+) -> RustCycle {
+    let time_s: Vec<f64> = (0..mps.len())
+        .map(|i| cyc.time_s[0] + i as f64 * dt_s)
+        .collect();
+    let mut grade = vec![cyc.grade[0]; mps.len()];
+    let mut road_type = vec![cyc.road_type[0]; mps.len()];
+    let mut dist_m = 0.0;
+    // index of the first sample of `cyc` at or beyond `dist_m`, which only
+    // advances as distance is non-decreasing
+    let mut i_cyc = 0;
+    for i in 1..mps.len() {
+        let step_m = 0.5 * (mps[i - 1] + mps[i]) * dt_s;
+        grade[i] = cache.interp_grade(dist_m + 0.5 * step_m);
+        dist_m += step_m;
+        while i_cyc < cyc.len() - 1 && cache.trapz_distances_m[i_cyc] < dist_m {
+            i_cyc += 1;
+        }
+        road_type[i] = cyc.road_type[i_cyc];
+    }
+
+    RustCycle {
+        time_s: time_s.into(),
+        mps: mps.into(),
+        grade: grade.into(),
+        road_type: road_type.into(),
+        name,
+        orphaned: false,
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Comparison of [PredictiveEcoCruise] against the original cycle
+pub struct EcoCruiseResults {
+    /// fuel plus net battery energy used over the original cycle \[kJ\]
+    pub baseline_energy_kj: f64,
+    /// fuel plus net battery energy used over the eco-cruise cycle \[kJ\]
+    pub eco_energy_kj: f64,
+    /// energy saved as a fraction of `baseline_energy_kj`
+    pub energy_savings_frac: f64,
+    /// duration of the original cycle \[s\]
+    pub baseline_time_s: f64,
+    /// duration of the eco-cruise cycle \[s\]
+    pub eco_time_s: f64,
+    /// additional trip time \[s\]
+    pub time_penalty_s: f64,
+    /// additional trip time as a fraction of `baseline_time_s`
+    pub time_penalty_frac: f64,
+    /// distance achieved over the original cycle \[m\]
+    pub baseline_dist_m: f64,
+    /// distance achieved over the eco-cruise cycle \[m\]
+    pub eco_dist_m: f64,
+    /// simulation over the original cycle
+    #[api(has_orphaned)]
+    pub sd_baseline: RustSimDrive,
+    /// simulation over the eco-cruise cycle, whose `cyc0` is the planned cycle
+    #[api(has_orphaned)]
+    pub sd_eco: RustSimDrive,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for EcoCruiseResults {}
+
+impl EcoCruiseResults {
+    fn new(sd_baseline: RustSimDrive, sd_eco: RustSimDrive) -> Self {
+        let energy_kj = |sd: &RustSimDrive| sd.fuel_kj + sd.ess_dischg_kj;
+        let duration_s = |sd: &RustSimDrive| sd.cyc0.time_s[sd.cyc0.len() - 1] - sd.cyc0.time_s[0];
+        let baseline_energy_kj = energy_kj(&sd_baseline);
+        let eco_energy_kj = energy_kj(&sd_eco);
+        let baseline_time_s = duration_s(&sd_baseline);
+        let eco_time_s = duration_s(&sd_eco);
+        Self {
+            baseline_energy_kj,
+            eco_energy_kj,
+            energy_savings_frac: 1.0 - eco_energy_kj / baseline_energy_kj,
+            baseline_time_s,
+            eco_time_s,
+            time_penalty_s: eco_time_s - baseline_time_s,
+            time_penalty_frac: eco_time_s / baseline_time_s - 1.0,
+            baseline_dist_m: sd_baseline.dist_m.sum(),
+            eco_dist_m: sd_eco.dist_m.sum(),
+            sd_baseline,
+            sd_eco,
+            orphaned: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eco_cruise_saves_energy_within_time_budget() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let eco = PredictiveEcoCruise::default();
+        let res = eco.run(&veh, &cyc).unwrap();
+
+        assert!(
+            res.energy_savings_frac > 0.05,
+            "{}",
+            res.energy_savings_frac
+        );
+        // each stop-to-stop segment may round up by one time step
+        let n_stops = eco.segments(&cyc, &cyc.build_cache()).len();
+        assert!(
+            res.time_penalty_s <= eco.max_time_penalty_frac * res.baseline_time_s + n_stops as f64
+        );
+        assert!(res
+            .eco_dist_m
+            .approx_eq(&res.baseline_dist_m, 0.01 * res.baseline_dist_m));
+        // stops are kept
+        assert_eq!(
+            eco.segments(&res.sd_eco.cyc0, &res.sd_eco.cyc0.build_cache())
+                .len(),
+            n_stops
+        );
+    }
+
+    #[test]
+    fn test_eco_cruise_respects_speed_limits_and_grade() {
+        let veh = RustVehicle::mock_vehicle();
+        let mut cyc = RustCycle::from_resource("hwfet.csv", false).unwrap();
+        cyc.grade =
+            Array1::from_iter(
+                (0..cyc.len()).map(|i| if (200..400).contains(&i) { 0.03 } else { 0.0 }),
+            );
+        let limit_mps = 22.0;
+        let eco = PredictiveEcoCruise {
+            speed_limits_by_distance_m: Some(vec![(0.0, limit_mps)]),
+            max_time_penalty_frac: 0.2,
+            ..Default::default()
+        };
+        let planned = eco.plan(&veh, &cyc).unwrap();
+        assert!(planned.mps.iter().all(|v| *v <= limit_mps + 1e-9));
+        let cache = cyc.build_cache();
+        let planned_cache = planned.build_cache();
+        for d in [1000.0, 3000.0, 5000.0, 8000.0] {
+            assert_eq!(planned_cache.interp_grade(d), cache.interp_grade(d));
+        }
+
+        // explicit limits replace, rather than blend with, the original
+        // cycle's top speed
+        let seg = Segment {
+            i_depart: 0,
+            i_arrive: 1,
+            start_m: 0.0,
+            length_m: 1e3,
+            time_s: 100.0,
+            max_speed_mps: 10.0,
+        };
+        let eco = PredictiveEcoCruise {
+            speed_limits_by_distance_m: Some(vec![(50.0, 5.0), (100.0, 20.0)]),
+            ..Default::default()
+        };
+        assert_eq!(eco.speed_limit(&seg, 0.0), 5.0);
+        assert_eq!(eco.speed_limit(&seg, 75.0), 5.0);
+        assert_eq!(eco.speed_limit(&seg, 150.0), 20.0);
+
+        // samples are copied, so the cycle must match the planned time step
+        let eco = PredictiveEcoCruise {
+            dt_s: 0.5,
+            ..Default::default()
+        };
+        assert!(eco.plan(&veh, &cyc).is_err());
+    }
+}
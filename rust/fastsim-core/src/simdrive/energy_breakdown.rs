@@ -0,0 +1,371 @@
+//! Module containing a structured breakdown of where energy comes from and
+//! goes to over a drive cycle, e.g. for Sankey diagrams
+
+use std::fmt;
+
+use super::RustSimDrive;
+use crate::cycle::to_microtrips;
+use crate::imports::*;
+use crate::proc_macros::{add_pyo3_api, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+#[add_pyo3_api(
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, FieldPath)]
+/// Energy \[kJ\] flowing from node `source` to node `sink` of an
+/// [EnergyBreakdown]
+pub struct EnergyFlow {
+    #[api(skip_set)]
+    pub source: String,
+    #[api(skip_set)]
+    pub sink: String,
+    #[api(skip_set)]
+    pub kj: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for EnergyFlow {}
+
+impl fmt::Display for EnergyFlow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}: {:.1} kJ", self.source, self.sink, self.kj)
+    }
+}
+
+#[add_pyo3_api(
+    /// Fractional difference between total sources and total sinks
+    #[getter]
+    pub fn get_audit_error(&self) -> f64 {
+        self.audit_error()
+    }
+
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, FieldPath)]
+/// Source-to-sink energy breakdown \[kJ\] over time steps `i_start..=i_end` of a
+/// [RustSimDrive].  Source and sink totals match the scalars set by
+/// [RustSimDrive::set_post_scalars], e.g. `drag_kj`, and `flows` traces
+/// energy from fuel, roadway charging, and the ESS through the powertrain to
+/// the wheels and road loads.
+pub struct EnergyBreakdown {
+    /// index of first time step, whose state is the starting point
+    #[api(skip_set)]
+    pub i_start: usize,
+    /// index of last time step
+    #[api(skip_set)]
+    pub i_end: usize,
+    /// achieved distance \[m\]
+    #[api(skip_set)]
+    pub dist_m: f64,
+
+    // sources
+    /// fuel energy into fuel converter
+    #[api(skip_set)]
+    pub fuel_kj: f64,
+    /// roadway charging energy into powertrain
+    #[api(skip_set)]
+    pub roadway_chg_kj: f64,
+    /// net ESS discharge energy, calculated from SOC, negative if net charging
+    #[api(skip_set)]
+    pub ess_dischg_kj: f64,
+    /// net kinetic energy released by slowing down, negative if speeding up
+    #[api(skip_set)]
+    pub ke_kj: f64,
+
+    // intermediate flows
+    /// fuel converter shaft output
+    #[api(skip_set)]
+    pub fc_out_kj: f64,
+    /// gross ESS discharge to powertrain
+    #[api(skip_set)]
+    pub ess_out_kj: f64,
+    /// gross ESS charge from powertrain
+    #[api(skip_set)]
+    pub ess_in_kj: f64,
+    /// gross transmission output to wheels while propelling
+    #[api(skip_set)]
+    pub wheel_out_kj: f64,
+    /// gross energy from wheels back into transmission while regenerating
+    #[api(skip_set)]
+    pub regen_kj: f64,
+
+    // sinks
+    /// fuel converter losses
+    #[api(skip_set)]
+    pub fc_loss_kj: f64,
+    /// motor losses
+    #[api(skip_set)]
+    pub mc_loss_kj: f64,
+    /// ESS losses
+    #[api(skip_set)]
+    pub ess_loss_kj: f64,
+    /// transmission losses
+    #[api(skip_set)]
+    pub trans_loss_kj: f64,
+    /// auxiliary loads
+    #[api(skip_set)]
+    pub aux_kj: f64,
+    /// aerodynamic drag
+    #[api(skip_set)]
+    pub drag_kj: f64,
+    /// rolling resistance
+    #[api(skip_set)]
+    pub rr_kj: f64,
+    /// net potential energy gained by climbing, negative if net descending
+    #[api(skip_set)]
+    pub ascent_kj: f64,
+    /// friction brakes
+    #[api(skip_set)]
+    pub brake_kj: f64,
+
+    /// nonzero flows between nodes, e.g. `fuel` -> `fuel_converter`
+    #[api(skip_set)]
+    pub flows: Vec<EnergyFlow>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for EnergyBreakdown {}
+
+impl fmt::Display for EnergyBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "energy breakdown for time steps {}..={} ({:.1} m)",
+            self.i_start, self.i_end, self.dist_m
+        )?;
+        for flow in &self.flows {
+            writeln!(f, "  {flow}")?;
+        }
+        write!(
+            f,
+            "  sources: {:.1} kJ, sinks: {:.1} kJ",
+            self.sources_kj(),
+            self.sinks_kj()
+        )
+    }
+}
+
+/// Sums of `arr * dt_s` over time steps `i_start + 1..=i_end`, split into
+/// positive and negative parts, with the negative part reported as positive
+fn sum_parts(arr: &Array1<f64>, dt_s: &Array1<f64>, i_start: usize, i_end: usize) -> (f64, f64) {
+    (i_start + 1..=i_end).fold((0.0, 0.0), |(pos, neg), i| {
+        let kj = arr[i] * dt_s[i];
+        if kj >= 0.0 {
+            (pos + kj, neg)
+        } else {
+            (pos, neg - kj)
+        }
+    })
+}
+
+fn sum(arr: &Array1<f64>, dt_s: &Array1<f64>, i_start: usize, i_end: usize) -> f64 {
+    let (pos, neg) = sum_parts(arr, dt_s, i_start, i_end);
+    pos - neg
+}
+
+impl EnergyBreakdown {
+    /// Total energy from fuel, roadway charging, ESS, and kinetic energy
+    pub fn sources_kj(&self) -> f64 {
+        self.fuel_kj + self.roadway_chg_kj + self.ess_dischg_kj + self.ke_kj
+    }
+
+    /// Total losses, auxiliary loads, and road loads
+    pub fn sinks_kj(&self) -> f64 {
+        self.fc_loss_kj
+            + self.mc_loss_kj
+            + self.ess_loss_kj
+            + self.trans_loss_kj
+            + self.aux_kj
+            + self.drag_kj
+            + self.rr_kj
+            + self.ascent_kj
+            + self.brake_kj
+    }
+
+    /// Fractional difference between [Self::sources_kj] and [Self::sinks_kj],
+    /// equivalent to `RustSimDrive::energy_audit_error` over the full cycle
+    pub fn audit_error(&self) -> f64 {
+        let sources_kj = self.sources_kj();
+        if sources_kj == 0.0 {
+            0.0
+        } else {
+            (sources_kj - self.sinks_kj()) / sources_kj
+        }
+    }
+
+    /// Adds a flow, reversing it if `kj` is negative and skipping it if zero
+    fn push_flow(&mut self, source: &str, sink: &str, kj: f64) {
+        let (source, sink, kj) = if kj < 0.0 {
+            (sink, source, -kj)
+        } else {
+            (source, sink, kj)
+        };
+        if kj > 0.0 {
+            self.flows.push(EnergyFlow {
+                source: source.into(),
+                sink: sink.into(),
+                kj,
+                orphaned: false,
+            });
+        }
+    }
+
+    fn set_flows(&mut self) {
+        self.flows.clear();
+        self.push_flow("fuel", "fuel_converter", self.fuel_kj);
+        self.push_flow("fuel_converter", "fuel_converter_loss", self.fc_loss_kj);
+        self.push_flow("fuel_converter", "powertrain", self.fc_out_kj);
+        self.push_flow("roadway_charger", "powertrain", self.roadway_chg_kj);
+        self.push_flow("ess", "powertrain", self.ess_out_kj);
+        self.push_flow("powertrain", "ess", self.ess_in_kj);
+        self.push_flow("ess", "ess_loss", self.ess_loss_kj);
+        self.push_flow("powertrain", "motor_loss", self.mc_loss_kj);
+        self.push_flow("powertrain", "aux", self.aux_kj);
+        self.push_flow("powertrain", "transmission_loss", self.trans_loss_kj);
+        self.push_flow("powertrain", "wheels", self.wheel_out_kj);
+        self.push_flow("wheels", "powertrain", self.regen_kj);
+        self.push_flow("kinetic_energy", "wheels", self.ke_kj);
+        self.push_flow("wheels", "drag", self.drag_kj);
+        self.push_flow("wheels", "rolling_resistance", self.rr_kj);
+        self.push_flow("wheels", "grade", self.ascent_kj);
+        self.push_flow("wheels", "friction_brakes", self.brake_kj);
+    }
+}
+
+impl RustSimDrive {
+    /// Returns the [EnergyBreakdown] over time steps `i_start..=i_end`.  Must
+    /// be called after [RustSimDrive::set_post_scalars], which sets `ess_loss_kw`.
+    pub fn energy_breakdown_for(
+        &self,
+        i_start: usize,
+        i_end: usize,
+    ) -> anyhow::Result<EnergyBreakdown> {
+        ensure!(
+            i_start <= i_end && i_end < self.len(),
+            "invalid time step range {i_start}..={i_end} for cycle of length {}",
+            self.len()
+        );
+        let dt_s = self.cyc.dt_s();
+        let (ess_out_kj, ess_in_kj) = sum_parts(&self.ess_kw_out_ach, &dt_s, i_start, i_end);
+        let (wheel_out_kj, regen_kj) = sum_parts(&self.trans_kw_out_ach, &dt_s, i_start, i_end);
+        let mut breakdown = EnergyBreakdown {
+            i_start,
+            i_end,
+            dist_m: self.dist_m.slice(s![i_start + 1..=i_end]).sum(),
+            fuel_kj: sum(&self.fs_kw_out_ach, &dt_s, i_start, i_end),
+            roadway_chg_kj: sum(&self.roadway_chg_kw_out_ach, &dt_s, i_start, i_end),
+            ess_dischg_kj: -(self.soc[i_end] - self.soc[i_start]) * self.veh.ess_max_kwh * 3.6e3,
+            ke_kj: 0.5
+                * self.veh.veh_kg
+                * (self.mps_ach[i_start].powi(2) - self.mps_ach[i_end].powi(2))
+                / 1_000.0,
+            fc_out_kj: sum(&self.fc_kw_out_ach, &dt_s, i_start, i_end),
+            ess_out_kj,
+            ess_in_kj,
+            wheel_out_kj,
+            regen_kj,
+            fc_loss_kj: sum(
+                &(&self.fc_kw_in_ach - &self.fc_kw_out_ach),
+                &dt_s,
+                i_start,
+                i_end,
+            ),
+            mc_loss_kj: sum(
+                &(&self.mc_elec_kw_in_ach - &self.mc_mech_kw_out_ach),
+                &dt_s,
+                i_start,
+                i_end,
+            ),
+            ess_loss_kj: sum(&self.ess_loss_kw, &dt_s, i_start, i_end),
+            trans_loss_kj: sum(
+                &(&self.trans_kw_in_ach - &self.trans_kw_out_ach),
+                &dt_s,
+                i_start,
+                i_end,
+            ),
+            aux_kj: sum(&self.aux_in_kw, &dt_s, i_start, i_end),
+            drag_kj: sum(&self.drag_kw, &dt_s, i_start, i_end),
+            rr_kj: sum(&self.rr_kw, &dt_s, i_start, i_end),
+            ascent_kj: sum(&self.ascent_kw, &dt_s, i_start, i_end),
+            brake_kj: sum(&self.cyc_fric_brake_kw, &dt_s, i_start, i_end),
+            flows: Vec::new(),
+            orphaned: false,
+        };
+        breakdown.set_flows();
+        Ok(breakdown)
+    }
+
+    /// Returns the [EnergyBreakdown] for each microtrip of `cyc0`, as split by
+    /// [to_microtrips](crate::cycle::to_microtrips).  Consecutive microtrips
+    /// share their boundary time step, so the breakdowns sum to the full-cycle
+    /// breakdown.
+    pub fn energy_breakdown_by_microtrip(
+        &self,
+        stop_speed_m_per_s: Option<f64>,
+    ) -> anyhow::Result<Vec<EnergyBreakdown>> {
+        let mut i_start = 0;
+        to_microtrips(&self.cyc0, stop_speed_m_per_s)
+            .iter()
+            .map(|microtrip| {
+                let i_end = i_start + microtrip.len().saturating_sub(1);
+                let breakdown = self.energy_breakdown_for(i_start, i_end)?;
+                i_start = i_end;
+                Ok(breakdown)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_energy_breakdown_matches_post_scalars() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let veh = RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        let mut sd = RustSimDrive::new(cyc, veh);
+        sd.sim_drive(None, None).unwrap();
+
+        let total = sd.energy_breakdown.clone().unwrap();
+        assert_eq!((total.i_start, total.i_end), (0, sd.len() - 1));
+        assert!(total.fuel_kj.approx_eq(&sd.fuel_kj, 1e-9));
+        assert!(total.drag_kj.approx_eq(&sd.drag_kj, 1e-9));
+        assert!(total.brake_kj.approx_eq(&sd.brake_kj, 1e-9));
+        assert!(total.fc_loss_kj.approx_eq(&sd.fc_kj, 1e-9));
+        assert!(total.ess_loss_kj.approx_eq(&sd.ess_eff_kj, 1e-9));
+        assert!(total.audit_error().approx_eq(&sd.energy_audit_error, 1e-9));
+        assert!(total.ess_out_kj > 0.0 && total.ess_in_kj > 0.0);
+        assert!(total
+            .flows
+            .iter()
+            .any(|flow| flow.source == "wheels" && flow.sink == "powertrain"));
+        assert!(total.flows.iter().all(|flow| flow.kj > 0.0));
+
+        let microtrips = sd.energy_breakdown_by_microtrip(None).unwrap();
+        assert!(microtrips.len() > 1);
+        assert_eq!(microtrips.last().unwrap().i_end, total.i_end);
+        let microtrip_sum = |f: fn(&EnergyBreakdown) -> f64| microtrips.iter().map(f).sum::<f64>();
+        assert!(microtrip_sum(|b| b.fuel_kj).approx_eq(&total.fuel_kj, 1e-9));
+        assert!(microtrip_sum(|b| b.drag_kj).approx_eq(&total.drag_kj, 1e-9));
+        assert!(microtrip_sum(|b| b.ess_dischg_kj).approx_eq(&total.ess_dischg_kj, 1e-9));
+        assert!(microtrip_sum(|b| b.dist_m).approx_eq(&total.dist_m, 1e-9));
+
+        let round_trip = EnergyBreakdown::from_yaml(total.to_yaml().unwrap(), false).unwrap();
+        assert_eq!(round_trip, total);
+    }
+}
@@ -0,0 +1,532 @@
+//! Module containing hybrid energy management strategies for
+//! [RustSimDrive](crate::simdrive::RustSimDrive).
+
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::simdrive::RustSimDrive;
+use crate::utils::{max, min};
+use crate::vehicle::{FcEffType, PowertrainType, RustVehicle};
+
+/// Hybrid energy management strategy, called by
+/// [RustSimDrive::solve_step](RustSimDrive::solve_step) at every time step
+/// after the achieved speed has been determined.  The default methods
+/// implement the classic FASTSim hybrid control logic, which is driven by
+/// [RustVehicle](RustVehicle) parameters such as `mph_fc_on`,
+/// `kw_demand_fc_on`, and `ess_dischg_to_fc_max_eff_perc`.
+///
+/// Implementors typically override only
+/// [set_hybrid_cont_decisions](EnergyManagement::set_hybrid_cont_decisions),
+/// which must set `mc_mech_kw_out_ach` and then call
+/// [RustSimDrive::set_ess_power](RustSimDrive::set_ess_power).  Custom
+/// strategies can be run via
+/// [RustSimDrive::solve_step_with_em](RustSimDrive::solve_step_with_em).
+pub trait EnergyManagement {
+    /// Sets regen/accel SOC buffers and whether the vehicle can be powered
+    /// all-electrically at time step `i`
+    fn set_hybrid_cont_calcs(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.set_hybrid_cont_calcs(i)
+    }
+
+    /// Sets fuel converter forced on/off state at time step `i`
+    fn set_fc_forced_state(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.set_fc_forced_state_rust(i)
+    }
+
+    /// Sets power split between motor and fuel converter and resulting ESS
+    /// state at time step `i`
+    fn set_hybrid_cont_decisions(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.set_hybrid_cont_decisions(i)
+    }
+}
+
+/// Energy management strategy options, selected via
+/// [RustSimDriveParams::energy_management](crate::simdrive::RustSimDriveParams::energy_management)
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+pub enum EnergyManagementTypes {
+    /// Classic FASTSim hybrid control logic
+    #[default]
+    Classic,
+    /// Equivalent consumption minimization strategy
+    Ecms(EcmsEnergyManagement),
+    /// Rule-based SOC thermostat strategy
+    Thermostat(ThermostatEnergyManagement),
+}
+
+impl EnergyManagementTypes {
+    /// Name of the selected strategy
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Ecms(_) => "Ecms",
+            Self::Thermostat(_) => "Thermostat",
+        }
+    }
+}
+
+impl EnergyManagement for EnergyManagementTypes {
+    fn set_hybrid_cont_calcs(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        match self {
+            Self::Classic => sd.set_hybrid_cont_calcs(i),
+            Self::Ecms(ecms) => ecms.set_hybrid_cont_calcs(sd, i),
+            Self::Thermostat(thrm) => thrm.set_hybrid_cont_calcs(sd, i),
+        }
+    }
+
+    fn set_fc_forced_state(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        match self {
+            Self::Classic => sd.set_fc_forced_state_rust(i),
+            Self::Ecms(ecms) => ecms.set_fc_forced_state(sd, i),
+            Self::Thermostat(thrm) => thrm.set_fc_forced_state(sd, i),
+        }
+    }
+
+    fn set_hybrid_cont_decisions(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        match self {
+            Self::Classic => sd.set_hybrid_cont_decisions(i),
+            Self::Ecms(ecms) => ecms.set_hybrid_cont_decisions(sd, i),
+            Self::Thermostat(thrm) => thrm.set_hybrid_cont_decisions(sd, i),
+        }
+    }
+}
+
+/// Equivalence consumption minimization strategy (ECMS).  At each traction
+/// time step, the motor/fuel converter power split that minimizes fuel power
+/// plus equivalent ESS power is chosen.  The equivalence factor is adapted
+/// to keep SOC near `soc_target`.  Braking and non-hybrid powertrains fall
+/// back to the classic logic.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (equiv_factor=None, soc_gain=None, soc_target=None, n_candidates=None))]
+    pub fn __new__(
+        equiv_factor: Option<f64>,
+        soc_gain: Option<f64>,
+        soc_target: Option<f64>,
+        n_candidates: Option<usize>,
+    ) -> Self {
+        let default = Self::default();
+        Self {
+            equiv_factor: equiv_factor.unwrap_or(default.equiv_factor),
+            soc_gain: soc_gain.unwrap_or(default.soc_gain),
+            soc_target,
+            n_candidates: n_candidates.unwrap_or(default.n_candidates),
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+)]
+pub struct EcmsEnergyManagement {
+    /// equivalence factor converting ESS electrical energy to fuel energy
+    pub equiv_factor: f64,
+    /// gain applied to deviation of SOC from `soc_target` to adapt
+    /// `equiv_factor`, per unit of SOC
+    pub soc_gain: f64,
+    /// SOC to be sustained; if `None`, midpoint of `min_soc` and `max_soc`
+    pub soc_target: Option<f64>,
+    /// number of motor power levels evaluated at each time step
+    pub n_candidates: usize,
+}
+
+impl SerdeAPI for EcmsEnergyManagement {}
+
+impl Default for EcmsEnergyManagement {
+    fn default() -> Self {
+        Self {
+            equiv_factor: 2.5,
+            soc_gain: 10.0,
+            soc_target: None,
+            n_candidates: 41,
+        }
+    }
+}
+
+impl EcmsEnergyManagement {
+    /// Equivalence factor adapted for SOC at previous time step
+    pub fn adapted_equiv_factor(&self, veh: &RustVehicle, soc: f64) -> f64 {
+        let soc_target = self.soc_target.unwrap_or((veh.max_soc + veh.min_soc) / 2.0);
+        max(0.0, self.equiv_factor + self.soc_gain * (soc_target - soc))
+    }
+}
+
+impl EnergyManagement for EcmsEnergyManagement {
+    fn set_hybrid_cont_decisions(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.set_hybrid_cont_decisions(i)?;
+        if !is_blended_hybrid(&sd.veh) || sd.trans_kw_in_ach[i] <= 0.0 {
+            return Ok(());
+        }
+        let equiv_factor = self.adapted_equiv_factor(&sd.veh, sd.soc[i - 1]);
+        let best = power_split_candidates(sd, i, self.n_candidates, &[])?
+            .into_iter()
+            .map(|split| (split.fc_kw_in + equiv_factor * split.ess_kw_out, split))
+            .min_by(|(cost_a, _), (cost_b, _)| cost_a.total_cmp(cost_b));
+        match best {
+            Some((_, split)) => split.apply(sd, i),
+            // no feasible split, so keep classic decision
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rule-based SOC thermostat strategy.  The fuel converter turns on when SOC
+/// drops below the `soc_frac_fc_on` point of the usable SOC window and stays
+/// on, operating near `fc_setpoint_kw`, until SOC rises above the
+/// `soc_frac_fc_off` point.  The fuel converter also turns on whenever the
+/// motor alone cannot meet demand.  Non-hybrid powertrains fall back to the
+/// classic logic.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (soc_frac_fc_on=None, soc_frac_fc_off=None, fc_setpoint_kw=None, n_candidates=None))]
+    pub fn __new__(
+        soc_frac_fc_on: Option<f64>,
+        soc_frac_fc_off: Option<f64>,
+        fc_setpoint_kw: Option<f64>,
+        n_candidates: Option<usize>,
+    ) -> Self {
+        let default = Self::default();
+        Self {
+            soc_frac_fc_on: soc_frac_fc_on.unwrap_or(default.soc_frac_fc_on),
+            soc_frac_fc_off: soc_frac_fc_off.unwrap_or(default.soc_frac_fc_off),
+            fc_setpoint_kw,
+            n_candidates: n_candidates.unwrap_or(default.n_candidates),
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+)]
+pub struct ThermostatEnergyManagement {
+    /// fraction of usable SOC window (`min_soc` to `max_soc`) below which
+    /// fuel converter turns on
+    pub soc_frac_fc_on: f64,
+    /// fraction of usable SOC window above which fuel converter turns off
+    pub soc_frac_fc_off: f64,
+    /// fuel converter output power while on \[kW\]; if `None`, power at peak
+    /// efficiency is used
+    pub fc_setpoint_kw: Option<f64>,
+    /// number of motor power levels evaluated when the setpoint is not
+    /// feasible
+    pub n_candidates: usize,
+}
+
+impl SerdeAPI for ThermostatEnergyManagement {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            (0.0..=1.0).contains(&self.soc_frac_fc_on)
+                && (0.0..=1.0).contains(&self.soc_frac_fc_off),
+            format_dbg!((self.soc_frac_fc_on, self.soc_frac_fc_off))
+        );
+        ensure!(
+            self.soc_frac_fc_on <= self.soc_frac_fc_off,
+            "`soc_frac_fc_on` must not exceed `soc_frac_fc_off`"
+        );
+        Ok(())
+    }
+}
+
+impl Default for ThermostatEnergyManagement {
+    fn default() -> Self {
+        Self {
+            soc_frac_fc_on: 0.4,
+            soc_frac_fc_off: 0.7,
+            fc_setpoint_kw: None,
+            n_candidates: 41,
+        }
+    }
+}
+
+impl ThermostatEnergyManagement {
+    /// Returns whether fuel converter should be on at time step `i`
+    fn fc_on(&self, sd: &RustSimDrive, i: usize) -> bool {
+        let soc_window = sd.veh.max_soc - sd.veh.min_soc;
+        let soc_fc_on = sd.veh.min_soc + self.soc_frac_fc_on * soc_window;
+        let soc_fc_off = sd.veh.min_soc + self.soc_frac_fc_off * soc_window;
+        let fc_was_on = sd.fc_time_on[i - 1] > 0.0;
+        sd.veh.no_elec_aux
+            || sd.high_acc_fc_on_tag[i]
+            || sd.fc_forced_on[i]
+            || sd.soc[i - 1] < soc_fc_on
+            || (fc_was_on && sd.soc[i - 1] < soc_fc_off)
+            || sd.trans_kw_in_ach[i] > sd.cur_max_mc_kw_out[i]
+    }
+}
+
+impl EnergyManagement for ThermostatEnergyManagement {
+    fn set_hybrid_cont_decisions(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.set_hybrid_cont_decisions(i)?;
+        if !is_blended_hybrid(&sd.veh) {
+            return Ok(());
+        }
+        let fc_on = self.fc_on(sd, i);
+        if sd.trans_kw_in_ach[i] <= 0.0 {
+            // braking or coasting: regen as much as possible and hold fuel
+            // converter state
+            sd.mc_mech_kw_out_ach[i] = -min(sd.cur_max_mech_mc_kw_in[i], -sd.trans_kw_in_ach[i]);
+            sd.can_pwr_all_elec[i] = !fc_on;
+            return sd.set_ess_power(i);
+        }
+        let fc_load_kw = fc_load_kw(sd, i);
+        let mc_mech_kw_desired = if fc_on {
+            let fc_setpoint_kw = self
+                .fc_setpoint_kw
+                .unwrap_or_else(|| sd.veh.max_fc_eff_kw());
+            fc_load_kw - min(fc_setpoint_kw, sd.cur_max_fc_kw_out[i])
+        } else {
+            fc_load_kw
+        };
+        let best = power_split_candidates(sd, i, self.n_candidates, &[mc_mech_kw_desired])?
+            .into_iter()
+            // keep fuel converter on when required, if possible
+            .map(|split| {
+                let state_penalty = if fc_on && split.fc_kw_out == 0.0 {
+                    sd.veh.fc_max_kw
+                } else {
+                    0.0
+                };
+                (
+                    (split.mc_mech_kw - mc_mech_kw_desired).abs() + state_penalty,
+                    split,
+                )
+            })
+            .min_by(|(dev_a, _), (dev_b, _)| dev_a.total_cmp(dev_b));
+        match best {
+            Some((_, split)) => split.apply(sd, i),
+            // no feasible split, so keep classic decision
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns true if vehicle has both a fuel converter and a motor sharing
+/// traction load, which is where alternative strategies apply
+fn is_blended_hybrid(veh: &RustVehicle) -> bool {
+    matches!(veh.veh_pt_type, PowertrainType::HEV | PowertrainType::PHEV)
+        && veh.fc_eff_type != FcEffType::H2FC
+        && !veh.no_elec_sys
+        && veh.fc_max_kw > 0.0
+        && veh.mc_max_kw > 0.0
+}
+
+/// Load on fuel converter if motor provided no power, including aux load
+/// when aux is powered by fuel converter
+fn fc_load_kw(sd: &RustSimDrive, i: usize) -> f64 {
+    if aux_on_fc(sd, i) {
+        sd.trans_kw_in_ach[i] + sd.aux_in_kw[i]
+    } else {
+        sd.trans_kw_in_ach[i]
+    }
+}
+
+fn aux_on_fc(sd: &RustSimDrive, i: usize) -> bool {
+    sd.veh.no_elec_aux || sd.high_acc_fc_on_tag[i]
+}
+
+/// Candidate power split between motor and fuel converter for one time step
+#[derive(Debug, Clone, Copy)]
+struct PowerSplit {
+    /// motor mechanical output power \[kW\]
+    mc_mech_kw: f64,
+    /// fuel converter output power \[kW\]
+    fc_kw_out: f64,
+    /// fuel converter input (fuel) power \[kW\]
+    fc_kw_in: f64,
+    /// ESS output power, neglecting roadway charging \[kW\]
+    ess_kw_out: f64,
+}
+
+impl PowerSplit {
+    /// Applies power split to time step `i` of `sd` and updates ESS state
+    fn apply(&self, sd: &mut RustSimDrive, i: usize) -> anyhow::Result<()> {
+        sd.mc_mech_kw_out_ach[i] = self.mc_mech_kw;
+        sd.can_pwr_all_elec[i] = self.fc_kw_out == 0.0 && !sd.fc_forced_on[i];
+        sd.set_ess_power(i)
+    }
+}
+
+/// Returns power splits at time step `i` that respect motor, fuel converter,
+/// and ESS power limits.  Motor power levels are evenly spaced between
+/// feasible bounds, with `extra_mc_mech_kw` also evaluated if within bounds.
+fn power_split_candidates(
+    sd: &RustSimDrive,
+    i: usize,
+    n: usize,
+    extra_mc_mech_kw: &[f64],
+) -> anyhow::Result<Vec<PowerSplit>> {
+    let fc_load_kw = fc_load_kw(sd, i);
+    let aux_elec_kw = if aux_on_fc(sd, i) {
+        0.0
+    } else {
+        sd.aux_in_kw[i]
+    };
+    let mc_mech_kw_max = min(sd.cur_max_mc_kw_out[i], sd.trans_kw_in_ach[i]);
+    let mc_mech_kw_min = max(
+        -sd.cur_max_mech_mc_kw_in[i],
+        fc_load_kw - sd.cur_max_fc_kw_out[i],
+    );
+    if mc_mech_kw_min > mc_mech_kw_max {
+        return Ok(vec![]);
+    }
+    let n = cmp::max(n, 2);
+    let step = (mc_mech_kw_max - mc_mech_kw_min) / (n - 1) as f64;
+    (0..n)
+        .map(|j| mc_mech_kw_min + step * j as f64)
+        .chain(
+            extra_mc_mech_kw
+                .iter()
+                .map(|kw| kw.clamp(mc_mech_kw_min, mc_mech_kw_max)),
+        )
+        .map(|mc_mech_kw| {
+            let fc_kw_out = max(0.0, fc_load_kw - mc_mech_kw);
+            // same efficiency lookups as `set_ess_power` and `set_fc_power`
+            let ess_kw_out = sd.mc_elec_kw_in_for(i, mc_mech_kw)? + aux_elec_kw;
+            let ess_feasible = ess_kw_out <= sd.cur_ess_max_kw_out[i] + 1e-6
+                && ess_kw_out >= -sd.cur_max_ess_chg_kw[i] - 1e-6;
+            let fc_state_feasible = !(sd.fc_forced_on[i] && fc_kw_out == 0.0);
+            if !(ess_feasible && fc_state_feasible) {
+                return Ok(None);
+            }
+            Ok(Some(PowerSplit {
+                mc_mech_kw,
+                fc_kw_out,
+                fc_kw_in: sd.fc_kw_in_for(i, fc_kw_out)?,
+                ess_kw_out,
+            }))
+        })
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::speed_torque_map::SpeedTorqueEffMap;
+    use crate::transmission::Transmission;
+
+    fn run_prius(em: EnergyManagementTypes) -> RustSimDrive {
+        let veh = RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = RustSimDrive::new(cyc, veh);
+        sd.sim_params.energy_management = em;
+        sd.sim_drive(None, None).unwrap();
+        sd
+    }
+
+    #[test]
+    fn test_classic_matches_default_solve_step() {
+        let sd_default = run_prius(Default::default());
+        let sd_classic = run_prius(EnergyManagementTypes::Classic);
+        assert_eq!(sd_default.mpgge, sd_classic.mpgge);
+        assert_eq!(sd_default.soc, sd_classic.soc);
+    }
+
+    #[test]
+    fn test_alternative_strategies() {
+        let sd_classic = run_prius(EnergyManagementTypes::Classic);
+        for em in [
+            EnergyManagementTypes::Ecms(Default::default()),
+            EnergyManagementTypes::Thermostat(Default::default()),
+        ] {
+            let name = em.name();
+            let sd = run_prius(em);
+            assert!(
+                sd.mpgge.is_finite() && sd.mpgge > 0.0,
+                "{name}: {}",
+                sd.mpgge
+            );
+            // strategies should deliver comparable fuel economy
+            assert!(
+                (sd.mpgge - sd_classic.mpgge).abs() / sd_classic.mpgge < 0.5,
+                "{name}: {} vs classic {}",
+                sd.mpgge,
+                sd_classic.mpgge
+            );
+            assert!(
+                sd.soc
+                    .iter()
+                    .all(|soc| *soc >= sd.veh.min_soc - 1e-3 && *soc <= sd.veh.max_soc + 1e-3),
+                "{name}"
+            );
+            assert!(sd.trace_miss_speed_mps < 1.0, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_non_hybrid_unaffected() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd_classic = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_classic.sim_drive(None, None).unwrap();
+        let mut sd_ecms = RustSimDrive::new(cyc, veh);
+        sd_ecms.sim_params.energy_management = EnergyManagementTypes::Ecms(Default::default());
+        sd_ecms.sim_drive(None, None).unwrap();
+        assert_eq!(sd_classic.mpgge, sd_ecms.mpgge);
+    }
+
+    #[test]
+    fn test_candidates_use_2d_maps() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        veh.transmission = Some(Transmission::default());
+        veh.fc_eff_map_2d = Some(SpeedTorqueEffMap {
+            speed_rpm: vec![1_000.0, 5_000.0],
+            torque_nm: vec![0.0, 150.0],
+            eff: vec![vec![0.2, 0.38], vec![0.15, 0.32]],
+            orphaned: false,
+        });
+        veh.mc_eff_map_2d = Some(SpeedTorqueEffMap {
+            speed_rpm: vec![0.0, 10_000.0],
+            torque_nm: vec![0.0, 200.0],
+            eff: vec![vec![0.7, 0.9], vec![0.8, 0.95]],
+            orphaned: false,
+        });
+        veh.set_derived().unwrap();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = RustSimDrive::new(cyc, veh);
+        sd.sim_params.energy_management = EnergyManagementTypes::Ecms(Default::default());
+        sd.sim_drive(None, None).unwrap();
+        // strategy is restored after each step
+        assert_eq!(sd.sim_params.energy_management.name(), "Ecms");
+
+        let fc_map = sd.veh.fc_eff_map_2d.as_ref().unwrap();
+        let mc_map = sd.veh.mc_eff_map_2d.as_ref().unwrap();
+        let mut checked = 0;
+        for i in 1..sd.len() {
+            let aux_elec_kw = if aux_on_fc(&sd, i) {
+                0.0
+            } else {
+                sd.aux_in_kw[i]
+            };
+            let speed = sd.trans_in_rad_per_sec[i];
+            for split in power_split_candidates(&sd, i, 5, &[]).unwrap() {
+                if split.fc_kw_out > 0.0 {
+                    let fc_eff = fc_map.eff_at_kw(speed, split.fc_kw_out).unwrap();
+                    assert!((split.fc_kw_in - split.fc_kw_out / fc_eff).abs() < 1e-9);
+                    checked += 1;
+                }
+                if split.mc_mech_kw > 0.0 {
+                    let mc_eff = mc_map.eff_at_kw(speed, split.mc_mech_kw).unwrap();
+                    let expected = split.mc_mech_kw / mc_eff + aux_elec_kw;
+                    assert!((split.ess_kw_out - expected).abs() < 1e-9);
+                }
+            }
+        }
+        assert!(checked > 0);
+    }
+}
@@ -0,0 +1,343 @@
+//! Module for simulating a platoon of vehicles following each other in one
+//! lane, where the leader drives a reference cycle and each follower uses IDM
+//! car following against the *achieved* speed of the vehicle ahead of it
+
+use super::{RustSimDrive, RustSimDriveParams};
+use crate::cycle::RustCycle;
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::RustVehicle;
+
+/// Tolerance on acceleration amplification ratios for a platoon to count as
+/// string stable
+const STRING_STABILITY_TOL: f64 = 1e-6;
+
+/// Speed below which time headway is not computed \[m/s\]
+const HEADWAY_MIN_SPEED_MPS: f64 = 1.0;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (cyc, vehs, sim_params=None))]
+    pub fn __new__(
+        cyc: RustCycle,
+        vehs: Vec<RustVehicle>,
+        sim_params: Option<Vec<RustSimDriveParams>>,
+    ) -> anyhow::Result<Self> {
+        let mut platoon = Self {
+            cyc,
+            vehs,
+            sim_params: sim_params.unwrap_or_default(),
+            orphaned: false,
+        };
+        platoon.init()?;
+        Ok(platoon)
+    }
+
+    #[pyo3(name = "run")]
+    /// Simulates the leader over `cyc`, then each follower behind the
+    /// vehicle ahead of it
+    pub fn run_py(&self) -> anyhow::Result<PlatoonResults> {
+        self.run()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Vehicles driving in one lane, the first following `cyc` and each of the
+/// rest following the vehicle ahead of it.  Vehicles start at rest, each
+/// `idm_minimum_gap_m` behind the one ahead of it; vehicle lengths are not
+/// modeled, so gaps are measured between successive vehicles' positions.
+pub struct Platoon {
+    /// reference cycle driven by the lead vehicle
+    #[api(has_orphaned)]
+    pub cyc: RustCycle,
+    /// vehicles, from the leader back
+    pub vehs: Vec<RustVehicle>,
+    /// simulation parameters for each vehicle, or empty for defaults.  IDM
+    /// car following (`idm_allow`) is always enabled for followers, whose
+    /// `idm_*` parameters set driver or ACC behavior, and is optional for
+    /// the leader, e.g. for eco-cruise.
+    #[serde(default)]
+    pub sim_params: Vec<RustSimDriveParams>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for Platoon {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(!self.vehs.is_empty(), "platoon has no vehicles");
+        ensure!(
+            self.sim_params.is_empty() || self.sim_params.len() == self.vehs.len(),
+            "platoon has {} vehicles but {} sets of simulation parameters",
+            self.vehs.len(),
+            self.sim_params.len()
+        );
+        for (k, params) in self.sim_params.iter().enumerate().skip(1) {
+            // time dilation would put the follower on a different time base
+            // than the vehicle it follows
+            ensure!(
+                !params.missed_trace_correction,
+                "follower {k} cannot use `missed_trace_correction`"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Platoon {
+    /// Simulation parameters of vehicle `k`
+    fn sim_params_for(&self, k: usize) -> RustSimDriveParams {
+        let mut params = self.sim_params.get(k).cloned().unwrap_or_default();
+        if k > 0 {
+            params.idm_allow = true;
+        }
+        params
+    }
+
+    /// Simulates the leader over `cyc`, then each follower behind the vehicle
+    /// ahead of it.  Because IDM only reacts to the previous step of the
+    /// vehicle ahead, simulating vehicles one after another is equivalent to
+    /// stepping them together.
+    pub fn run(&self) -> anyhow::Result<PlatoonResults> {
+        let mut sds: Vec<RustSimDrive> = Vec::with_capacity(self.vehs.len());
+        for (k, veh) in self.vehs.iter().enumerate() {
+            let lead_cyc = match sds.last() {
+                Some(lead) => achieved_cycle(lead, k),
+                None => self.cyc.clone(),
+            };
+            let mut sd = RustSimDrive::new(lead_cyc, veh.clone());
+            sd.sim_params = self.sim_params_for(k);
+            sd.sim_drive(None, None)
+                .with_context(|| format!("platoon vehicle {k} failed to simulate"))?;
+            sds.push(sd);
+        }
+
+        let mut members: Vec<PlatoonMember> = sds
+            .iter()
+            .enumerate()
+            .map(|(k, sd)| PlatoonMember::new(k, sd))
+            .collect();
+        for k in 1..members.len() {
+            members[k].rms_accel_ratio =
+                members[k].rms_accel_m_per_s2 / members[k - 1].rms_accel_m_per_s2;
+            members[k].peak_accel_ratio =
+                members[k].peak_accel_m_per_s2 / members[k - 1].peak_accel_m_per_s2;
+        }
+        let max_rms_accel_ratio = members
+            .iter()
+            .skip(1)
+            .map(|m| m.rms_accel_ratio)
+            .fold(f64::NAN, f64::max);
+
+        Ok(PlatoonResults {
+            string_stable: members
+                .iter()
+                .skip(1)
+                .all(|m| m.rms_accel_ratio <= 1.0 + STRING_STABILITY_TOL),
+            max_rms_accel_ratio,
+            collision: members.iter().any(|m| m.collision),
+            members,
+            sds,
+            orphaned: false,
+        })
+    }
+}
+
+/// Trace achieved by `lead`, to be followed by vehicle `k`
+fn achieved_cycle(lead: &RustSimDrive, k: usize) -> RustCycle {
+    RustCycle {
+        time_s: lead.cyc.time_s.clone(),
+        mps: lead.mps_ach.clone(),
+        grade: lead.cyc.grade.clone(),
+        road_type: lead.cyc.road_type.clone(),
+        name: format!("{} (platoon vehicle {})", lead.cyc0.name, k - 1),
+        orphaned: false,
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Energy use, gaps, and string stability metrics of one [Platoon] vehicle
+pub struct PlatoonMember {
+    /// position in the platoon, 0 for the leader
+    pub position: usize,
+    /// [RustVehicle::scenario_name]
+    pub scenario_name: String,
+    /// distance traveled \[mi\]
+    pub dist_mi: f64,
+    /// fuel energy used \[kJ\]
+    pub fuel_kj: f64,
+    /// net battery discharge energy \[kJ\]
+    pub ess_dischg_kj: f64,
+    pub mpgge: f64,
+    pub electric_kwh_per_mi: f64,
+    /// gap to the vehicle ahead at each time step \[m\], empty for the leader
+    pub gap_m: Vec<f64>,
+    /// smallest gap to the vehicle ahead \[m\], NaN for the leader
+    pub min_gap_m: f64,
+    /// mean gap to the vehicle ahead \[m\], NaN for the leader
+    pub mean_gap_m: f64,
+    /// mean of gap divided by speed while moving faster than 1 m/s \[s\], NaN
+    /// for the leader
+    pub mean_time_headway_s: f64,
+    /// whether the gap to the vehicle ahead ever closed
+    pub collision: bool,
+    /// root mean square of acceleration \[m/s^2\]
+    pub rms_accel_m_per_s2: f64,
+    /// largest absolute acceleration \[m/s^2\]
+    pub peak_accel_m_per_s2: f64,
+    /// `rms_accel_m_per_s2` divided by that of the vehicle ahead; string
+    /// stability requires this to be at most 1.  NaN for the leader.
+    pub rms_accel_ratio: f64,
+    /// `peak_accel_m_per_s2` divided by that of the vehicle ahead, NaN for
+    /// the leader
+    pub peak_accel_ratio: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for PlatoonMember {}
+
+impl PlatoonMember {
+    /// Metrics of completed simulation `sd` of vehicle `position`, with
+    /// ratios to the vehicle ahead left as NaN
+    fn new(position: usize, sd: &RustSimDrive) -> Self {
+        let accel: Vec<f64> = (1..sd.mps_ach.len())
+            .map(|i| (sd.mps_ach[i] - sd.mps_ach[i - 1]) / sd.cyc.dt_s_at_i(i))
+            .collect();
+        let (gap_m, min_gap_m, mean_gap_m, mean_time_headway_s) = if position > 0 {
+            let gap_m = sd.gap_to_lead_vehicle_m().to_vec();
+            let headways: Vec<f64> = gap_m
+                .iter()
+                .zip(&sd.mps_ach)
+                .filter(|(_, v)| **v > HEADWAY_MIN_SPEED_MPS)
+                .map(|(gap, v)| gap / v)
+                .collect();
+            (
+                gap_m.clone(),
+                gap_m.iter().copied().fold(f64::INFINITY, f64::min),
+                mean(&gap_m),
+                mean(&headways),
+            )
+        } else {
+            (vec![], f64::NAN, f64::NAN, f64::NAN)
+        };
+        Self {
+            position,
+            scenario_name: sd.veh.scenario_name.clone(),
+            dist_mi: sd.dist_mi.sum(),
+            fuel_kj: sd.fuel_kj,
+            ess_dischg_kj: sd.ess_dischg_kj,
+            mpgge: sd.mpgge,
+            electric_kwh_per_mi: sd.electric_kwh_per_mi,
+            collision: min_gap_m <= 0.0,
+            gap_m,
+            min_gap_m,
+            mean_gap_m,
+            mean_time_headway_s,
+            rms_accel_m_per_s2: mean(&accel.iter().map(|a| a * a).collect::<Vec<_>>()).sqrt(),
+            peak_accel_m_per_s2: accel.iter().fold(0.0, |peak, a| f64::max(peak, a.abs())),
+            rms_accel_ratio: f64::NAN,
+            peak_accel_ratio: f64::NAN,
+            orphaned: false,
+        }
+    }
+}
+
+/// Mean of `x`, NaN if empty
+fn mean(x: &[f64]) -> f64 {
+    if x.is_empty() {
+        f64::NAN
+    } else {
+        x.iter().sum::<f64>() / x.len() as f64
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Outcome of [Platoon::run]
+pub struct PlatoonResults {
+    /// metrics of each vehicle, from the leader back
+    pub members: Vec<PlatoonMember>,
+    /// whether acceleration is never amplified from one vehicle to the next,
+    /// i.e. every follower's `rms_accel_ratio` is at most 1
+    pub string_stable: bool,
+    /// largest `rms_accel_ratio` of any follower, NaN without followers
+    pub max_rms_accel_ratio: f64,
+    /// whether any follower closed the gap to the vehicle ahead
+    pub collision: bool,
+    /// completed simulation of each vehicle, from the leader back, where
+    /// each follower's `cyc0` is the trace achieved by the vehicle ahead
+    pub sds: Vec<RustSimDrive>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for PlatoonResults {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platoon_followers_track_achieved_speed() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let veh = RustVehicle::mock_vehicle();
+        let mut platoon = Platoon {
+            cyc: cyc.clone(),
+            vehs: vec![veh; 3],
+            sim_params: vec![],
+            orphaned: false,
+        };
+        platoon.init().unwrap();
+        let res = platoon.run().unwrap();
+
+        assert_eq!(res.members.len(), 3);
+        assert!(!res.collision);
+        let leader = &res.members[0];
+        assert!(leader.gap_m.is_empty() && leader.min_gap_m.is_nan());
+        for k in 1..3 {
+            let follower = &res.members[k];
+            assert_eq!(follower.gap_m.len(), cyc.len());
+            assert!(follower.min_gap_m > 0.0);
+            assert!(follower
+                .dist_mi
+                .approx_eq(&leader.dist_mi, 0.01 * leader.dist_mi));
+            // each follower's lead trace is what the vehicle ahead achieved
+            assert_eq!(res.sds[k].cyc0.mps, res.sds[k - 1].mps_ach);
+        }
+        // the default IDM parameters amplify accelerations down the platoon
+        assert!(!res.string_stable);
+        assert!(res.max_rms_accel_ratio > 1.0);
+
+        // longer headway and gentler acceleration, as with ACC, damp them
+        let acc = RustSimDriveParams {
+            idm_dt_headway_s: 3.0,
+            idm_accel_m_per_s2: 0.5,
+            idm_decel_m_per_s2: 1.0,
+            ..Default::default()
+        };
+        platoon.sim_params = vec![RustSimDriveParams::default(), acc.clone(), acc];
+        platoon.init().unwrap();
+        let res_acc = platoon.run().unwrap();
+        assert!(res_acc.string_stable);
+        for k in 1..3 {
+            assert!(res_acc.members[k].mean_time_headway_s > res.members[k].mean_time_headway_s);
+            assert!(res_acc.members[k].mpgge > res.members[k].mpgge);
+        }
+    }
+
+    #[test]
+    fn test_platoon_rejects_mismatched_sim_params() {
+        let mut platoon = Platoon {
+            cyc: RustCycle::from_resource("udds.csv", false).unwrap(),
+            vehs: vec![RustVehicle::mock_vehicle(); 2],
+            sim_params: vec![RustSimDriveParams::default()],
+            orphaned: false,
+        };
+        assert!(platoon.init().is_err());
+        platoon.sim_params = vec![RustSimDriveParams::default(); 2];
+        platoon.sim_params[1].missed_trace_correction = true;
+        assert!(platoon.init().is_err());
+    }
+}
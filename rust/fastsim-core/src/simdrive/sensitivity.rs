@@ -0,0 +1,457 @@
+//! Module for sensitivity analysis of simulation outputs to vehicle
+//! parameters: local elasticities by central differences about the vehicle's
+//! own parameter values, and variance-based Sobol indices over parameter
+//! ranges, with all simulations run in parallel through [ParameterSweep]
+
+use super::sweep::{sobol, ParameterSweep, SweepParam, SOBOL_MAX_DIMS};
+use crate::cycle::RustCycle;
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::RustVehicle;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (params, outputs, rel_step=0.01, n_samples=64))]
+    pub fn __new__(
+        params: Vec<SweepParam>,
+        outputs: Vec<String>,
+        rel_step: f64,
+        n_samples: usize,
+    ) -> anyhow::Result<Self> {
+        let mut analysis = Self {
+            params,
+            outputs,
+            rel_step,
+            n_samples,
+            orphaned: false,
+        };
+        analysis.init()?;
+        Ok(analysis)
+    }
+
+    #[pyo3(name = "run")]
+    #[pyo3(signature = (veh, cyc, parallelize=None))]
+    /// Computes the sensitivity of each output to each parameter for `veh`
+    /// driving `cyc`
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive` calls, defaults to `true`
+    pub fn run_py(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        parallelize: Option<bool>,
+    ) -> anyhow::Result<SensitivityReport> {
+        self.run(veh, cyc, parallelize)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Sensitivity of scalar simulation outputs to [RustVehicle] parameters
+pub struct SensitivityAnalysis {
+    /// parameters, with the ranges sampled for Sobol indices
+    pub params: Vec<SweepParam>,
+    /// outputs, as in [ParameterSweep::outputs], e.g. `mpgge`,
+    /// [super::sweep::RANGE_OUTPUT], or [super::sweep::ACCEL_OUTPUT]
+    pub outputs: Vec<String>,
+    /// central difference step as a fraction of each parameter's value, or of
+    /// its range if the value is zero
+    #[serde(default = "SensitivityAnalysis::default_rel_step")]
+    pub rel_step: f64,
+    /// number of base samples for Sobol indices, or 0 to skip them.  Costs
+    /// `n_samples * (params.len() + 2)` simulations.
+    #[serde(default = "SensitivityAnalysis::default_n_samples")]
+    pub n_samples: usize,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SensitivityAnalysis {
+    fn init(&mut self) -> anyhow::Result<()> {
+        self.sweep().init()?;
+        ensure!(
+            self.rel_step > 0.0 && self.rel_step < 1.0,
+            "`rel_step` must be in (0, 1), got {}",
+            self.rel_step
+        );
+        ensure!(
+            self.n_samples == 0 || 2 * self.params.len() <= SOBOL_MAX_DIMS,
+            "Sobol indices support at most {} parameters, got {}",
+            SOBOL_MAX_DIMS / 2,
+            self.params.len()
+        );
+        Ok(())
+    }
+}
+
+impl SensitivityAnalysis {
+    fn default_rel_step() -> f64 {
+        0.01
+    }
+
+    fn default_n_samples() -> usize {
+        64
+    }
+
+    fn sweep(&self) -> ParameterSweep {
+        ParameterSweep {
+            params: self.params.clone(),
+            outputs: self.outputs.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Nominal parameter values, i.e. those of `veh`, followed by each value
+    /// perturbed down then up by its step, one row per vehicle
+    fn local_samples(&self, nominal: &[f64]) -> Vec<Vec<f64>> {
+        let mut rows = vec![nominal.to_vec()];
+        for (i, param) in self.params.iter().enumerate() {
+            let step = self.step(param, nominal[i]);
+            for sign in [-1.0, 1.0] {
+                let mut row = nominal.to_vec();
+                row[i] += sign * step;
+                rows.push(row);
+            }
+        }
+        rows
+    }
+
+    fn step(&self, param: &SweepParam, nominal: f64) -> f64 {
+        if nominal != 0.0 {
+            self.rel_step * nominal.abs()
+        } else {
+            self.rel_step * (param.max - param.min)
+        }
+    }
+
+    /// Saltelli sample matrices `A` and `B`, followed by `A` with column `i`
+    /// taken from `B` for each parameter `i`, one row per vehicle
+    fn sobol_samples(&self) -> anyhow::Result<Vec<Vec<f64>>> {
+        let d = self.params.len();
+        // skip the origin, which lies on the boundary of every range
+        let unit = sobol(self.n_samples + 1, 2 * d)?;
+        let scale = |row: &[f64]| -> Vec<f64> {
+            row.iter()
+                .zip(&self.params)
+                .map(|(u, param)| param.scale(*u))
+                .collect()
+        };
+        let a: Vec<Vec<f64>> = unit[1..].iter().map(|row| scale(&row[..d])).collect();
+        let b: Vec<Vec<f64>> = unit[1..].iter().map(|row| scale(&row[d..])).collect();
+        let mut rows = a.clone();
+        rows.extend(b.iter().cloned());
+        for i in 0..d {
+            rows.extend(a.iter().zip(&b).map(|(a, b)| {
+                let mut row = a.clone();
+                row[i] = b[i];
+                row
+            }));
+        }
+        Ok(rows)
+    }
+
+    /// Computes the sensitivity of each output to each parameter for `veh`
+    /// driving `cyc`.  Samples whose vehicle is invalid or fails to simulate
+    /// are left out of the estimates and counted in
+    /// [SensitivityReport::n_failed].
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive` calls, defaults to `true`
+    pub fn run(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        parallelize: Option<bool>,
+    ) -> anyhow::Result<SensitivityReport> {
+        let nominal = self
+            .params
+            .iter()
+            .map(|param| {
+                veh.get_field(&param.path)?
+                    .as_f64()
+                    .with_context(|| format!("parameter `{}` is not numeric", param.path))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut samples = self.local_samples(&nominal);
+        let n_local = samples.len();
+        if self.n_samples > 0 {
+            samples.extend(self.sobol_samples()?);
+        }
+
+        let results = self
+            .sweep()
+            .run_samples(veh, cyc, samples.clone(), parallelize)?;
+        if let Some(err) = &results.errors[0] {
+            bail!("simulation failed at nominal parameter values: {err}");
+        }
+
+        let d = self.params.len();
+        let n = self.n_samples;
+        let outputs = self
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(k, output)| {
+                let y: Vec<f64> = results.rows.iter().map(|row| row[d + k]).collect();
+                let (local, global) = y.split_at(n_local);
+                let variance = if n > 0 {
+                    sample_variance(&global[..2 * n])
+                } else {
+                    f64::NAN
+                };
+                let mut params: Vec<ParamSensitivity> = self
+                    .params
+                    .iter()
+                    .enumerate()
+                    .map(|(i, param)| {
+                        let (lo, hi) = (&samples[1 + 2 * i], &samples[2 + 2 * i]);
+                        let gradient = (local[2 + 2 * i] - local[1 + 2 * i]) / (hi[i] - lo[i]);
+                        let (first_order, total_order) = if n > 0 {
+                            sobol_indices(
+                                &global[..n],
+                                &global[n..2 * n],
+                                &global[(2 + i) * n..(3 + i) * n],
+                                variance,
+                            )
+                        } else {
+                            (f64::NAN, f64::NAN)
+                        };
+                        ParamSensitivity {
+                            param: param.path.clone(),
+                            nominal: nominal[i],
+                            gradient,
+                            elasticity: gradient * nominal[i] / local[0],
+                            first_order,
+                            total_order,
+                            orphaned: false,
+                        }
+                    })
+                    .collect();
+                params.sort_by(|a, b| b.rank_key().total_cmp(&a.rank_key()));
+                OutputSensitivity {
+                    output: output.clone(),
+                    nominal: local[0],
+                    variance,
+                    params,
+                    orphaned: false,
+                }
+            })
+            .collect();
+
+        Ok(SensitivityReport {
+            outputs,
+            n_simulations: results.len(),
+            n_failed: results.errors.iter().filter(|err| err.is_some()).count(),
+            orphaned: false,
+        })
+    }
+}
+
+/// Unbiased variance of the finite values of `y`
+fn sample_variance(y: &[f64]) -> f64 {
+    let y: Vec<f64> = y.iter().copied().filter(|y| y.is_finite()).collect();
+    if y.len() < 2 {
+        return f64::NAN;
+    }
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    y.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / (y.len() - 1) as f64
+}
+
+/// First-order (Saltelli 2010) and total (Jansen 1999) Sobol index estimates
+/// from outputs at samples `A`, `B`, and `A` with one column from `B`, using
+/// only samples where all three are finite.  Outputs are centered on their
+/// mean, which greatly reduces the error of the first-order estimate when the
+/// mean is large relative to the spread.
+fn sobol_indices(f_a: &[f64], f_b: &[f64], f_ab: &[f64], variance: f64) -> (f64, f64) {
+    let valid: Vec<(f64, f64, f64)> = f_a
+        .iter()
+        .zip(f_b)
+        .zip(f_ab)
+        .map(|((a, b), ab)| (*a, *b, *ab))
+        .filter(|(a, b, ab)| a.is_finite() && b.is_finite() && ab.is_finite())
+        .collect();
+    if valid.is_empty() || variance.is_nan() || variance <= 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let n = valid.len() as f64;
+    let mean = valid.iter().map(|(a, b, _)| a + b).sum::<f64>() / (2.0 * n);
+    let first = valid
+        .iter()
+        .map(|(a, b, ab)| (b - mean) * (ab - a))
+        .sum::<f64>()
+        / n
+        / variance;
+    let total = valid.iter().map(|(a, _, ab)| (a - ab).powi(2)).sum::<f64>() / (2.0 * n) / variance;
+    (first, total)
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Sensitivity of one output to one parameter
+pub struct ParamSensitivity {
+    /// [FieldPath] of the parameter
+    pub param: String,
+    /// parameter value of the vehicle analyzed
+    pub nominal: f64,
+    /// derivative of the output with respect to the parameter at `nominal`
+    pub gradient: f64,
+    /// fractional change in the output per fractional change in the parameter
+    /// at `nominal`
+    pub elasticity: f64,
+    /// share of output variance due to the parameter alone, NaN if not
+    /// computed or if the output does not vary
+    pub first_order: f64,
+    /// share of output variance due to the parameter, including its
+    /// interactions with other parameters, NaN if not computed or if the
+    /// output does not vary
+    pub total_order: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for ParamSensitivity {}
+
+impl ParamSensitivity {
+    /// Key that ranks by `total_order` when available, and by magnitude of
+    /// `elasticity` otherwise, with NaN last
+    fn rank_key(&self) -> f64 {
+        let key = if self.total_order.is_finite() {
+            self.total_order
+        } else {
+            self.elasticity.abs()
+        };
+        if key.is_nan() {
+            f64::NEG_INFINITY
+        } else {
+            key
+        }
+    }
+}
+
+#[add_pyo3_api(
+    #[pyo3(name = "ranking")]
+    /// Parameter paths, most influential first
+    pub fn ranking_py(&self) -> Vec<String> {
+        self.ranking()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Sensitivity of one output to every parameter
+pub struct OutputSensitivity {
+    pub output: String,
+    /// output value at the nominal parameter values
+    pub nominal: f64,
+    /// variance of the output over the sampled parameter ranges, NaN if Sobol
+    /// indices were not computed
+    pub variance: f64,
+    /// sensitivity to each parameter, most influential first
+    pub params: Vec<ParamSensitivity>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for OutputSensitivity {}
+
+impl OutputSensitivity {
+    /// Parameter paths, most influential first
+    pub fn ranking(&self) -> Vec<String> {
+        self.params.iter().map(|p| p.param.clone()).collect()
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Outcome of [SensitivityAnalysis::run]
+pub struct SensitivityReport {
+    /// sensitivities of each output, in the order of
+    /// [SensitivityAnalysis::outputs]
+    pub outputs: Vec<OutputSensitivity>,
+    /// total number of simulations run
+    pub n_simulations: usize,
+    /// number of samples whose vehicle was invalid or failed to simulate
+    pub n_failed: usize,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SensitivityReport {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simdrive::sweep::{ACCEL_OUTPUT, RANGE_OUTPUT};
+
+    #[test]
+    fn test_sensitivity_ranks_influential_params_first() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("hwfet.csv", false).unwrap();
+        let mut analysis = SensitivityAnalysis {
+            params: vec![
+                SweepParam {
+                    path: "val0_to60_mph".into(),
+                    min: 5.0,
+                    max: 15.0,
+                    levels: 2,
+                    orphaned: false,
+                },
+                SweepParam {
+                    path: "drag_coef".into(),
+                    min: 0.25,
+                    max: 0.4,
+                    levels: 2,
+                    orphaned: false,
+                },
+            ],
+            outputs: vec!["mpgge".into()],
+            rel_step: 0.01,
+            n_samples: 16,
+            orphaned: false,
+        };
+        analysis.init().unwrap();
+        let report = analysis.run(&veh, &cyc, None).unwrap();
+        assert_eq!(report.n_simulations, 5 + 16 * 4);
+        assert_eq!(report.n_failed, 0);
+
+        let mpgge = &report.outputs[0];
+        assert_eq!(mpgge.ranking(), vec!["drag_coef", "val0_to60_mph"]);
+        let drag = &mpgge.params[0];
+        assert!(drag.elasticity < 0.0 && drag.gradient < 0.0);
+        assert!(drag.total_order > 0.9 && drag.first_order > 0.9);
+        // reference acceleration time is an input with no effect on the model
+        let accel = &mpgge.params[1];
+        assert!(accel.elasticity.approx_eq(&0.0, 1e-9));
+        assert!(accel.total_order.approx_eq(&0.0, 1e-9));
+
+        // local elasticities only, for derived outputs
+        analysis.outputs = vec![RANGE_OUTPUT.into(), ACCEL_OUTPUT.into()];
+        analysis.n_samples = 0;
+        let local = analysis.run(&veh, &cyc, None).unwrap();
+        assert_eq!(local.n_simulations, 5);
+        let range_mi = veh.fs_kwh / veh.props.kwh_per_gge * mpgge.nominal;
+        assert!(local.outputs[0].nominal.approx_eq(&range_mi, 1e-9));
+        assert_eq!(local.outputs[0].ranking(), mpgge.ranking());
+        assert!(local.outputs[0].params[0].total_order.is_nan());
+        let accel_s = local.outputs[1].nominal;
+        assert!(accel_s > 1.0 && accel_s < 30.0, "{accel_s}");
+    }
+
+    #[test]
+    fn test_sensitivity_rejects_too_many_sobol_params() {
+        let param = SweepParam {
+            path: "drag_coef".into(),
+            min: 0.25,
+            max: 0.4,
+            levels: 2,
+            orphaned: false,
+        };
+        let mut analysis = SensitivityAnalysis {
+            params: vec![param; SOBOL_MAX_DIMS / 2 + 1],
+            outputs: vec!["mpgge".into()],
+            rel_step: 0.01,
+            n_samples: 8,
+            orphaned: false,
+        };
+        assert!(analysis.init().is_err());
+        analysis.n_samples = 0;
+        assert!(analysis.init().is_ok());
+    }
+}
@@ -3,7 +3,10 @@
 use crate::cycle::{RustCycle, RustCycleCache};
 use crate::imports::*;
 use crate::params;
-use crate::simdrive::{RustSimDrive, RustSimDriveParams};
+use crate::simdrive::{
+    diagnostics::SimDiagnostics, energy_management::EnergyManagement, RustSimDrive,
+    RustSimDriveParams,
+};
 use crate::utils::{arrmax, first_grtr, max, min};
 use crate::vehicle::*;
 
@@ -114,6 +117,27 @@ impl RustSimDrive {
         let ess_perc_dead = Array::zeros(cyc_len);
         let drag_kw = Array::zeros(cyc_len);
         let ess_loss_kw = Array::zeros(cyc_len);
+        let ess_voltage_v = Array::zeros(cyc_len);
+        let ess_current_a = Array::zeros(cyc_len);
+        let ess_c_rate = Array::zeros(cyc_len);
+        let ess_v_rc_v = Array::zeros(cyc_len);
+        let trans_eff_ach = Array::zeros(cyc_len);
+        let cat_conv_frac = Array::zeros(cyc_len);
+        let fuel2_share = Array::zeros(cyc_len);
+        let fuel1_kw_out_ach = Array::zeros(cyc_len);
+        let fuel2_kw_out_ach = Array::zeros(cyc_len);
+        let co2_g_per_s = Array::zeros(cyc_len);
+        let eo_nox_g_per_s = Array::zeros(cyc_len);
+        let eo_co_g_per_s = Array::zeros(cyc_len);
+        let eo_hc_g_per_s = Array::zeros(cyc_len);
+        let eo_pm_g_per_s = Array::zeros(cyc_len);
+        let tp_nox_g_per_s = Array::zeros(cyc_len);
+        let tp_co_g_per_s = Array::zeros(cyc_len);
+        let tp_hc_g_per_s = Array::zeros(cyc_len);
+        let tp_pm_g_per_s = Array::zeros(cyc_len);
+        let trans_gear = Array::zeros(cyc_len);
+        let trans_in_rad_per_sec = Array::zeros(cyc_len);
+        let ess_te_deg_c = Array::zeros(cyc_len);
         let accel_kw = Array::zeros(cyc_len);
         let ascent_kw = Array::zeros(cyc_len);
         let rr_kw = Array::zeros(cyc_len);
@@ -121,9 +145,26 @@ impl RustSimDrive {
         let trace_miss_iters = Array::zeros(cyc_len);
         let newton_iters = Array::zeros(cyc_len);
         let fuel_kj = 0.0;
+        let co2_g = 0.0;
+        let eo_nox_g = 0.0;
+        let eo_co_g = 0.0;
+        let eo_hc_g = 0.0;
+        let eo_pm_g = 0.0;
+        let tp_nox_g = 0.0;
+        let tp_co_g = 0.0;
+        let tp_hc_g = 0.0;
+        let tp_pm_g = 0.0;
         let ess_dischg_kj = 0.0;
         let energy_audit_error = 0.0;
         let mpgge = 0.0;
+        let fuel_kg = 0.0;
+        let fuel_native = 0.0;
+        let mi_per_fuel_native = 0.0;
+        let fuel1_kj = 0.0;
+        let fuel2_kj = 0.0;
+        let fuel2_kg = 0.0;
+        let fuel2_native = 0.0;
+        let mi_per_fuel2_native = 0.0;
         let roadway_chg_kj = 0.0;
         let battery_kwh_per_mi = 0.0;
         let electric_kwh_per_mi = 0.0;
@@ -235,6 +276,27 @@ impl RustSimDrive {
             ess_perc_dead,
             drag_kw,
             ess_loss_kw,
+            ess_voltage_v,
+            ess_current_a,
+            ess_c_rate,
+            ess_v_rc_v,
+            trans_eff_ach,
+            cat_conv_frac,
+            fuel2_share,
+            fuel1_kw_out_ach,
+            fuel2_kw_out_ach,
+            co2_g_per_s,
+            eo_nox_g_per_s,
+            eo_co_g_per_s,
+            eo_hc_g_per_s,
+            eo_pm_g_per_s,
+            tp_nox_g_per_s,
+            tp_co_g_per_s,
+            tp_hc_g_per_s,
+            tp_pm_g_per_s,
+            trans_gear,
+            trans_in_rad_per_sec,
+            ess_te_deg_c,
             accel_kw,
             ascent_kw,
             rr_kw,
@@ -242,9 +304,26 @@ impl RustSimDrive {
             trace_miss_iters,
             newton_iters,
             fuel_kj,
+            co2_g,
+            eo_nox_g,
+            eo_co_g,
+            eo_hc_g,
+            eo_pm_g,
+            tp_nox_g,
+            tp_co_g,
+            tp_hc_g,
+            tp_pm_g,
             ess_dischg_kj,
             energy_audit_error,
             mpgge,
+            fuel_kg,
+            fuel_native,
+            mi_per_fuel_native,
+            fuel1_kj,
+            fuel2_kj,
+            fuel2_kg,
+            fuel2_native,
+            mi_per_fuel2_native,
             roadway_chg_kj,
             battery_kwh_per_mi,
             electric_kwh_per_mi,
@@ -269,6 +348,10 @@ impl RustSimDrive {
             idm_target_speed_m_per_s,
             cyc0_cache,
             aux_in_kw_override: None,
+            diagnostics: None,
+            energy_breakdown: None,
+            weather: None,
+            dist_m_before_step: Default::default(),
         }
     }
 
@@ -374,6 +457,27 @@ impl RustSimDrive {
         self.ess_perc_dead = Array::zeros(cyc_len);
         self.drag_kw = Array::zeros(cyc_len);
         self.ess_loss_kw = Array::zeros(cyc_len);
+        self.ess_voltage_v = Array::zeros(cyc_len);
+        self.ess_current_a = Array::zeros(cyc_len);
+        self.ess_c_rate = Array::zeros(cyc_len);
+        self.ess_v_rc_v = Array::zeros(cyc_len);
+        self.trans_eff_ach = Array::zeros(cyc_len);
+        self.cat_conv_frac = Array::zeros(cyc_len);
+        self.fuel2_share = Array::zeros(cyc_len);
+        self.fuel1_kw_out_ach = Array::zeros(cyc_len);
+        self.fuel2_kw_out_ach = Array::zeros(cyc_len);
+        self.co2_g_per_s = Array::zeros(cyc_len);
+        self.eo_nox_g_per_s = Array::zeros(cyc_len);
+        self.eo_co_g_per_s = Array::zeros(cyc_len);
+        self.eo_hc_g_per_s = Array::zeros(cyc_len);
+        self.eo_pm_g_per_s = Array::zeros(cyc_len);
+        self.tp_nox_g_per_s = Array::zeros(cyc_len);
+        self.tp_co_g_per_s = Array::zeros(cyc_len);
+        self.tp_hc_g_per_s = Array::zeros(cyc_len);
+        self.tp_pm_g_per_s = Array::zeros(cyc_len);
+        self.trans_gear = Array::zeros(cyc_len);
+        self.trans_in_rad_per_sec = Array::zeros(cyc_len);
+        self.ess_te_deg_c = Array::zeros(cyc_len);
         self.accel_kw = Array::zeros(cyc_len);
         self.ascent_kw = Array::zeros(cyc_len);
         self.rr_kw = Array::zeros(cyc_len);
@@ -402,54 +506,53 @@ impl RustSimDrive {
         let init_soc = match init_soc {
             Some(x) => x,
             None => {
-                if self.veh.veh_pt_type == CONV {
+                match self.veh.veh_pt_type {
                     // If no EV / Hybrid components, no SOC considerations.
-                    (self.veh.max_soc + self.veh.min_soc) / 2.0
-                } else if self.veh.veh_pt_type == HEV {
-                    // ####################################
-                    // ### Charge Balancing Vehicle SOC ###
-                    // ####################################
-                    // Charge balancing SOC for HEV vehicle types. Iterating init_soc and comparing to final SOC.
-                    // Iterating until tolerance met or 30 attempts made.
-                    let mut init_soc = (self.veh.max_soc + self.veh.min_soc) / 2.0;
-                    let mut ess_2fuel_kwh = 1.0;
-                    while ess_2fuel_kwh > self.veh.ess_to_fuel_ok_error
-                        && self.hev_sim_count < self.sim_params.sim_count_max
-                    {
-                        self.hev_sim_count += 1;
-                        self.walk(init_soc, aux_in_kw_override.clone())?;
-                        let fuel_kj = (&self.fs_kw_out_ach * self.cyc.dt_s()).sum();
-                        let roadway_chg_kj = (&self.roadway_chg_kw_out_ach * self.cyc.dt_s()).sum();
-                        if (fuel_kj + roadway_chg_kj) > 0.0 {
-                            ess_2fuel_kwh = ((self.soc[0]
-                                - self
-                                    .soc
-                                    .last()
-                                    .ok_or_else(|| anyhow!(format_dbg!(self.soc)))?)
-                                * self.veh.ess_max_kwh
-                                * 3.6e3
-                                / (fuel_kj + roadway_chg_kj))
-                                .abs();
-                        } else {
-                            ess_2fuel_kwh = 0.0;
+                    PowertrainType::Conv => (self.veh.max_soc + self.veh.min_soc) / 2.0,
+                    PowertrainType::HEV => {
+                        // ####################################
+                        // ### Charge Balancing Vehicle SOC ###
+                        // ####################################
+                        // Charge balancing SOC for HEV vehicle types. Iterating init_soc and comparing to final SOC.
+                        // Iterating until tolerance met or 30 attempts made.
+                        let mut init_soc = (self.veh.max_soc + self.veh.min_soc) / 2.0;
+                        let mut ess_2fuel_kwh = 1.0;
+                        while ess_2fuel_kwh > self.veh.ess_to_fuel_ok_error
+                            && self.hev_sim_count < self.sim_params.sim_count_max
+                        {
+                            self.hev_sim_count += 1;
+                            self.walk(init_soc, aux_in_kw_override.clone())?;
+                            let fuel_kj = (&self.fs_kw_out_ach * self.cyc.dt_s()).sum();
+                            let roadway_chg_kj =
+                                (&self.roadway_chg_kw_out_ach * self.cyc.dt_s()).sum();
+                            if (fuel_kj + roadway_chg_kj) > 0.0 {
+                                ess_2fuel_kwh = ((self.soc[0]
+                                    - self
+                                        .soc
+                                        .last()
+                                        .ok_or_else(|| anyhow!(format_dbg!(self.soc)))?)
+                                    * self.veh.ess_max_kwh
+                                    * 3.6e3
+                                    / (fuel_kj + roadway_chg_kj))
+                                    .abs();
+                            } else {
+                                ess_2fuel_kwh = 0.0;
+                            }
+                            init_soc = min(
+                                self.veh.max_soc,
+                                max(
+                                    self.veh.min_soc,
+                                    *self
+                                        .soc
+                                        .last()
+                                        .ok_or_else(|| anyhow!(format_dbg!(self.soc)))?,
+                                ),
+                            );
                         }
-                        init_soc = min(
-                            self.veh.max_soc,
-                            max(
-                                self.veh.min_soc,
-                                *self
-                                    .soc
-                                    .last()
-                                    .ok_or_else(|| anyhow!(format_dbg!(self.soc)))?,
-                            ),
-                        );
+                        init_soc
                     }
-                    init_soc
-                } else if self.veh.veh_pt_type == PHEV || self.veh.veh_pt_type == BEV {
                     // If EV, initializing initial SOC to maximum SOC.
-                    self.veh.max_soc
-                } else {
-                    bail!("Failed to properly initialize SOC.");
+                    PowertrainType::PHEV | PowertrainType::BEV => self.veh.max_soc,
                 }
             }
         };
@@ -465,12 +568,13 @@ impl RustSimDrive {
         aux_in_kw_override: Option<Array1<f64>>,
     ) -> anyhow::Result<()> {
         // Initialize and run sim_drive_walk as appropriate for vehicle attribute vehPtType.
-        let init_soc_auto = match self.veh.veh_pt_type.as_str() {
+        let init_soc_auto = match self.veh.veh_pt_type {
             // If no EV / Hybrid components, no SOC considerations.
-            CONV => (self.veh.max_soc + self.veh.min_soc) / 2.0,
-            HEV => (self.veh.max_soc + self.veh.min_soc) / 2.0,
+            PowertrainType::Conv | PowertrainType::HEV => {
+                (self.veh.max_soc + self.veh.min_soc) / 2.0
+            }
             // If EV, initializing initial SOC to maximum SOC.
-            _ => self.veh.max_soc,
+            PowertrainType::PHEV | PowertrainType::BEV => self.veh.max_soc,
         };
         let init_soc = init_soc.unwrap_or(init_soc_auto);
         self.walk(init_soc, aux_in_kw_override)?;
@@ -528,7 +632,7 @@ impl RustSimDrive {
         aux_in_kw_override: Option<Array1<f64>>,
     ) -> anyhow::Result<()> {
         ensure!(
-            self.veh.veh_pt_type == CONV
+            self.veh.veh_pt_type == PowertrainType::Conv
                 || (self.veh.min_soc..=self.veh.max_soc).contains(&init_soc),
             "provided init_soc={} is outside range min_soc={} to max_soc={}",
             init_soc,
@@ -537,6 +641,7 @@ impl RustSimDrive {
         );
 
         self.init_arrays();
+        self.diagnostics = self.sim_params.diagnostics.then(SimDiagnostics::default);
 
         // set `self.aux_in_kw_override` if it has been provided and not previously set
         if let Some(arr) = aux_in_kw_override {
@@ -550,6 +655,20 @@ impl RustSimDrive {
         self.cur_soc_target[0] = self.veh.max_soc;
         self.ess_cur_kwh[0] = init_soc * self.veh.ess_max_kwh;
         self.soc[0] = init_soc;
+        if let Some(ess_circuit) = &self.veh.ess_circuit {
+            self.ess_te_deg_c.fill(ess_circuit.te_ref_deg_c);
+            self.ess_voltage_v[0] = ess_circuit.ocv_v(init_soc);
+        }
+        match &self.veh.transmission {
+            Some(transmission) => {
+                self.trans_gear[0] = 1;
+                self.trans_eff_ach[0] = transmission.eff(1);
+                self.trans_in_rad_per_sec[0] =
+                    transmission.in_rad_per_sec(1, self.cyc0.mps[0] / self.veh.wheel_radius_m);
+            }
+            None => self.trans_eff_ach[0] = self.veh.trans_eff,
+        }
+        self.cat_conv_frac.fill(1.0);
         self.mps_ach[0] = self.cyc0.mps[0];
         self.mph_ach[0] = self.cyc0.mph_at_i(0);
 
@@ -587,7 +706,8 @@ impl RustSimDrive {
         if self.sim_params.coast_allow {
             self.set_coast_speed(self.i)?;
         }
-        self.solve_step(self.i)?;
+        self.solve_step(self.i)
+            .map_err(|err| FastSimError::at_step(self.i, err))?;
 
         if self.sim_params.missed_trace_correction
             && (self.cyc0.dist_m().slice(s![0..self.i]).sum() > 0.0)
@@ -604,16 +724,36 @@ impl RustSimDrive {
         Ok(())
     }
 
-    /// Perform all the calculations to solve 1 time step.
+    /// Perform all the calculations to solve 1 time step, using the energy
+    /// management strategy in `sim_params.energy_management`.
     pub fn solve_step(&mut self, i: usize) -> anyhow::Result<()> {
+        // moved out for the step rather than cloned, since the step needs
+        // `&mut self`
+        let em = std::mem::take(&mut self.sim_params.energy_management);
+        let res = self.solve_step_with_em(i, &em);
+        self.sim_params.energy_management = em;
+        res
+    }
+
+    /// Perform all the calculations to solve 1 time step, using `em` to make
+    /// hybrid control decisions.
+    pub fn solve_step_with_em<EM: EnergyManagement + ?Sized>(
+        &mut self,
+        i: usize,
+        em: &EM,
+    ) -> anyhow::Result<()> {
         self.set_misc_calcs(i)?;
         self.set_comp_lims(i)?;
         self.set_power_calcs(i)?;
         self.set_ach_speed(i)?;
-        self.set_hybrid_cont_calcs(i)?;
-        self.set_fc_forced_state_rust(i)?;
-        self.set_hybrid_cont_decisions(i)?;
+        em.set_hybrid_cont_calcs(self, i)?;
+        em.set_fc_forced_state(self, i)?;
+        em.set_hybrid_cont_decisions(self, i)?;
         self.set_fc_power(i)?;
+        self.set_emissions(i)?;
+        if self.sim_params.diagnostics {
+            self.check_step(i)?;
+        }
         Ok(())
     }
 
@@ -650,6 +790,8 @@ impl RustSimDrive {
     /// i: index of time step
     /// initSoc: initial SOC for electrified vehicles
     pub fn set_comp_lims(&mut self, i: usize) -> anyhow::Result<()> {
+        self.set_trans_calcs(i)?;
+
         // max fuel storage power output
         self.cur_max_fs_kw_out[i] = min(
             self.veh.fs_max_kw,
@@ -672,7 +814,15 @@ impl RustSimDrive {
                     * (self.soc[i - 1] - self.veh.min_soc)
                     / self.cyc.dt_s_at_i(i)
             };
-        self.cur_ess_max_kw_out[i] = min(self.veh.ess_max_kw, self.ess_cap_lim_dischg_kw[i]);
+        let (ess_max_dischg_kw, ess_max_chg_kw) = match &self.veh.ess_circuit {
+            Some(ess_circuit) => ess_circuit.pwr_lims_kw(
+                self.soc[i - 1],
+                self.ess_v_rc_v[i - 1],
+                self.ess_te_deg_c[i],
+            ),
+            None => (self.veh.ess_max_kw, self.veh.ess_max_kw),
+        };
+        self.cur_ess_max_kw_out[i] = min(ess_max_dischg_kw, self.ess_cap_lim_dischg_kw[i]);
 
         self.ess_cap_lim_chg_kw[i] = if self.veh.ess_max_kwh == 0.0 || self.veh.ess_max_kw == 0.0 {
             0.0
@@ -685,10 +835,10 @@ impl RustSimDrive {
             )
         };
 
-        self.cur_max_ess_chg_kw[i] = min(self.ess_cap_lim_chg_kw[i], self.veh.ess_max_kw);
+        self.cur_max_ess_chg_kw[i] = min(self.ess_cap_lim_chg_kw[i], ess_max_chg_kw);
 
         // Current maximum electrical power that can go toward propulsion, not including motor limitations
-        self.cur_max_elec_kw[i] = if self.veh.fc_eff_type == H2FC {
+        self.cur_max_elec_kw[i] = if self.veh.fc_eff_type == FcEffType::H2FC {
             self.cur_max_fc_kw_out[i] + self.cur_max_roadway_chg_kw[i] + self.cur_ess_max_kw_out[i]
                 - self.aux_in_kw[i]
         } else {
@@ -698,9 +848,33 @@ impl RustSimDrive {
         // Current maximum electrical power that can go toward propulsion, including motor limitations
         self.cur_max_avail_elec_kw[i] = min(self.cur_max_elec_kw[i], self.veh.mc_max_elec_in_kw);
 
+        // `mc_eff_map_2d` and motor speed at the prescribed vehicle speed and
+        // selected gear, as achieved speed is not yet known
+        let mc_eff_map_2d = self
+            .veh
+            .mc_eff_map_2d
+            .as_ref()
+            .zip(self.veh.transmission.as_ref())
+            .map(|(mc_eff_map_2d, transmission)| {
+                (
+                    mc_eff_map_2d,
+                    transmission.in_rad_per_sec(
+                        self.trans_gear[i] as usize,
+                        self.cyc.mps[i] / self.veh.wheel_radius_m,
+                    ),
+                )
+            });
+
         self.mc_elec_in_lim_kw[i] = if self.cur_max_elec_kw[i] > 0.0 {
             // limit power going into e-machine controller to
-            if self.cur_max_avail_elec_kw[i] == arrmax(&self.veh.mc_kw_in_array) {
+            if let Some((mc_eff_map_2d, mc_rad_per_sec)) = mc_eff_map_2d {
+                // efficiency is evaluated at the electrical input power
+                min(
+                    self.cur_max_avail_elec_kw[i]
+                        * mc_eff_map_2d.eff_at_kw(mc_rad_per_sec, self.cur_max_avail_elec_kw[i])?,
+                    self.veh.mc_max_kw,
+                )
+            } else if self.cur_max_avail_elec_kw[i] == arrmax(&self.veh.mc_kw_in_array) {
                 min(
                     *self
                         .veh
@@ -741,6 +915,18 @@ impl RustSimDrive {
 
         self.cur_max_mc_elec_kw_in[i] = if self.cur_max_mc_kw_out[i] == 0.0 {
             0.0
+        } else if let Some((mc_eff_map_2d, mc_rad_per_sec)) = mc_eff_map_2d {
+            let mc_eff = mc_eff_map_2d.eff_at_kw(mc_rad_per_sec, self.cur_max_mc_kw_out[i])?;
+            if self.cur_max_mc_kw_out[i] < 0.0 {
+                self.cur_max_mc_kw_out[i] * mc_eff
+            } else {
+                ensure!(
+                    mc_eff > 0.0,
+                    "{}\n`mc_eff_map_2d` efficiency must be positive when motoring",
+                    format_dbg!((mc_rad_per_sec, self.cur_max_mc_kw_out[i]))
+                );
+                self.cur_max_mc_kw_out[i] / mc_eff
+            }
         } else if self.cur_max_mc_kw_out[i] == self.veh.mc_max_kw {
             self.cur_max_mc_kw_out[i]
                 / self
@@ -771,6 +957,17 @@ impl RustSimDrive {
         };
         self.cur_max_mech_mc_kw_in[i] = if self.cur_max_ess_chg_kw[i] == 0.0 {
             0.0
+        } else if let Some((mc_eff_map_2d, mc_rad_per_sec)) = mc_eff_map_2d {
+            // negative power for regenerative braking
+            let mc_eff = mc_eff_map_2d.eff_at_kw(
+                mc_rad_per_sec,
+                -(self.cur_max_ess_chg_kw[i] - self.cur_max_roadway_chg_kw[i]),
+            )?;
+            if mc_eff > 0.0 {
+                min(self.veh.mc_max_kw, self.cur_max_ess_chg_kw[i] / mc_eff)
+            } else {
+                self.veh.mc_max_kw
+            }
         } else if self.veh.mc_max_kw == self.cur_max_ess_chg_kw[i] - self.cur_max_roadway_chg_kw[i]
         {
             min(
@@ -805,31 +1002,31 @@ impl RustSimDrive {
             / 1e3
             * self.max_trac_mps[i];
 
-        self.cur_max_trans_kw_out[i] = if self.veh.fc_eff_type == H2FC {
+        self.cur_max_trans_kw_out[i] = if self.veh.fc_eff_type == FcEffType::H2FC {
             if self.veh.no_elec_sys || self.veh.no_elec_aux || self.high_acc_fc_on_tag[i] {
                 min(
-                    (self.cur_max_mc_kw_out[i] - self.aux_in_kw[i]) * self.veh.trans_eff,
-                    self.cur_max_trac_kw[i] / self.veh.trans_eff,
+                    (self.cur_max_mc_kw_out[i] - self.aux_in_kw[i]) * self.trans_eff_ach[i],
+                    self.cur_max_trac_kw[i] / self.trans_eff_ach[i],
                 )
             } else {
                 min(
                     (self.cur_max_mc_kw_out[i] - min(self.cur_max_elec_kw[i], 0.0))
-                        * self.veh.trans_eff,
-                    self.cur_max_trac_kw[i] / self.veh.trans_eff,
+                        * self.trans_eff_ach[i],
+                    self.cur_max_trac_kw[i] / self.trans_eff_ach[i],
                 )
             }
         } else if self.veh.no_elec_sys || self.veh.no_elec_aux || self.high_acc_fc_on_tag[i] {
             min(
                 (self.cur_max_mc_kw_out[i] + self.cur_max_fc_kw_out[i] - self.aux_in_kw[i])
-                    * self.veh.trans_eff,
-                self.cur_max_trac_kw[i] / self.veh.trans_eff,
+                    * self.trans_eff_ach[i],
+                self.cur_max_trac_kw[i] / self.trans_eff_ach[i],
             )
         } else {
             min(
                 (self.cur_max_mc_kw_out[i] + self.cur_max_fc_kw_out[i]
                     - min(self.cur_max_elec_kw[i], 0.0))
-                    * self.veh.trans_eff,
-                self.cur_max_trac_kw[i] / self.veh.trans_eff,
+                    * self.trans_eff_ach[i],
+                self.cur_max_trac_kw[i] / self.trans_eff_ach[i],
             )
         };
         if self.impose_coast[i] {
@@ -838,6 +1035,70 @@ impl RustSimDrive {
         Ok(())
     }
 
+    /// Selects transmission gear for time step `i` from the prescribed speed
+    /// and the previous time step's load, if `veh.transmission` is provided,
+    /// and sets the corresponding transmission efficiency.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_trans_calcs(&mut self, i: usize) -> anyhow::Result<()> {
+        self.trans_eff_ach[i] = match &self.veh.transmission {
+            Some(transmission) => {
+                let max_in_kw = self.veh.fc_max_kw + self.veh.mc_max_kw;
+                let load_frac = if max_in_kw > 0.0 {
+                    self.trans_kw_in_ach[i - 1] / max_in_kw
+                } else {
+                    0.0
+                };
+                let gear = transmission.select_gear(
+                    self.trans_gear[i - 1] as usize,
+                    self.cyc.mps[i] / self.veh.wheel_radius_m,
+                    load_frac,
+                );
+                self.trans_gear[i] = gear as u32;
+                transmission.eff(gear)
+            }
+            None => self.veh.trans_eff,
+        };
+        Ok(())
+    }
+
+    /// Returns time \[s\] and distance traveled \[m\] at which `weather` is
+    /// evaluated for time step `i`, distance is only computed for
+    /// distance-indexed weather
+    pub fn weather_coords_for_step(&self, i: usize) -> (f64, f64) {
+        let dist_m = match &self.weather {
+            Some(weather) if !weather.dist_m.is_empty() => self.dist_m_before(i),
+            _ => 0.0,
+        };
+        (self.cyc.time_s[i], dist_m)
+    }
+
+    /// Returns distance traveled \[m\] before time step `i`, i.e. the sum of
+    /// `dist_m[..i]`, from the running total updated by `set_ach_speed` when
+    /// available
+    fn dist_m_before(&self, i: usize) -> f64 {
+        match self.dist_m_before_step {
+            (step, dist_m) if step == i => dist_m,
+            _ => self.dist_m.slice(s![0..i]).sum(),
+        }
+    }
+
+    /// Returns air density \[kg/m**3\] and headwind speed \[m/s\] for time step
+    /// `i`, taken from `weather` if provided
+    pub fn air_for_step(&self, i: usize) -> (f64, f64) {
+        match &self.weather {
+            Some(weather) => {
+                let (time_s, dist_m) = self.weather_coords_for_step(i);
+                (
+                    weather.air_density_kg_per_m3_at(time_s, dist_m),
+                    weather.headwind_mps_at(time_s, dist_m),
+                )
+            }
+            None => (self.props.air_density_kg_per_m3, 0.0),
+        }
+    }
+
     /// Calculate power requirements to meet cycle and determine if
     /// cycle can be met.
     /// Arguments
@@ -852,11 +1113,14 @@ impl RustSimDrive {
 
         let grade = self.lookup_grade_for_step(i, Some(mps_ach));
 
+        let mps_avg = (self.mps_ach[i - 1] + mps_ach) / 2.0;
+        let (air_density_kg_per_m3, headwind_mps) = self.air_for_step(i);
+        let mps_air = mps_avg + headwind_mps;
         self.drag_kw[i] = 0.5
-            * self.props.air_density_kg_per_m3
+            * air_density_kg_per_m3
             * self.veh.drag_coef
             * self.veh.frontal_area_m2
-            * ((self.mps_ach[i - 1] + mps_ach) / 2.0).powi(3)
+            * (mps_air * mps_air.abs() * mps_avg)
             / 1e3;
         self.accel_kw[i] = self.veh.veh_kg / (2.0 * self.cyc.dt_s_at_i(i))
             * (mps_ach.powi(2) - self.mps_ach[i - 1].powi(2))
@@ -897,7 +1161,7 @@ impl RustSimDrive {
                         .exp());
         self.cyc_regen_brake_kw[i] = max(
             min(
-                self.cur_max_mech_mc_kw_in[i] * self.veh.trans_eff,
+                self.cur_max_mech_mc_kw_in[i] * self.trans_eff_ach[i],
                 self.regen_contrl_lim_kw_perc[i] * -self.cyc_whl_kw_req[i],
             ),
             0.0,
@@ -914,13 +1178,13 @@ impl RustSimDrive {
         }
 
         self.trans_kw_in_ach[i] = if self.trans_kw_out_ach[i] > 0.0 {
-            self.trans_kw_out_ach[i] / self.veh.trans_eff
+            self.trans_kw_out_ach[i] / self.trans_eff_ach[i]
         } else {
-            self.trans_kw_out_ach[i] * self.veh.trans_eff
+            self.trans_kw_out_ach[i] * self.trans_eff_ach[i]
         };
 
         self.min_mc_kw_2help_fc[i] = if self.cyc_met[i] {
-            if self.veh.fc_eff_type == H2FC {
+            if self.veh.fc_eff_type == FcEffType::H2FC {
                 max(self.trans_kw_in_ach[i], -self.cur_max_mech_mc_kw_in[i])
             } else {
                 max(
@@ -951,24 +1215,28 @@ impl RustSimDrive {
             let mut grade_diff = grade_tol + 1.0;
             let max_grade_iter = 3;
             let mut grade_iter = 0;
+            let (air_density_kg_per_m3, headwind_mps) = self.air_for_step(i);
+            // half of the drag force coefficient [kg/m]
+            let drag_half_coef =
+                0.5 * air_density_kg_per_m3 * self.veh.drag_coef * self.veh.frontal_area_m2;
             while grade_diff > grade_tol && grade_iter < max_grade_iter {
                 grade_iter += 1;
                 grade = grade_estimate;
 
                 let drag3 = 1.0 / 16.0
-                    * self.props.air_density_kg_per_m3
+                    * air_density_kg_per_m3
                     * self.veh.drag_coef
                     * self.veh.frontal_area_m2;
                 let accel2 = 0.5 * self.veh.veh_kg / self.cyc.dt_s_at_i(i);
                 let drag2 = 3.0 / 16.0
-                    * self.props.air_density_kg_per_m3
+                    * air_density_kg_per_m3
                     * self.veh.drag_coef
                     * self.veh.frontal_area_m2
                     * self.mps_ach[i - 1];
                 let wheel2 = 0.5 * self.veh.wheel_inertia_kg_m2 * self.veh.num_wheels
                     / (self.cyc.dt_s_at_i(i) * self.veh.wheel_radius_m.powi(2));
                 let drag1 = 3.0 / 16.0
-                    * self.props.air_density_kg_per_m3
+                    * air_density_kg_per_m3
                     * self.veh.drag_coef
                     * self.veh.frontal_area_m2
                     * self.mps_ach[i - 1].powi(2);
@@ -981,7 +1249,7 @@ impl RustSimDrive {
                 let accel0 =
                     -0.5 * self.veh.veh_kg * self.mps_ach[i - 1].powi(2) / self.cyc.dt_s_at_i(i);
                 let drag0 = 1.0 / 16.0
-                    * self.props.air_density_kg_per_m3
+                    * air_density_kg_per_m3
                     * self.veh.drag_coef
                     * self.veh.frontal_area_m2
                     * self.mps_ach[i - 1].powi(3);
@@ -1002,10 +1270,19 @@ impl RustSimDrive {
                     * self.mps_ach[i - 1].powi(2)
                     / (self.cyc.dt_s_at_i(i) * self.veh.wheel_radius_m.powi(2));
 
+                // additional drag terms from (v + w)^2 * v - v^3 = 2 * w * v^2 + w^2 * v
+                // with average speed v and headwind w
+                let wind2 = drag_half_coef * headwind_mps / 2.0;
+                let wind1 = drag_half_coef
+                    * (headwind_mps * self.mps_ach[i - 1] + headwind_mps.powi(2) / 2.0);
+                let wind0 = drag_half_coef
+                    * (headwind_mps * self.mps_ach[i - 1].powi(2) / 2.0
+                        + headwind_mps.powi(2) * self.mps_ach[i - 1] / 2.0);
+
                 let t3 = drag3 / 1e3;
-                let t2 = (accel2 + drag2 + wheel2) / 1e3;
-                let t1 = (drag1 + roll1 + ascent1) / 1e3;
-                let t0 = (accel0 + drag0 + roll0 + ascent0 + wheel0) / 1e3
+                let t2 = (accel2 + drag2 + wheel2 + wind2) / 1e3;
+                let t1 = (drag1 + roll1 + ascent1 + wind1) / 1e3;
+                let t0 = (accel0 + drag0 + roll0 + ascent0 + wheel0 + wind0) / 1e3
                     - self.cur_max_trans_kw_out[i];
 
                 // initial guess
@@ -1080,7 +1357,14 @@ impl RustSimDrive {
 
         self.mph_ach[i] = self.mps_ach[i] * params::MPH_PER_MPS;
         self.dist_m[i] = self.mps_ach[i] * self.cyc.dt_s_at_i(i);
+        self.dist_m_before_step = (i + 1, self.dist_m_before(i) + self.dist_m[i]);
         self.dist_mi[i] = self.dist_m[i] * 1.0 / params::M_PER_MI;
+        if let Some(transmission) = &self.veh.transmission {
+            self.trans_in_rad_per_sec[i] = transmission.in_rad_per_sec(
+                self.trans_gear[i] as usize,
+                self.mps_ach[i] / self.veh.wheel_radius_m,
+            );
+        }
         Ok(())
     }
 
@@ -1538,12 +1822,15 @@ impl RustSimDrive {
             0.0
         } else if self.fc_forced_on[i]
             && self.can_pwr_all_elec[i]
-            && (self.veh.veh_pt_type == HEV || self.veh.veh_pt_type == PHEV)
-            && (self.veh.fc_eff_type != H2FC)
+            && matches!(
+                self.veh.veh_pt_type,
+                PowertrainType::HEV | PowertrainType::PHEV
+            )
+            && (self.veh.fc_eff_type != FcEffType::H2FC)
         {
             self.mc_mech_kw_4forced_fc[i]
         } else if self.trans_kw_in_ach[i] <= 0.0 {
-            if self.veh.fc_eff_type != H2FC && self.veh.fc_max_kw > 0.0 {
+            if self.veh.fc_eff_type != FcEffType::H2FC && self.veh.fc_max_kw > 0.0 {
                 if self.can_pwr_all_elec[i] {
                     -min(self.cur_max_mech_mc_kw_in[i], -self.trans_kw_in_ach[i])
                 } else {
@@ -1564,46 +1851,114 @@ impl RustSimDrive {
             max(self.min_mc_kw_2help_fc[i], self.mc_kw_if_fc_req[i])
         };
 
-        self.mc_elec_kw_in_ach[i] = if self.mc_mech_kw_out_ach[i] == 0.0 {
+        self.set_ess_power(i)
+    }
+
+    /// Motor electrical input power for mechanical output power `mc_mech_kw`
+    /// at time step `i`, from `veh.mc_eff_map_2d` at `trans_in_rad_per_sec[i]`
+    /// if provided, and otherwise from `veh.mc_full_eff_array`
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    /// mc_mech_kw: motor mechanical output power, negative when regenerating
+    pub fn mc_elec_kw_in_for(&self, i: usize, mc_mech_kw: f64) -> anyhow::Result<f64> {
+        Ok(if mc_mech_kw == 0.0 {
             0.0
-        } else if self.mc_mech_kw_out_ach[i] < 0.0 {
-            if -self.mc_mech_kw_out_ach[i] == arrmax(&self.veh.mc_kw_in_array) {
+        } else if let Some(mc_eff_map_2d) = &self.veh.mc_eff_map_2d {
+            let mc_eff = mc_eff_map_2d.eff_at_kw(self.trans_in_rad_per_sec[i], mc_mech_kw)?;
+            if mc_mech_kw < 0.0 {
+                mc_mech_kw * mc_eff
+            } else {
+                ensure!(
+                    mc_eff > 0.0,
+                    "{}\n`mc_eff_map_2d` efficiency must be positive when motoring",
+                    format_dbg!((self.trans_in_rad_per_sec[i], mc_mech_kw))
+                );
+                mc_mech_kw / mc_eff
+            }
+        } else if mc_mech_kw < 0.0 {
+            if -mc_mech_kw == arrmax(&self.veh.mc_kw_in_array) {
                 // this unwrap call has already been checked above
-                self.mc_mech_kw_out_ach[i] * self.veh.mc_full_eff_array.last().unwrap()
+                mc_mech_kw * self.veh.mc_full_eff_array.last().unwrap()
             } else {
-                self.mc_mech_kw_out_ach[i]
+                mc_mech_kw
                     * self.veh.mc_full_eff_array[cmp::max(
                         1,
                         first_grtr(
                             &self.veh.mc_kw_in_array,
-                            min(
-                                arrmax(&self.veh.mc_kw_in_array) * 0.9999,
-                                -self.mc_mech_kw_out_ach[i],
-                            ),
+                            min(arrmax(&self.veh.mc_kw_in_array) * 0.9999, -mc_mech_kw),
                         )
                         .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
                             - 1,
                     )]
             }
-        } else if self.veh.mc_max_kw == self.mc_mech_kw_out_ach[i] {
+        } else if self.veh.mc_max_kw == mc_mech_kw {
             // this unwrap call has already been checked
-            self.mc_mech_kw_out_ach[i] / self.veh.mc_full_eff_array.last().unwrap()
+            mc_mech_kw / self.veh.mc_full_eff_array.last().unwrap()
         } else {
-            self.mc_mech_kw_out_ach[i]
+            mc_mech_kw
                 / self.veh.mc_full_eff_array[cmp::max(
                     1,
                     first_grtr(
                         &self.veh.mc_kw_out_array,
-                        min(self.veh.mc_max_kw * 0.9999, self.mc_mech_kw_out_ach[i]),
+                        min(self.veh.mc_max_kw * 0.9999, mc_mech_kw),
                     )
                     .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
                         - 1,
                 )]
-        };
+        })
+    }
+
+    /// Fuel converter input power for output power `fc_kw_out` at time step
+    /// `i`, from `veh.fc_eff_map_2d` at `trans_in_rad_per_sec[i]` if
+    /// provided, and otherwise from `veh.fc_eff_array`
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    /// fc_kw_out: fuel converter output power
+    pub fn fc_kw_in_for(&self, i: usize, fc_kw_out: f64) -> anyhow::Result<f64> {
+        Ok(if fc_kw_out == 0.0 {
+            0.0
+        } else if let Some(fc_eff_map_2d) = &self.veh.fc_eff_map_2d {
+            let fc_eff = fc_eff_map_2d.eff_at_kw(self.trans_in_rad_per_sec[i], fc_kw_out)?;
+            ensure!(
+                fc_eff > 0.0,
+                "{}\n`fc_eff_map_2d` efficiency must be positive when producing power",
+                format_dbg!((self.trans_in_rad_per_sec[i], fc_kw_out))
+            );
+            fc_kw_out / fc_eff
+        } else if self.veh.fc_eff_array[first_grtr(
+            &self.veh.fc_kw_out_array,
+            min(fc_kw_out, self.veh.fc_max_kw),
+        )
+        .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
+            - 1]
+            != 0.0
+        {
+            fc_kw_out
+                / (self.veh.fc_eff_array[first_grtr(
+                    &self.veh.fc_kw_out_array,
+                    min(fc_kw_out, self.veh.fc_max_kw),
+                )
+                .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
+                    - 1])
+        } else {
+            0.0
+        })
+    }
+
+    /// Sets achieved motor electrical input power, roadway charging power, ESS
+    /// power, ESS energy, SOC, and fuel converter on-time that result from the
+    /// motor mechanical output power decided by the hybrid controller.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_ess_power(&mut self, i: usize) -> anyhow::Result<()> {
+        self.mc_elec_kw_in_ach[i] = self.mc_elec_kw_in_for(i, self.mc_mech_kw_out_ach[i])?;
 
         self.roadway_chg_kw_out_ach[i] = if self.cur_max_roadway_chg_kw[i] == 0.0 {
             0.0
-        } else if self.veh.fc_eff_type == H2FC {
+        } else if self.veh.fc_eff_type == FcEffType::H2FC {
             max(
                 0.0,
                 max(
@@ -1629,7 +1984,7 @@ impl RustSimDrive {
 
         self.ess_kw_out_ach[i] = if self.veh.ess_max_kw == 0.0 || self.veh.ess_max_kwh == 0.0 {
             0.0
-        } else if self.veh.fc_eff_type == H2FC {
+        } else if self.veh.fc_eff_type == FcEffType::H2FC {
             if self.trans_kw_out_ach[i] >= 0.0 {
                 min(
                     self.cur_ess_max_kw_out[i],
@@ -1656,6 +2011,20 @@ impl RustSimDrive {
 
         self.ess_cur_kwh[i] = if self.veh.no_elec_sys {
             0.0
+        } else if let Some(ess_circuit) = &self.veh.ess_circuit {
+            let step = ess_circuit.solve(
+                self.ess_kw_out_ach[i],
+                self.soc[i - 1],
+                self.ess_v_rc_v[i - 1],
+                self.ess_te_deg_c[i],
+                self.cyc.dt_s_at_i(i),
+            );
+            self.ess_voltage_v[i] = step.voltage_v;
+            self.ess_current_a[i] = step.current_a;
+            self.ess_c_rate[i] = step.current_a / ess_circuit.cap_ah(self.veh.ess_max_kwh);
+            self.ess_v_rc_v[i] = step.v_rc_v;
+            self.ess_loss_kw[i] = step.loss_kw;
+            self.ess_cur_kwh[i - 1] - step.chem_kw * self.cyc.dt_s_at_i(i) / 3.6e3
         } else if self.ess_kw_out_ach[i] < 0.0 {
             self.ess_cur_kwh[i - 1]
                 - self.ess_kw_out_ach[i] * self.cyc.dt_s_at_i(i) / 3.6e3
@@ -1688,7 +2057,7 @@ impl RustSimDrive {
     pub fn set_fc_power(&mut self, i: usize) -> anyhow::Result<()> {
         self.fc_kw_out_ach[i] = if self.veh.fc_max_kw == 0.0 {
             0.0
-        } else if self.veh.fc_eff_type == H2FC {
+        } else if self.veh.fc_eff_type == FcEffType::H2FC {
             min(
                 self.cur_max_fc_kw_out[i],
                 max(
@@ -1719,36 +2088,102 @@ impl RustSimDrive {
             self.fc_kw_out_ach[i] / self.veh.fc_max_kw
         };
 
-        self.fc_kw_in_ach[i] = if self.fc_kw_out_ach[i] == 0.0 {
-            0.0
-        } else if self.veh.fc_eff_array[first_grtr(
-            &self.veh.fc_kw_out_array,
-            min(self.fc_kw_out_ach[i], self.veh.fc_max_kw),
-        )
-        .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
-            - 1]
-            != 0.0
-        {
-            self.fc_kw_out_ach[i]
-                / (self.veh.fc_eff_array[first_grtr(
-                    &self.veh.fc_kw_out_array,
-                    min(self.fc_kw_out_ach[i], self.veh.fc_max_kw),
-                )
-                .ok_or_else(|| anyhow!(format_dbg!("`first_grtr` returned `None`")))?
-                    - 1])
-        } else {
-            0.0
-        };
+        self.fc_kw_in_ach[i] = self.fc_kw_in_for(i, self.fc_kw_out_ach[i])?;
 
         self.fs_kw_out_ach[i] = self.fc_kw_in_ach[i];
 
         self.fs_kwh_out_ach[i] = self.fs_kw_out_ach[i] * self.cyc.dt_s_at_i(i) / 3.6e3;
+        self.set_fuel_split(i);
         Ok(())
     }
 
+    /// Splits fuel storage output power between `veh.fuel_props()` and
+    /// `veh.secondary_fuel`, if provided, based on fuel converter output
+    /// power fraction.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_fuel_split(&mut self, i: usize) {
+        self.fuel2_share[i] = match &self.veh.secondary_fuel {
+            Some(secondary_fuel) if self.veh.fc_max_kw > 0.0 => {
+                secondary_fuel.share(self.fc_kw_out_ach[i] / self.veh.fc_max_kw)
+            }
+            _ => 0.0,
+        };
+        self.fuel2_kw_out_ach[i] = self.fuel2_share[i] * self.fs_kw_out_ach[i];
+        self.fuel1_kw_out_ach[i] = self.fs_kw_out_ach[i] - self.fuel2_kw_out_ach[i];
+    }
+
+    /// Sets emission rates for the current time step from fuel converter
+    /// input power, if `veh.emissions` is provided.
+    /// Arguments
+    /// ------------
+    /// i: index of time step
+    pub fn set_emissions(&mut self, i: usize) -> anyhow::Result<()> {
+        if let Some(emissions) = &self.veh.emissions {
+            let mut step = emissions.solve(
+                &self.veh.fuel_props(),
+                self.fuel1_kw_out_ach[i],
+                self.fc_kw_out_ach_pct[i],
+                self.cat_conv_frac[i],
+            );
+            if let Some(secondary_fuel) = &self.veh.secondary_fuel {
+                step += emissions.solve(
+                    &secondary_fuel.fuel,
+                    self.fuel2_kw_out_ach[i],
+                    self.fc_kw_out_ach_pct[i],
+                    self.cat_conv_frac[i],
+                );
+            }
+            self.co2_g_per_s[i] = step.co2_g_per_s;
+            self.eo_nox_g_per_s[i] = step.eo_g_per_s[0];
+            self.eo_co_g_per_s[i] = step.eo_g_per_s[1];
+            self.eo_hc_g_per_s[i] = step.eo_g_per_s[2];
+            self.eo_pm_g_per_s[i] = step.eo_g_per_s[3];
+            self.tp_nox_g_per_s[i] = step.tp_g_per_s[0];
+            self.tp_co_g_per_s[i] = step.tp_g_per_s[1];
+            self.tp_hc_g_per_s[i] = step.tp_g_per_s[2];
+            self.tp_pm_g_per_s[i] = step.tp_g_per_s[3];
+        }
+        Ok(())
+    }
+
+    /// Battery wear calcs.  Sets `add_kwh`, the cumulative energy added to the
+    /// battery during each charge event; `dod_cycs`, the depth of discharge
+    /// of each completed charge event; and `ess_perc_dead`, the fraction of
+    /// battery cycle life consumed by each charge event per `ess_life_coef_a`
+    /// and `ess_life_coef_b`.
+    pub fn set_battery_wear(&mut self) {
+        for i in 1..self.len() {
+            self.add_kwh[i] = if self.ess_cur_kwh[i] > self.ess_cur_kwh[i - 1] {
+                (self.ess_cur_kwh[i] - self.ess_cur_kwh[i - 1]) + self.add_kwh[i - 1]
+            } else {
+                0.0
+            };
+        }
+        for i in 1..self.len() {
+            self.dod_cycs[i] = if self.veh.ess_max_kwh == 0.0 || self.add_kwh[i] != 0.0 {
+                0.0
+            } else {
+                self.add_kwh[i - 1] / self.veh.ess_max_kwh
+            };
+        }
+        for i in 0..self.len() {
+            self.ess_perc_dead[i] = if self.dod_cycs[i] != 0.0 {
+                (self.veh.ess_life_coef_a / self.dod_cycs[i]).powf(1.0 / self.veh.ess_life_coef_b)
+            } else {
+                0.0
+            };
+        }
+    }
+
     /// Sets scalar variables that can be calculated after a cycle is run.
     /// This includes mpgge, various energy metrics, and others
     pub fn set_post_scalars(&mut self) -> anyhow::Result<()> {
+        let i_last = self.len() - 1;
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics.set_hotspots(&self.newton_iters, &self.trace_miss_iters, i_last);
+        }
         self.mpgge = if self.fs_kwh_out_ach.sum() == 0.0 {
             0.0
         } else {
@@ -1777,6 +2212,37 @@ impl RustSimDrive {
             0.0
         };
         self.fuel_kj = (&self.fs_kw_out_ach * &dt_s).sum();
+        self.fuel2_kj = (&self.fuel2_kw_out_ach * &dt_s).sum();
+        self.fuel1_kj = self.fuel_kj - self.fuel2_kj;
+        let fuel = self.veh.fuel_props();
+        self.fuel_native = fuel.native_units_from_kj(self.fuel1_kj);
+        self.mi_per_fuel_native = if self.fuel_native == 0.0 {
+            0.0
+        } else {
+            dist_mi / self.fuel_native
+        };
+        (self.fuel2_kg, self.fuel2_native) = match &self.veh.secondary_fuel {
+            Some(secondary_fuel) => (
+                secondary_fuel.fuel.kg_from_kj(self.fuel2_kj),
+                secondary_fuel.fuel.native_units_from_kj(self.fuel2_kj),
+            ),
+            None => (0.0, 0.0),
+        };
+        self.mi_per_fuel2_native = if self.fuel2_native == 0.0 {
+            0.0
+        } else {
+            dist_mi / self.fuel2_native
+        };
+        self.fuel_kg = fuel.kg_from_kj(self.fuel1_kj) + self.fuel2_kg;
+        self.co2_g = (&self.co2_g_per_s * &dt_s).sum();
+        self.eo_nox_g = (&self.eo_nox_g_per_s * &dt_s).sum();
+        self.eo_co_g = (&self.eo_co_g_per_s * &dt_s).sum();
+        self.eo_hc_g = (&self.eo_hc_g_per_s * &dt_s).sum();
+        self.eo_pm_g = (&self.eo_pm_g_per_s * &dt_s).sum();
+        self.tp_nox_g = (&self.tp_nox_g_per_s * &dt_s).sum();
+        self.tp_co_g = (&self.tp_co_g_per_s * &dt_s).sum();
+        self.tp_hc_g = (&self.tp_hc_g_per_s * &dt_s).sum();
+        self.tp_pm_g = (&self.tp_pm_g_per_s * &dt_s).sum();
 
         self.ess2fuel_kwh = if (self.fuel_kj + self.roadway_chg_kj) == 0.0 {
             1.0
@@ -1792,6 +2258,9 @@ impl RustSimDrive {
         for i in 1..self.cyc.len() {
             self.ess_loss_kw[i] = if self.veh.ess_max_kw == 0.0 || self.veh.ess_max_kwh == 0.0 {
                 0.0
+            } else if self.veh.ess_circuit.is_some() {
+                // already calculated by equivalent circuit in `set_ess_power`
+                self.ess_loss_kw[i]
             } else if self.ess_kw_out_ach[i] < 0.0 {
                 -self.ess_kw_out_ach[i]
                     - (-self.ess_kw_out_ach[i] * self.veh.ess_round_trip_eff.sqrt())
@@ -1835,6 +2304,7 @@ impl RustSimDrive {
         self.energy_audit_error =
             ((self.roadway_chg_kj + self.ess_dischg_kj + self.fuel_kj + self.ke_kj) - self.net_kj)
                 / (self.roadway_chg_kj + self.ess_dischg_kj + self.fuel_kj + self.ke_kj);
+        self.energy_breakdown = Some(self.energy_breakdown_for(0, i_last)?);
 
         if self.energy_audit_error.abs() > self.sim_params.energy_audit_error_tol {
             #[cfg(feature = "logging")]
@@ -1897,6 +2367,28 @@ impl RustSimDrive {
                 self.sim_params.trace_miss_speed_mps_tol
             );
         }
+
+        if self.sim_params.error_on_energy_audit
+            && self.energy_audit_error.abs() > self.sim_params.energy_audit_error_tol
+        {
+            bail!(FastSimError::EnergyAudit {
+                error: self.energy_audit_error,
+                tol: self.sim_params.energy_audit_error_tol,
+            });
+        }
+        if self.sim_params.error_on_trace_miss && self.trace_miss {
+            let (i, _) = (&self.mps_ach - &self.cyc.mps)
+                .iter()
+                .map(|x| x.abs())
+                .enumerate()
+                .fold((0, 0.0), |acc, (i, x)| if x > acc.1 { (i, x) } else { acc });
+            bail!(FastSimError::TraceMiss {
+                i,
+                speed_mps: self.trace_miss_speed_mps,
+                dist_frac: self.trace_miss_dist_frac,
+                time_frac: self.trace_miss_time_frac,
+            });
+        }
         Ok(())
     }
 }
@@ -61,6 +61,48 @@ impl SimDriveVec {
         Ok(())
     }
 
+    /// Calls `sim_drive` method for each simdrive instance in vec, running
+    /// every instance even if some fail.
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive` calls
+    /// # Returns:
+    /// result of each `sim_drive` call, in order
+    pub fn sim_drive_each(&mut self, parallelize: Option<bool>) -> Vec<anyhow::Result<()>> {
+        let parallelize = parallelize.unwrap_or(true);
+        if parallelize {
+            self.0
+                .par_iter_mut()
+                .map(|sd| sd.sim_drive(None, None))
+                .collect()
+        } else {
+            self.0
+                .iter_mut()
+                .map(|sd| sd.sim_drive(None, None))
+                .collect()
+        }
+    }
+
+    /// Calls `sim_drive_accel` method for each simdrive instance in vec,
+    /// running every instance even if some fail.
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive_accel` calls
+    /// # Returns:
+    /// result of each `sim_drive_accel` call, in order
+    pub fn sim_drive_accel_each(&mut self, parallelize: Option<bool>) -> Vec<anyhow::Result<()>> {
+        let parallelize = parallelize.unwrap_or(true);
+        if parallelize {
+            self.0
+                .par_iter_mut()
+                .map(|sd| sd.sim_drive_accel(None, None))
+                .collect()
+        } else {
+            self.0
+                .iter_mut()
+                .map(|sd| sd.sim_drive_accel(None, None))
+                .collect()
+        }
+    }
+
     pub fn push(&mut self, sd: RustSimDrive) {
         self.0.push(sd);
     }
@@ -0,0 +1,358 @@
+//! Module containing a streaming simulator that accepts speed samples
+//! incrementally, e.g. from telematics feeds or co-simulation loops
+
+// crate local
+use super::{RustSimDrive, RustSimDriveParams};
+use crate::cycle::RustCycle;
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::*;
+
+/// Number of samples for which arrays are initially allocated
+const INIT_CAPACITY: usize = 64;
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// Outputs of the most recent time step and running totals of
+/// [SimDriveStream]
+pub struct SimDriveStreamState {
+    /// time step index
+    pub i: usize,
+    /// elapsed time \[s\]
+    pub time_s: f64,
+    /// prescribed speed \[m/s\]
+    pub mps: f64,
+    /// achieved speed \[m/s\]
+    pub mps_ach: f64,
+    /// whether prescribed speed was achieved
+    pub trace_met: bool,
+    /// battery state of charge
+    pub soc: f64,
+    /// fuel storage output power \[kW\]
+    pub fs_kw_out_ach: f64,
+    /// fuel converter output power \[kW\]
+    pub fc_kw_out_ach: f64,
+    /// electric motor mechanical output power \[kW\]
+    pub mc_mech_kw_out_ach: f64,
+    /// battery output power \[kW\]
+    pub ess_kw_out_ach: f64,
+    /// CO2 emission rate \[g/s\], only calculated if `veh.emissions` is provided
+    pub co2_g_per_s: f64,
+    /// cumulative distance \[mi\]
+    pub cumu_dist_mi: f64,
+    /// cumulative fuel energy \[kJ\]
+    pub cumu_fuel_kj: f64,
+    /// cumulative net battery discharge energy \[kJ\]
+    pub cumu_ess_dischg_kj: f64,
+    /// cumulative roadway charging energy \[kJ\]
+    pub cumu_roadway_chg_kj: f64,
+    /// cumulative CO2 emissions \[g\]
+    pub cumu_co2_g: f64,
+    /// fuel economy so far \[mi/gge\]
+    pub mpgge: f64,
+    /// electrical energy consumption so far \[kWh/mi\]
+    pub electric_kwh_per_mi: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SimDriveStreamState {}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (veh, init_soc=None, sim_params=None))]
+    pub fn __new__(
+        veh: RustVehicle,
+        init_soc: Option<f64>,
+        sim_params: Option<RustSimDriveParams>,
+    ) -> anyhow::Result<Self> {
+        Self::new(veh, init_soc, sim_params)
+    }
+
+    #[pyo3(name = "step")]
+    /// Simulates one sample and returns step outputs and running totals.
+    /// # Arguments
+    /// - `dt_s`: time since previous sample \[s\]
+    /// - `mps`: prescribed speed \[m/s\]
+    /// - `grade`: road grade
+    pub fn step_py(&mut self, dt_s: f64, mps: f64, grade: f64) -> anyhow::Result<SimDriveStreamState> {
+        self.step(dt_s, mps, grade)
+    }
+
+    #[pyo3(name = "to_sim_drive")]
+    /// Returns `RustSimDrive` with arrays for all received samples and post
+    /// scalars set
+    pub fn to_sim_drive_py(&self) -> anyhow::Result<RustSimDrive> {
+        self.to_sim_drive()
+    }
+
+    #[pyo3(name = "len")]
+    pub fn len_py(&self) -> usize {
+        self.len()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Streaming simulator that takes one (dt, speed, grade) sample at a time,
+/// starting from rest at time zero.  Arrays of `sd` are preallocated beyond
+/// the received samples and are reallocated with doubled capacity as needed,
+/// replaying received samples so that results are identical to running
+/// `RustSimDrive::walk` over the full cycle with the same `init_soc`.
+///
+/// Coasting, IDM, and missed trace correction modify the cycle ahead of the
+/// current step and are therefore not supported.
+pub struct SimDriveStream {
+    /// underlying simulation, only the first `len()` elements of its arrays
+    /// are valid
+    #[api(skip_set)]
+    pub sd: RustSimDrive,
+    /// initial battery state of charge
+    #[api(skip_set)]
+    pub init_soc: f64,
+    #[api(skip_set)]
+    pub state: SimDriveStreamState,
+}
+
+impl SerdeAPI for SimDriveStream {}
+
+impl SimDriveStream {
+    /// # Arguments
+    /// - `veh`: vehicle
+    /// - `init_soc`: initial battery state of charge, defaults to the middle
+    ///   of the SOC window for conventional and hybrid vehicles and `max_soc`
+    ///   for plug-in vehicles
+    /// - `sim_params`: simulation parameters, defaults to
+    ///   `RustSimDriveParams::default()`
+    pub fn new(
+        veh: RustVehicle,
+        init_soc: Option<f64>,
+        sim_params: Option<RustSimDriveParams>,
+    ) -> anyhow::Result<Self> {
+        let sim_params = sim_params.unwrap_or_default();
+        ensure!(
+            !sim_params.coast_allow && !sim_params.idm_allow && !sim_params.missed_trace_correction,
+            "{}\ncoasting, IDM, and missed trace correction are not supported for streaming",
+            format_dbg!((
+                sim_params.coast_allow,
+                sim_params.idm_allow,
+                sim_params.missed_trace_correction
+            ))
+        );
+        let init_soc = init_soc.unwrap_or(match veh.veh_pt_type {
+            PowertrainType::Conv | PowertrainType::HEV => (veh.max_soc + veh.min_soc) / 2.0,
+            PowertrainType::PHEV | PowertrainType::BEV => veh.max_soc,
+        });
+        let mut sd = RustSimDrive::new(padded_cycle(&[0.0], &[0.0], &[0.0], INIT_CAPACITY), veh);
+        sd.sim_params = sim_params;
+        sd.init_for_step(init_soc, None)?;
+        let state = SimDriveStreamState {
+            soc: sd.soc[0],
+            ..Default::default()
+        };
+        Ok(Self {
+            sd,
+            init_soc,
+            state,
+        })
+    }
+
+    /// Number of samples received, including the initial sample at rest
+    pub fn len(&self) -> usize {
+        self.sd.i
+    }
+
+    /// Always false because of the initial sample at rest
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Simulates one sample and returns step outputs and running totals.
+    /// # Arguments
+    /// - `dt_s`: time since previous sample \[s\]
+    /// - `mps`: prescribed speed \[m/s\]
+    /// - `grade`: road grade
+    pub fn step(&mut self, dt_s: f64, mps: f64, grade: f64) -> anyhow::Result<SimDriveStreamState> {
+        ensure!(
+            dt_s > 0.0 && dt_s.is_finite(),
+            "{}\ntime step must be positive",
+            format_dbg!(dt_s)
+        );
+        ensure!(
+            mps >= 0.0 && mps.is_finite(),
+            "{}\nspeed must be non-negative",
+            format_dbg!(mps)
+        );
+        ensure!(grade.is_finite(), "{}", format_dbg!(grade));
+
+        if self.len() == self.sd.len() {
+            self.grow()?;
+        }
+        let i = self.len();
+        let time_s = self.sd.cyc0.time_s[i - 1] + dt_s;
+        for cyc in [&mut self.sd.cyc, &mut self.sd.cyc0] {
+            cyc.time_s[i] = time_s;
+            cyc.mps[i] = mps;
+            cyc.grade[i] = grade;
+        }
+        if grade != 0.0 && self.sd.cyc0_cache.grade_all_zero {
+            self.sd.cyc0_cache = self.sd.cyc0.build_cache();
+        }
+        self.sd.step()?;
+
+        let sd = &self.sd;
+        let state = &mut self.state;
+        state.i = i;
+        state.time_s = time_s;
+        state.mps = mps;
+        state.mps_ach = sd.mps_ach[i];
+        state.trace_met = sd.cyc_met[i];
+        state.soc = sd.soc[i];
+        state.fs_kw_out_ach = sd.fs_kw_out_ach[i];
+        state.fc_kw_out_ach = sd.fc_kw_out_ach[i];
+        state.mc_mech_kw_out_ach = sd.mc_mech_kw_out_ach[i];
+        state.ess_kw_out_ach = sd.ess_kw_out_ach[i];
+        state.co2_g_per_s = sd.co2_g_per_s[i];
+        state.cumu_dist_mi += sd.dist_mi[i];
+        state.cumu_fuel_kj += sd.fs_kw_out_ach[i] * dt_s;
+        state.cumu_ess_dischg_kj = (sd.soc[0] - sd.soc[i]) * sd.veh.ess_max_kwh * 3.6e3;
+        state.cumu_roadway_chg_kj += sd.roadway_chg_kw_out_ach[i] * dt_s;
+        state.cumu_co2_g += sd.co2_g_per_s[i] * dt_s;
+        state.mpgge = if state.cumu_fuel_kj > 0.0 {
+            state.cumu_dist_mi / (state.cumu_fuel_kj / 3.6e3 / sd.props.kwh_per_gge)
+        } else {
+            0.0
+        };
+        state.electric_kwh_per_mi = if state.cumu_dist_mi > 0.0 {
+            (state.cumu_roadway_chg_kj + state.cumu_ess_dischg_kj) / 3.6e3 / state.cumu_dist_mi
+        } else {
+            0.0
+        };
+        Ok(self.state.clone())
+    }
+
+    /// Returns `RustSimDrive` with arrays for all received samples and post
+    /// scalars set, obtained by re-running the received samples
+    pub fn to_sim_drive(&self) -> anyhow::Result<RustSimDrive> {
+        let mut sd = self.replay(self.len())?;
+        sd.set_post_scalars()?;
+        Ok(sd)
+    }
+
+    /// Reallocates `sd` with double capacity
+    fn grow(&mut self) -> anyhow::Result<()> {
+        self.sd = self.replay(2 * self.sd.len())?;
+        Ok(())
+    }
+
+    /// Returns new `RustSimDrive` with capacity for `capacity` samples that
+    /// has been stepped through all received samples
+    fn replay(&self, capacity: usize) -> anyhow::Result<RustSimDrive> {
+        let n = self.len();
+        let cyc0 = &self.sd.cyc0;
+        let mut sd = RustSimDrive::new(
+            padded_cycle(
+                &cyc0.time_s.as_slice().unwrap()[..n],
+                &cyc0.mps.as_slice().unwrap()[..n],
+                &cyc0.grade.as_slice().unwrap()[..n],
+                capacity,
+            ),
+            self.sd.veh.clone(),
+        );
+        sd.sim_params = self.sd.sim_params.clone();
+        sd.props = self.sd.props.clone();
+        sd.init_for_step(self.init_soc, None)?;
+        while sd.i < n {
+            sd.step()?;
+        }
+        Ok(sd)
+    }
+}
+
+/// Returns cycle with `capacity` samples, of which those beyond the
+/// provided samples are placeholders at rest
+fn padded_cycle(time_s: &[f64], mps: &[f64], grade: &[f64], capacity: usize) -> RustCycle {
+    let n = time_s.len();
+    let t_last = time_s[n - 1];
+    RustCycle {
+        time_s: Array::from_iter(
+            time_s
+                .iter()
+                .copied()
+                .chain((1..=capacity - n).map(|k| t_last + k as f64)),
+        ),
+        mps: Array::from_iter(
+            mps.iter()
+                .copied()
+                .chain(std::iter::repeat(0.0))
+                .take(capacity),
+        ),
+        grade: Array::from_iter(
+            grade
+                .iter()
+                .copied()
+                .chain(std::iter::repeat(0.0))
+                .take(capacity),
+        ),
+        road_type: Array::zeros(capacity),
+        name: String::from("stream"),
+        orphaned: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_matches_sim_drive() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        for veh in [
+            RustVehicle::mock_vehicle(),
+            RustVehicle::from_file(
+                resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+                false,
+            )
+            .unwrap(),
+        ] {
+            let mut stream = SimDriveStream::new(veh.clone(), None, None).unwrap();
+            let mut state = SimDriveStreamState::default();
+            for i in 1..cyc.len() {
+                state = stream
+                    .step(cyc.dt_s_at_i(i), cyc.mps[i], cyc.grade[i])
+                    .unwrap();
+            }
+            assert_eq!(stream.len(), cyc.len());
+            assert!(stream.sd.len() > cyc.len());
+
+            let mut sd = RustSimDrive::new(cyc.clone(), veh);
+            sd.sim_drive(Some(stream.init_soc), None).unwrap();
+            assert!((state.mpgge - sd.mpgge).abs() < 1e-9 * sd.mpgge.max(1.0));
+            assert!((state.cumu_fuel_kj - sd.fuel_kj).abs() < 1e-6);
+            assert!((state.cumu_ess_dischg_kj - sd.ess_dischg_kj).abs() < 1e-6);
+            assert_eq!(state.soc, *sd.soc.last().unwrap());
+
+            let sd_stream = stream.to_sim_drive().unwrap();
+            assert_eq!(sd_stream.mps_ach, sd.mps_ach);
+            assert_eq!(sd_stream.fs_kw_out_ach, sd.fs_kw_out_ach);
+            assert_eq!(sd_stream.mpgge, sd.mpgge);
+        }
+    }
+
+    #[test]
+    fn test_stream_validation() {
+        let veh = RustVehicle::mock_vehicle();
+        let sim_params = RustSimDriveParams {
+            coast_allow: true,
+            ..Default::default()
+        };
+        assert!(SimDriveStream::new(veh.clone(), None, Some(sim_params)).is_err());
+        let mut stream = SimDriveStream::new(veh, None, None).unwrap();
+        assert!(stream.step(0.0, 1.0, 0.0).is_err());
+        assert!(stream.step(1.0, -1.0, 0.0).is_err());
+        let state = stream.step(1.0, 1.0, 0.02).unwrap();
+        assert_eq!(state.i, 1);
+        assert_eq!(state.time_s, 1.0);
+        assert!(state.fs_kw_out_ach > 0.0);
+    }
+}
@@ -0,0 +1,745 @@
+//! Module for signal phase and timing (SPaT) aware approach speed planning:
+//! attaching signalized intersections to a cycle and replanning the speed
+//! trace around each so that the vehicle passes on green, without stopping
+//! when a green window is reachable
+
+use super::eco_cruise::{cycle_from_speeds, STOP_SPEED_MPS};
+use super::RustSimDrive;
+use crate::cycle::{calc_constant_jerk_trajectory, speed_for_constant_jerk, RustCycle};
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::RustVehicle;
+
+/// Tolerance on acceleration limits \[m/s^2\] and on the speed and distance
+/// reached by a trajectory
+const TOL: f64 = 1e-6;
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (dist_m, cycle_s, green_s, offset_s=0.0))]
+    pub fn __new__(dist_m: f64, cycle_s: f64, green_s: f64, offset_s: f64) -> anyhow::Result<Self> {
+        let mut signal = Self {
+            dist_m,
+            cycle_s,
+            green_s,
+            offset_s,
+            orphaned: false,
+        };
+        signal.init()?;
+        Ok(signal)
+    }
+
+    #[pyo3(name = "is_green")]
+    /// Whether the signal is green at `time_s`
+    pub fn is_green_py(&self, time_s: f64) -> bool {
+        self.is_green(time_s)
+    }
+
+    #[pyo3(name = "next_green_s")]
+    /// Earliest time at or after `time_s` at which the signal is green
+    pub fn next_green_s_py(&self, time_s: f64) -> f64 {
+        self.next_green_s(time_s)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Signalized intersection along a cycle.  The signal is green for `green_s`
+/// at the start of each signal cycle, and red (including any yellow) for the
+/// rest, with signal cycles starting at `offset_s` plus multiples of
+/// `cycle_s`.
+pub struct TrafficSignal {
+    /// distance of the stop line from the start of the cycle \[m\]
+    pub dist_m: f64,
+    /// signal cycle length \[s\]
+    pub cycle_s: f64,
+    /// green phase duration \[s\]
+    pub green_s: f64,
+    /// time at which a green phase starts, relative to the start of the
+    /// cycle \[s\]
+    pub offset_s: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for TrafficSignal {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.dist_m >= 0.0,
+            "`dist_m` must be non-negative, got {}",
+            self.dist_m
+        );
+        ensure!(
+            self.cycle_s > 0.0,
+            "`cycle_s` must be positive, got {}",
+            self.cycle_s
+        );
+        ensure!(
+            self.green_s > 0.0 && self.green_s <= self.cycle_s,
+            "`green_s` must be positive and at most `cycle_s`, got {}",
+            self.green_s
+        );
+        Ok(())
+    }
+}
+
+impl TrafficSignal {
+    /// Whether the signal is green at `time_s`
+    pub fn is_green(&self, time_s: f64) -> bool {
+        (time_s - self.offset_s).rem_euclid(self.cycle_s) < self.green_s
+    }
+
+    /// Earliest time at or after `time_s` at which the signal is green
+    pub fn next_green_s(&self, time_s: f64) -> f64 {
+        let phase_s = (time_s - self.offset_s).rem_euclid(self.cycle_s);
+        if phase_s < self.green_s {
+            time_s
+        } else {
+            time_s + self.cycle_s - phase_s
+        }
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (
+        signals,
+        anticipate=true,
+        approach_dist_m=300.0,
+        horizon_s=60.0,
+        accel_max_m_per_s2=1.5,
+        decel_max_m_per_s2=2.0,
+        stop_window_m=10.0,
+    ))]
+    pub fn __new__(
+        signals: Vec<TrafficSignal>,
+        anticipate: bool,
+        approach_dist_m: f64,
+        horizon_s: f64,
+        accel_max_m_per_s2: f64,
+        decel_max_m_per_s2: f64,
+        stop_window_m: f64,
+    ) -> anyhow::Result<Self> {
+        let mut planner = Self {
+            signals,
+            anticipate,
+            approach_dist_m,
+            horizon_s,
+            accel_max_m_per_s2,
+            decel_max_m_per_s2,
+            stop_window_m,
+            orphaned: false,
+        };
+        planner.init()?;
+        Ok(planner)
+    }
+
+    #[pyo3(name = "plan")]
+    /// Returns `cyc` replanned to pass each signal on green
+    pub fn plan_py(&self, cyc: &RustCycle) -> anyhow::Result<SpatPlan> {
+        self.plan(cyc)
+    }
+
+    #[pyo3(name = "run")]
+    /// Plans `cyc` around the signals, then simulates `veh` over the
+    /// planned cycle
+    pub fn run_py(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<SpatResults> {
+        self.run(veh, cyc)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Approach speed planner for a cycle with signalized intersections.  Stops
+/// in the cycle within `stop_window_m` of a signal are governed by the
+/// signal, and all other stops are kept.  For each signal, in order of
+/// distance, the speed trace from up to `approach_dist_m` before the signal
+/// until it rejoins the cycle after the signal is replaced by constant-jerk
+/// trajectories, either
+/// - passing the signal on green without stopping, if `anticipate` and a
+///   green window is reachable, with the earliest rejoining of the cycle, or
+/// - stopping at the signal until it turns green.
+///
+/// The cycle must have a constant time step.
+pub struct SpatPlanner {
+    /// signalized intersections
+    pub signals: Vec<TrafficSignal>,
+    /// if true, the driver knows the signal timing and avoids stopping when
+    /// possible; if false, the driver follows the cycle and only stops, or
+    /// waits longer, when the signal is red
+    pub anticipate: bool,
+    /// distance before a signal over which the approach may be replanned \[m\]
+    pub approach_dist_m: f64,
+    /// time after passing a signal within which the replanned trace must
+    /// rejoin the cycle, and the most time by which an approach may be
+    /// lengthened \[s\]
+    pub horizon_s: f64,
+    /// maximum acceleration \[m/s^2\]
+    pub accel_max_m_per_s2: f64,
+    /// maximum deceleration, positive \[m/s^2\]
+    pub decel_max_m_per_s2: f64,
+    /// distance from a signal within which a stop in the cycle is a stop at
+    /// the signal \[m\]
+    pub stop_window_m: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SpatPlanner {
+    fn init(&mut self) -> anyhow::Result<()> {
+        for signal in self.signals.iter_mut() {
+            signal.init()?;
+        }
+        for (name, value) in [
+            ("approach_dist_m", self.approach_dist_m),
+            ("horizon_s", self.horizon_s),
+            ("accel_max_m_per_s2", self.accel_max_m_per_s2),
+            ("decel_max_m_per_s2", self.decel_max_m_per_s2),
+        ] {
+            ensure!(
+                value.is_finite() && value > 0.0,
+                "`{name}` must be positive, got {value}"
+            );
+        }
+        ensure!(
+            self.stop_window_m >= 0.0,
+            "`stop_window_m` must be non-negative, got {}",
+            self.stop_window_m
+        );
+        Ok(())
+    }
+}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// How the planned cycle passes one [TrafficSignal]
+pub struct SignalPassage {
+    /// distance of the signal from the start of the cycle \[m\]
+    pub dist_m: f64,
+    /// time at which the vehicle passes the signal \[s\]
+    pub crossing_time_s: f64,
+    /// whether the original cycle stops at the signal
+    pub stopped_in_cycle: bool,
+    /// whether the planned cycle stops at the signal
+    pub stopped: bool,
+    /// time stopped at the signal in the planned cycle \[s\]
+    pub wait_s: f64,
+    /// whether the vehicle passes on green; false only if no trajectory
+    /// within the acceleration limits does, in which case the cycle is left
+    /// unchanged around this signal
+    pub on_green: bool,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SignalPassage {}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Outcome of [SpatPlanner::plan]
+pub struct SpatPlan {
+    /// planned cycle
+    #[api(has_orphaned)]
+    pub cyc: RustCycle,
+    /// passage of each signal reached by the cycle, in order of distance
+    pub passages: Vec<SignalPassage>,
+    /// number of signals at which the original cycle stops
+    pub n_stops_in_cycle: usize,
+    /// number of signals at which the planned cycle stops
+    pub n_stops: usize,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SpatPlan {}
+
+#[add_pyo3_api]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Outcome of [SpatPlanner::run]
+pub struct SpatResults {
+    /// planned cycle and signal passages
+    #[api(has_orphaned)]
+    pub plan: SpatPlan,
+    /// simulation over the planned cycle
+    #[api(has_orphaned)]
+    pub sd: RustSimDrive,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SpatResults {}
+
+/// Portion of the trace around one signal that may be replanned
+struct Approach {
+    /// index of the sample where replanning starts
+    i0: usize,
+    t0: f64,
+    d0: f64,
+    v0: f64,
+    /// (index, distance, speed) of the samples after the signal at which the
+    /// replanned trace may rejoin the cycle
+    rejoin: Vec<(usize, f64, f64)>,
+    v_max: f64,
+    dt_s: f64,
+}
+
+/// Replacement for the speed trace after `Approach::i0` up to and including
+/// sample `j`
+struct Splice {
+    j: usize,
+    mps: Vec<f64>,
+    crossing_time_s: f64,
+    wait_s: f64,
+}
+
+/// Trapezoidal cumulative distance of speeds `mps` sampled every `dt_s`
+fn cumulative_dist_m(mps: &[f64], dt_s: f64) -> Vec<f64> {
+    let mut dist_m = vec![0.0; mps.len()];
+    for i in 1..mps.len() {
+        dist_m[i] = dist_m[i - 1] + 0.5 * (mps[i - 1] + mps[i]) * dt_s;
+    }
+    dist_m
+}
+
+/// Time at which speeds `mps`, sampled every `dt_s` after starting from
+/// `v0` at `d0` at `t0`, first pass `dist_m`
+fn crossing_time_s(t0: f64, d0: f64, v0: f64, mps: &[f64], dt_s: f64, dist_m: f64) -> f64 {
+    let mut d = d0;
+    let mut v_prev = v0;
+    for (m, v) in mps.iter().enumerate() {
+        let d_next = d + 0.5 * (v_prev + v) * dt_s;
+        if d_next > dist_m {
+            return t0 + (m as f64 + (dist_m - d) / (d_next - d)) * dt_s;
+        }
+        d = d_next;
+        v_prev = *v;
+    }
+    t0 + mps.len() as f64 * dt_s
+}
+
+/// Sum of squared accelerations of speeds `mps` following `v0`
+fn accel_effort(v0: f64, mps: &[f64], dt_s: f64) -> f64 {
+    let mut v_prev = v0;
+    mps.iter()
+        .map(|v| {
+            let accel = (v - v_prev) / dt_s;
+            v_prev = *v;
+            accel * accel
+        })
+        .sum()
+}
+
+impl SpatPlanner {
+    /// Speeds of the constant-jerk trajectory from (`d0`, `v0`) that reaches
+    /// (`dr`, `vr`) after `n` steps, or `None` if it exceeds the acceleration
+    /// limits or `v_max`, reverses, or, if `keep_moving`, stops on the way
+    fn trajectory(
+        &self,
+        (d0, v0): (f64, f64),
+        (dr, vr): (f64, f64),
+        n: usize,
+        ap: &Approach,
+        keep_moving: bool,
+    ) -> Option<Vec<f64>> {
+        let (jerk, accel0) = calc_constant_jerk_trajectory(n, d0, v0, dr, vr, ap.dt_s).ok()?;
+        let mut mps: Vec<f64> = (1..=n)
+            .map(|m| speed_for_constant_jerk(m, v0, accel0, jerk, ap.dt_s))
+            .collect();
+        let mut v_prev = v0;
+        let mut d = d0;
+        for (m, v) in mps.iter().enumerate() {
+            let accel = (v - v_prev) / ap.dt_s;
+            if *v < -TOL
+                || *v > ap.v_max + TOL
+                || accel > self.accel_max_m_per_s2 + TOL
+                || -accel > self.decel_max_m_per_s2 + TOL
+                || (keep_moving && m + 1 < n && *v <= STOP_SPEED_MPS)
+            {
+                return None;
+            }
+            d += 0.5 * (v_prev + v) * ap.dt_s;
+            v_prev = *v;
+        }
+        // the trajectory helpers assume evenly spaced samples
+        if (d - dr).abs() > TOL.max(TOL * (dr - d0)) || (v_prev - vr).abs() > TOL.max(TOL * vr) {
+            return None;
+        }
+        for v in mps.iter_mut() {
+            *v = v.max(0.0);
+        }
+        *mps.last_mut().unwrap() = vr;
+        Some(mps)
+    }
+
+    /// Trajectory that passes `signal` on green without stopping and rejoins
+    /// the cycle earliest, relative to the cycle
+    fn plan_pass(&self, signal: &TrafficSignal, ap: &Approach) -> Option<Splice> {
+        let horizon_steps = (self.horizon_s / ap.dt_s).ceil() as usize;
+        let mut best: Option<(i64, f64, Splice)> = None;
+        for &(j, d_j, v_j) in &ap.rejoin {
+            let steps_in_cycle = j - ap.i0;
+            // fewer steps rejoin earlier, so the first found is best for `j`
+            for n in 2..=steps_in_cycle + horizon_steps {
+                let Some(mps) = self.trajectory((ap.d0, ap.v0), (d_j, v_j), n, ap, true) else {
+                    continue;
+                };
+                let t = crossing_time_s(ap.t0, ap.d0, ap.v0, &mps, ap.dt_s, signal.dist_m);
+                if !signal.is_green(t) {
+                    continue;
+                }
+                let delay = n as i64 - steps_in_cycle as i64;
+                let effort = accel_effort(ap.v0, &mps, ap.dt_s);
+                if best
+                    .as_ref()
+                    .is_none_or(|(d, e, _)| delay < *d || (delay == *d && effort < *e))
+                {
+                    best = Some((
+                        delay,
+                        effort,
+                        Splice {
+                            j,
+                            mps,
+                            crossing_time_s: t,
+                            wait_s: 0.0,
+                        },
+                    ));
+                }
+                break;
+            }
+        }
+        best.map(|(_, _, splice)| splice)
+    }
+
+    /// Trajectory that stops at `signal`, waits until it is green, and
+    /// rejoins the cycle earliest, relative to the cycle.  Of the approaches
+    /// that allow the earliest departure, the gentlest is used.
+    fn plan_stop(&self, signal: &TrafficSignal, ap: &Approach) -> Option<Splice> {
+        let x = signal.dist_m;
+        let horizon_steps = (self.horizon_s / ap.dt_s).ceil() as usize;
+        let max_steps = ap.rejoin[0].0 - ap.i0 + horizon_steps;
+
+        // (steps to stop, steps waiting, speeds)
+        let mut arrival: Option<(usize, usize, Vec<f64>)> = None;
+        for n in 2..=max_steps {
+            let Some(mps) = self.trajectory((ap.d0, ap.v0), (x, 0.0), n, ap, false) else {
+                continue;
+            };
+            let t_arrive = ap.t0 + n as f64 * ap.dt_s;
+            let wait_steps = ((signal.next_green_s(t_arrive) - t_arrive) / ap.dt_s - TOL)
+                .ceil()
+                .max(0.0) as usize;
+            if !signal.is_green(t_arrive + wait_steps as f64 * ap.dt_s) {
+                continue;
+            }
+            if arrival
+                .as_ref()
+                .is_none_or(|(n_best, wait_best, _)| n + wait_steps <= n_best + wait_best)
+            {
+                arrival = Some((n, wait_steps, mps));
+            }
+        }
+        let (n_arrive, wait_steps, mut mps) = arrival?;
+        mps.extend(vec![0.0; wait_steps]);
+        let steps_to_depart = n_arrive + wait_steps;
+        let t_depart = ap.t0 + steps_to_depart as f64 * ap.dt_s;
+
+        let mut best: Option<(i64, Vec<f64>, usize)> = None;
+        for &(j, d_j, v_j) in &ap.rejoin {
+            for n in 2..=max_steps {
+                let Some(depart) = self.trajectory((x, 0.0), (d_j, v_j), n, ap, true) else {
+                    continue;
+                };
+                let delay = (steps_to_depart + n) as i64 - (j - ap.i0) as i64;
+                if best.as_ref().is_none_or(|(d, _, _)| delay < *d) {
+                    best = Some((delay, depart, j));
+                }
+                break;
+            }
+        }
+        let (_, depart, j) = best?;
+        mps.extend(depart);
+        Some(Splice {
+            j,
+            mps,
+            crossing_time_s: t_depart,
+            wait_s: wait_steps as f64 * ap.dt_s,
+        })
+    }
+
+    /// Replans `cyc` to pass each signal on green
+    pub fn plan(&self, cyc: &RustCycle) -> anyhow::Result<SpatPlan> {
+        ensure!(cyc.len() >= 2, "cycle must have at least 2 samples");
+        let dt_s = cyc.dt_s_at_i(1);
+        ensure!(
+            cyc.dt_s()
+                .iter()
+                .skip(1)
+                .all(|dt| (dt - dt_s).abs() < 1e-6 * dt_s),
+            "SPaT planning requires a constant time step"
+        );
+        let horizon_steps = (self.horizon_s / dt_s).ceil() as usize;
+        let mut signals = self.signals.clone();
+        signals.sort_by(|a, b| a.dist_m.total_cmp(&b.dist_m));
+
+        let mut mps = cyc.mps.to_vec();
+        let mut passages = vec![];
+        // samples before this have been planned around a previous signal
+        let mut i_planned = 0;
+        for signal in &signals {
+            let x = signal.dist_m;
+            let dist_m = cumulative_dist_m(&mps, dt_s);
+            let time_s = |i: usize| cyc.time_s[0] + i as f64 * dt_s;
+            let stopped = |i: usize| mps[i] <= STOP_SPEED_MPS;
+            let near = |i: usize| (dist_m[i] - x).abs() <= self.stop_window_m;
+
+            let Some(i_cross) = (i_planned.max(1)..mps.len()).find(|&i| dist_m[i] > x) else {
+                // signal not reached
+                continue;
+            };
+            let i_near: Vec<usize> = (i_planned..mps.len())
+                .skip_while(|&i| !near(i))
+                .take_while(|&i| near(i))
+                .collect();
+            let n_stopped_near = i_near.iter().filter(|&&i| stopped(i)).count();
+            let stopped_in_cycle = n_stopped_near > 0;
+            let t_cross = crossing_time_s(
+                time_s(i_cross - 1),
+                dist_m[i_cross - 1],
+                mps[i_cross - 1],
+                &mps[i_cross..=i_cross],
+                dt_s,
+                x,
+            );
+            let green_in_cycle = signal.is_green(t_cross);
+
+            // replanning starts at most `approach_dist_m` before the signal,
+            // and after any other stop
+            let i_approach = (i_planned..i_cross)
+                .find(|&i| dist_m[i] >= x - self.approach_dist_m)
+                .unwrap_or(i_cross - 1);
+            let i0 = (i_approach..i_cross)
+                .rev()
+                .find(|&i| stopped(i) && !near(i))
+                .unwrap_or(i_approach);
+            // and ends past any stop at the signal, before the next stop
+            let rejoin: Vec<(usize, f64, f64)> =
+                match (i_cross..mps.len()).find(|&i| dist_m[i] > x + self.stop_window_m) {
+                    Some(j_first) => (j_first..mps.len().min(j_first + horizon_steps + 1))
+                        .take_while(|&j| !stopped(j))
+                        .map(|j| (j, dist_m[j], mps[j]))
+                        .collect(),
+                    None => vec![],
+                };
+
+            let replan = !green_in_cycle || (self.anticipate && stopped_in_cycle);
+            let splice = if replan && !rejoin.is_empty() && dist_m[i0] < x {
+                let ap = Approach {
+                    i0,
+                    t0: time_s(i0),
+                    d0: dist_m[i0],
+                    v0: mps[i0],
+                    v_max: mps[i0..=rejoin.last().unwrap().0]
+                        .iter()
+                        .fold(0.0, |max, v| f64::max(max, *v)),
+                    rejoin,
+                    dt_s,
+                };
+                if self.anticipate {
+                    self.plan_pass(signal, &ap)
+                } else {
+                    None
+                }
+                .or_else(|| self.plan_stop(signal, &ap))
+            } else {
+                None
+            };
+
+            let passage = match splice {
+                Some(splice) => {
+                    let tail = mps.split_off(splice.j + 1);
+                    mps.truncate(i0 + 1);
+                    mps.extend(&splice.mps);
+                    i_planned = mps.len() - 1;
+                    mps.extend(tail);
+                    SignalPassage {
+                        dist_m: x,
+                        crossing_time_s: splice.crossing_time_s,
+                        stopped_in_cycle,
+                        stopped: splice.mps.iter().any(|v| *v <= STOP_SPEED_MPS),
+                        wait_s: splice.wait_s,
+                        on_green: true,
+                        orphaned: false,
+                    }
+                }
+                None => {
+                    i_planned = i_near.last().map_or(i_cross, |&i| i.max(i_cross));
+                    SignalPassage {
+                        dist_m: x,
+                        crossing_time_s: t_cross,
+                        stopped_in_cycle,
+                        stopped: stopped_in_cycle,
+                        wait_s: n_stopped_near.saturating_sub(1) as f64 * dt_s,
+                        on_green: green_in_cycle,
+                        orphaned: false,
+                    }
+                }
+            };
+            passages.push(passage);
+        }
+
+        let cache = cyc.build_cache();
+        let n_stops_in_cycle = passages.iter().filter(|p| p.stopped_in_cycle).count();
+        let n_stops = passages.iter().filter(|p| p.stopped).count();
+        Ok(SpatPlan {
+            cyc: cycle_from_speeds(cyc, &cache, mps, dt_s, format!("{} SPaT", cyc.name)),
+            passages,
+            n_stops_in_cycle,
+            n_stops,
+            orphaned: false,
+        })
+    }
+
+    /// Plans `cyc` around the signals, then simulates `veh` over the
+    /// planned cycle
+    pub fn run(&self, veh: &RustVehicle, cyc: &RustCycle) -> anyhow::Result<SpatResults> {
+        let plan = self.plan(cyc)?;
+        let mut sd = RustSimDrive::new(plan.cyc.clone(), veh.clone());
+        sd.sim_drive(None, None)
+            .context("simulation of planned cycle failed")?;
+        Ok(SpatResults {
+            plan,
+            sd,
+            orphaned: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cycle that stops for 20 s at about 575 m and ends at about 1390 m
+    fn two_stop_cycle() -> RustCycle {
+        let mut mps = vec![0.0; 5];
+        for cruise_steps in [30, 40] {
+            mps.extend((1..=15).map(f64::from));
+            mps.extend(vec![15.0; cruise_steps]);
+            mps.extend((0..15).rev().map(f64::from));
+            mps.extend(vec![0.0; 20]);
+        }
+        RustCycle {
+            time_s: Array1::from_iter((0..mps.len()).map(|i| i as f64)),
+            grade: Array1::zeros(mps.len()),
+            road_type: Array1::zeros(mps.len()),
+            mps: mps.into(),
+            name: "two stops".into(),
+            orphaned: false,
+        }
+    }
+
+    /// Distance and time of the first stop of `cyc`
+    fn first_stop(cyc: &RustCycle) -> (f64, f64) {
+        let dist_m = cumulative_dist_m(cyc.mps.as_slice().unwrap(), 1.0);
+        let i = (1..cyc.len())
+            .find(|&i| cyc.mps[i] == 0.0 && cyc.mps[i - 1] > 0.0)
+            .unwrap();
+        (dist_m[i], cyc.time_s[i])
+    }
+
+    #[test]
+    fn test_spat_avoids_stop_when_green_is_reachable() {
+        let cyc = two_stop_cycle();
+        let (stop_m, arrive_s) = first_stop(&cyc);
+        // green from 5 s after the cycle arrives at the stop, for 30 s
+        let signal = TrafficSignal {
+            dist_m: stop_m,
+            cycle_s: 60.0,
+            green_s: 30.0,
+            offset_s: arrive_s + 5.0,
+            orphaned: false,
+        };
+        let planner = SpatPlanner {
+            signals: vec![signal.clone()],
+            anticipate: true,
+            approach_dist_m: 300.0,
+            horizon_s: 60.0,
+            accel_max_m_per_s2: 1.5,
+            decel_max_m_per_s2: 2.0,
+            stop_window_m: 10.0,
+            orphaned: false,
+        };
+        let plan = planner.plan(&cyc).unwrap();
+        let passage = &plan.passages[0];
+        assert!(passage.stopped_in_cycle);
+        assert!(!passage.stopped);
+        assert!(passage.on_green);
+        assert!(signal.is_green(passage.crossing_time_s));
+        assert_eq!((plan.n_stops_in_cycle, plan.n_stops), (1, 0));
+        // skipping the 20 s stop shortens the trip, over the same distance
+        assert!(plan.cyc.len() < cyc.len());
+        assert!(plan.cyc.dist_m().sum().approx_eq(&cyc.dist_m().sum(), 1e-6));
+        assert!(plan.cyc.mps.iter().all(|v| *v <= 15.0 + 1e-9));
+
+        // without knowing the timing, the driver stops until green
+        let naive = SpatPlanner {
+            anticipate: false,
+            ..planner.clone()
+        };
+        let naive_plan = naive.plan(&cyc).unwrap();
+        let passage = &naive_plan.passages[0];
+        assert!(passage.stopped);
+        assert!(signal.is_green(passage.crossing_time_s));
+        assert!(passage.crossing_time_s >= arrive_s + 5.0);
+        assert!(naive_plan
+            .cyc
+            .dist_m()
+            .sum()
+            .approx_eq(&cyc.dist_m().sum(), 1e-6));
+
+        let veh = RustVehicle::mock_vehicle();
+        let res = planner.run(&veh, &cyc).unwrap();
+        let naive_res = naive.run(&veh, &cyc).unwrap();
+        assert!(res.sd.fuel_kj < naive_res.sd.fuel_kj);
+    }
+
+    #[test]
+    fn test_spat_stops_at_red_when_green_is_unreachable() {
+        let cyc = two_stop_cycle();
+        let (stop_m, arrive_s) = first_stop(&cyc);
+        // signal in the middle of the cruise before the stop, red for 90 s
+        // from shortly before the cycle passes it
+        let cruise_m = stop_m - 150.0;
+        let dist_m = cumulative_dist_m(cyc.mps.as_slice().unwrap(), 1.0);
+        let pass_s = cyc.time_s[dist_m.iter().position(|d| *d > cruise_m).unwrap()];
+        let signal = TrafficSignal {
+            dist_m: cruise_m,
+            cycle_s: 100.0,
+            green_s: 10.0,
+            offset_s: pass_s - 15.0,
+            orphaned: false,
+        };
+        let planner = SpatPlanner {
+            signals: vec![signal.clone()],
+            anticipate: true,
+            approach_dist_m: 300.0,
+            horizon_s: 60.0,
+            accel_max_m_per_s2: 1.5,
+            decel_max_m_per_s2: 2.0,
+            stop_window_m: 10.0,
+            orphaned: false,
+        };
+        let plan = planner.plan(&cyc).unwrap();
+        let passage = &plan.passages[0];
+        assert!(!passage.stopped_in_cycle);
+        assert!(passage.stopped);
+        assert!(passage.on_green);
+        assert!(signal.is_green(passage.crossing_time_s));
+        assert!(passage.wait_s > 0.0);
+        assert!(plan.cyc.len() > cyc.len());
+        assert!(plan.cyc.dist_m().sum().approx_eq(&cyc.dist_m().sum(), 1e-6));
+        // the stop after the signal is kept
+        let (_, planned_arrive_s) = first_stop(&plan.cyc);
+        assert!(planned_arrive_s < arrive_s + 100.0);
+        let planned_dist_m = cumulative_dist_m(plan.cyc.mps.as_slice().unwrap(), 1.0);
+        assert!((0..plan.cyc.len())
+            .any(|i| plan.cyc.mps[i] == 0.0 && (planned_dist_m[i] - stop_m).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,820 @@
+//! Module for parameter sweeps / design of experiments: sampling vehicle
+//! parameters over ranges, running the resulting vehicles in parallel with
+//! [SimDriveVec], and tabulating chosen scalar outputs against the inputs
+
+use std::fmt;
+use std::io::Write;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::simdrive_iter::SimDriveVec;
+use super::RustSimDrive;
+use crate::cycle::RustCycle;
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::{PowertrainType, RustVehicle};
+
+/// Sobol direction number initializers (Joe & Kuo, `new-joe-kuo-6.21201`) for
+/// dimensions 2 and up: polynomial degree `s`, coefficients `a`, and initial
+/// direction numbers `m`.  Dimension 1 is the van der Corput sequence.
+const SOBOL_INIT: [(u32, u32, &[u32]); 15] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+
+/// Maximum number of parameters supported by [SweepDesign::Sobol]
+pub const SOBOL_MAX_DIMS: usize = SOBOL_INIT.len() + 1;
+
+/// Bits of precision of Sobol points
+const SOBOL_BITS: u32 = 32;
+
+/// Output for the on-cycle range, mi: usable battery energy at
+/// `electric_kwh_per_mi` for BEVs, and fuel energy at `mpgge` otherwise
+pub const RANGE_OUTPUT: &str = "range_mi";
+
+/// Output for the simulated 0-60 mph time, s, i.e. the modeled counterpart of
+/// [RustVehicle::val0_to60_mph], from an extra acceleration run per vehicle
+pub const ACCEL_OUTPUT: &str = "val0_to60_mph";
+
+/// Sampling scheme of a [ParameterSweep]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SweepDesign {
+    /// every combination of `levels` evenly spaced values of each parameter
+    #[default]
+    #[serde(rename = "full_factorial")]
+    FullFactorial,
+    /// `n_samples` points, with each parameter's range split into `n_samples`
+    /// strata that are each sampled exactly once
+    #[serde(rename = "latin_hypercube")]
+    LatinHypercube,
+    /// first `n_samples` points of the Sobol low-discrepancy sequence, which
+    /// are best balanced when `n_samples` is a power of 2.  Direction numbers
+    /// are tabulated for at most [SOBOL_MAX_DIMS] parameters.
+    #[serde(rename = "sobol")]
+    Sobol,
+}
+
+impl SweepDesign {
+    pub const ALL: [Self; 3] = [Self::FullFactorial, Self::LatinHypercube, Self::Sobol];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FullFactorial => "full_factorial",
+            Self::LatinHypercube => "latin_hypercube",
+            Self::Sobol => "sobol",
+        }
+    }
+}
+
+impl fmt::Display for SweepDesign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for SweepDesign {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|design| design.as_str() == s)
+            .with_context(|| {
+                format!(
+                    "invalid sweep design `{s}`, must be one of {:?}",
+                    Self::ALL.map(|design| design.as_str())
+                )
+            })
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (path, min, max, levels=2))]
+    pub fn __new__(path: String, min: f64, max: f64, levels: usize) -> anyhow::Result<Self> {
+        let mut param = Self {
+            path,
+            min,
+            max,
+            levels,
+            orphaned: false,
+        };
+        param.init()?;
+        Ok(param)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Range of values of one [RustVehicle] field in a [ParameterSweep]
+pub struct SweepParam {
+    /// [FieldPath] to the field, e.g. `drag_coef`, or `fc_pwr_out_perc.3`
+    /// for an element of an array field
+    pub path: String,
+    /// lower bound of range
+    pub min: f64,
+    /// upper bound of range
+    pub max: f64,
+    /// number of evenly spaced values from `min` to `max`, inclusive, used by
+    /// [SweepDesign::FullFactorial]
+    #[serde(default = "SweepParam::default_levels")]
+    pub levels: usize,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SweepParam {
+    fn default_levels() -> usize {
+        2
+    }
+
+    /// Maps `u` in \[0, 1\] onto the range
+    pub fn scale(&self, u: f64) -> f64 {
+        self.min + u * (self.max - self.min)
+    }
+
+    /// Evenly spaced values used by [SweepDesign::FullFactorial]
+    pub fn level_values(&self) -> Vec<f64> {
+        if self.levels == 1 {
+            return vec![self.min];
+        }
+        (0..self.levels)
+            .map(|j| self.scale(j as f64 / (self.levels - 1) as f64))
+            .collect()
+    }
+}
+
+impl SerdeAPI for SweepParam {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(!self.path.is_empty(), "sweep parameter path is empty");
+        ensure!(
+            self.min.is_finite() && self.max.is_finite() && self.min <= self.max,
+            "sweep parameter `{}` has invalid range [{}, {}]",
+            self.path,
+            self.min,
+            self.max
+        );
+        ensure!(
+            self.levels >= 1,
+            "sweep parameter `{}` must have at least 1 level",
+            self.path
+        );
+        Ok(())
+    }
+}
+
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (params, outputs, design="full_factorial", n_samples=0, seed=0))]
+    pub fn __new__(
+        params: Vec<SweepParam>,
+        outputs: Vec<String>,
+        design: &str,
+        n_samples: usize,
+        seed: u64,
+    ) -> anyhow::Result<Self> {
+        let mut sweep = Self {
+            params,
+            design: design.parse()?,
+            n_samples,
+            seed,
+            outputs,
+            orphaned: false,
+        };
+        sweep.init()?;
+        Ok(sweep)
+    }
+
+    #[getter]
+    pub fn get_design(&self) -> String {
+        self.design.to_string()
+    }
+
+    #[pyo3(name = "samples")]
+    /// Returns the sampled parameter values, one row per vehicle
+    pub fn samples_py(&self) -> anyhow::Result<Vec<Vec<f64>>> {
+        self.samples()
+    }
+
+    #[pyo3(name = "run")]
+    #[pyo3(signature = (veh, cyc, parallelize=None))]
+    /// Simulates `veh` over `cyc` with each sampled set of parameter values
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive` calls, defaults to `true`
+    pub fn run_py(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        parallelize: Option<bool>,
+    ) -> anyhow::Result<SweepResults> {
+        self.run(veh, cyc, parallelize)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// Design of experiments over [RustVehicle] parameters
+pub struct ParameterSweep {
+    /// parameters to vary
+    pub params: Vec<SweepParam>,
+    #[serde(default)]
+    #[api(skip_get, skip_set)]
+    pub design: SweepDesign,
+    /// number of samples for [SweepDesign::LatinHypercube] and
+    /// [SweepDesign::Sobol], ignored by [SweepDesign::FullFactorial]
+    #[serde(default)]
+    pub n_samples: usize,
+    /// random seed for [SweepDesign::LatinHypercube]
+    #[serde(default)]
+    pub seed: u64,
+    /// dot-separated paths to scalar [RustSimDrive] fields to report, e.g.
+    /// `mpgge`, `electric_kwh_per_mi`, `trace_miss_speed_mps`, or
+    /// `energy_breakdown.regen_kj`.  Booleans, e.g. `trace_miss`, are reported
+    /// as 0 or 1.  [RANGE_OUTPUT] and [ACCEL_OUTPUT] are computed rather
+    /// than read from a field.
+    pub outputs: Vec<String>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for ParameterSweep {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(!self.params.is_empty(), "parameter sweep has no parameters");
+        for param in self.params.iter_mut() {
+            param.init()?;
+        }
+        self.validate_outputs()?;
+        #[cfg(not(feature = "simdrivelabel"))]
+        ensure!(
+            !self.outputs.iter().any(|output| output == ACCEL_OUTPUT),
+            "output `{ACCEL_OUTPUT}` requires the `simdrivelabel` feature"
+        );
+        match self.design {
+            SweepDesign::FullFactorial => {}
+            SweepDesign::LatinHypercube | SweepDesign::Sobol => ensure!(
+                self.n_samples > 0,
+                "`n_samples` must be positive for {} sweeps",
+                self.design
+            ),
+        }
+        if self.design == SweepDesign::Sobol {
+            ensure!(
+                self.params.len() <= SOBOL_MAX_DIMS,
+                "sobol sweeps support at most {SOBOL_MAX_DIMS} parameters, got {}",
+                self.params.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl ParameterSweep {
+    /// Returns the sampled parameter values, one row per vehicle, with values
+    /// in the order of `params`
+    pub fn samples(&self) -> anyhow::Result<Vec<Vec<f64>>> {
+        let unit_samples = match self.design {
+            SweepDesign::FullFactorial => {
+                return Ok(full_factorial(
+                    &self
+                        .params
+                        .iter()
+                        .map(SweepParam::level_values)
+                        .collect::<Vec<_>>(),
+                ))
+            }
+            SweepDesign::LatinHypercube => {
+                latin_hypercube(self.n_samples, self.params.len(), self.seed)
+            }
+            SweepDesign::Sobol => sobol(self.n_samples, self.params.len())?,
+        };
+        Ok(unit_samples
+            .into_iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&self.params)
+                    .map(|(u, param)| param.scale(*u))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Returns a copy of `veh` with the swept parameters set to `values`, with
+    /// derived fields updated
+    pub fn vehicle_for(&self, veh: &RustVehicle, values: &[f64]) -> anyhow::Result<RustVehicle> {
+        ensure!(
+            values.len() == self.params.len(),
+            "expected {} values, got {}",
+            self.params.len(),
+            values.len()
+        );
+        let mut veh = veh.clone();
+        for (param, value) in self.params.iter().zip(values) {
+            veh.set_field(&param.path, *value)?;
+        }
+        veh.set_derived()?;
+        Ok(veh)
+    }
+
+    /// Simulates `veh` over `cyc` with each sampled set of parameter values.
+    /// Vehicles that are invalid or fail to simulate get NaN outputs and an
+    /// entry in [SweepResults::errors] rather than failing the sweep.
+    /// # Arguments:
+    /// * parallelize: whether to parallelize `sim_drive` calls, defaults to `true`
+    pub fn run(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        parallelize: Option<bool>,
+    ) -> anyhow::Result<SweepResults> {
+        self.run_samples(veh, cyc, self.samples()?, parallelize)
+    }
+
+    /// Same as [Self::run], but with given `samples` of parameter values, one
+    /// row per vehicle, in place of [Self::samples]
+    pub fn run_samples(
+        &self,
+        veh: &RustVehicle,
+        cyc: &RustCycle,
+        samples: Vec<Vec<f64>>,
+        parallelize: Option<bool>,
+    ) -> anyhow::Result<SweepResults> {
+        self.validate_outputs()?;
+        let mut errors: Vec<Option<String>> = vec![None; samples.len()];
+        // indices into `samples` of the vehicles in `sdv`
+        let mut sdv_idxs = Vec::with_capacity(samples.len());
+        let mut sdv = SimDriveVec(Vec::with_capacity(samples.len()));
+        for (i, values) in samples.iter().enumerate() {
+            match self.vehicle_for(veh, values) {
+                Ok(veh) => {
+                    sdv_idxs.push(i);
+                    sdv.push(RustSimDrive::new(cyc.clone(), veh));
+                }
+                Err(err) => errors[i] = Some(format!("{err:#}")),
+            }
+        }
+        let sim_results = sdv.sim_drive_each(parallelize);
+        let accel_results = if self.outputs.iter().any(|output| output == ACCEL_OUTPUT) {
+            accel_times(&sdv, parallelize)
+        } else {
+            sdv.0.iter().map(|_| Ok(f64::NAN)).collect()
+        };
+
+        let mut outputs = vec![vec![f64::NAN; self.outputs.len()]; samples.len()];
+        for (((i, sd), res), accel) in sdv_idxs
+            .into_iter()
+            .zip(&sdv.0)
+            .zip(sim_results)
+            .zip(accel_results)
+        {
+            match res.and(accel) {
+                Ok(accel_s) => outputs[i] = self.outputs_for(sd, accel_s)?,
+                Err(err) => errors[i] = Some(format!("{err:#}")),
+            }
+        }
+
+        Ok(SweepResults {
+            inputs: self.params.iter().map(|param| param.path.clone()).collect(),
+            outputs: self.outputs.clone(),
+            rows: samples
+                .into_iter()
+                .zip(outputs)
+                .map(|(mut row, outputs)| {
+                    row.extend(outputs);
+                    row
+                })
+                .collect(),
+            errors,
+            orphaned: false,
+        })
+    }
+
+    /// Ensures each of `outputs` is [RANGE_OUTPUT], [ACCEL_OUTPUT], or the
+    /// path of a [RustSimDrive] field, ignoring array indices
+    fn validate_outputs(&self) -> anyhow::Result<()> {
+        let fields = RustSimDrive::list_fields();
+        for output in &self.outputs {
+            if output == RANGE_OUTPUT || output == ACCEL_OUTPUT {
+                continue;
+            }
+            let field_path = output
+                .split('.')
+                .filter(|segment| segment.parse::<usize>().is_err())
+                .collect::<Vec<_>>()
+                .join(".");
+            ensure!(
+                fields.iter().any(|field| field.path == field_path),
+                "output `{output}` is not a `RustSimDrive` field"
+            );
+        }
+        Ok(())
+    }
+
+    /// Values of `outputs` for a completed simulation with 0-60 mph time
+    /// `accel_s`, with missing optional values as NaN
+    fn outputs_for(&self, sd: &RustSimDrive, accel_s: f64) -> anyhow::Result<Vec<f64>> {
+        self.outputs
+            .iter()
+            .map(|path| {
+                match path.as_str() {
+                    RANGE_OUTPUT => return Ok(range_mi(sd)),
+                    ACCEL_OUTPUT => return Ok(accel_s),
+                    _ => {}
+                }
+                match sd.get_field(path)? {
+                    FieldValue::None => Ok(f64::NAN),
+                    FieldValue::Bool(b) => Ok(if b { 1.0 } else { 0.0 }),
+                    value @ (FieldValue::Int(_) | FieldValue::Float(_)) => value.as_f64(),
+                    _ => bail!("output `{path}` is not a scalar"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// On-cycle range, mi, of a completed simulation; see [RANGE_OUTPUT]
+fn range_mi(sd: &RustSimDrive) -> f64 {
+    match sd.veh.veh_pt_type {
+        PowertrainType::BEV => {
+            sd.veh.ess_max_kwh * (sd.veh.max_soc - sd.veh.min_soc) / sd.electric_kwh_per_mi
+        }
+        _ => sd.veh.fs_kwh / sd.props.kwh_per_gge * sd.mpgge,
+    }
+}
+
+/// 0-60 mph time, s, of the vehicle of each simulation in `sdv`
+#[cfg(feature = "simdrivelabel")]
+fn accel_times(sdv: &SimDriveVec, parallelize: Option<bool>) -> Vec<anyhow::Result<f64>> {
+    let accel_cyc = crate::simdrivelabel::make_accel_trace();
+    let mut accel_sdv = SimDriveVec(
+        sdv.0
+            .iter()
+            .map(|sd| RustSimDrive::new(accel_cyc.clone(), sd.veh.clone()))
+            .collect(),
+    );
+    let results = accel_sdv.sim_drive_accel_each(parallelize);
+    accel_sdv
+        .0
+        .iter()
+        .zip(results)
+        .map(|(sd, res)| {
+            res.context("acceleration run failed")?;
+            Ok(crate::simdrivelabel::net_accel_of(
+                sd,
+                &sd.veh.scenario_name,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "simdrivelabel"))]
+fn accel_times(sdv: &SimDriveVec, _parallelize: Option<bool>) -> Vec<anyhow::Result<f64>> {
+    sdv.0
+        .iter()
+        .map(|_| {
+            Err(anyhow!(
+                "`{ACCEL_OUTPUT}` requires the `simdrivelabel` feature"
+            ))
+        })
+        .collect()
+}
+
+/// Every combination of `levels`, varying the last parameter fastest
+fn full_factorial(levels: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    levels.iter().fold(vec![vec![]], |rows, values| {
+        rows.iter()
+            .flat_map(|row| {
+                values.iter().map(move |value| {
+                    let mut row = row.clone();
+                    row.push(*value);
+                    row
+                })
+            })
+            .collect()
+    })
+}
+
+/// `n` Latin hypercube samples of `dims` dimensions in \[0, 1)
+fn latin_hypercube(n: usize, dims: usize, seed: u64) -> Vec<Vec<f64>> {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut rows = vec![Vec::with_capacity(dims); n];
+    let mut strata: Vec<usize> = (0..n).collect();
+    for _ in 0..dims {
+        strata.shuffle(&mut rng);
+        for (row, stratum) in rows.iter_mut().zip(&strata) {
+            row.push((*stratum as f64 + rng.gen::<f64>()) / n as f64);
+        }
+    }
+    rows
+}
+
+/// First `n` points, starting at the origin, of the `dims`-dimensional Sobol
+/// sequence in \[0, 1), generated in Gray code order
+pub(super) fn sobol(n: usize, dims: usize) -> anyhow::Result<Vec<Vec<f64>>> {
+    ensure!(
+        dims <= SOBOL_MAX_DIMS,
+        "sobol sequence supports at most {SOBOL_MAX_DIMS} dimensions, got {dims}"
+    );
+    ensure!(
+        (n as u64) < (1 << SOBOL_BITS),
+        "sobol sequence supports fewer than 2^{SOBOL_BITS} points"
+    );
+    let directions: Vec<Vec<u32>> = (0..dims).map(sobol_directions).collect();
+    let mut x = vec![0u32; dims];
+    let mut rows = Vec::with_capacity(n);
+    for i in 0..n {
+        if i > 0 {
+            // index of the rightmost zero bit of `i - 1`
+            let c = (i - 1).trailing_ones() as usize;
+            for (x, v) in x.iter_mut().zip(&directions) {
+                *x ^= v[c];
+            }
+        }
+        rows.push(
+            x.iter()
+                .map(|x| *x as f64 / (1u64 << SOBOL_BITS) as f64)
+                .collect(),
+        );
+    }
+    Ok(rows)
+}
+
+/// Direction numbers, scaled to [SOBOL_BITS] bits, of Sobol dimension `dim`
+/// (0-based)
+fn sobol_directions(dim: usize) -> Vec<u32> {
+    let bits = SOBOL_BITS as usize;
+    if dim == 0 {
+        return (1..=bits).map(|k| 1 << (bits - k)).collect();
+    }
+    let (s, a, m) = SOBOL_INIT[dim - 1];
+    let s = s as usize;
+    let mut v: Vec<u32> = m
+        .iter()
+        .enumerate()
+        .map(|(k, m)| m << (bits - k - 1))
+        .collect();
+    for k in s..bits {
+        let mut vk = v[k - s] ^ (v[k - s] >> s);
+        for j in 1..s {
+            if (a >> (s - 1 - j)) & 1 == 1 {
+                vk ^= v[k - j];
+            }
+        }
+        v.push(vk);
+    }
+    v
+}
+
+#[add_pyo3_api(
+    #[pyo3(name = "column")]
+    /// Returns the values of input or output column `name`
+    pub fn column_py(&self, name: &str) -> anyhow::Result<Vec<f64>> {
+        self.column(name)
+    }
+
+    #[pyo3(name = "to_csv")]
+    /// Returns the results as CSV with a header row
+    pub fn to_csv_py(&self) -> anyhow::Result<String> {
+        self.to_csv_string()
+    }
+
+    /// Returns a dict of column name to values, plus `error`, suitable for
+    /// e.g. `pandas.DataFrame`
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for name in self.names() {
+            dict.set_item(name, self.column(name).map_err(to_pyerr)?)?;
+        }
+        dict.set_item("error", self.errors.clone())?;
+        Ok(dict)
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.len()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// Results of a [ParameterSweep]: one row per sample of input values followed
+/// by output values
+pub struct SweepResults {
+    /// names of input columns, i.e. swept parameter paths
+    #[api(skip_set)]
+    pub inputs: Vec<String>,
+    /// names of output columns
+    #[api(skip_set)]
+    pub outputs: Vec<String>,
+    /// input values followed by output values, NaN where unavailable
+    #[api(skip_set)]
+    pub rows: Vec<Vec<f64>>,
+    /// for each row, the reason its outputs are unavailable, if any
+    #[api(skip_set)]
+    pub errors: Vec<Option<String>>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SweepResults {}
+
+impl SweepResults {
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Input column names followed by output column names
+    pub fn names(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Returns the values of input or output column `name`
+    pub fn column(&self, name: &str) -> anyhow::Result<Vec<f64>> {
+        let names = self.names();
+        let j = names
+            .iter()
+            .position(|n| *n == name)
+            .with_context(|| format!("unknown column `{name}`, expected one of {names:?}"))?;
+        Ok(self.rows.iter().map(|row| row[j]).collect())
+    }
+
+    /// Writes the results as CSV with a header row of column names, followed
+    /// by an `error` column
+    pub fn to_csv_writer<W: Write>(&self, wtr: W) -> anyhow::Result<()> {
+        let mut wtr = csv::Writer::from_writer(wtr);
+        let mut header = self.names();
+        header.push("error");
+        wtr.write_record(header)?;
+        for (row, err) in self.rows.iter().zip(&self.errors) {
+            wtr.write_record(
+                row.iter()
+                    .map(f64::to_string)
+                    .chain([err.clone().unwrap_or_default()]),
+            )?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn to_csv_string(&self) -> anyhow::Result<String> {
+        let mut buf = Vec::new();
+        self.to_csv_writer(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep(design: SweepDesign, n_samples: usize) -> ParameterSweep {
+        let mut sweep = ParameterSweep {
+            params: vec![
+                SweepParam {
+                    path: "drag_coef".into(),
+                    min: 0.2,
+                    max: 0.4,
+                    levels: 3,
+                    orphaned: false,
+                },
+                SweepParam {
+                    path: "fc_max_kw".into(),
+                    min: 80.0,
+                    max: 120.0,
+                    levels: 2,
+                    orphaned: false,
+                },
+            ],
+            design,
+            n_samples,
+            seed: 42,
+            outputs: vec!["mpgge".into(), "trace_miss".into()],
+            orphaned: false,
+        };
+        sweep.init().unwrap();
+        sweep
+    }
+
+    #[test]
+    fn test_samples_cover_ranges() {
+        let samples = sweep(SweepDesign::FullFactorial, 0).samples().unwrap();
+        assert_eq!(samples.len(), 6);
+        assert_eq!(samples[0], vec![0.2, 80.0]);
+        assert_eq!(samples[1], vec![0.2, 120.0]);
+        assert_eq!(samples[5], vec![0.4, 120.0]);
+
+        // each stratum of each dimension is sampled exactly once
+        let n = 16;
+        for samples in [latin_hypercube(n, 3, 42), sobol(n, SOBOL_MAX_DIMS).unwrap()] {
+            assert_eq!(samples.len(), n);
+            for j in 0..samples[0].len() {
+                let mut strata: Vec<usize> = samples
+                    .iter()
+                    .map(|row| (row[j] * n as f64) as usize)
+                    .collect();
+                strata.sort();
+                assert_eq!(strata, (0..n).collect::<Vec<_>>(), "dimension {j}");
+            }
+        }
+        let samples = sweep(SweepDesign::Sobol, 4).samples().unwrap();
+        assert!(samples[1].approx_eq(&vec![0.3, 100.0], 1e-12));
+        assert_eq!(
+            sweep(SweepDesign::LatinHypercube, 8).samples().unwrap(),
+            sweep(SweepDesign::LatinHypercube, 8).samples().unwrap()
+        );
+        assert_eq!(sobol(4, 2).unwrap()[1], vec![0.5, 0.5]);
+        assert!(sobol(4, SOBOL_MAX_DIMS + 1).is_err());
+    }
+
+    #[test]
+    fn test_run_sweep() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sweep = sweep(SweepDesign::FullFactorial, 0);
+        sweep.params.push(SweepParam {
+            path: "fc_max_kw".into(),
+            min: -1.0,
+            max: -1.0,
+            levels: 1,
+            orphaned: false,
+        });
+        // last parameter overrides `fc_max_kw` with an invalid value
+        let res = sweep.run(&veh, &cyc, None).unwrap();
+        assert_eq!(res.len(), 6);
+        assert!(res.errors.iter().all(Option::is_some));
+        assert!(res.column("mpgge").unwrap().iter().all(|x| x.is_nan()));
+
+        sweep.params.pop();
+        let res = sweep.run(&veh, &cyc, Some(false)).unwrap();
+        assert!(res.errors.iter().all(Option::is_none), "{:?}", res.errors);
+        let mpgge = res.column("mpgge").unwrap();
+        // less drag is more efficient
+        assert!(mpgge[0] > mpgge[2] && mpgge[2] > mpgge[4]);
+        let mut sd = RustSimDrive::new(
+            cyc.clone(),
+            sweep.vehicle_for(&veh, &res.rows[3][..2]).unwrap(),
+        );
+        sd.sim_drive(None, None).unwrap();
+        assert_eq!(mpgge[3], sd.mpgge);
+        assert_eq!(
+            res.column("trace_miss").unwrap()[3],
+            sd.trace_miss as u8 as f64
+        );
+
+        // nested fields and array elements are valid outputs
+        sweep.outputs.extend([
+            "energy_breakdown.regen_kj".into(),
+            "soc.0".into(),
+            RANGE_OUTPUT.into(),
+        ]);
+        sweep.init().unwrap();
+        sweep.outputs.push("not_a_field".into());
+        assert!(sweep.init().is_err());
+        assert!(sweep.run(&veh, &cyc, None).is_err());
+        let csv = res.to_csv_string().unwrap();
+        assert!(csv.starts_with("drag_coef,fc_max_kw,mpgge,trace_miss,error\n"));
+    }
+
+    #[test]
+    fn test_vehicle_for_array_element() {
+        let veh = RustVehicle::mock_vehicle();
+        let sweep = ParameterSweep {
+            params: vec![SweepParam {
+                path: "fc_eff_map.3".into(),
+                min: 0.3,
+                max: 0.3,
+                levels: 1,
+                orphaned: false,
+            }],
+            ..Default::default()
+        };
+        let new_veh = sweep.vehicle_for(&veh, &[0.31]).unwrap();
+        assert_eq!(new_veh.fc_eff_map[3], 0.31);
+        assert!(sweep.vehicle_for(&veh, &[0.31, 0.2]).is_err());
+        let sweep = ParameterSweep {
+            params: vec![SweepParam {
+                path: "scenario_name".into(),
+                ..sweep.params[0].clone()
+            }],
+            ..Default::default()
+        };
+        assert!(sweep.vehicle_for(&veh, &[0.31]).is_err());
+    }
+}
@@ -144,21 +144,22 @@ pub fn make_accel_trace_py() -> RustCycle {
     make_accel_trace()
 }
 
-pub fn get_net_accel(sd_accel: &mut RustSimDrive, scenario_name: &String) -> anyhow::Result<f64> {
+pub fn get_net_accel(sd_accel: &mut RustSimDrive, scenario_name: &str) -> anyhow::Result<f64> {
     #[cfg(feature = "logging")]
     log::debug!("running `sim_drive_accel`");
     sd_accel.sim_drive_accel(None, None)?;
+    Ok(net_accel_of(sd_accel, scenario_name))
+}
+
+/// Time, s, to reach 60 mph in a completed `sim_drive_accel` run over
+/// [make_accel_trace], or 1e3 if 60 mph is never reached
+pub fn net_accel_of(sd_accel: &RustSimDrive, scenario_name: &str) -> f64 {
     if sd_accel.mph_ach.iter().any(|&x| x >= 60.) {
-        Ok(interpolate(
-            &60.,
-            &sd_accel.mph_ach,
-            &sd_accel.cyc0.time_s,
-            false,
-        ))
+        interpolate(&60., &sd_accel.mph_ach, &sd_accel.cyc0.time_s, false)
     } else {
         #[cfg(feature = "logging")]
         log::warn!("vehicle '{}' never achieves 60 mph", scenario_name);
-        Ok(1e3)
+        1e3
     }
 }
 
@@ -166,7 +167,7 @@ pub fn get_net_accel(sd_accel: &mut RustSimDrive, scenario_name: &String) -> any
 #[pyfunction(name = "get_net_accel")]
 /// pyo3 version of [get_net_accel]
 pub fn get_net_accel_py(sd_accel: &mut RustSimDrive, scenario_name: &str) -> anyhow::Result<f64> {
-    let result = get_net_accel(sd_accel, &scenario_name.to_string())?;
+    let result = get_net_accel(sd_accel, scenario_name)?;
     Ok(result)
 }
 
@@ -229,8 +230,8 @@ pub fn get_label_fe(
     out.adj_params = adj_params.clone();
 
     // run calculations for non-PHEV powertrains
-    if veh.veh_pt_type != vehicle::PHEV {
-        if veh.veh_pt_type != vehicle::BEV {
+    if veh.veh_pt_type != vehicle::PowertrainType::PHEV {
+        if veh.veh_pt_type != vehicle::PowertrainType::BEV {
             // compare to Excel 'VehicleIO'!C203 or 'VehicleIO'!labUddsMpgge
             out.lab_udds_mpgge = sd["udds"].mpgge;
             // compare to Excel 'VehicleIO'!C203 or 'VehicleIO'!labHwyMpgge
@@ -242,7 +243,7 @@ pub fn get_label_fe(
             out.lab_comb_mpgge = 0.;
         }
 
-        if veh.veh_pt_type == vehicle::BEV {
+        if veh.veh_pt_type == vehicle::PowertrainType::BEV {
             out.lab_udds_kwh_per_mi = sd["udds"].battery_kwh_per_mi;
             out.lab_hwy_kwh_per_mi = sd["hwy"].battery_kwh_per_mi;
             out.lab_comb_kwh_per_mi =
@@ -254,7 +255,7 @@ pub fn get_label_fe(
         }
 
         // adjusted values for mpg
-        if veh.veh_pt_type != vehicle::BEV {
+        if veh.veh_pt_type != vehicle::PowertrainType::BEV {
             // non-EV case
             // CV or HEV case (not PHEV)
             // HEV SOC iteration is handled in simdrive.SimDriveClassic
@@ -273,7 +274,7 @@ pub fn get_label_fe(
         }
 
         // adjusted kW-hr/mi
-        if veh.veh_pt_type == vehicle::BEV {
+        if veh.veh_pt_type == vehicle::PowertrainType::BEV {
             // EV Case
             out.adj_udds_kwh_per_mi =
                 (1. / max(
@@ -804,7 +805,7 @@ mod simdrivelabel_tests {
             scenario_name: "2016 Chevrolet Volt".into(),
             selection: 13,
             veh_year: 2016,
-            veh_pt_type: "PHEV".into(),
+            veh_pt_type: vehicle::PowertrainType::PHEV,
             drag_coef: 0.3,
             frontal_area_m2: 2.565,
             glider_kg: 950.564,
@@ -825,7 +826,7 @@ mod simdrivelabel_tests {
             fc_eff_map: Array1::from(vec![
                 0.1, 0.12, 0.16, 0.22, 0.28, 0.33, 0.35, 0.36, 0.35, 0.34, 0.32, 0.3,
             ]),
-            fc_eff_type: "SI".into(),
+            fc_eff_type: vehicle::FcEffType::SI,
             fc_sec_to_peak_pwr: 6.0,
             fc_base_kg: 61.0,
             fc_kw_per_kg: 2.13,
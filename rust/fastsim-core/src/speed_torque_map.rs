@@ -0,0 +1,433 @@
+//! Module containing 2-D efficiency maps over speed and torque for fuel
+//! converters and electric machines
+
+use crate::imports::*;
+use crate::params::RAD_PER_SEC_PER_RPM;
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+#[add_pyo3_api(
+    #[staticmethod]
+    #[pyo3(name = "from_csv_file")]
+    #[pyo3(signature = (filepath, fuel_lhv_kj_per_kg=None))]
+    pub fn from_csv_file_py(filepath: &Bound<PyAny>, fuel_lhv_kj_per_kg: Option<f64>) -> anyhow::Result<Self> {
+        Self::from_csv_file(PathBuf::extract_bound(filepath)?, fuel_lhv_kj_per_kg)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_csv_str")]
+    #[pyo3(signature = (csv_str, fuel_lhv_kj_per_kg=None))]
+    pub fn from_csv_str_py(csv_str: &str, fuel_lhv_kj_per_kg: Option<f64>) -> anyhow::Result<Self> {
+        Self::from_csv_str(csv_str, fuel_lhv_kj_per_kg)
+    }
+
+    #[pyo3(name = "eff_at")]
+    pub fn eff_at_py(&self, speed_rad_per_sec: f64, torque_nm: f64) -> anyhow::Result<f64> {
+        self.eff_at(speed_rad_per_sec, torque_nm)
+    }
+
+    #[pyo3(name = "eff_at_kw")]
+    pub fn eff_at_kw_py(&self, speed_rad_per_sec: f64, kw: f64) -> anyhow::Result<f64> {
+        self.eff_at_kw(speed_rad_per_sec, kw)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ApproxEq, FieldPath)]
+/// Efficiency map over shaft speed and torque.  Points outside the map are
+/// clamped to its boundary.  If all `torque_nm` breakpoints are
+/// non-negative, the map is assumed symmetric and negative torques (e.g.
+/// regenerative braking) are looked up by magnitude.
+pub struct SpeedTorqueEffMap {
+    /// shaft speed breakpoints \[rpm\]
+    pub speed_rpm: Vec<f64>,
+    /// shaft torque breakpoints \[N·m\]
+    pub torque_nm: Vec<f64>,
+    /// efficiency at each of `speed_rpm` (outer) and `torque_nm` (inner)
+    pub eff: Vec<Vec<f64>>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for SpeedTorqueEffMap {
+    fn init(&mut self) -> anyhow::Result<()> {
+        for (name, bins) in [
+            ("speed_rpm", &self.speed_rpm),
+            ("torque_nm", &self.torque_nm),
+        ] {
+            ensure!(
+                bins.len() >= 2,
+                "{}\n`{name}` must have at least 2 elements",
+                format_dbg!(bins.len())
+            );
+            ensure!(
+                bins.windows(2).all(|w| w[0] < w[1]),
+                "{}\n`{name}` must be strictly increasing",
+                format_dbg!(bins)
+            );
+        }
+        ensure!(
+            self.speed_rpm[0] >= 0.0,
+            "{}\n`speed_rpm` must be non-negative",
+            format_dbg!(self.speed_rpm[0])
+        );
+        ensure!(
+            self.eff.len() == self.speed_rpm.len()
+                && self.eff.iter().all(|row| row.len() == self.torque_nm.len()),
+            "{}\n`eff` must have shape (`speed_rpm.len()`, `torque_nm.len()`)",
+            format_dbg!((self.speed_rpm.len(), self.torque_nm.len()))
+        );
+        ensure!(
+            self.eff
+                .iter()
+                .flatten()
+                .all(|eff| (0.0..=1.0).contains(eff)),
+            "{}\n`eff` must be between 0 and 1",
+            format_dbg!(&self.eff)
+        );
+        Ok(())
+    }
+}
+
+/// Row of a long-format speed-torque map CSV file, with either efficiency
+/// or brake-specific fuel consumption
+#[derive(Deserialize)]
+struct SpeedTorqueMapRecord {
+    speed_rpm: f64,
+    torque_nm: f64,
+    #[serde(default)]
+    eff: Option<f64>,
+    #[serde(default)]
+    bsfc_g_per_kwh: Option<f64>,
+}
+
+impl SpeedTorqueEffMap {
+    /// Load map from long-format CSV file with columns `speed_rpm`,
+    /// `torque_nm`, and either `eff` or `bsfc_g_per_kwh`, with one row per
+    /// grid point.  `fuel_lhv_kj_per_kg`, the fuel lower heating value (e.g.
+    /// `Fuel::lhv_kj_per_kg`), is required to convert BSFC to efficiency.
+    pub fn from_csv_file<P: AsRef<Path>>(
+        filepath: P,
+        fuel_lhv_kj_per_kg: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        let filepath = filepath.as_ref();
+        let file = File::open(filepath).with_context(|| format!("{filepath:?}"))?;
+        Self::from_csv_reader(file, fuel_lhv_kj_per_kg)
+            .with_context(|| format!("Failed to load map from {filepath:?}"))
+    }
+
+    /// Load map from long-format CSV string, see [Self::from_csv_file]
+    pub fn from_csv_str(csv_str: &str, fuel_lhv_kj_per_kg: Option<f64>) -> anyhow::Result<Self> {
+        Self::from_csv_reader(csv_str.as_bytes(), fuel_lhv_kj_per_kg)
+    }
+
+    fn from_csv_reader<R: std::io::Read>(
+        rdr: R,
+        fuel_lhv_kj_per_kg: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        let mut records = vec![];
+        for result in csv::Reader::from_reader(rdr).deserialize() {
+            let record: SpeedTorqueMapRecord = result?;
+            let eff = match (record.eff, record.bsfc_g_per_kwh) {
+                (Some(eff), None) => eff,
+                (None, Some(bsfc_g_per_kwh)) => {
+                    let fuel_lhv_kj_per_kg = fuel_lhv_kj_per_kg.with_context(|| {
+                        format_dbg!(
+                            "`fuel_lhv_kj_per_kg` is required to convert BSFC to efficiency"
+                        )
+                    })?;
+                    if bsfc_g_per_kwh > 0.0 {
+                        3.6e6 / (bsfc_g_per_kwh * fuel_lhv_kj_per_kg)
+                    } else {
+                        0.0
+                    }
+                }
+                _ => bail!(
+                    "{}\nEach row must have exactly one of `eff` or `bsfc_g_per_kwh`",
+                    format_dbg!((record.speed_rpm, record.torque_nm))
+                ),
+            };
+            records.push((record.speed_rpm, record.torque_nm, eff));
+        }
+
+        let unique_sorted = |mut vals: Vec<f64>| {
+            vals.sort_by(|a, b| a.total_cmp(b));
+            vals.dedup();
+            vals
+        };
+        let speed_rpm = unique_sorted(records.iter().map(|r| r.0).collect());
+        let torque_nm = unique_sorted(records.iter().map(|r| r.1).collect());
+        ensure!(
+            records.len() == speed_rpm.len() * torque_nm.len(),
+            "{}\nMap must have exactly one row per speed and torque combination",
+            format_dbg!((records.len(), speed_rpm.len(), torque_nm.len()))
+        );
+        let mut eff = vec![vec![f64::NAN; torque_nm.len()]; speed_rpm.len()];
+        for (speed, torque, eff_val) in records {
+            // breakpoints were built from these same values, so `position` succeeds
+            let i = speed_rpm.iter().position(|s| *s == speed).unwrap();
+            let j = torque_nm.iter().position(|t| *t == torque).unwrap();
+            ensure!(
+                eff[i][j].is_nan(),
+                "{}\nDuplicate map point",
+                format_dbg!((speed, torque))
+            );
+            eff[i][j] = eff_val;
+        }
+
+        let mut map = Self {
+            speed_rpm,
+            torque_nm,
+            eff,
+            orphaned: false,
+        };
+        map.init()?;
+        Ok(map)
+    }
+
+    /// Efficiency at `speed_rad_per_sec` and `torque_nm`
+    pub fn eff_at(&self, speed_rad_per_sec: f64, torque_nm: f64) -> anyhow::Result<f64> {
+        let torque_nm = if self.torque_nm[0] >= 0.0 {
+            torque_nm.abs()
+        } else {
+            torque_nm
+        };
+        ensure!(
+            !speed_rad_per_sec.is_nan() && !torque_nm.is_nan(),
+            "{}\nspeed and torque must not be NaN",
+            format_dbg!((speed_rad_per_sec, torque_nm))
+        );
+        // bilinear interpolation directly on the breakpoints, as this is
+        // called at least once per time step
+        let (i, speed_frac) = bracket(&self.speed_rpm, speed_rad_per_sec / RAD_PER_SEC_PER_RPM);
+        let (j, torque_frac) = bracket(&self.torque_nm, torque_nm);
+        let lerp = |row: &[f64]| row[j] + torque_frac * (row[j + 1] - row[j]);
+        let (lo, hi) = (lerp(&self.eff[i]), lerp(&self.eff[i + 1]));
+        Ok(lo + speed_frac * (hi - lo))
+    }
+
+    /// Efficiency at `speed_rad_per_sec` and shaft power `kw`
+    pub fn eff_at_kw(&self, speed_rad_per_sec: f64, kw: f64) -> anyhow::Result<f64> {
+        // speed is clamped before calculating torque so that torque is
+        // finite whenever the map has a nonzero minimum speed
+        let speed_rad_per_sec = speed_rad_per_sec.max(self.speed_rpm[0] * RAD_PER_SEC_PER_RPM);
+        let torque_nm = if speed_rad_per_sec > 0.0 {
+            kw * 1e3 / speed_rad_per_sec
+        } else {
+            kw.signum() * f64::INFINITY
+        };
+        self.eff_at(speed_rad_per_sec, torque_nm)
+    }
+}
+
+/// Returns index `i` of the interval `bins[i]..=bins[i + 1]` containing `x`,
+/// clamped to `bins`, and the fractional position of `x` within it.  `bins`
+/// must be strictly increasing with at least 2 elements.  Used in place of
+/// [crate::utils::multilinear], which needs an `ArrayD` of values and copies
+/// the point and grid on every call, as maps are evaluated several times per
+/// time step; `test_eff_at_matches_multilinear` checks that both agree.
+fn bracket(bins: &[f64], x: f64) -> (usize, f64) {
+    let x = x.clamp(bins[0], bins[bins.len() - 1]);
+    let i = bins
+        .partition_point(|bin| *bin <= x)
+        .clamp(1, bins.len() - 1)
+        - 1;
+    (i, (x - bins[i]) / (bins[i + 1] - bins[i]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::simdrive::RustSimDrive;
+    use crate::thermal::{SimDriveHot, ThermalState};
+    use crate::transmission::Transmission;
+    use crate::vehicle::RustVehicle;
+    use crate::vehicle_thermal::VehicleThermal;
+
+    const EFF_CSV: &str = "speed_rpm,torque_nm,eff
+1000,0,0.0
+1000,500,0.4
+2000,0,0.0
+2000,500,0.3
+";
+
+    #[test]
+    fn test_map_from_csv() {
+        let map = SpeedTorqueEffMap::from_csv_str(EFF_CSV, None).unwrap();
+        assert_eq!(map.speed_rpm, vec![1000.0, 2000.0]);
+        assert_eq!(map.torque_nm, vec![0.0, 500.0]);
+        assert_eq!(map.eff, vec![vec![0.0, 0.4], vec![0.0, 0.3]]);
+
+        let speed = 1500.0 * RAD_PER_SEC_PER_RPM;
+        assert!((map.eff_at(speed, 250.0).unwrap() - 0.175).abs() < 1e-9);
+        // symmetric in torque and clamped outside the map
+        assert_eq!(
+            map.eff_at(speed, -250.0).unwrap(),
+            map.eff_at(speed, 250.0).unwrap()
+        );
+        assert!((map.eff_at(0.0, 1e4).unwrap() - 0.4).abs() < 1e-9);
+
+        let bsfc_csv = "speed_rpm,torque_nm,bsfc_g_per_kwh
+1000,100,250
+1000,200,200
+2000,100,260
+2000,200,210
+";
+        assert!(SpeedTorqueEffMap::from_csv_str(bsfc_csv, None).is_err());
+        let map = SpeedTorqueEffMap::from_csv_str(bsfc_csv, Some(45_000.0)).unwrap();
+        assert!((map.eff[0][1] - 0.4).abs() < 1e-9);
+
+        // incomplete grid
+        let lines: Vec<&str> = EFF_CSV.lines().collect();
+        assert!(SpeedTorqueEffMap::from_csv_str(&lines[..4].join("\n"), None).is_err());
+    }
+
+    /// Engine map with efficiency that decreases with speed
+    fn speed_dependent_fc_map() -> SpeedTorqueEffMap {
+        SpeedTorqueEffMap {
+            speed_rpm: vec![600.0, 1_200.0, 2_100.0],
+            torque_nm: vec![0.0, 400.0, 1_000.0, 2_000.0],
+            eff: vec![
+                vec![0.0, 0.40, 0.44, 0.45],
+                vec![0.0, 0.36, 0.42, 0.43],
+                vec![0.0, 0.30, 0.36, 0.38],
+            ],
+            orphaned: false,
+        }
+    }
+
+    #[test]
+    fn test_eff_at_matches_multilinear() {
+        let map = speed_dependent_fc_map();
+        let grid = vec![map.speed_rpm.clone(), map.torque_nm.clone()];
+        let values = Array::from_shape_vec((3, 4), map.eff.concat())
+            .unwrap()
+            .into_dyn();
+        for speed_rpm in [600.0, 750.0, 1_200.0, 1_500.0, 2_100.0] {
+            for torque_nm in [0.0, 150.0, 400.0, 1_234.0, 2_000.0] {
+                let expected =
+                    crate::utils::multilinear(&[speed_rpm, torque_nm], &grid, &values).unwrap();
+                let eff = map
+                    .eff_at(speed_rpm * RAD_PER_SEC_PER_RPM, torque_nm)
+                    .unwrap();
+                assert!(
+                    (eff - expected).abs() < 1e-12,
+                    "{}",
+                    format_dbg!((speed_rpm, torque_nm))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sim_drive_with_2d_maps() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/Regional_Delivery_Class_8_Truck.yaml"),
+            false,
+        )
+        .unwrap();
+        veh.transmission = Some(Transmission::default());
+        let cyc = RustCycle::from_resource("HHDDTCruiseSmooth.csv", false).unwrap();
+
+        let mut sd_1d = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_1d.sim_drive(None, None).unwrap();
+
+        veh.fc_eff_map_2d = Some(speed_dependent_fc_map());
+        veh.set_derived().unwrap();
+        let mut sd_2d = RustSimDrive::new(cyc, veh.clone());
+        sd_2d.sim_drive(None, None).unwrap();
+
+        let map = veh.fc_eff_map_2d.as_ref().unwrap();
+        for i in 1..sd_2d.len() {
+            if sd_2d.fc_kw_out_ach[i] > 0.0 {
+                let eff = sd_2d.fc_kw_out_ach[i] / sd_2d.fc_kw_in_ach[i];
+                let expected = map
+                    .eff_at_kw(sd_2d.trans_in_rad_per_sec[i], sd_2d.fc_kw_out_ach[i])
+                    .unwrap();
+                assert!((eff - expected).abs() < 1e-9, "{}", format_dbg!(i));
+            }
+        }
+        assert!(sd_2d.fuel_kj != sd_1d.fuel_kj);
+
+        // 2-D maps require transmission input speed
+        veh.transmission = None;
+        assert!(veh.set_derived().is_err());
+    }
+
+    #[test]
+    fn test_sim_drive_hot_with_2d_map() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/Regional_Delivery_Class_8_Truck.yaml"),
+            false,
+        )
+        .unwrap();
+        veh.transmission = Some(Transmission::default());
+        let cyc = RustCycle::from_resource("HHDDTCruiseSmooth.csv", false).unwrap();
+        let run = |veh: RustVehicle| {
+            let mut sdh = SimDriveHot::new(
+                cyc.clone(),
+                veh,
+                VehicleThermal::default(),
+                Some(ThermalState::default()),
+                None,
+            );
+            sdh.sim_drive(None, None).unwrap();
+            sdh
+        };
+
+        let sdh_1d = run(veh.clone());
+        veh.fc_eff_map_2d = Some(speed_dependent_fc_map());
+        veh.set_derived().unwrap();
+        let sdh_2d = run(veh.clone());
+
+        let map = veh.fc_eff_map_2d.as_ref().unwrap();
+        let sd = &sdh_2d.sd;
+        for i in 1..sd.len() {
+            if sd.fc_kw_out_ach[i] > 0.0 {
+                let eff = sd.fc_kw_out_ach[i] / sd.fc_kw_in_ach[i];
+                let expected = map
+                    .eff_at_kw(sd.trans_in_rad_per_sec[i], sd.fc_kw_out_ach[i])
+                    .unwrap()
+                    * sdh_2d.history.fc_eta_temp_coeff[i];
+                assert!((eff - expected).abs() < 1e-9, "{}", format_dbg!(i));
+            }
+        }
+        assert!(sdh_2d.sd.fuel_kj != sdh_1d.sd.fuel_kj);
+    }
+
+    #[test]
+    fn test_motor_limits_with_2d_map() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/2016_TOYOTA_Prius_Two.yaml"),
+            false,
+        )
+        .unwrap();
+        veh.transmission = Some(Transmission::default());
+        // constant efficiency, so limits are easy to check
+        veh.mc_eff_map_2d = Some(SpeedTorqueEffMap {
+            speed_rpm: vec![0.0, 10_000.0],
+            torque_nm: vec![0.0, 400.0],
+            eff: vec![vec![0.5, 0.5], vec![0.5, 0.5]],
+            orphaned: false,
+        });
+        veh.set_derived().unwrap();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = RustSimDrive::new(cyc, veh.clone());
+        sd.sim_drive(None, None).unwrap();
+
+        for i in 1..sd.len() {
+            if sd.cur_max_elec_kw[i] > 0.0 {
+                let expected = (sd.cur_max_avail_elec_kw[i] * 0.5).min(veh.mc_max_kw);
+                assert!((sd.mc_elec_in_lim_kw[i] - expected).abs() < 1e-9);
+            }
+            if sd.cur_max_mc_kw_out[i] > 0.0 {
+                let expected = sd.cur_max_mc_kw_out[i] / 0.5;
+                assert!((sd.cur_max_mc_elec_kw_in[i] - expected).abs() < 1e-9);
+            }
+            if sd.cur_max_ess_chg_kw[i] > 0.0 {
+                let expected = (sd.cur_max_ess_chg_kw[i] / 0.5).min(veh.mc_max_kw);
+                assert!((sd.cur_max_mech_mc_kw_in[i] - expected).abs() < 1e-9);
+            }
+        }
+    }
+}
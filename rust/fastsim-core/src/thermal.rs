@@ -8,21 +8,29 @@ use crate::imports::*;
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
 use crate::simdrive;
+use crate::simdrive::energy_management::EnergyManagement;
 use crate::vehicle;
+use crate::vehicle::{FcEffType, PowertrainType};
 use crate::vehicle_thermal::*;
+use crate::weather::WeatherProfile;
 
 #[add_pyo3_api(
     /// method for instantiating SimDriveHot
     #[new]
-    #[pyo3(signature = (cyc, veh, vehthrm, init_state=None, amb_te_deg_c=None))]
+    #[pyo3(signature = (cyc, veh, vehthrm, init_state=None, amb_te_deg_c=None, weather=None))]
     pub fn __new__(
         cyc: cycle::RustCycle,
         veh: vehicle::RustVehicle,
         vehthrm: VehicleThermal,
         init_state: Option<ThermalState>,
         amb_te_deg_c: Option<Vec<f64>>,
-     ) -> Self {
-        Self::new(cyc, veh, vehthrm, init_state, amb_te_deg_c.map(Array1::from))
+        weather: Option<WeatherProfile>,
+     ) -> anyhow::Result<Self> {
+        let sdh = Self::new(cyc, veh, vehthrm, init_state, amb_te_deg_c.map(Array1::from));
+        match weather {
+            Some(weather) => sdh.with_weather(weather),
+            None => Ok(sdh),
+        }
     }
 
     #[pyo3(name = "gap_to_lead_vehicle_m")]
@@ -185,6 +193,38 @@ use crate::vehicle_thermal::*;
     pub fn set_post_scalars_py(&mut self) -> anyhow::Result<()> {
         self.set_post_scalars()
     }
+    #[pyo3(name = "to_timeseries_file", signature = (filepath, columns=None))]
+    /// Writes `time_s` and `columns`, or all time series if `None`, to `filepath`
+    /// with format determined by its extension: `csv`, `parquet`, or
+    /// `arrow`/`ipc`/`feather`
+    pub fn to_timeseries_file_py(
+        &self,
+        filepath: &Bound<PyAny>,
+        columns: Option<Vec<String>>
+    ) -> PyResult<()> {
+        self.to_timeseries_file(PathBuf::extract_bound(filepath)?, columns.as_deref())
+            .map_err(to_py_io_err)
+    }
+
+    #[pyo3(name = "timeseries", signature = (columns=None))]
+    /// Returns dict of `time_s` and `columns`, or all time series if `None`,
+    /// e.g. for `polars.DataFrame(sdh.timeseries())`
+    pub fn timeseries_py<'py>(
+        &self,
+        py: Python<'py>,
+        columns: Option<Vec<String>>
+    ) -> PyResult<Bound<'py, PyDict>> {
+        self.to_timeseries()
+            .and_then(|table| table.select_opt(columns.as_deref()))
+            .map_err(to_pyerr)?
+            .to_pydict(py)
+    }
+
+    #[pyo3(name = "timeseries_units")]
+    /// Returns dict of time series column name to unit, for columns with a known unit
+    pub fn timeseries_units_py(&self) -> PyResult<std::collections::BTreeMap<String, String>> {
+        Ok(self.to_timeseries().map_err(to_pyerr)?.units())
+    }
 )]
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct SimDriveHot {
@@ -206,6 +246,7 @@ pub struct SimDriveHot {
 impl SerdeAPI for SimDriveHot {
     fn init(&mut self) -> anyhow::Result<()> {
         self.sd.veh.init()?;
+        self.vehthrm.init()?;
         Ok(())
     }
 }
@@ -253,6 +294,22 @@ impl SimDriveHot {
         }
     }
 
+    /// Drive with ambient temperature, solar load, wind, and air density
+    /// from `weather` rather than a fixed or per-step ambient temperature.
+    /// The initial ambient temperature is taken from `weather`.
+    pub fn with_weather(mut self, mut weather: WeatherProfile) -> anyhow::Result<Self> {
+        ensure!(
+            self.amb_te_deg_c.is_none(),
+            "{}
+`amb_te_deg_c` and `weather` cannot both be provided",
+            format_dbg!()
+        );
+        weather.init()?;
+        self.state.amb_te_deg_c = weather.amb_te_deg_c_at(self.sd.cyc.time_s[0], 0.0);
+        self.sd.weather = Some(weather);
+        Ok(self)
+    }
+
     pub fn gap_to_lead_vehicle_m(&self) -> Array1<f64> {
         self.sd.gap_to_lead_vehicle_m()
     }
@@ -267,44 +324,40 @@ impl SimDriveHot {
         let init_soc = match init_soc {
             Some(x) => x,
             None => {
-                if self.sd.veh.veh_pt_type == vehicle::CONV {
+                match self.sd.veh.veh_pt_type {
                     // If no EV / Hybrid components, no SOC considerations.
-                    (self.sd.veh.max_soc + self.sd.veh.min_soc) / 2.0
-                } else if self.sd.veh.veh_pt_type == vehicle::HEV {
-                    // ####################################
-                    // ### Charge Balancing Vehicle SOC ###
-                    // ####################################
-                    // Charge balancing SOC for HEV vehicle types. Iterating init_soc and comparing to final SOC.
-                    // Iterating until tolerance met or 30 attempts made.
-                    let mut init_soc = (self.sd.veh.max_soc + self.sd.veh.min_soc) / 2.0;
-                    let mut ess_2fuel_kwh = 1.0;
-                    while ess_2fuel_kwh > self.sd.veh.ess_to_fuel_ok_error
-                        && self.sd.hev_sim_count < self.sd.sim_params.sim_count_max
-                    {
-                        self.sd.hev_sim_count += 1;
-                        self.walk(init_soc, aux_in_kw_override.clone());
-                        let fuel_kj = (&self.sd.fs_kw_out_ach * self.sd.cyc.dt_s()).sum();
-                        let roadway_chg_kj =
-                            (&self.sd.roadway_chg_kw_out_ach * self.sd.cyc.dt_s()).sum();
-                        if (fuel_kj + roadway_chg_kj) > 0.0 {
-                            ess_2fuel_kwh = ((self.sd.soc[0] - self.sd.soc.last().unwrap())
-                                * self.sd.veh.ess_max_kwh
-                                * 3.6e3
-                                / (fuel_kj + roadway_chg_kj))
-                                .abs();
-                        } else {
-                            ess_2fuel_kwh = 0.0;
+                    PowertrainType::Conv => (self.sd.veh.max_soc + self.sd.veh.min_soc) / 2.0,
+                    PowertrainType::HEV => {
+                        // ####################################
+                        // ### Charge Balancing Vehicle SOC ###
+                        // ####################################
+                        // Charge balancing SOC for HEV vehicle types. Iterating init_soc and comparing to final SOC.
+                        // Iterating until tolerance met or 30 attempts made.
+                        let mut init_soc = (self.sd.veh.max_soc + self.sd.veh.min_soc) / 2.0;
+                        let mut ess_2fuel_kwh = 1.0;
+                        while ess_2fuel_kwh > self.sd.veh.ess_to_fuel_ok_error
+                            && self.sd.hev_sim_count < self.sd.sim_params.sim_count_max
+                        {
+                            self.sd.hev_sim_count += 1;
+                            self.walk(init_soc, aux_in_kw_override.clone());
+                            let fuel_kj = (&self.sd.fs_kw_out_ach * self.sd.cyc.dt_s()).sum();
+                            let roadway_chg_kj =
+                                (&self.sd.roadway_chg_kw_out_ach * self.sd.cyc.dt_s()).sum();
+                            if (fuel_kj + roadway_chg_kj) > 0.0 {
+                                ess_2fuel_kwh = ((self.sd.soc[0] - self.sd.soc.last().unwrap())
+                                    * self.sd.veh.ess_max_kwh
+                                    * 3.6e3
+                                    / (fuel_kj + roadway_chg_kj))
+                                    .abs();
+                            } else {
+                                ess_2fuel_kwh = 0.0;
+                            }
+                            init_soc = min(1.0, max(0.0, *self.sd.soc.last().unwrap()));
                         }
-                        init_soc = min(1.0, max(0.0, *self.sd.soc.last().unwrap()));
+                        init_soc
                     }
-                    init_soc
-                } else if self.sd.veh.veh_pt_type == vehicle::PHEV
-                    || self.sd.veh.veh_pt_type == vehicle::BEV
-                {
                     // If EV, initializing initial SOC to maximum SOC.
-                    self.sd.veh.max_soc
-                } else {
-                    bail!("Failed to properly initialize SOC.");
+                    PowertrainType::PHEV | PowertrainType::BEV => self.sd.veh.max_soc,
                 }
             }
         };
@@ -331,6 +384,9 @@ impl SimDriveHot {
             CabinHvacModelTypes::External => {}
         }
         self.sd.init_for_step(init_soc, aux_in_kw_override).unwrap();
+        if self.sd.veh.ess_circuit.is_some() {
+            self.sd.ess_te_deg_c.fill(self.state.ess_te_deg_c);
+        }
     }
 
     pub fn set_speed_for_target_gap_using_idm(&mut self, i: usize) {
@@ -347,10 +403,23 @@ impl SimDriveHot {
         self.set_comp_lims(self.sd.i)?;
         self.set_power_calcs(self.sd.i)?;
         self.set_ach_speed(self.sd.i)?;
-        self.set_hybrid_cont_calcs(self.sd.i)?;
-        self.set_fc_forced_state_rust(self.sd.i)?;
-        self.set_hybrid_cont_decisions(self.sd.i)?;
+        // moved out for the step rather than cloned, as in
+        // `RustSimDrive::solve_step`
+        let (i, em) = (
+            self.sd.i,
+            std::mem::take(&mut self.sd.sim_params.energy_management),
+        );
+        let res = em
+            .set_hybrid_cont_calcs(&mut self.sd, i)
+            .and_then(|_| em.set_fc_forced_state(&mut self.sd, i))
+            .and_then(|_| em.set_hybrid_cont_decisions(&mut self.sd, i));
+        self.sd.sim_params.energy_management = em;
+        res?;
         self.set_fc_power(self.sd.i)?;
+        self.sd.set_emissions(self.sd.i)?;
+        if self.sd.sim_params.diagnostics {
+            self.sd.check_step(self.sd.i)?;
+        }
 
         self.sd.i += 1; // increment time step counter
         self.history.push(self.state.clone());
@@ -375,6 +444,11 @@ impl SimDriveHot {
 
         if let Some(amb_te_deg_c) = &self.amb_te_deg_c {
             self.state.amb_te_deg_c = amb_te_deg_c[i];
+        } else if let Some(weather) = &self.sd.weather {
+            let (time_s, dist_m) = self.sd.weather_coords_for_step(i);
+            self.state.amb_te_deg_c = weather.amb_te_deg_c_at(time_s, dist_m);
+            self.state.cab_qdot_solar_kw =
+                weather.solar_w_per_m2_at(time_s, dist_m) * self.vehthrm.cab_solar_area_m2 / 1e3;
         }
 
         if let FcModelTypes::Internal(..) = &self.vehthrm.fc_model {
@@ -390,7 +464,15 @@ impl SimDriveHot {
         }
 
         if self.vehthrm.cat_model == ComponentModelTypes::Internal {
-            self.set_cat_thermal_calcs(i)
+            self.set_cat_thermal_calcs(i);
+            if let Some(emissions) = &self.sd.veh.emissions {
+                self.sd.cat_conv_frac[i] = emissions
+                    .cat_conv_frac(self.state.cat_te_deg_c, self.vehthrm.cat_te_lightoff_deg_c);
+            }
+        }
+
+        if self.sd.veh.ess_circuit.is_some() {
+            self.set_ess_thermal_calcs(i)
         }
 
         if self.vehthrm.fc_model != FcModelTypes::External {
@@ -403,15 +485,29 @@ impl SimDriveHot {
         }
     }
 
+    /// Solve battery thermal behavior as a lumped thermal mass heated by
+    /// equivalent circuit losses and passing the updated temperature to `sd`
+    pub fn set_ess_thermal_calcs(&mut self, i: usize) {
+        self.state.ess_qdot_kw = self.sd.ess_loss_kw[i - 1];
+        self.state.ess_qdot_to_amb_kw = self.vehthrm.ess_htc_to_amb
+            * 1e-3
+            * (self.state.ess_te_deg_c - self.state.amb_te_deg_c);
+        self.state.ess_te_deg_c += (self.state.ess_qdot_kw - self.state.ess_qdot_to_amb_kw)
+            / self.vehthrm.ess_c_kj_k
+            * self.sd.cyc.dt_s_at_i(i);
+        self.sd.ess_te_deg_c[i] = self.state.ess_te_deg_c;
+    }
+
     /// Solve fuel converter thermal behavior assuming convection parameters of sphere.
     pub fn set_fc_thermal_calcs(&mut self, i: usize) {
         // Constitutive equations for fuel converter
         // calculation of adiabatic flame temperature
+        let fuel = self.sd.veh.fuel_props();
         self.state.fc_te_adiabatic_deg_c = self.air.get_te_from_h(
-            ((1.0 + self.state.fc_lambda * self.sd.props.fuel_afr_stoich)
+            ((1.0 + self.state.fc_lambda * fuel.afr_stoich)
                 * self.air.get_h(self.state.amb_te_deg_c)
-                + self.sd.props.get_fuel_lhv_kj_per_kg() * 1e3 * self.state.fc_lambda.min(1.0))
-                / (1.0 + self.state.fc_lambda * self.sd.props.fuel_afr_stoich),
+                + fuel.lhv_kj_per_kg * 1e3 * self.state.fc_lambda.min(1.0))
+                / (1.0 + self.state.fc_lambda * fuel.afr_stoich),
         );
 
         // limited between 0 and 1, but should really not get near 1
@@ -519,6 +615,10 @@ impl SimDriveHot {
                 // inside deadband; no hvac power is needed
 
                 self.state.cab_qdot_from_hvac_kw = 0.0;
+                if self.vehthrm.cabin_hvac_components.is_some() {
+                    self.state
+                        .set_cab_hvac_components_pwr(&HvacComponentsPower::default());
+                }
                 hvac_model.i_cntrl_kw = 0.0; // reset to 0.0
             } else {
                 hvac_model.p_cntrl_kw = hvac_model.p_cntrl_kw_per_deg_c * te_delta_vs_set_deg_c;
@@ -559,13 +659,20 @@ impl SimDriveHot {
                         (-hvac_model.p_cntrl_kw - hvac_model.i_cntrl_kw - hvac_model.d_cntrl_kw)
                             .max(-hvac_model.cntrl_max_kw);
 
-                    self.state.cab_hvac_pwr_aux_kw = (-self.state.cab_qdot_from_hvac_kw
-                        / hvac_model.cop)
-                        .min(hvac_model.pwr_max_aux_load_for_cooling_kw)
-                        .max(0.0);
-                    // correct if limit is exceeded
-                    self.state.cab_qdot_from_hvac_kw =
-                        -self.state.cab_hvac_pwr_aux_kw * hvac_model.cop;
+                    if let Some(components) = &self.vehthrm.cabin_hvac_components {
+                        let pwr = components
+                            .cool(-self.state.cab_qdot_from_hvac_kw, self.state.amb_te_deg_c);
+                        self.state.set_cab_hvac_components_pwr(&pwr);
+                        self.state.cab_qdot_from_hvac_kw = pwr.qdot_kw;
+                    } else {
+                        self.state.cab_hvac_pwr_aux_kw = (-self.state.cab_qdot_from_hvac_kw
+                            / hvac_model.cop)
+                            .min(hvac_model.pwr_max_aux_load_for_cooling_kw)
+                            .max(0.0);
+                        // correct if limit is exceeded
+                        self.state.cab_qdot_from_hvac_kw =
+                            -self.state.cab_hvac_pwr_aux_kw * hvac_model.cop;
+                    }
                 } else {
                     // HEATING MODE; cabin is colder than set point
 
@@ -580,6 +687,7 @@ impl SimDriveHot {
                             .min(hvac_model.cntrl_max_kw);
 
                     if hvac_model.use_fc_waste_heat {
+                        let qdot_req_kw = self.state.cab_qdot_from_hvac_kw;
                         // limit heat transfer to be substantially less than what is physically possible
                         // i.e. the engine can't drop below cabin temperature to heat the cabin
                         self.state.cab_qdot_from_hvac_kw = self
@@ -596,8 +704,22 @@ impl SimDriveHot {
                         // TODO: think about what to do for PHEV, which needs careful consideration here
                         // HEV probably also needs careful consideration
                         // There needs to be an engine temperature (e.g. 60°C) below which the engine is forced on
-                        assert!(self.sd.veh.veh_pt_type != "BEV");
+                        assert!(self.sd.veh.veh_pt_type != PowertrainType::BEV);
                         // assume blower has negligible impact on aux load, may want to revise later
+                        // electric components, if any, make up for insufficient waste heat
+                        if let Some(components) = &self.vehthrm.cabin_hvac_components {
+                            let pwr = components.heat(
+                                qdot_req_kw - self.state.cab_qdot_from_hvac_kw,
+                                self.state.amb_te_deg_c,
+                            );
+                            self.state.set_cab_hvac_components_pwr(&pwr);
+                            self.state.cab_qdot_from_hvac_kw += pwr.qdot_kw;
+                        }
+                    } else if let Some(components) = &self.vehthrm.cabin_hvac_components {
+                        let pwr = components
+                            .heat(self.state.cab_qdot_from_hvac_kw, self.state.amb_te_deg_c);
+                        self.state.set_cab_hvac_components_pwr(&pwr);
+                        self.state.cab_qdot_from_hvac_kw = pwr.qdot_kw;
                     } else {
                         self.state.cab_hvac_pwr_aux_kw = (self.state.cab_qdot_from_hvac_kw
                             / hvac_model.cop)
@@ -611,6 +733,7 @@ impl SimDriveHot {
 
             self.state.cab_prev_te_deg_c = self.state.cab_te_deg_c;
             self.state.cab_te_deg_c += (self.state.cab_qdot_from_hvac_kw
+                + self.state.cab_qdot_solar_kw
                 - self.state.cab_qdot_to_amb_kw)
                 / self.vehthrm.cab_c_kj__k
                 * self.sd.cyc.dt_s_at_i(i);
@@ -620,8 +743,14 @@ impl SimDriveHot {
     /// Solve exhport thermal behavior.
     pub fn set_exhport_thermal_calcs(&mut self, i: usize) {
         // lambda index may need adjustment, depending on how this ends up being modeled.
-        self.state.exh_mdot = self.sd.fs_kw_out_ach[i - 1] / self.sd.props.get_fuel_lhv_kj_per_kg()
-            * (1.0 + self.sd.props.fuel_afr_stoich * self.state.fc_lambda);
+        let fuel = self.sd.veh.fuel_props();
+        self.state.exh_mdot = self.sd.fuel1_kw_out_ach[i - 1] / fuel.lhv_kj_per_kg
+            * (1.0 + fuel.afr_stoich * self.state.fc_lambda);
+        if let Some(secondary_fuel) = &self.sd.veh.secondary_fuel {
+            self.state.exh_mdot += self.sd.fuel2_kw_out_ach[i - 1]
+                / secondary_fuel.fuel.lhv_kj_per_kg
+                * (1.0 + secondary_fuel.fuel.afr_stoich * self.state.fc_lambda);
+        }
         self.state.exh_hdot_kw = (1.0 - self.state.fc_qdot_per_net_heat)
             * (self.sd.fc_kw_in_ach[i - 1] - self.sd.fc_kw_out_ach[i - 1]);
 
@@ -869,7 +998,7 @@ impl SimDriveHot {
     pub fn set_fc_power(&mut self, i: usize) -> anyhow::Result<()> {
         if self.sd.veh.fc_max_kw == 0.0 {
             self.sd.fc_kw_out_ach[i] = 0.0;
-        } else if self.sd.veh.fc_eff_type == vehicle::H2FC {
+        } else if self.sd.veh.fc_eff_type == FcEffType::H2FC {
             self.sd.fc_kw_out_ach[i] = min(
                 self.sd.cur_max_fc_kw_out[i],
                 max(
@@ -948,7 +1077,10 @@ impl SimDriveHot {
                 }
             }
 
-            if self.sd.fc_kw_out_ach[i] == *self.sd.veh.input_kw_out_array.max()? {
+            if self.sd.veh.fc_eff_map_2d.is_some() {
+                self.sd.fc_kw_in_ach[i] = self.sd.fc_kw_in_for(i, self.sd.fc_kw_out_ach[i])?
+                    / self.state.fc_eta_temp_coeff
+            } else if self.sd.fc_kw_out_ach[i] == *self.sd.veh.input_kw_out_array.max()? {
                 self.sd.fc_kw_in_ach[i] = self.sd.fc_kw_out_ach[i]
                     / (self.sd.veh.fc_eff_array.last().unwrap() * self.state.fc_eta_temp_coeff)
             } else {
@@ -973,6 +1105,7 @@ impl SimDriveHot {
         self.sd.fs_kw_out_ach[i] = self.sd.fc_kw_in_ach[i];
 
         self.sd.fs_kwh_out_ach[i] = self.sd.fs_kw_out_ach[i] * self.sd.cyc.dt_s_at_i(i) / 3.6e3;
+        self.sd.set_fuel_split(i);
         Ok(())
     }
 
@@ -993,6 +1126,7 @@ impl SimDriveHot {
         cab_te_deg_c_init=None,
         exhport_te_deg_c_init=None,
         cat_te_deg_c_init=None,
+        ess_te_deg_c_init=None,
     ))]
     pub fn __new__(
         amb_te_deg_c: Option<f64>,
@@ -1000,6 +1134,7 @@ impl SimDriveHot {
         cab_te_deg_c_init: Option<f64>,
         exhport_te_deg_c_init: Option<f64>,
         cat_te_deg_c_init: Option<f64>,
+        ess_te_deg_c_init: Option<f64>,
     ) -> Self {
         Self::new(
             amb_te_deg_c,
@@ -1007,6 +1142,7 @@ impl SimDriveHot {
             cab_te_deg_c_init,
             exhport_te_deg_c_init,
             cat_te_deg_c_init,
+            ess_te_deg_c_init,
         )
     }
 )]
@@ -1047,6 +1183,15 @@ pub struct ThermalState {
     pub cab_qdot_from_hvac_kw: f64,
     /// aux load from hvac
     pub cab_hvac_pwr_aux_kw: f64,
+    /// heat pump electric power \[kW\], included in `cab_hvac_pwr_aux_kw`
+    #[serde(default)]
+    pub cab_heat_pump_pwr_kw: f64,
+    /// PTC heater electric power \[kW\], included in `cab_hvac_pwr_aux_kw`
+    #[serde(default)]
+    pub cab_ptc_heater_pwr_kw: f64,
+    /// AC compressor electric power \[kW\], included in `cab_hvac_pwr_aux_kw`
+    #[serde(default)]
+    pub cab_ac_compressor_pwr_kw: f64,
 
     // exhaust variables
     /// exhaust mass flow rate \[kg/s\]
@@ -1086,6 +1231,14 @@ pub struct ThermalState {
     /// net heat generation in cat \[W\]
     pub cat_qdot_net: f64,
 
+    // battery (ess) variables
+    /// battery temperature \[°C\]
+    pub ess_te_deg_c: f64,
+    /// battery heat generation from equivalent circuit losses \[kW\]
+    pub ess_qdot_kw: f64,
+    /// battery heat transfer to ambient \[kW\]
+    pub ess_qdot_to_amb_kw: f64,
+
     /// ambient temperature
     pub amb_te_deg_c: f64,
     #[serde(skip)]
@@ -1101,6 +1254,7 @@ impl ThermalState {
         cab_te_deg_c_init: Option<f64>,
         exhport_te_deg_c_init: Option<f64>,
         cat_te_deg_c_init: Option<f64>,
+        ess_te_deg_c_init: Option<f64>,
     ) -> Self {
         // Note default temperature is defined twice, see default()
         let default_te_deg_c = 22.0;
@@ -1112,10 +1266,19 @@ impl ThermalState {
             cab_prev_te_deg_c: cab_te_deg_c_init.unwrap_or(amb_te_deg_c),
             exhport_te_deg_c: exhport_te_deg_c_init.unwrap_or(amb_te_deg_c),
             cat_te_deg_c: cat_te_deg_c_init.unwrap_or(amb_te_deg_c),
+            ess_te_deg_c: ess_te_deg_c_init.unwrap_or(amb_te_deg_c),
             // fc_te_adiabatic_deg_c // chad is pretty sure 'fc_te_adiabatic_deg_c' gets overridden in first time step
             ..Default::default()
         }
     }
+
+    /// Sets cabin HVAC electric power from `pwr`, including the total aux load
+    fn set_cab_hvac_components_pwr(&mut self, pwr: &HvacComponentsPower) {
+        self.cab_heat_pump_pwr_kw = pwr.heat_pump_kw;
+        self.cab_ptc_heater_pwr_kw = pwr.ptc_heater_kw;
+        self.cab_ac_compressor_pwr_kw = pwr.ac_compressor_kw;
+        self.cab_hvac_pwr_aux_kw = pwr.elec_kw();
+    }
 }
 
 impl Default for ThermalState {
@@ -1140,6 +1303,9 @@ impl Default for ThermalState {
             cab_qdot_to_amb_kw: 0.0,
             cab_qdot_from_hvac_kw: 0.0,
             cab_hvac_pwr_aux_kw: 0.0,
+            cab_heat_pump_pwr_kw: 0.0,
+            cab_ptc_heater_pwr_kw: 0.0,
+            cab_ac_compressor_pwr_kw: 0.0,
 
             exh_mdot: 0.0,
             exh_hdot_kw: 0.0,
@@ -1158,6 +1324,11 @@ impl Default for ThermalState {
             cat_re_ext: 0.0,
             cat_qdot_from_exh: 0.0,
             cat_qdot_net: 0.0,
+
+            ess_te_deg_c: default_te_deg_c, // overridden by new()
+            ess_qdot_kw: 0.0,
+            ess_qdot_to_amb_kw: 0.0,
+
             amb_te_deg_c: default_te_deg_c, // overridden by new()
 
             orphaned: false,
@@ -0,0 +1,495 @@
+//! Module for exporting simulation time series to columnar formats (CSV, and
+//! Parquet and Arrow IPC with the `arrow` feature) for analysis in e.g.
+//! Polars or pandas
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::imports::*;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::simdrive::RustSimDrive;
+use crate::thermal::SimDriveHot;
+
+/// Name of the time column, which is always the first column of a [TimeSeriesTable]
+pub const TIME_COLUMN: &str = "time_s";
+
+/// Field name tokens and the units they imply, per the naming convention used
+/// throughout fastsim, e.g. `fc_kw_in_ach` is in kW
+const UNIT_TOKENS: [(&str, &str); 18] = [
+    ("g_per_s", "g/s"),
+    ("m_per_s2", "m/s^2"),
+    ("mps2", "m/s^2"),
+    ("m_per_s", "m/s"),
+    ("mps", "m/s"),
+    ("rad_per_sec", "rad/s"),
+    ("deg_c", "degC"),
+    ("kwh", "kWh"),
+    ("kw", "kW"),
+    ("kj", "kJ"),
+    ("kg", "kg"),
+    ("mph", "mph"),
+    ("mi", "mi"),
+    ("m", "m"),
+    ("s", "s"),
+    ("g", "g"),
+    ("v", "V"),
+    ("a", "A"),
+];
+
+/// Name suffixes of dimensionless quantities, e.g. `regen_contrl_lim_kw_perc`.
+/// Note that `_eff` is not among them, as fields like `ess_desired_kw_4fc_eff`
+/// are powers for peak efficiency rather than efficiencies.
+const DIMENSIONLESS_SUFFIXES: [&str; 6] = ["_pct", "_perc", "_frac", "soc", "_share", "_coeff"];
+
+/// Returns the unit implied by a field name, or `None` if the quantity is
+/// dimensionless or the unit cannot be inferred
+pub fn unit_for(name: &str) -> Option<&'static str> {
+    if DIMENSIONLESS_SUFFIXES.iter().any(|sfx| name.ends_with(sfx)) {
+        return None;
+    }
+    let padded = format!("_{name}_");
+    // rightmost unit token wins, preferring the longest match, so that
+    // `max_trac_m_per_s` is m/s rather than s
+    UNIT_TOKENS
+        .iter()
+        .filter_map(|(token, unit)| {
+            let token = format!("_{token}_");
+            padded
+                .rfind(&token)
+                .map(|start| ((start + token.len(), token.len()), *unit))
+        })
+        .max_by_key(|(key, _)| *key)
+        .map(|(_, unit)| unit)
+}
+
+/// Single named column of a [TimeSeriesTable]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Column {
+    pub name: String,
+    /// unit inferred from `name` with [unit_for]
+    pub unit: Option<String>,
+    /// values, with booleans stored as 0.0/1.0
+    pub values: Vec<f64>,
+}
+
+/// Table of equal-length time series columns, starting with [TIME_COLUMN]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeSeriesTable {
+    pub columns: Vec<Column>,
+}
+
+impl TimeSeriesTable {
+    /// Creates a table with only a [TIME_COLUMN]
+    pub fn new(time_s: Vec<f64>) -> Self {
+        Self {
+            columns: vec![Column {
+                name: TIME_COLUMN.into(),
+                unit: unit_for(TIME_COLUMN).map(String::from),
+                values: time_s,
+            }],
+        }
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |col| col.values.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.columns.iter().map(|col| col.name.as_str()).collect()
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|col| col.name == name)
+    }
+
+    /// Map of column name to unit, for columns with a known unit
+    pub fn units(&self) -> BTreeMap<String, String> {
+        self.columns
+            .iter()
+            .filter_map(|col| Some((col.name.clone(), col.unit.clone()?)))
+            .collect()
+    }
+
+    /// Appends a column, with unit inferred from `name`
+    pub fn push<S: Into<String>>(&mut self, name: S, values: Vec<f64>) -> anyhow::Result<()> {
+        let name = name.into();
+        ensure!(
+            values.len() == self.len(),
+            "column `{name}` has length {} but table has length {}",
+            values.len(),
+            self.len()
+        );
+        ensure!(
+            self.column(&name).is_none(),
+            "column `{name}` already exists"
+        );
+        self.columns.push(Column {
+            unit: unit_for(&name).map(String::from),
+            name,
+            values,
+        });
+        Ok(())
+    }
+
+    /// Appends every time series field of `value`, in declaration order, with
+    /// column names prefixed by `prefix`
+    pub fn push_fields<T: TimeSeries>(&mut self, value: &T, prefix: &str) -> anyhow::Result<()> {
+        value.push_columns(self, prefix)
+    }
+
+    /// Returns a table with [TIME_COLUMN] followed by `names`, in the given order
+    pub fn select<S: AsRef<str>>(&self, names: &[S]) -> anyhow::Result<Self> {
+        let mut columns = vec![self.columns[0].clone()];
+        for name in names.iter().map(|name| name.as_ref()) {
+            if name == TIME_COLUMN {
+                continue;
+            }
+            let col = self.column(name).with_context(|| {
+                format!(
+                    "unknown column `{name}`, expected one of {:?}",
+                    self.names()
+                )
+            })?;
+            columns.push(col.clone());
+        }
+        Ok(Self { columns })
+    }
+
+    /// Returns a table with only the columns in `names`, or all columns if `None`
+    pub fn select_opt<S: AsRef<str>>(&self, names: Option<&[S]>) -> anyhow::Result<Self> {
+        match names {
+            Some(names) => self.select(names),
+            None => Ok(self.clone()),
+        }
+    }
+
+    /// Writes the table as CSV with a single header row of column names
+    pub fn to_csv_writer<W: Write>(&self, wtr: W) -> anyhow::Result<()> {
+        let mut wtr = csv::Writer::from_writer(wtr);
+        wtr.write_record(self.names())?;
+        for i in 0..self.len() {
+            wtr.write_record(self.columns.iter().map(|col| col.values[i].to_string()))?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn to_csv_string(&self) -> anyhow::Result<String> {
+        let mut buf = Vec::new();
+        self.to_csv_writer(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    /// Writes the table to a file whose format is determined by its extension:
+    /// `csv`, or with the `arrow` feature, `parquet` and `arrow`/`ipc`/`feather`
+    /// (Arrow IPC).  Units are stored as `unit` field metadata in Parquet and
+    /// Arrow files.
+    pub fn to_file<P: AsRef<Path>>(&self, filepath: P) -> anyhow::Result<()> {
+        let filepath = filepath.as_ref();
+        let extension = filepath
+            .extension()
+            .and_then(OsStr::to_str)
+            .with_context(|| format!("File extension could not be parsed: {filepath:?}"))?
+            .to_lowercase();
+        match extension.as_str() {
+            "csv" => self.to_csv_writer(File::create(filepath).map_err(FastSimError::Io)?),
+            #[cfg(feature = "arrow")]
+            "parquet" => self.to_parquet_writer(File::create(filepath).map_err(FastSimError::Io)?),
+            #[cfg(feature = "arrow")]
+            "arrow" | "ipc" | "feather" => {
+                self.to_ipc_writer(File::create(filepath).map_err(FastSimError::Io)?)
+            }
+            #[cfg(not(feature = "arrow"))]
+            "parquet" | "arrow" | "ipc" | "feather" => bail!(FastSimError::Format(format!(
+                "writing `{extension}` files requires the `arrow` feature"
+            ))),
+            _ => bail!(FastSimError::Format(format!(
+                "Unsupported time series format {extension:?}, must be one of \
+                    [\"csv\", \"parquet\", \"arrow\", \"ipc\", \"feather\"]"
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "pyo3")]
+impl TimeSeriesTable {
+    /// Converts the table to a dict of column name to values, in column order,
+    /// e.g. for `polars.DataFrame(...)`
+    pub fn to_pydict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for col in &self.columns {
+            dict.set_item(&col.name, &col.values)?;
+        }
+        Ok(dict)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl TimeSeriesTable {
+    /// Converts the table to an Arrow record batch of `Float64` columns with a
+    /// `unit` metadata entry on each field with a known unit
+    pub fn to_record_batch(&self) -> anyhow::Result<arrow::record_batch::RecordBatch> {
+        use arrow::array::{ArrayRef, Float64Array};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .map(|col| {
+                let field = Field::new(&col.name, DataType::Float64, false);
+                match &col.unit {
+                    Some(unit) => {
+                        field.with_metadata(HashMap::from([("unit".to_string(), unit.clone())]))
+                    }
+                    None => field,
+                }
+            })
+            .collect();
+        let arrays: Vec<ArrayRef> = self
+            .columns
+            .iter()
+            .map(|col| Arc::new(Float64Array::from(col.values.clone())) as ArrayRef)
+            .collect();
+        Ok(arrow::record_batch::RecordBatch::try_new(
+            Arc::new(Schema::new(fields)),
+            arrays,
+        )?)
+    }
+
+    /// Writes the table in Arrow IPC file format
+    pub fn to_ipc_writer<W: Write>(&self, wtr: W) -> anyhow::Result<()> {
+        let batch = self.to_record_batch()?;
+        let mut writer = arrow::ipc::writer::FileWriter::try_new(wtr, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Writes the table in Parquet format
+    pub fn to_parquet_writer<W: Write + Send>(&self, wtr: W) -> anyhow::Result<()> {
+        let batch = self.to_record_batch()?;
+        let mut writer = parquet::arrow::ArrowWriter::try_new(wtr, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+/// Structs whose `Array1` and `Vec` fields are time series, implemented with
+/// `#[derive(TimeSeries)]` and by every `*HistoryVec`
+pub trait TimeSeries {
+    /// Appends a column to `table` for each time series field, with booleans
+    /// stored as 0.0/1.0 and names prefixed by `prefix`.  Fails if any field
+    /// length differs from `table.len()`.
+    fn push_columns(&self, table: &mut TimeSeriesTable, prefix: &str) -> anyhow::Result<()>;
+
+    /// Values of the time series field `name`, with booleans as 0.0/1.0, or
+    /// `None` if there is no such field
+    fn series(&self, name: &str) -> Option<Vec<f64>>;
+}
+
+impl RustSimDrive {
+    /// Returns `time_s` and every time series array, e.g. `fc_kw_out_ach`, as
+    /// a [TimeSeriesTable]
+    pub fn to_timeseries(&self) -> anyhow::Result<TimeSeriesTable> {
+        let mut table = TimeSeriesTable::new(self.cyc.time_s.to_vec());
+        table.push_fields(self, "")?;
+        Ok(table)
+    }
+
+    /// Writes `time_s` and `columns`, or all time series if `None`, to
+    /// `filepath`; see [TimeSeriesTable::to_file] for supported formats
+    pub fn to_timeseries_file<P: AsRef<Path>, S: AsRef<str>>(
+        &self,
+        filepath: P,
+        columns: Option<&[S]>,
+    ) -> anyhow::Result<()> {
+        self.to_timeseries()?.select_opt(columns)?.to_file(filepath)
+    }
+}
+
+impl SimDriveHot {
+    /// Returns the time series of [RustSimDrive::to_timeseries] followed by the
+    /// thermal state history, e.g. `history.fc_te_deg_c`.  Fails if the
+    /// history was not saved at every time step.
+    pub fn to_timeseries(&self) -> anyhow::Result<TimeSeriesTable> {
+        let mut table = self.sd.to_timeseries()?;
+        ensure!(
+            self.history.len() == table.len(),
+            "{}\nthermal state history has {} entries but cycle has {} time steps; \
+            history must be saved at every time step to be exported",
+            format_dbg!(),
+            self.history.len(),
+            table.len()
+        );
+        table
+            .push_fields(&self.history, "history.")
+            .with_context(|| "failed to add thermal state history")?;
+        Ok(table)
+    }
+
+    /// Writes `time_s` and `columns`, or all time series if `None`, to
+    /// `filepath`; see [TimeSeriesTable::to_file] for supported formats
+    pub fn to_timeseries_file<P: AsRef<Path>, S: AsRef<str>>(
+        &self,
+        filepath: P,
+        columns: Option<&[S]>,
+    ) -> anyhow::Result<()> {
+        self.to_timeseries()?.select_opt(columns)?.to_file(filepath)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::thermal::ThermalState;
+    use crate::vehicle::RustVehicle;
+    use crate::vehicle_thermal::VehicleThermal;
+
+    #[test]
+    fn test_unit_for() {
+        assert_eq!(unit_for("fc_kw_in_ach"), Some("kW"));
+        assert_eq!(unit_for("mc_kw_if_fc_req"), Some("kW"));
+        assert_eq!(unit_for("add_kwh"), Some("kWh"));
+        assert_eq!(unit_for("idm_target_speed_m_per_s"), Some("m/s"));
+        assert_eq!(unit_for("co2_g_per_s"), Some("g/s"));
+        assert_eq!(unit_for("ess_te_deg_c"), Some("degC"));
+        assert_eq!(unit_for("dist_mi"), Some("mi"));
+        assert_eq!(unit_for("time_s"), Some("s"));
+        assert_eq!(unit_for("ess_lim_mc_regen_perc_kw"), Some("kW"));
+        assert_eq!(unit_for("regen_contrl_lim_kw_perc"), None);
+        assert_eq!(unit_for("soc"), None);
+        assert_eq!(unit_for("newton_iters"), None);
+        assert_eq!(unit_for("mc_elec_in_kw_for_max_fc_eff"), Some("kW"));
+        assert_eq!(unit_for("ess_desired_kw_4fc_eff"), Some("kW"));
+        assert_eq!(unit_for("trans_eff_ach"), None);
+    }
+
+    #[test]
+    fn test_simdrive_timeseries() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = RustSimDrive::new(cyc.clone(), RustVehicle::mock_vehicle());
+        sd.sim_drive(None, None).unwrap();
+
+        let table = sd.to_timeseries().unwrap();
+        assert_eq!(table.len(), cyc.len());
+        assert_eq!(table.names()[0], TIME_COLUMN);
+        assert!(table.columns.len() > 100);
+        assert_eq!(
+            table.column("fc_kw_out_ach").unwrap().values,
+            sd.fc_kw_out_ach.to_vec()
+        );
+        assert_eq!(
+            table.column("fc_kw_out_ach").unwrap().unit.as_deref(),
+            Some("kW")
+        );
+        assert_eq!(table.column("cyc_met").unwrap().values[0], 1.0);
+        // nested structs and scalars are not time series
+        assert!(table.column("veh").is_none());
+        assert!(table.column("mpgge").is_none());
+        assert_eq!(
+            sd.series("soc"),
+            Some(table.column("soc").unwrap().values.clone())
+        );
+        assert_eq!(sd.series("cyc_met").unwrap()[0], 1.0);
+        assert!(sd.series("mpgge").is_none());
+
+        let selected = table.select(&["soc", "mps_ach"]).unwrap();
+        assert_eq!(selected.names(), vec![TIME_COLUMN, "soc", "mps_ach"]);
+        assert!(table.select(&["not_a_column"]).is_err());
+
+        let csv = selected.to_csv_string().unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("time_s,soc,mps_ach"));
+        assert_eq!(lines.count(), cyc.len());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sd.csv");
+        sd.to_timeseries_file(&path, Some(&["fc_kw_out_ach"]))
+            .unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("time_s,fc_kw_out_ach\n"));
+        assert!(sd
+            .to_timeseries_file(dir.path().join("sd.xlsx"), None::<&[&str]>)
+            .is_err());
+    }
+
+    #[test]
+    fn test_simdrivehot_timeseries() {
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sdh = SimDriveHot::new(
+            cyc.clone(),
+            RustVehicle::mock_vehicle(),
+            VehicleThermal::default(),
+            Some(ThermalState::default()),
+            None,
+        );
+        sdh.sim_drive(None, None).unwrap();
+        let table = sdh.to_timeseries().unwrap();
+        assert_eq!(table.len(), cyc.len());
+        assert_eq!(
+            table.column("history.fc_te_deg_c").unwrap().values,
+            sdh.history.fc_te_deg_c
+        );
+        assert_eq!(
+            table.column("history.fc_te_deg_c").unwrap().unit.as_deref(),
+            Some("degC")
+        );
+        assert!(table.column("fc_kw_out_ach").is_some());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_arrow_roundtrip() {
+        use arrow::array::Float64Array;
+        use arrow::record_batch::RecordBatch;
+
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let mut sd = RustSimDrive::new(cyc, RustVehicle::mock_vehicle());
+        sd.sim_drive(None, None).unwrap();
+        let table = sd
+            .to_timeseries()
+            .unwrap()
+            .select(&["fc_kw_out_ach", "soc", "cyc_met"])
+            .unwrap();
+
+        let check = |batches: Vec<RecordBatch>| {
+            assert_eq!(batches.len(), 1);
+            let batch = &batches[0];
+            assert_eq!(batch.num_rows(), table.len());
+            for (field, col) in batch.schema().fields().iter().zip(&table.columns) {
+                assert_eq!(field.name(), &col.name);
+                assert_eq!(field.metadata().get("unit"), col.unit.as_ref());
+            }
+            for (array, col) in batch.columns().iter().zip(&table.columns) {
+                let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+                assert_eq!(array.values().to_vec(), col.values);
+            }
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sd.parquet");
+        table.to_file(&path).unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReader::try_new(
+            File::open(&path).unwrap(),
+            table.len(),
+        )
+        .unwrap();
+        check(reader.collect::<Result<_, _>>().unwrap());
+
+        let path = dir.path().join("sd.arrow");
+        table.to_file(&path).unwrap();
+        let reader =
+            arrow::ipc::reader::FileReader::try_new(File::open(&path).unwrap(), None).unwrap();
+        check(reader.collect::<Result<_, _>>().unwrap());
+    }
+}
@@ -72,20 +72,22 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
             .extension()
             .and_then(OsStr::to_str)
             .with_context(|| format!("File extension could not be parsed: {filepath:?}"))?;
-        self.to_writer(File::create(filepath)?, extension)
+        self.to_writer(File::create(filepath).map_err(FastSimError::Io)?, extension)
     }
 
     fn to_writer<W: std::io::Write>(&self, mut wtr: W, format: &str) -> anyhow::Result<()> {
         match format.trim_start_matches('.').to_lowercase().as_str() {
-            "yaml" | "yml" => serde_yaml::to_writer(wtr, self)?,
-            "json" => serde_json::to_writer(wtr, self)?,
-            "toml" => wtr.write_all(self.to_toml()?.as_bytes())?,
+            "yaml" | "yml" => serde_yaml::to_writer(wtr, self).map_err(FastSimError::format)?,
+            "json" => serde_json::to_writer(wtr, self).map_err(FastSimError::format)?,
+            "toml" => wtr
+                .write_all(self.to_toml()?.as_bytes())
+                .map_err(FastSimError::Io)?,
             #[cfg(feature = "bincode")]
-            "bin" => bincode::serialize_into(wtr, self)?,
-            _ => bail!(
+            "bin" => bincode::serialize_into(wtr, self).map_err(FastSimError::format)?,
+            _ => bail!(FastSimError::Format(format!(
                 "Unsupported format {format:?}, must be one of {:?}",
                 Self::ACCEPTED_BYTE_FORMATS
-            ),
+            ))),
         }
         Ok(())
     }
@@ -103,13 +105,15 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
             .extension()
             .and_then(OsStr::to_str)
             .with_context(|| format!("File extension could not be parsed: {filepath:?}"))?;
-        let file = File::open(filepath).with_context(|| {
-            if !filepath.exists() {
-                format!("File not found: {filepath:?}")
-            } else {
-                format!("Could not open file: {filepath:?}")
-            }
-        })?;
+        let file = File::open(filepath)
+            .map_err(FastSimError::Io)
+            .with_context(|| {
+                if !filepath.exists() {
+                    format!("File not found: {filepath:?}")
+                } else {
+                    format!("Could not open file: {filepath:?}")
+                }
+            })?;
         Self::from_reader(file, extension, skip_init)
     }
 
@@ -124,10 +128,10 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
             "yaml" | "yml" => self.to_yaml(),
             "json" => self.to_json(),
             "toml" => self.to_toml(),
-            _ => bail!(
+            _ => bail!(FastSimError::Format(format!(
                 "Unsupported format {format:?}, must be one of {:?}",
                 Self::ACCEPTED_STR_FORMATS
-            ),
+            ))),
         }
     }
 
@@ -144,10 +148,10 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
                 "yaml" | "yml" => Self::from_yaml(contents, skip_init)?,
                 "json" => Self::from_json(contents, skip_init)?,
                 "toml" => Self::from_toml(contents, skip_init)?,
-                _ => bail!(
+                _ => bail!(FastSimError::Format(format!(
                     "Unsupported format {format:?}, must be one of {:?}",
                     Self::ACCEPTED_STR_FORMATS
-                ),
+                ))),
             },
         )
     }
@@ -165,19 +169,19 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
         skip_init: bool,
     ) -> anyhow::Result<Self> {
         let mut deserialized: Self = match format.trim_start_matches('.').to_lowercase().as_str() {
-            "yaml" | "yml" => serde_yaml::from_reader(rdr)?,
-            "json" => serde_json::from_reader(rdr)?,
+            "yaml" | "yml" => serde_yaml::from_reader(rdr).map_err(FastSimError::format)?,
+            "json" => serde_json::from_reader(rdr).map_err(FastSimError::format)?,
             "toml" => {
                 let mut buf = String::new();
-                rdr.read_to_string(&mut buf)?;
+                rdr.read_to_string(&mut buf).map_err(FastSimError::Io)?;
                 Self::from_toml(buf, skip_init)?
             }
             #[cfg(feature = "bincode")]
-            "bin" => bincode::deserialize_from(rdr)?,
-            _ => bail!(
+            "bin" => bincode::deserialize_from(rdr).map_err(FastSimError::format)?,
+            _ => bail!(FastSimError::Format(format!(
                 "Unsupported format {format:?}, must be one of {:?}",
                 Self::ACCEPTED_BYTE_FORMATS
-            ),
+            ))),
         };
         if !skip_init {
             deserialized.init()?;
@@ -187,7 +191,7 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
 
     /// Write (serialize) an object to a JSON string
     fn to_json(&self) -> anyhow::Result<String> {
-        Ok(serde_json::to_string(&self)?)
+        Ok(serde_json::to_string(&self).map_err(FastSimError::format)?)
     }
 
     /// Read (deserialize) an object to a JSON string
@@ -197,7 +201,8 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
     /// * `json_str` - JSON-formatted string to deserialize from
     ///
     fn from_json<S: AsRef<str>>(json_str: S, skip_init: bool) -> anyhow::Result<Self> {
-        let mut json_de: Self = serde_json::from_str(json_str.as_ref())?;
+        let mut json_de: Self =
+            serde_json::from_str(json_str.as_ref()).map_err(FastSimError::format)?;
         if !skip_init {
             json_de.init()?;
         }
@@ -206,7 +211,7 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
 
     /// Write (serialize) an object to a YAML string
     fn to_yaml(&self) -> anyhow::Result<String> {
-        Ok(serde_yaml::to_string(&self)?)
+        Ok(serde_yaml::to_string(&self).map_err(FastSimError::format)?)
     }
 
     /// Read (deserialize) an object from a YAML string
@@ -216,7 +221,8 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
     /// * `yaml_str` - YAML-formatted string to deserialize from
     ///
     fn from_yaml<S: AsRef<str>>(yaml_str: S, skip_init: bool) -> anyhow::Result<Self> {
-        let mut yaml_de: Self = serde_yaml::from_str(yaml_str.as_ref())?;
+        let mut yaml_de: Self =
+            serde_yaml::from_str(yaml_str.as_ref()).map_err(FastSimError::format)?;
         if !skip_init {
             yaml_de.init()?;
         }
@@ -224,11 +230,11 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
     }
 
     fn to_toml(&self) -> anyhow::Result<String> {
-        Ok(toml::to_string(&self)?)
+        Ok(toml::to_string(&self).map_err(FastSimError::format)?)
     }
 
     fn from_toml<S: AsRef<str>>(toml_str: S, skip_init: bool) -> anyhow::Result<Self> {
-        let mut toml_de: Self = toml::from_str(toml_str.as_ref())?;
+        let mut toml_de: Self = toml::from_str(toml_str.as_ref()).map_err(FastSimError::format)?;
         if !skip_init {
             toml_de.init()?;
         }
@@ -238,7 +244,7 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
     /// Write (serialize) an object to bincode-encoded bytes
     #[cfg(feature = "bincode")]
     fn to_bincode(&self) -> anyhow::Result<Vec<u8>> {
-        Ok(bincode::serialize(&self)?)
+        Ok(bincode::serialize(&self).map_err(FastSimError::format)?)
     }
 
     /// Read (deserialize) an object from bincode-encoded bytes
@@ -249,7 +255,7 @@ pub trait SerdeAPI: Serialize + for<'a> Deserialize<'a> {
     ///
     #[cfg(feature = "bincode")]
     fn from_bincode(encoded: &[u8], skip_init: bool) -> anyhow::Result<Self> {
-        let mut bincode_de: Self = bincode::deserialize(encoded)?;
+        let mut bincode_de: Self = bincode::deserialize(encoded).map_err(FastSimError::format)?;
         if !skip_init {
             bincode_de.init()?;
         }
@@ -355,6 +361,8 @@ macro_rules! impl_approx_eq_for_strict_eq_types {
 impl_approx_eq_for_strict_eq_types!(
     u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, &str, String
 );
+impl_approx_eq_for_strict_eq_types!(crate::vehicle::PowertrainType, crate::vehicle::FcEffType);
+impl_approx_eq_for_strict_eq_types!(crate::fuel::FuelType, crate::fuel::FuelUnit);
 
 macro_rules! impl_approx_eq_for_floats {
     ($($float_type: ty),*) => {
@@ -0,0 +1,316 @@
+//! Module containing a multi-gear transmission model with a speed- and
+//! load-based shift schedule
+
+use crate::imports::*;
+use crate::params::RAD_PER_SEC_PER_RPM;
+use crate::proc_macros::{add_pyo3_api, ApproxEq, FieldPath};
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+#[add_pyo3_api(
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn default_py() -> Self {
+        Self::default()
+    }
+
+    #[getter]
+    pub fn get_num_gears(&self) -> usize {
+        self.num_gears()
+    }
+
+    #[pyo3(name = "overall_ratio")]
+    pub fn overall_ratio_py(&self, gear: usize) -> anyhow::Result<f64> {
+        self.overall_ratio(gear)
+    }
+
+    #[pyo3(name = "select_gear")]
+    pub fn select_gear_py(&self, prev_gear: usize, out_rad_per_sec: f64, load_frac: f64) -> usize {
+        self.select_gear(prev_gear, out_rad_per_sec, load_frac)
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq, FieldPath)]
+/// Multi-gear transmission with final drive and shift schedule.  Gears are
+/// numbered from 1 (lowest) to `num_gears()`.
+///
+/// When assigned to `RustVehicle::transmission`, the transmission efficiency
+/// at each time step is that of the selected gear rather than `trans_eff`,
+/// and the transmission input (engine and/or motor) speed is calculated.
+///
+/// Shift thresholds are linearly interpolated between their low-load and
+/// full-load values based on the load fraction, i.e. the ratio of
+/// transmission input power to the sum of `fc_max_kw` and `mc_max_kw`.
+pub struct Transmission {
+    /// gear ratios (input speed / output speed), lowest gear first
+    pub gear_ratios: Vec<f64>,
+    /// final drive ratio (input speed / wheel speed)
+    pub final_drive_ratio: f64,
+    /// efficiency of each gear, including the final drive
+    pub gear_effs: Vec<f64>,
+    /// minimum input speed \[rpm\], e.g. engine idle speed, below which the
+    /// clutch or torque converter slips
+    pub min_in_rpm: f64,
+    /// maximum input speed \[rpm\], upshifts are forced above this speed
+    pub max_in_rpm: f64,
+    /// input speed \[rpm\] above which to upshift at zero load
+    pub upshift_rpm_low_load: f64,
+    /// input speed \[rpm\] above which to upshift at full load
+    pub upshift_rpm_full_load: f64,
+    /// input speed \[rpm\] below which to downshift at zero load
+    pub downshift_rpm_low_load: f64,
+    /// input speed \[rpm\] below which to downshift at full load
+    pub downshift_rpm_full_load: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl Default for Transmission {
+    /// Generic 10-speed automated manual transmission for a heavy-duty diesel
+    fn default() -> Self {
+        Self {
+            gear_ratios: vec![12.8, 9.25, 6.76, 4.92, 3.58, 2.61, 1.89, 1.38, 1.0, 0.73],
+            final_drive_ratio: 3.36,
+            gear_effs: vec![0.94, 0.94, 0.95, 0.95, 0.96, 0.96, 0.96, 0.97, 0.98, 0.97],
+            min_in_rpm: 600.0,
+            max_in_rpm: 2_100.0,
+            upshift_rpm_low_load: 1_150.0,
+            upshift_rpm_full_load: 1_700.0,
+            downshift_rpm_low_load: 800.0,
+            downshift_rpm_full_load: 1_100.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl SerdeAPI for Transmission {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            !self.gear_ratios.is_empty(),
+            "{}\n`gear_ratios` must not be empty",
+            format_dbg!(self.gear_ratios.len())
+        );
+        ensure!(
+            self.gear_ratios.iter().all(|r| *r > 0.0)
+                && self.gear_ratios.windows(2).all(|w| w[0] > w[1]),
+            "{}\n`gear_ratios` must be positive and strictly decreasing",
+            format_dbg!(&self.gear_ratios)
+        );
+        ensure!(
+            self.gear_effs.len() == self.gear_ratios.len(),
+            "{}\n`gear_effs` must have the same length as `gear_ratios`",
+            format_dbg!((self.gear_effs.len(), self.gear_ratios.len()))
+        );
+        ensure!(
+            self.gear_effs.iter().all(|eff| *eff > 0.0 && *eff <= 1.0),
+            "{}\n`gear_effs` must be between 0 and 1",
+            format_dbg!(&self.gear_effs)
+        );
+        ensure!(
+            self.final_drive_ratio > 0.0,
+            "{}",
+            format_dbg!(self.final_drive_ratio)
+        );
+        ensure!(
+            self.min_in_rpm >= 0.0 && self.min_in_rpm < self.max_in_rpm,
+            "{}",
+            format_dbg!((self.min_in_rpm, self.max_in_rpm))
+        );
+        ensure!(
+            self.downshift_rpm_low_load < self.upshift_rpm_low_load
+                && self.downshift_rpm_full_load < self.upshift_rpm_full_load,
+            "{}\ndownshift speeds must be less than upshift speeds",
+            format_dbg!((
+                self.downshift_rpm_low_load,
+                self.upshift_rpm_low_load,
+                self.downshift_rpm_full_load,
+                self.upshift_rpm_full_load
+            ))
+        );
+        ensure!(
+            self.upshift_rpm_low_load <= self.max_in_rpm
+                && self.upshift_rpm_full_load <= self.max_in_rpm,
+            "{}\nupshift speeds must not exceed `max_in_rpm`",
+            format_dbg!((
+                self.upshift_rpm_low_load,
+                self.upshift_rpm_full_load,
+                self.max_in_rpm
+            ))
+        );
+        Ok(())
+    }
+}
+
+impl Transmission {
+    pub fn num_gears(&self) -> usize {
+        self.gear_ratios.len()
+    }
+
+    /// Overall ratio of input speed to wheel speed in `gear`, including the
+    /// final drive
+    pub fn overall_ratio(&self, gear: usize) -> anyhow::Result<f64> {
+        ensure!(
+            (1..=self.num_gears()).contains(&gear),
+            "{}\n`gear` must be between 1 and {}",
+            format_dbg!(gear),
+            self.num_gears()
+        );
+        Ok(self.gear_ratios[gear - 1] * self.final_drive_ratio)
+    }
+
+    /// Efficiency in `gear`, including the final drive
+    pub fn eff(&self, gear: usize) -> f64 {
+        self.gear_effs[gear.clamp(1, self.num_gears()) - 1]
+    }
+
+    /// Input speed \[rad/s\] in `gear` for wheel speed `out_rad_per_sec`,
+    /// without enforcing `min_in_rpm`
+    fn unclutched_in_rad_per_sec(&self, gear: usize, out_rad_per_sec: f64) -> f64 {
+        out_rad_per_sec * self.gear_ratios[gear - 1] * self.final_drive_ratio
+    }
+
+    /// Input speed \[rad/s\] in `gear` for wheel speed `out_rad_per_sec`,
+    /// held at `min_in_rpm` while the clutch slips
+    pub fn in_rad_per_sec(&self, gear: usize, out_rad_per_sec: f64) -> f64 {
+        let gear = gear.clamp(1, self.num_gears());
+        self.unclutched_in_rad_per_sec(gear, out_rad_per_sec)
+            .max(self.min_in_rpm * RAD_PER_SEC_PER_RPM)
+    }
+
+    /// Upshift and downshift input speeds \[rad/s\] at `load_frac`
+    pub fn shift_rad_per_sec(&self, load_frac: f64) -> (f64, f64) {
+        let load_frac = load_frac.clamp(0.0, 1.0);
+        let up = self.upshift_rpm_low_load
+            + load_frac * (self.upshift_rpm_full_load - self.upshift_rpm_low_load);
+        let down = self.downshift_rpm_low_load
+            + load_frac * (self.downshift_rpm_full_load - self.downshift_rpm_low_load);
+        (up * RAD_PER_SEC_PER_RPM, down * RAD_PER_SEC_PER_RPM)
+    }
+
+    /// Selects gear given the previous gear, wheel speed `out_rad_per_sec`,
+    /// and load fraction.  Upshifts only if the next gear would not
+    /// immediately trigger a downshift, and upshifts regardless of load if
+    /// `max_in_rpm` would otherwise be exceeded.
+    pub fn select_gear(&self, prev_gear: usize, out_rad_per_sec: f64, load_frac: f64) -> usize {
+        let (up, down) = self.shift_rad_per_sec(load_frac);
+        let max_in = self.max_in_rpm * RAD_PER_SEC_PER_RPM;
+        let mut gear = prev_gear.clamp(1, self.num_gears());
+        while gear < self.num_gears() {
+            let in_speed = self.unclutched_in_rad_per_sec(gear, out_rad_per_sec);
+            let next_in_speed = self.unclutched_in_rad_per_sec(gear + 1, out_rad_per_sec);
+            if in_speed > max_in || (in_speed > up && next_in_speed >= down) {
+                gear += 1;
+            } else {
+                break;
+            }
+        }
+        while gear > 1 {
+            let in_speed = self.unclutched_in_rad_per_sec(gear, out_rad_per_sec);
+            let prev_in_speed = self.unclutched_in_rad_per_sec(gear - 1, out_rad_per_sec);
+            if in_speed < down && prev_in_speed <= max_in {
+                gear -= 1;
+            } else {
+                break;
+            }
+        }
+        gear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::RustCycle;
+    use crate::simdrive::RustSimDrive;
+    use crate::vehicle::RustVehicle;
+
+    #[test]
+    fn test_transmission_validation() {
+        let mut trans = Transmission::default();
+        assert!(trans.init().is_ok());
+        trans.gear_effs.pop();
+        assert!(trans.init().is_err());
+        let mut trans = Transmission {
+            gear_ratios: vec![3.0, 3.5],
+            gear_effs: vec![0.95, 0.95],
+            ..Default::default()
+        };
+        assert!(trans.init().is_err());
+        trans.gear_ratios = vec![3.5, 3.0];
+        assert!(trans.init().is_ok());
+    }
+
+    #[test]
+    fn test_select_gear() {
+        let trans = Transmission::default();
+        // launch from standstill in first gear
+        assert_eq!(trans.select_gear(1, 0.0, 0.5), 1);
+        // highway cruise at ~100 km/h ends up in top gear from any gear
+        let whl_rad_per_sec = 27.8 / 0.506;
+        for prev_gear in 1..=trans.num_gears() {
+            assert_eq!(
+                trans.select_gear(prev_gear, whl_rad_per_sec, 0.3),
+                trans.num_gears()
+            );
+        }
+        // hysteresis: at intermediate speed, the previous gear is held
+        let gear = 5;
+        let (up, down) = trans.shift_rad_per_sec(0.0);
+        let whl_rad_per_sec = (up + down) / 2.0 / trans.overall_ratio(gear).unwrap();
+        assert_eq!(trans.select_gear(gear, whl_rad_per_sec, 0.0), gear);
+        // higher load holds lower gears to higher speeds
+        let whl_rad_per_sec = 5.0;
+        assert!(
+            trans.select_gear(1, whl_rad_per_sec, 1.0) < trans.select_gear(1, whl_rad_per_sec, 0.0)
+        );
+        // input speed never exceeds `max_in_rpm` if a higher gear is available
+        for gear in 1..=trans.num_gears() {
+            let whl_rad_per_sec = 0.99 * trans.max_in_rpm * RAD_PER_SEC_PER_RPM
+                / trans.overall_ratio(trans.num_gears()).unwrap();
+            let new_gear = trans.select_gear(gear, whl_rad_per_sec, 1.0);
+            assert!(
+                trans.in_rad_per_sec(new_gear, whl_rad_per_sec)
+                    <= trans.max_in_rpm * RAD_PER_SEC_PER_RPM
+            );
+        }
+    }
+
+    #[test]
+    fn test_sim_drive_with_transmission() {
+        let mut veh = RustVehicle::from_file(
+            resources_path().join("vehdb/Regional_Delivery_Class_8_Truck.yaml"),
+            false,
+        )
+        .unwrap();
+        let cyc = RustCycle::from_resource("HHDDTCruiseSmooth.csv", false).unwrap();
+
+        let mut sd_base = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_base.sim_drive(None, None).unwrap();
+        assert!(sd_base.trans_gear.iter().all(|g| *g == 0));
+
+        veh.transmission = Some(Transmission::default());
+        let mut sd = RustSimDrive::new(cyc, veh.clone());
+        sd.sim_drive(None, None).unwrap();
+
+        // all gears are used on a cruise cycle with a launch
+        assert_eq!(sd.trans_gear[1], 1);
+        assert_eq!(
+            *sd.trans_gear.iter().max().unwrap() as usize,
+            veh.transmission.as_ref().unwrap().num_gears()
+        );
+        // input speed is within bounds while moving
+        let trans = veh.transmission.as_ref().unwrap();
+        for i in 1..sd.cyc.len() {
+            assert!(sd.trans_in_rad_per_sec[i] >= trans.min_in_rpm * RAD_PER_SEC_PER_RPM - 1e-9);
+            assert!(
+                sd.trans_in_rad_per_sec[i] <= trans.max_in_rpm * RAD_PER_SEC_PER_RPM + 1e-9,
+                "{}",
+                format_dbg!((i, sd.trans_gear[i], sd.trans_in_rad_per_sec[i]))
+            );
+            assert_eq!(sd.trans_eff_ach[i], trans.eff(sd.trans_gear[i] as usize));
+        }
+        // per-gear efficiencies are higher than the vehicle's lumped
+        // `trans_eff`, so less fuel is used
+        assert!(sd.fuel_kj < sd_base.fuel_kj);
+        assert!(sd.trans_kj < sd_base.trans_kj);
+    }
+}
@@ -218,6 +218,19 @@ pub fn interpolate_vectors(
     yl + dydx * (x - xl)
 }
 
+/// Linear interpolation of `y_data` at `x`, holding the end values outside
+/// of `x_data`, which must be strictly increasing with at least 2 elements.
+/// Unlike [interpolate], this does not allocate, for lookups made at every
+/// time step.
+pub fn interpolate_clamped(x: f64, x_data: &[f64], y_data: &[f64]) -> f64 {
+    let x = x.clamp(x_data[0], x_data[x_data.len() - 1]);
+    let i = x_data
+        .partition_point(|xi| *xi <= x)
+        .clamp(1, x_data.len() - 1)
+        - 1;
+    y_data[i] + (x - x_data[i]) / (x_data[i + 1] - x_data[i]) * (y_data[i + 1] - y_data[i])
+}
+
 /// Generate all permutations of indices for a given *N*-dimensional array shape
 ///
 /// # Arguments
@@ -811,6 +824,8 @@ mod tests {
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
         let y_lookup = interpolate_vectors(&x, &xs.to_vec(), &ys.to_vec(), false);
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
+        let y_lookup = interpolate_clamped(x, xs.as_slice().unwrap(), ys.as_slice().unwrap());
+        assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
     }
 
     #[test]
@@ -823,6 +838,8 @@ mod tests {
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
         let y_lookup = interpolate_vectors(&x, &xs.to_vec(), &ys.to_vec(), false);
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
+        let y_lookup = interpolate_clamped(x, xs.as_slice().unwrap(), ys.as_slice().unwrap());
+        assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
     }
 
     #[test]
@@ -835,6 +852,8 @@ mod tests {
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
         let y_lookup = interpolate_vectors(&x, &xs.to_vec(), &ys.to_vec(), false);
         assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
+        let y_lookup = interpolate_clamped(x, xs.as_slice().unwrap(), ys.as_slice().unwrap());
+        assert!((expected_y_lookup - y_lookup).abs() < 1e-6);
     }
 
     #[test]
@@ -859,6 +878,8 @@ mod tests {
         assert_eq!(expected_y_lookup, y_lookup);
         let y_lookup = interpolate_vectors(&x, &xs.to_vec(), &ys.to_vec(), false);
         assert_eq!(expected_y_lookup, y_lookup);
+        let y_lookup = interpolate_clamped(x, xs.as_slice().unwrap(), ys.as_slice().unwrap());
+        assert_eq!(expected_y_lookup, y_lookup);
     }
 
     #[test]
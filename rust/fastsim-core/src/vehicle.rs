@@ -2,41 +2,143 @@
 
 use crate::calibration::skewness_shift;
 // local
+use crate::emissions::EmissionsModel;
+use crate::ess_circuit::EssCircuitModel;
+use crate::fuel::{Fuel, SecondaryFuel};
 use crate::imports::*;
 use crate::params::*;
-use crate::proc_macros::{add_pyo3_api, doc_field, ApproxEq};
+use crate::proc_macros::{add_pyo3_api, doc_field, ApproxEq, FieldPath};
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
+use crate::speed_torque_map::SpeedTorqueEffMap;
+use crate::transmission::Transmission;
+#[cfg(feature = "pyo3")]
+use crate::utils;
+#[cfg(feature = "pyo3")]
+use crate::vehicle_validation::ValidationReport;
 
-#[cfg(feature = "validation")]
-use lazy_static::lazy_static;
-#[cfg(feature = "validation")]
-use regex::Regex;
 #[cfg(feature = "validation")]
 use validator::Validate;
 
-// veh_pt_type options
-pub const CONV: &str = "Conv";
-pub const HEV: &str = "HEV";
-pub const PHEV: &str = "PHEV";
-pub const BEV: &str = "BEV";
-pub const VEH_PT_TYPES: [&str; 4] = [CONV, HEV, PHEV, BEV];
-#[cfg(feature = "validation")]
-lazy_static! {
-    static ref VEH_PT_TYPE_OPTIONS_REGEX: Regex = Regex::new("Conv|HEV|PHEV|BEV").unwrap();
+/// Vehicle powertrain type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum PowertrainType {
+    /// Conventional vehicle with fuel converter only
+    #[default]
+    Conv,
+    /// Hybrid electric vehicle, charge-sustaining
+    HEV,
+    /// Plug-in hybrid electric vehicle, charge-depleting then charge-sustaining
+    PHEV,
+    /// Battery electric vehicle
+    BEV,
 }
 
-// fc_eff_type options
-pub const SI: &str = "SI";
-pub const ATKINSON: &str = "Atkinson";
-pub const DIESEL: &str = "Diesel";
-pub const H2FC: &str = "H2FC";
-pub const HD_DIESEL: &str = "HD_Diesel";
-pub const FC_EFF_TYPES: [&str; 5] = [SI, ATKINSON, DIESEL, H2FC, HD_DIESEL];
-#[cfg(feature = "validation")]
-lazy_static! {
-    static ref FC_EFF_TYPE_OPTIONS_REGEX: Regex =
-        Regex::new("SI|Atkinson|Diesel|H2FC|HD_Diesel").unwrap();
+impl PowertrainType {
+    /// All valid powertrain types
+    pub const ALL: [Self; 4] = [Self::Conv, Self::HEV, Self::PHEV, Self::BEV];
+
+    /// String representation used in vehicle files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Conv => "Conv",
+            Self::HEV => "HEV",
+            Self::PHEV => "PHEV",
+            Self::BEV => "BEV",
+        }
+    }
+
+    /// Returns true if vehicle has an electric traction drive, i.e. is not [Conv](Self::Conv)
+    pub fn is_electrified(&self) -> bool {
+        *self != Self::Conv
+    }
+}
+
+impl std::fmt::Display for PowertrainType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for PowertrainType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|pt| pt.as_str() == s)
+            .with_context(|| {
+                format!(
+                    "invalid powertrain type `{s}`, must be one of {:?}",
+                    Self::ALL.map(|pt| pt.as_str())
+                )
+            })
+    }
+}
+
+/// Fuel converter efficiency type, used for calculating
+/// [fc_eff_map](RustVehicle::fc_eff_map) and in other calculations if [H2FC](FcEffType::H2FC)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum FcEffType {
+    /// Spark ignition
+    #[default]
+    SI,
+    /// Atkinson cycle
+    Atkinson,
+    /// Compression ignition
+    Diesel,
+    /// Hydrogen fuel cell
+    H2FC,
+    /// Heavy-duty compression ignition
+    #[serde(rename = "HD_Diesel", alias = "HDDiesel")]
+    HDDiesel,
+}
+
+impl FcEffType {
+    /// All valid fuel converter efficiency types
+    pub const ALL: [Self; 5] = [
+        Self::SI,
+        Self::Atkinson,
+        Self::Diesel,
+        Self::H2FC,
+        Self::HDDiesel,
+    ];
+
+    /// String representation used in vehicle files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SI => "SI",
+            Self::Atkinson => "Atkinson",
+            Self::Diesel => "Diesel",
+            Self::H2FC => "H2FC",
+            Self::HDDiesel => "HD_Diesel",
+        }
+    }
+}
+
+impl std::fmt::Display for FcEffType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for FcEffType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "HDDiesel" => Ok(Self::HDDiesel),
+            _ => Self::ALL
+                .into_iter()
+                .find(|fc_eff_type| fc_eff_type.as_str() == s)
+                .with_context(|| {
+                    format!(
+                        "invalid fuel converter efficiency type `{s}`, must be one of {:?}",
+                        Self::ALL.map(|fc_eff_type| fc_eff_type.as_str())
+                    )
+                }),
+        }
+    }
 }
 
 #[doc_field]
@@ -93,9 +195,110 @@ lazy_static! {
         self.fc_peak_eff()
     }
 
+    #[getter]
+    pub fn get_veh_pt_type(&self) -> String {
+        self.veh_pt_type.to_string()
+    }
+
+    #[setter]
+    pub fn set_veh_pt_type(&mut self, new_value: &str) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, veh_pt_type, new_value.parse()?)
+    }
+
+    #[getter]
+    pub fn get_fc_eff_type(&self) -> String {
+        self.fc_eff_type.to_string()
+    }
+
+    #[setter]
+    pub fn set_fc_eff_type(&mut self, new_value: &str) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, fc_eff_type, new_value.parse()?)
+    }
+
+    #[getter]
+    pub fn get_ess_circuit(&self) -> Option<EssCircuitModel> {
+        self.ess_circuit.clone()
+    }
+
+    #[setter]
+    pub fn set_ess_circuit(&mut self, new_value: Option<EssCircuitModel>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, ess_circuit, new_value)
+    }
+
+    #[getter]
+    pub fn get_transmission(&self) -> Option<Transmission> {
+        self.transmission.clone()
+    }
+
+    #[setter]
+    pub fn set_transmission(&mut self, new_value: Option<Transmission>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, transmission, new_value)
+    }
+
+    #[getter]
+    pub fn get_fc_eff_map_2d(&self) -> Option<SpeedTorqueEffMap> {
+        self.fc_eff_map_2d.clone()
+    }
+
+    #[setter]
+    pub fn set_fc_eff_map_2d(&mut self, new_value: Option<SpeedTorqueEffMap>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, fc_eff_map_2d, new_value)
+    }
+
+    #[getter]
+    pub fn get_mc_eff_map_2d(&self) -> Option<SpeedTorqueEffMap> {
+        self.mc_eff_map_2d.clone()
+    }
+
+    #[setter]
+    pub fn set_mc_eff_map_2d(&mut self, new_value: Option<SpeedTorqueEffMap>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, mc_eff_map_2d, new_value)
+    }
+
+    #[getter]
+    pub fn get_emissions(&self) -> Option<EmissionsModel> {
+        self.emissions.clone()
+    }
+
+    #[setter]
+    pub fn set_emissions(&mut self, new_value: Option<EmissionsModel>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, emissions, new_value)
+    }
+
+    #[getter]
+    pub fn get_fuel(&self) -> Option<Fuel> {
+        self.fuel.clone()
+    }
+
+    #[setter]
+    pub fn set_fuel(&mut self, new_value: Option<Fuel>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, fuel, new_value)
+    }
+
+    #[getter]
+    pub fn get_secondary_fuel(&self) -> Option<SecondaryFuel> {
+        self.secondary_fuel.clone()
+    }
+
+    #[setter]
+    pub fn set_secondary_fuel(&mut self, new_value: Option<SecondaryFuel>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, secondary_fuel, new_value)
+    }
+
+    #[getter]
+    pub fn get_fuel_props(&self) -> Fuel {
+        self.fuel_props()
+    }
+
+    #[pyo3(name = "validate")]
+    /// Checks vehicle inputs and returns a `ValidationReport` of every problem found
+    pub fn validate_py(&self) -> ValidationReport {
+        self.validate()
+    }
+
     #[pyo3(name = "set_derived")]
-    pub fn set_derived_py(&mut self) {
-        self.set_derived().unwrap()
+    pub fn set_derived_py(&mut self) -> PyResult<()> {
+        self.set_derived().map_err(to_pyerr)
     }
 
     /// An identify function to allow RustVehicle to be used as a python vehicle and respond to this method
@@ -133,7 +336,7 @@ lazy_static! {
     }
 )]
 #[cfg_attr(feature = "validation", derive(Validate))]
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ApproxEq, FieldPath)]
 /// Struct containing vehicle attributes
 /// # Python Examples
 /// ```python
@@ -161,17 +364,11 @@ pub struct RustVehicle {
     #[serde(alias = "vehModelYear")]
     #[doc_field(skip_doc)]
     pub veh_year: u32,
-    /// Vehicle powertrain type, one of \[[CONV](CONV), [HEV](HEV), [PHEV](PHEV), [BEV](BEV)\]
+    /// Vehicle powertrain type, see [PowertrainType](PowertrainType)
     #[serde(alias = "vehPtType")]
-    #[cfg_attr(
-        feature = "validation",
-        validate(regex(
-            path = "VEH_PT_TYPE_OPTIONS_REGEX",
-            message = "must be one of [\"Conv\", \"HEV\", \"PHEV\", \"BEV\"]"
-        ))
-    )]
+    #[api(skip_get, skip_set)]
     #[doc_field(skip_doc)]
-    pub veh_pt_type: String,
+    pub veh_pt_type: PowertrainType,
     /// Aerodynamic drag coefficient
     #[serde(alias = "dragCoef")]
     #[cfg_attr(feature = "validation", validate(range(min = 0)))]
@@ -234,17 +431,11 @@ pub struct RustVehicle {
     /// Fuel converter efficiency map
     #[serde(default)]
     pub fc_eff_map: Array1<f64>,
-    /// Fuel converter efficiency type, one of \[[SI](SI), [ATKINSON](ATKINSON), [DIESEL](DIESEL), [H2FC](H2FC), [HD_DIESEL](HD_DIESEL)\]
+    /// Fuel converter efficiency type, see [FcEffType](FcEffType)
     /// Used for calculating [fc_eff_map](RustVehicle::fc_eff_map), and other calculations if H2FC
     #[serde(alias = "fcEffType")]
-    #[cfg_attr(
-        feature = "validation",
-        validate(regex(
-            path = "FC_EFF_TYPE_OPTIONS_REGEX",
-            message = "must be one of [\"SI\", \"Atkinson\", \"Diesel\", \"H2FC\", \"HD_Diesel\"]"
-        ))
-    )]
-    pub fc_eff_type: String,
+    #[api(skip_get, skip_set)]
+    pub fc_eff_type: FcEffType,
     /// Fuel converter time to peak power, $s$
     #[serde(alias = "fuelConvSecsToPeakPwr")]
     #[cfg_attr(feature = "validation", validate(range(min = 0)))]
@@ -588,6 +779,41 @@ pub struct RustVehicle {
     #[serde(skip)]
     #[cfg_attr(feature = "validation", validate(range(min = 0, max = 1)))]
     pub mc_peak_eff_override: Option<f64>,
+    /// Optional equivalent-circuit traction battery model, replaces
+    /// `ess_round_trip_eff` losses and `ess_max_kw` power limits if provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub ess_circuit: Option<EssCircuitModel>,
+    /// Optional multi-gear transmission, replaces `trans_eff` with per-gear
+    /// efficiency and enables calculation of transmission input speed if
+    /// provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub transmission: Option<Transmission>,
+    /// Optional fuel converter efficiency map over speed and torque, used
+    /// instead of `fc_eff_map` if provided.  Requires `transmission`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub fc_eff_map_2d: Option<SpeedTorqueEffMap>,
+    /// Optional electric motor efficiency map over speed and torque,
+    /// including power electronics, used instead of `mc_eff_map` if
+    /// provided.  Requires `transmission`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub mc_eff_map_2d: Option<SpeedTorqueEffMap>,
+    /// Optional tailpipe emissions model, enables calculation of CO2 and
+    /// criteria pollutant emissions if provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub emissions: Option<EmissionsModel>,
+    /// Optional fuel properties, gasoline per `props` if not provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub fuel: Option<Fuel>,
+    /// Optional second fuel burned by the fuel converter alongside `fuel`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub secondary_fuel: Option<SecondaryFuel>,
     #[serde(skip)]
     #[doc(hidden)]
     #[doc_field(skip_doc)]
@@ -678,6 +904,13 @@ impl RustVehicle {
         arrmax(&self.fc_eff_array)
     }
 
+    /// Returns `fuel` if provided, otherwise gasoline per `props`
+    pub fn fuel_props(&self) -> Fuel {
+        self.fuel
+            .clone()
+            .unwrap_or_else(|| Fuel::from_props(&self.props))
+    }
+
     pub fn set_mc_peak_eff(&mut self, new_peak: f64) {
         let mc_max_eff = self.mc_eff_array.max().unwrap().clone();
         self.mc_eff_array *= new_peak / mc_max_eff;
@@ -822,44 +1055,44 @@ impl RustVehicle {
     ///     - `veh_kg`
     ///     - `max_trac_mps2`
     pub fn set_derived(&mut self) -> anyhow::Result<()> {
-        // Vehicle input validation
+        // Vehicle input validation.  The report includes the `validator` range
+        // checks so that every problem is listed at once, and their
+        // `ValidationErrors` are kept as the source of the error.
+        let report = self.validate();
+        #[cfg(feature = "logging")]
+        for issue in report.warnings() {
+            log::warn!("{issue}");
+        }
         #[cfg(feature = "validation")]
-        self.validate()?;
-
-        if self.scenario_name != "Template Vehicle for setting up data types" {
-            if self.veh_pt_type == BEV {
-                assert!(
-                    self.fs_max_kw == 0.0,
-                    "max_fuel_stor_kw must be zero for provided BEV powertrain type in {}",
-                    self.scenario_name
-                );
-                assert!(
-                    self.fs_kwh == 0.0,
-                    "fuel_stor_kwh must be zero for provided BEV powertrain type in {}",
-                    self.scenario_name
-                );
-                assert!(
-                    self.fc_max_kw == 0.0,
-                    "max_fuel_conv_kw must be zero for provided BEV powertrain type in {}",
-                    self.scenario_name
-                );
-            } else if (self.veh_pt_type == CONV) && !self.stop_start {
-                assert!(
-                    self.mc_max_kw == 0.0,
-                    "max_mc_kw must be zero for provided Conv powertrain type in {}",
-                    self.scenario_name
-                );
-                assert!(
-                    self.ess_max_kw == 0.0,
-                    "max_ess_kw must be zero for provided Conv powertrain type in {}",
-                    self.scenario_name
-                );
-                assert!(
-                    self.ess_max_kwh == 0.0,
-                    "max_ess_kwh must be zero for provided Conv powertrain type in {}",
-                    self.scenario_name
-                );
-            }
+        if let Err(errs) = Validate::validate(self) {
+            let report_err = report
+                .to_error()
+                .unwrap_or_else(|| FastSimError::InvalidVehicle("input validation failed".into()));
+            return Err(anyhow::Error::new(errs).context(report_err));
+        }
+        report.to_result()?;
+        if let Some(ess_circuit) = self.ess_circuit.as_mut() {
+            ess_circuit.init().map_err(FastSimError::invalid_vehicle)?;
+        }
+        if let Some(transmission) = self.transmission.as_mut() {
+            transmission.init().map_err(FastSimError::invalid_vehicle)?;
+        }
+        for eff_map_2d in [self.fc_eff_map_2d.as_mut(), self.mc_eff_map_2d.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            eff_map_2d.init().map_err(FastSimError::invalid_vehicle)?;
+        }
+        if let Some(emissions) = self.emissions.as_mut() {
+            emissions.init().map_err(FastSimError::invalid_vehicle)?;
+        }
+        if let Some(fuel) = self.fuel.as_mut() {
+            fuel.init().map_err(FastSimError::invalid_vehicle)?;
+        }
+        if let Some(secondary_fuel) = self.secondary_fuel.as_mut() {
+            secondary_fuel
+                .init()
+                .map_err(FastSimError::invalid_vehicle)?;
         }
         // ### Build roadway power lookup table
         // self.max_roadway_chg_kw = Array1::from_vec(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
@@ -970,15 +1203,21 @@ impl RustVehicle {
         // TODO: this could perhaps be done in the input validators
         ensure!(
             arrmin(&self.fc_eff_array) >= 0.0,
-            "minimum FC efficiency < 0 is not allowed"
+            FastSimError::InvalidVehicle("minimum FC efficiency < 0 is not allowed".into())
+        );
+        ensure!(
+            self.fc_peak_eff() < 1.0,
+            FastSimError::InvalidVehicle("fc_peak_eff >= 1 is not allowed".into())
         );
-        ensure!(self.fc_peak_eff() < 1.0, "fc_peak_eff >= 1 is not allowed");
         if !self.no_elec_sys {
             ensure!(
                 arrmin(&self.mc_full_eff_array) >= 0.0,
-                "minimum MC efficiency < 0 is not allowed"
+                FastSimError::InvalidVehicle("minimum MC efficiency < 0 is not allowed".into())
+            );
+            ensure!(
+                self.mc_peak_eff() < 1.0,
+                FastSimError::InvalidVehicle("mc_peak_eff >= 1 is not allowed".into())
             );
-            ensure!(self.mc_peak_eff() < 1.0, "mc_peak_eff >= 1 is not allowed");
         }
 
         self.set_veh_mass();
@@ -995,7 +1234,7 @@ impl RustVehicle {
             scenario_name: String::from("2016 FORD Escape 4cyl 2WD"),
             selection: 5,
             veh_year: 2016,
-            veh_pt_type: String::from("Conv"),
+            veh_pt_type: PowertrainType::Conv,
             drag_coef: 0.355,
             frontal_area_m2: 3.066,
             glider_kg: 1359.166,
@@ -1017,7 +1256,7 @@ impl RustVehicle {
                 0.1, 0.12, 0.16, 0.22, 0.28, 0.33, 0.35, 0.36, 0.35, 0.34, 0.32, 0.3,
             ],
             fc_peak_eff_override: Default::default(),
-            fc_eff_type: String::from("SI"),
+            fc_eff_type: FcEffType::SI,
             fc_sec_to_peak_pwr: 6.0,
             fc_base_kg: 61.0,
             fc_kw_per_kg: 2.13,
@@ -1025,6 +1264,13 @@ impl RustVehicle {
             idle_fc_kw: 2.5,
             mc_max_kw: 0.0,
             mc_peak_eff_override: Default::default(),
+            ess_circuit: Default::default(),
+            transmission: Default::default(),
+            fc_eff_map_2d: Default::default(),
+            mc_eff_map_2d: Default::default(),
+            emissions: Default::default(),
+            fuel: Default::default(),
+            secondary_fuel: Default::default(),
             mc_pwr_out_perc: array![0.0, 0.02, 0.04, 0.06, 0.08, 0.1, 0.2, 0.4, 0.6, 0.8, 1.0],
             mc_eff_map: array![0.12, 0.16, 0.21, 0.29, 0.35, 0.42, 0.75, 0.92, 0.93, 0.93, 0.92,],
             mc_sec_to_peak_pwr: 4.0,
@@ -1168,6 +1414,13 @@ impl RustVehicle {
             ess_to_fuel_ok_error_doc: Default::default(),
             fc_peak_eff_override_doc: Default::default(),
             mc_peak_eff_override_doc: Default::default(),
+            ess_circuit_doc: Default::default(),
+            transmission_doc: Default::default(),
+            fc_eff_map_2d_doc: Default::default(),
+            mc_eff_map_2d_doc: Default::default(),
+            emissions_doc: Default::default(),
+            fuel_doc: Default::default(),
+            secondary_fuel_doc: Default::default(),
         };
         v.set_derived().unwrap();
         v
@@ -1348,7 +1601,7 @@ mod tests {
         let scenario_name = String::from("2016 FORD Escape 4cyl 2WD");
         let selection = 5;
         let veh_year = 2016;
-        let veh_pt_type = String::from("whoops"); // bad input
+        let veh_pt_type = PowertrainType::Conv;
         let drag_coef = 0.355;
         let frontal_area_m2 = 3.066;
         let glider_kg = -50.0; // bad input
@@ -1366,7 +1619,7 @@ mod tests {
         let fc_pwr_out_perc = vec![
             0.0, 0.005, 0.015, 0.04, 0.06, 0.1, 0.14, 0.2, 0.4, 0.6, 0.8, 1.0,
         ];
-        let fc_eff_type = String::from("SI");
+        let fc_eff_type = FcEffType::SI;
         let fc_sec_to_peak_pwr = 6.0;
         let fc_base_kg = 61.0;
         let fc_kw_per_kg = 2.13;
@@ -1513,7 +1766,7 @@ mod tests {
             scenario_name,
             selection,
             veh_year,
-            veh_pt_type, // bad input
+            veh_pt_type,
             drag_coef,
             frontal_area_m2,
             glider_kg, // bad input
@@ -1606,7 +1859,6 @@ mod tests {
 
         // hard-coded fields where bad inputs were provided above
         let bad_fields = [
-            "veh_pt_type",
             "glider_kg",
             "fc_max_kw",
             "min_soc",
@@ -1627,4 +1879,37 @@ mod tests {
             .all(|key| bad_fields.contains(key)));
         assert!(validation_errs_hashmap.len() == bad_fields.len());
     }
+
+    #[test]
+    fn test_powertrain_type_parsing() {
+        let mut veh = RustVehicle::mock_vehicle();
+        veh.veh_pt_type = PowertrainType::HEV;
+        veh.fc_eff_type = FcEffType::HDDiesel;
+        let veh_yaml = veh.to_yaml().unwrap();
+        assert!(veh_yaml.contains("veh_pt_type: HEV"));
+        assert!(veh_yaml.contains("fc_eff_type: HD_Diesel"));
+        let veh_rt = RustVehicle::from_yaml(&veh_yaml, false).unwrap();
+        assert_eq!(veh_rt.veh_pt_type, PowertrainType::HEV);
+        assert_eq!(veh_rt.fc_eff_type, FcEffType::HDDiesel);
+
+        assert_eq!(
+            "PHEV".parse::<PowertrainType>().unwrap(),
+            PowertrainType::PHEV
+        );
+        assert_eq!(
+            "HDDiesel".parse::<FcEffType>().unwrap(),
+            FcEffType::HDDiesel
+        );
+        let err_msg = "whoops".parse::<PowertrainType>().unwrap_err().to_string();
+        assert!(err_msg.contains("must be one of"), "{err_msg}");
+
+        // misspelled types must be rejected at deserialization
+        let bad_yaml = veh_yaml.replace("veh_pt_type: HEV", "veh_pt_type: whoops");
+        let err_msg = RustVehicle::from_yaml(&bad_yaml, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err_msg.contains("whoops"), "{err_msg}");
+        let bad_yaml = veh_yaml.replace("fc_eff_type: HD_Diesel", "fc_eff_type: Desiel");
+        assert!(RustVehicle::from_yaml(&bad_yaml, false).is_err());
+    }
 }
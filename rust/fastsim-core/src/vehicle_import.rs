@@ -9,10 +9,11 @@ use std::io::Read;
 use std::path::PathBuf;
 use zip::ZipArchive;
 
+use crate::fuel::{Fuel, FuelType, FuelUnit};
 use crate::imports::*;
 #[cfg(feature = "pyo3")]
 use crate::pyo3imports::*;
-use crate::vehicle::RustVehicle;
+use crate::vehicle::{FcEffType, PowertrainType, RustVehicle};
 use crate::vehicle_utils::abc_to_drag_coeffs;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -875,15 +876,15 @@ fn try_make_single_vehicle(
         return None;
     }
     let veh_pt_type = match fe_gov_data.alt_veh_type.as_str() {
-        "Hybrid" => crate::vehicle::HEV,
-        "Plug-in Hybrid" => crate::vehicle::PHEV,
-        "EV" => crate::vehicle::BEV,
-        _ => crate::vehicle::CONV,
+        "Hybrid" => PowertrainType::HEV,
+        "Plug-in Hybrid" => PowertrainType::PHEV,
+        "EV" => PowertrainType::BEV,
+        _ => PowertrainType::Conv,
     };
 
     let fs_max_kw: f64;
     let fc_max_kw: f64;
-    let fc_eff_type: String;
+    let fc_eff_type: FcEffType;
     let fc_eff_map: Array1<f64>;
     let mc_max_kw: f64;
     let min_soc: f64;
@@ -900,11 +901,19 @@ fn try_make_single_vehicle(
 
     let ref_veh = RustVehicle::default();
 
-    if veh_pt_type == crate::vehicle::CONV {
+    let fuel = FuelType::from_fueleconomy_gov(&fe_gov_data.fuel_type).map(Fuel::new);
+    // tank volume is in native gallons for liquid fuels and assumed to be in
+    // gallons of gasoline equivalent otherwise
+    let kwh_per_tank_gal = match &fuel {
+        Some(fuel) if fuel.native_unit == FuelUnit::Gal => fuel.kwh_per_native_unit(),
+        _ => ref_veh.props.kwh_per_gge,
+    };
+
+    if veh_pt_type == PowertrainType::Conv {
         fs_max_kw = 2000.0;
-        fs_kwh = other_inputs.fuel_tank_gal * ref_veh.props.kwh_per_gge;
+        fs_kwh = other_inputs.fuel_tank_gal * kwh_per_tank_gal;
         fc_max_kw = epa_data.eng_pwr_hp as f64 / HP_PER_KW;
-        fc_eff_type = String::from(crate::vehicle::SI);
+        fc_eff_type = FcEffType::SI;
         fc_eff_map = Array::from_vec(vec![
             0.1, 0.12, 0.16, 0.22, 0.28, 0.33, 0.35, 0.36, 0.35, 0.34, 0.32, 0.3,
         ]);
@@ -919,13 +928,13 @@ fn try_make_single_vehicle(
         val_range_miles = 0.0;
         ess_max_kw = 0.0;
         ess_max_kwh = 0.0;
-    } else if veh_pt_type == crate::vehicle::HEV {
+    } else if veh_pt_type == PowertrainType::HEV {
         fs_max_kw = 2000.0;
-        fs_kwh = other_inputs.fuel_tank_gal * ref_veh.props.kwh_per_gge;
+        fs_kwh = other_inputs.fuel_tank_gal * kwh_per_tank_gal;
         fc_max_kw = other_inputs
             .fc_max_kw
             .unwrap_or(epa_data.eng_pwr_hp as f64 / HP_PER_KW);
-        fc_eff_type = String::from(crate::vehicle::ATKINSON);
+        fc_eff_type = FcEffType::Atkinson;
         fc_eff_map = Array::from_vec(vec![
             0.10, 0.12, 0.28, 0.35, 0.375, 0.39, 0.40, 0.40, 0.38, 0.37, 0.36, 0.35,
         ]);
@@ -940,13 +949,13 @@ fn try_make_single_vehicle(
         ess_max_kw = other_inputs.ess_max_kw;
         ess_max_kwh = other_inputs.ess_max_kwh;
         mc_max_kw = other_inputs.mc_max_kw;
-    } else if veh_pt_type == crate::vehicle::PHEV {
+    } else if veh_pt_type == PowertrainType::PHEV {
         fs_max_kw = 2000.0;
-        fs_kwh = other_inputs.fuel_tank_gal * ref_veh.props.kwh_per_gge;
+        fs_kwh = other_inputs.fuel_tank_gal * kwh_per_tank_gal;
         fc_max_kw = other_inputs
             .fc_max_kw
             .unwrap_or(epa_data.eng_pwr_hp as f64 / HP_PER_KW);
-        fc_eff_type = String::from(crate::vehicle::ATKINSON);
+        fc_eff_type = FcEffType::Atkinson;
         fc_eff_map = Array::from_vec(vec![
             0.10, 0.12, 0.28, 0.35, 0.375, 0.39, 0.40, 0.40, 0.38, 0.37, 0.36, 0.35,
         ]);
@@ -961,11 +970,12 @@ fn try_make_single_vehicle(
         ess_max_kw = other_inputs.ess_max_kw;
         ess_max_kwh = other_inputs.ess_max_kwh;
         mc_max_kw = other_inputs.mc_max_kw;
-    } else if veh_pt_type == crate::vehicle::BEV {
+    } else {
+        // PowertrainType::BEV
         fs_max_kw = 0.0;
         fs_kwh = 0.0;
         fc_max_kw = 0.0;
-        fc_eff_type = String::from(crate::vehicle::SI);
+        fc_eff_type = FcEffType::SI;
         fc_eff_map = Array::from_vec(vec![
             0.10, 0.12, 0.16, 0.22, 0.28, 0.33, 0.35, 0.36, 0.35, 0.34, 0.32, 0.30,
         ]);
@@ -980,9 +990,6 @@ fn try_make_single_vehicle(
         trans_eff = 0.98;
         val_range_miles = fe_gov_data.range_ev as f64;
         ess_dischg_to_fc_max_eff_perc = 0.0;
-    } else {
-        println!("Unhandled vehicle powertrain type: {veh_pt_type}");
-        return None;
     }
 
     // TODO: fix glider_kg calculation
@@ -1011,7 +1018,7 @@ fn try_make_single_vehicle(
         max_roadway_chg_kw: Default::default(),
         selection: 0,
         veh_year: fe_gov_data.year,
-        veh_pt_type: String::from(veh_pt_type),
+        veh_pt_type,
         drag_coef: 0.0, // overridden
         frontal_area_m2: 0.85 * (other_inputs.vehicle_width_in * other_inputs.vehicle_height_in)
             / (IN_PER_M * IN_PER_M),
@@ -1041,6 +1048,11 @@ fn try_make_single_vehicle(
         val_range_miles,
         ess_max_kwh,
         ess_max_kw,
+        fuel: if veh_pt_type == PowertrainType::BEV {
+            None
+        } else {
+            fuel
+        },
         ..Default::default()
     };
     veh.set_derived().unwrap();
@@ -1655,6 +1667,8 @@ mod tests {
         let v = try_make_single_vehicle(&fegov_data, &epatest_data, &other_inputs).unwrap();
         assert_eq!(v.scenario_name, String::from("2020 Toyota Camry"));
         assert_eq!(v.val_comb_mpgge, 18.7389);
+        assert_eq!(v.fuel_props().fuel_type, FuelType::Gasoline);
+        assert!((v.fs_kwh - 15.8 * v.props.kwh_per_gge).abs() < 1e-9);
     }
 
     #[test]
@@ -118,7 +118,8 @@ pub struct HVACModel {
     /// current derivative control amount
     pub d_cntrl_kw: f64,
     /// coefficient between 0 and 1 to calculate HVAC efficiency by multiplying by
-    /// coefficient of performance (COP)
+    /// coefficient of performance (COP), unused if
+    /// `VehicleThermal::cabin_hvac_components` is provided
     pub frac_of_ideal_cop: f64,
     /// whether heat comes from [FuelConverter]
     pub use_fc_waste_heat: bool,
@@ -153,6 +154,374 @@ impl Default for HVACModel {
     }
 }
 
+/// Resistive positive temperature coefficient (PTC) cabin heater
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (max_kw=6.0, eff=0.95))]
+    pub fn __new__(max_kw: f64, eff: f64) -> anyhow::Result<Self> {
+        let mut ptc_heater = Self {
+            max_kw,
+            eff,
+            orphaned: false,
+        };
+        ptc_heater.init()?;
+        Ok(ptc_heater)
+    }
+)]
+pub struct PtcHeater {
+    /// maximum heat output \[kW\]
+    pub max_kw: f64,
+    /// ratio of heat delivered to cabin to electric power input
+    pub eff: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for PtcHeater {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_kw >= 0.0,
+            "{}\n`max_kw` must be non-negative",
+            format_dbg!(self.max_kw)
+        );
+        ensure!(
+            self.eff > 0.0 && self.eff <= 1.0,
+            "{}\n`eff` must be greater than 0 and at most 1",
+            format_dbg!(self.eff)
+        );
+        Ok(())
+    }
+}
+
+impl Default for PtcHeater {
+    fn default() -> Self {
+        Self {
+            max_kw: 6.0,
+            eff: 0.95,
+            orphaned: false,
+        }
+    }
+}
+
+/// Validates ambient temperature breakpoints `te_amb_deg_c` and the
+/// corresponding positive `values` of a component performance table
+fn validate_amb_table(te_amb_deg_c: &[f64], values: &[f64], name: &str) -> anyhow::Result<()> {
+    ensure!(
+        !te_amb_deg_c.is_empty() && te_amb_deg_c.len() == values.len(),
+        "{}\n`te_amb_deg_c` and `{name}` must be non-empty and have the same length",
+        format_dbg!((te_amb_deg_c.len(), values.len()))
+    );
+    ensure!(
+        te_amb_deg_c.windows(2).all(|w| w[0] < w[1]),
+        "{}\n`te_amb_deg_c` must be strictly increasing",
+        format_dbg!(te_amb_deg_c)
+    );
+    ensure!(
+        values.iter().all(|v| *v > 0.0),
+        "{}\n`{name}` must be positive",
+        format_dbg!(values)
+    );
+    Ok(())
+}
+
+/// Air-source heat pump for cabin heating with coefficient of performance
+/// (COP) and heating capacity that degrade with falling ambient temperature
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    pub fn __new__(
+        te_amb_deg_c: Vec<f64>,
+        cop: Vec<f64>,
+        max_heat_kw: Vec<f64>,
+        te_amb_min_deg_c: f64,
+    ) -> anyhow::Result<Self> {
+        let mut heat_pump = Self {
+            te_amb_deg_c,
+            cop,
+            max_heat_kw,
+            te_amb_min_deg_c,
+            orphaned: false,
+        };
+        heat_pump.init()?;
+        Ok(heat_pump)
+    }
+
+    #[pyo3(name = "cop_at")]
+    pub fn cop_at_py(&self, te_amb_deg_c: f64) -> f64 {
+        self.cop_at(te_amb_deg_c)
+    }
+
+    #[pyo3(name = "max_heat_kw_at")]
+    pub fn max_heat_kw_at_py(&self, te_amb_deg_c: f64) -> f64 {
+        self.max_heat_kw_at(te_amb_deg_c)
+    }
+)]
+pub struct HeatPump {
+    /// ambient temperature \[°C\] breakpoints for `cop` and `max_heat_kw`
+    pub te_amb_deg_c: Vec<f64>,
+    /// heating COP, i.e. heat delivered per electric power input, at each of
+    /// `te_amb_deg_c`
+    pub cop: Vec<f64>,
+    /// maximum heat output \[kW\] at each of `te_amb_deg_c`
+    pub max_heat_kw: Vec<f64>,
+    /// ambient temperature \[°C\] below which the heat pump cannot operate
+    pub te_amb_min_deg_c: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for HeatPump {
+    fn init(&mut self) -> anyhow::Result<()> {
+        validate_amb_table(&self.te_amb_deg_c, &self.cop, "cop")?;
+        validate_amb_table(&self.te_amb_deg_c, &self.max_heat_kw, "max_heat_kw")?;
+        Ok(())
+    }
+}
+
+impl Default for HeatPump {
+    fn default() -> Self {
+        Self {
+            te_amb_deg_c: vec![-20.0, -10.0, 0.0, 10.0, 20.0],
+            cop: vec![1.2, 1.6, 2.1, 2.7, 3.3],
+            max_heat_kw: vec![2.0, 3.0, 4.0, 5.0, 5.5],
+            te_amb_min_deg_c: -20.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl HeatPump {
+    /// Heating COP at ambient temperature `te_amb_deg_c` \[°C\]
+    pub fn cop_at(&self, te_amb_deg_c: f64) -> f64 {
+        if self.cop.len() == 1 {
+            self.cop[0]
+        } else {
+            interpolate_vectors(&te_amb_deg_c, &self.te_amb_deg_c, &self.cop, false)
+        }
+    }
+
+    /// Maximum heat output \[kW\] at ambient temperature `te_amb_deg_c` \[°C\],
+    /// zero below `te_amb_min_deg_c`
+    pub fn max_heat_kw_at(&self, te_amb_deg_c: f64) -> f64 {
+        if te_amb_deg_c < self.te_amb_min_deg_c {
+            0.0
+        } else if self.max_heat_kw.len() == 1 {
+            self.max_heat_kw[0]
+        } else {
+            interpolate_vectors(&te_amb_deg_c, &self.te_amb_deg_c, &self.max_heat_kw, false)
+        }
+    }
+}
+
+/// Electrically driven air conditioning compressor for cabin cooling
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    pub fn __new__(te_amb_deg_c: Vec<f64>, cop: Vec<f64>, max_cool_kw: f64) -> anyhow::Result<Self> {
+        let mut ac_compressor = Self {
+            te_amb_deg_c,
+            cop,
+            max_cool_kw,
+            orphaned: false,
+        };
+        ac_compressor.init()?;
+        Ok(ac_compressor)
+    }
+
+    #[pyo3(name = "cop_at")]
+    pub fn cop_at_py(&self, te_amb_deg_c: f64) -> f64 {
+        self.cop_at(te_amb_deg_c)
+    }
+)]
+pub struct AcCompressor {
+    /// ambient temperature \[°C\] breakpoints for `cop`
+    pub te_amb_deg_c: Vec<f64>,
+    /// cooling COP, i.e. heat removed per electric power input, at each of
+    /// `te_amb_deg_c`
+    pub cop: Vec<f64>,
+    /// maximum heat removal \[kW\]
+    pub max_cool_kw: f64,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for AcCompressor {
+    fn init(&mut self) -> anyhow::Result<()> {
+        validate_amb_table(&self.te_amb_deg_c, &self.cop, "cop")?;
+        ensure!(
+            self.max_cool_kw >= 0.0,
+            "{}\n`max_cool_kw` must be non-negative",
+            format_dbg!(self.max_cool_kw)
+        );
+        Ok(())
+    }
+}
+
+impl Default for AcCompressor {
+    fn default() -> Self {
+        Self {
+            te_amb_deg_c: vec![20.0, 30.0, 40.0, 50.0],
+            cop: vec![3.5, 2.8, 2.2, 1.7],
+            max_cool_kw: 5.0,
+            orphaned: false,
+        }
+    }
+}
+
+impl AcCompressor {
+    /// Cooling COP at ambient temperature `te_amb_deg_c` \[°C\]
+    pub fn cop_at(&self, te_amb_deg_c: f64) -> f64 {
+        if self.cop.len() == 1 {
+            self.cop[0]
+        } else {
+            interpolate_vectors(&te_amb_deg_c, &self.te_amb_deg_c, &self.cop, false)
+        }
+    }
+}
+
+/// Cabin heat delivered by [HvacComponents] and the electric power drawn by
+/// each component to deliver it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HvacComponentsPower {
+    /// heat delivered to cabin \[kW\], negative for cooling
+    pub qdot_kw: f64,
+    /// heat pump electric power \[kW\]
+    pub heat_pump_kw: f64,
+    /// PTC heater electric power \[kW\]
+    pub ptc_heater_kw: f64,
+    /// AC compressor electric power \[kW\]
+    pub ac_compressor_kw: f64,
+}
+
+impl HvacComponentsPower {
+    /// Total electric power \[kW\] drawn by HVAC components
+    pub fn elec_kw(&self) -> f64 {
+        self.heat_pump_kw + self.ptc_heater_kw + self.ac_compressor_kw
+    }
+}
+
+/// Electric cabin HVAC hardware delivering the heating or cooling demanded by
+/// the [HVACModel] controller, in place of the `frac_of_ideal_cop`
+/// approximation.  Heating demand is met by the heat pump first, with the PTC
+/// heater covering any shortfall.  A missing component provides no heating or
+/// cooling.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[add_pyo3_api(
+    #[new]
+    #[pyo3(signature = (ptc_heater=None, heat_pump=None, ac_compressor=None))]
+    pub fn __new__(
+        ptc_heater: Option<PtcHeater>,
+        heat_pump: Option<HeatPump>,
+        ac_compressor: Option<AcCompressor>,
+    ) -> anyhow::Result<Self> {
+        let mut components = Self {
+            ptc_heater,
+            heat_pump,
+            ac_compressor,
+            orphaned: false,
+        };
+        components.init()?;
+        Ok(components)
+    }
+
+    #[getter]
+    pub fn get_ptc_heater(&self) -> Option<PtcHeater> {
+        self.ptc_heater.clone()
+    }
+
+    #[setter]
+    pub fn set_ptc_heater(&mut self, new_value: Option<PtcHeater>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, ptc_heater, new_value)
+    }
+
+    #[getter]
+    pub fn get_heat_pump(&self) -> Option<HeatPump> {
+        self.heat_pump.clone()
+    }
+
+    #[setter]
+    pub fn set_heat_pump(&mut self, new_value: Option<HeatPump>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, heat_pump, new_value)
+    }
+
+    #[getter]
+    pub fn get_ac_compressor(&self) -> Option<AcCompressor> {
+        self.ac_compressor.clone()
+    }
+
+    #[setter]
+    pub fn set_ac_compressor(&mut self, new_value: Option<AcCompressor>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, ac_compressor, new_value)
+    }
+)]
+pub struct HvacComponents {
+    /// resistive heater
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub ptc_heater: Option<PtcHeater>,
+    /// heat pump, used before `ptc_heater`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub heat_pump: Option<HeatPump>,
+    /// air conditioning compressor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub ac_compressor: Option<AcCompressor>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for HvacComponents {
+    fn init(&mut self) -> anyhow::Result<()> {
+        if let Some(ptc_heater) = &mut self.ptc_heater {
+            ptc_heater.init()?;
+        }
+        if let Some(heat_pump) = &mut self.heat_pump {
+            heat_pump.init()?;
+        }
+        if let Some(ac_compressor) = &mut self.ac_compressor {
+            ac_compressor.init()?;
+        }
+        Ok(())
+    }
+}
+
+impl HvacComponents {
+    /// Deliver up to `qdot_req_kw` \[kW\] of heat to the cabin at ambient
+    /// temperature `te_amb_deg_c` \[°C\]
+    pub fn heat(&self, qdot_req_kw: f64, te_amb_deg_c: f64) -> HvacComponentsPower {
+        let mut pwr = HvacComponentsPower::default();
+        let mut qdot_rem_kw = qdot_req_kw.max(0.0);
+        if let Some(heat_pump) = &self.heat_pump {
+            let qdot_kw = qdot_rem_kw.min(heat_pump.max_heat_kw_at(te_amb_deg_c));
+            if qdot_kw > 0.0 {
+                pwr.heat_pump_kw = qdot_kw / heat_pump.cop_at(te_amb_deg_c);
+                pwr.qdot_kw += qdot_kw;
+                qdot_rem_kw -= qdot_kw;
+            }
+        }
+        if let Some(ptc_heater) = &self.ptc_heater {
+            let qdot_kw = qdot_rem_kw.min(ptc_heater.max_kw);
+            pwr.ptc_heater_kw = qdot_kw / ptc_heater.eff;
+            pwr.qdot_kw += qdot_kw;
+        }
+        pwr
+    }
+
+    /// Remove up to `qdot_req_kw` \[kW\] of heat from the cabin at ambient
+    /// temperature `te_amb_deg_c` \[°C\]
+    pub fn cool(&self, qdot_req_kw: f64, te_amb_deg_c: f64) -> HvacComponentsPower {
+        let mut pwr = HvacComponentsPower::default();
+        if let Some(ac_compressor) = &self.ac_compressor {
+            let qdot_kw = qdot_req_kw.max(0.0).min(ac_compressor.max_cool_kw);
+            pwr.ac_compressor_kw = qdot_kw / ac_compressor.cop_at(te_amb_deg_c);
+            pwr.qdot_kw = -qdot_kw;
+        }
+        pwr
+    }
+}
+
 /// Whether HVAC model is handled by FASTSim (internal) or not
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub enum CabinHvacModelTypes {
@@ -218,6 +587,16 @@ pub fn get_sphere_conv_params(re: f64) -> (f64, f64) {
         check_orphaned_and_set!(self, cabin_hvac_model, CabinHvacModelTypes::External)
     }
 
+    #[getter]
+    pub fn get_cabin_hvac_components(&self) -> Option<HvacComponents> {
+        self.cabin_hvac_components.clone()
+    }
+
+    #[setter]
+    pub fn set_cabin_hvac_components(&mut self, new_value: Option<HvacComponents>) -> anyhow::Result<()> {
+        check_orphaned_and_set!(self, cabin_hvac_components, new_value)
+    }
+
     pub fn set_fc_model_internal_exponential(
         &mut self,
         offset: f64,
@@ -383,7 +762,7 @@ pub struct VehicleThermal {
     // battery
     /// battery thermal mass \[kJ/K\]
     pub ess_c_kj_k: f64,
-    /// effective (incl. any thermal management system) heat transfer coefficient from battery to ambient
+    /// effective (incl. any thermal management system) heat transfer coefficient \[W/K\] from battery to ambient
     pub ess_htc_to_amb: f64,
     // battery controls
     // TODO:
@@ -404,6 +783,15 @@ pub struct VehicleThermal {
     /// parameter for heat transfer coeff \[W / (m ** 2 * K)\] from cabin to ambient during
     /// vehicle stop
     pub cab_htc_to_amb_stop: f64,
+    /// effective cabin area \[m ** 2\] absorbing global horizontal solar
+    /// irradiance, i.e. glazing area times transmissivity and incidence factor
+    #[serde(default = "VehicleThermal::default_cab_solar_area_m2")]
+    pub cab_solar_area_m2: f64,
+    /// electric heating and cooling hardware used by an internal
+    /// `cabin_hvac_model`, which falls back to `frac_of_ideal_cop` if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[api(skip_get, skip_set)]
+    pub cabin_hvac_components: Option<HvacComponents>,
 
     // exhaust port
     /// 'external' (effectively no model) is default
@@ -437,7 +825,14 @@ pub struct VehicleThermal {
     pub orphaned: bool,
 }
 
-impl SerdeAPI for VehicleThermal {}
+impl SerdeAPI for VehicleThermal {
+    fn init(&mut self) -> anyhow::Result<()> {
+        if let Some(components) = &mut self.cabin_hvac_components {
+            components.init()?;
+        }
+        Ok(())
+    }
+}
 
 impl Default for VehicleThermal {
     fn default() -> Self {
@@ -458,6 +853,8 @@ impl Default for VehicleThermal {
             cab_l_width: 2.0,
             cab_r_to_amb: 0.02,
             cab_htc_to_amb_stop: 10.0,
+            cab_solar_area_m2: Self::default_cab_solar_area_m2(),
+            cabin_hvac_components: None,
             exhport_model: ComponentModelTypes::External, // turned off by default
             exhport_ha_to_amb: 5.0,
             exhport_ha_int: 100.0,
@@ -474,6 +871,10 @@ impl Default for VehicleThermal {
 }
 
 impl VehicleThermal {
+    const fn default_cab_solar_area_m2() -> f64 {
+        1.0
+    }
+
     /// derived temperature \[ºC\] at which thermostat is fully open
     pub fn tstat_te_fo_deg_c(&self) -> f64 {
         self.tstat_te_sto_deg_c + self.tstat_te_delta_deg_c
@@ -489,3 +890,71 @@ impl VehicleThermal {
         PI * self.cat_l.powf(2.0 / 4.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hvac_components() {
+        let components = HvacComponents {
+            ptc_heater: Some(PtcHeater::default()),
+            heat_pump: Some(HeatPump::default()),
+            ac_compressor: Some(AcCompressor::default()),
+            ..Default::default()
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        let heat_pump = components.heat_pump.as_ref().unwrap();
+        assert!(close(heat_pump.cop_at(-5.0), 1.85));
+        assert!(close(heat_pump.max_heat_kw_at(-5.0), 3.5));
+        assert_eq!(heat_pump.max_heat_kw_at(-30.0), 0.0);
+
+        // heat pump covers small loads alone
+        let pwr = components.heat(2.0, 10.0);
+        assert!(close(pwr.qdot_kw, 2.0));
+        assert!(close(pwr.heat_pump_kw, 2.0 / 2.7));
+        assert_eq!(pwr.ptc_heater_kw, 0.0);
+        // PTC heater makes up for the heat pump capacity limit
+        let pwr = components.heat(5.0, -10.0);
+        assert!(close(pwr.qdot_kw, 5.0));
+        assert!(close(pwr.heat_pump_kw, 3.0 / 1.6));
+        assert!(close(pwr.ptc_heater_kw, 2.0 / 0.95));
+        // heat pump is shut off below its minimum ambient temperature
+        let pwr = components.heat(5.0, -25.0);
+        assert_eq!(pwr.heat_pump_kw, 0.0);
+        assert_eq!(pwr.ptc_heater_kw, 5.0 / 0.95);
+        assert_eq!(pwr.elec_kw(), pwr.ptc_heater_kw);
+
+        // AC compressor is limited by its capacity
+        let pwr = components.cool(8.0, 40.0);
+        assert_eq!(pwr.qdot_kw, -5.0);
+        assert!(close(pwr.ac_compressor_kw, 5.0 / 2.2));
+        // no cooling without an AC compressor
+        let no_ac = HvacComponents {
+            ac_compressor: None,
+            ..components.clone()
+        };
+        assert_eq!(no_ac.cool(2.0, 40.0), HvacComponentsPower::default());
+
+        let mut vehthrm = VehicleThermal {
+            cabin_hvac_components: Some(components),
+            ..Default::default()
+        };
+        vehthrm.init().unwrap();
+        vehthrm
+            .cabin_hvac_components
+            .as_mut()
+            .unwrap()
+            .heat_pump
+            .as_mut()
+            .unwrap()
+            .cop
+            .pop();
+        assert!(vehthrm.init().is_err());
+        let mut ptc_heater = PtcHeater {
+            eff: 1.5,
+            ..Default::default()
+        };
+        assert!(ptc_heater.init().is_err());
+    }
+}
@@ -0,0 +1,481 @@
+//! Module containing vehicle input validation, which reports every problem
+//! with a [RustVehicle] at once rather than failing on the first
+
+use std::fmt;
+
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+use crate::vehicle::*;
+
+#[cfg(feature = "validation")]
+use validator::Validate;
+
+/// Severity of a [ValidationIssue]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Input is suspicious but can be simulated
+    #[default]
+    Warning,
+    /// Input would produce NaNs, panics, or meaningless results
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[add_pyo3_api(
+    #[getter]
+    pub fn get_severity(&self) -> String {
+        self.severity.to_string()
+    }
+
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// Single problem found by [RustVehicle::validate]
+pub struct ValidationIssue {
+    /// path to offending field, e.g. `fc_pwr_out_perc[3]` or `transmission.gear_ratios`
+    #[api(skip_set)]
+    pub path: String,
+    #[api(skip_get, skip_set)]
+    pub severity: Severity,
+    #[api(skip_set)]
+    pub message: String,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for ValidationIssue {}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+#[add_pyo3_api(
+    #[pyo3(name = "is_valid")]
+    pub fn is_valid_py(&self) -> bool {
+        self.is_valid()
+    }
+
+    #[pyo3(name = "errors")]
+    pub fn errors_py(&self) -> Vec<ValidationIssue> {
+        self.errors().cloned().collect()
+    }
+
+    #[pyo3(name = "warnings")]
+    pub fn warnings_py(&self) -> Vec<ValidationIssue> {
+        self.warnings().cloned().collect()
+    }
+
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+/// All problems found by [RustVehicle::validate]
+pub struct ValidationReport {
+    #[api(skip_set)]
+    pub issues: Vec<ValidationIssue>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for ValidationReport {}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "no issues found");
+        }
+        let lines: Vec<String> = self.issues.iter().map(|issue| issue.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl ValidationReport {
+    /// Returns true if there are no issues with [Severity::Error]
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// [FastSimError::InvalidVehicle] listing all errors, if any
+    pub fn to_error(&self) -> Option<FastSimError> {
+        if self.is_valid() {
+            return None;
+        }
+        let errors: Vec<String> = self.errors().map(|issue| issue.to_string()).collect();
+        Some(FastSimError::InvalidVehicle(format!(
+            "{} error(s) found\n{}",
+            errors.len(),
+            errors.join("\n")
+        )))
+    }
+
+    /// Returns [FastSimError::InvalidVehicle] listing all errors, if any
+    pub fn to_result(&self) -> anyhow::Result<()> {
+        match self.to_error() {
+            Some(err) => bail!(err),
+            None => Ok(()),
+        }
+    }
+
+    fn push<S: Into<String>>(&mut self, path: S, severity: Severity, message: String) {
+        self.issues.push(ValidationIssue {
+            path: path.into(),
+            severity,
+            message,
+            orphaned: false,
+        });
+    }
+
+    fn error<S: Into<String>>(&mut self, path: S, message: String) {
+        self.push(path, Severity::Error, message)
+    }
+
+    fn warning<S: Into<String>>(&mut self, path: S, message: String) {
+        self.push(path, Severity::Warning, message)
+    }
+
+    /// Checks that `x` (e.g. `fc_pwr_out_perc`) is strictly increasing and
+    /// that `y` (e.g. `fc_eff_map`) has matching length and values in [0, 1]
+    fn check_eff_curve(&mut self, x_name: &str, x: &Array1<f64>, y_name: &str, y: &Array1<f64>) {
+        if x.is_empty() {
+            self.error(x_name, "must not be empty".into());
+            return;
+        }
+        for i in 1..x.len() {
+            if x[i].partial_cmp(&x[i - 1]) != Some(cmp::Ordering::Greater) {
+                self.error(
+                    format!("{x_name}[{i}]"),
+                    format!(
+                        "must be strictly increasing, but {} follows {}",
+                        x[i],
+                        x[i - 1]
+                    ),
+                );
+            }
+        }
+        if x[0] != 0.0 || x[x.len() - 1] != 1.0 {
+            self.warning(
+                x_name,
+                format!(
+                    "expected to span 0 to 1, but spans {} to {}",
+                    x[0],
+                    x[x.len() - 1]
+                ),
+            );
+        }
+        if !y.is_empty() && y.len() != x.len() {
+            self.error(
+                y_name,
+                format!(
+                    "length ({}) does not match length of `{x_name}` ({})",
+                    y.len(),
+                    x.len()
+                ),
+            );
+        }
+        for (i, eff) in y.iter().enumerate() {
+            if !(0.0..=1.0).contains(eff) {
+                self.error(
+                    format!("{y_name}[{i}]"),
+                    format!("efficiency ({eff}) must be between 0 and 1"),
+                );
+            }
+        }
+    }
+
+    /// Checks that an optional nested component initializes, reporting any
+    /// error under `path`
+    fn check_component<T: SerdeAPI + Clone>(&mut self, path: &str, component: Option<&T>) {
+        if let Some(component) = component {
+            if let Err(err) = component.clone().init() {
+                self.error(path, format!("{err:#}"));
+            }
+        }
+    }
+}
+
+/// Returns false for NaN
+fn is_positive(x: f64) -> bool {
+    x > 0.0
+}
+
+/// Returns true if `x` is in (0, 1], false for NaN
+fn is_efficiency(x: f64) -> bool {
+    x > 0.0 && x <= 1.0
+}
+
+impl RustVehicle {
+    /// Checks vehicle inputs and returns every problem found, with field
+    /// paths and severities.  [RustVehicle::set_derived] (and thus
+    /// [SerdeAPI::init]) fails if any problem has [Severity::Error].
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        // field-level range checks from `validator`
+        #[cfg(feature = "validation")]
+        if let Err(errs) = Validate::validate(self) {
+            let mut field_errs: Vec<_> = errs.field_errors().into_iter().collect();
+            field_errs.sort_by_key(|(field, _)| *field);
+            for (field, errs) in field_errs {
+                for err in errs {
+                    let value = err.params.get("value").map(|v| v.to_string());
+                    let bounds: Vec<String> = ["min", "max"]
+                        .iter()
+                        .filter_map(|k| err.params.get(*k).map(|v| format!("{k} = {v}")))
+                        .collect();
+                    report.error(
+                        field,
+                        format!(
+                            "value {} fails `{}` check ({})",
+                            value.unwrap_or_default(),
+                            err.code,
+                            bounds.join(", ")
+                        ),
+                    );
+                }
+            }
+        }
+
+        // state of charge window
+        if self.min_soc > self.max_soc {
+            report.error(
+                "min_soc",
+                format!(
+                    "`min_soc` ({}) must not exceed `max_soc` ({})",
+                    self.min_soc, self.max_soc
+                ),
+            );
+        }
+
+        // efficiency curves
+        if !self.fc_eff_map.is_empty() || self.fc_eff_array.is_empty() {
+            report.check_eff_curve(
+                "fc_pwr_out_perc",
+                &self.fc_pwr_out_perc,
+                "fc_eff_map",
+                &self.fc_eff_map,
+            );
+        }
+        report.check_eff_curve(
+            "mc_pwr_out_perc",
+            &self.mc_pwr_out_perc,
+            "mc_eff_map",
+            &self.mc_eff_map,
+        );
+        for (name, eff) in [
+            ("trans_eff", self.trans_eff),
+            ("alt_eff", self.alt_eff),
+            ("chg_eff", self.chg_eff),
+        ] {
+            if !is_efficiency(eff) {
+                report.error(name, format!("efficiency ({eff}) must be in (0, 1]"));
+            }
+        }
+        if self.ess_max_kwh > 0.0 && !is_efficiency(self.ess_round_trip_eff) {
+            report.error(
+                "ess_round_trip_eff",
+                format!("efficiency ({}) must be in (0, 1]", self.ess_round_trip_eff),
+            );
+        }
+
+        // geometry and mass
+        for (name, value) in [
+            ("wheel_radius_m", self.wheel_radius_m),
+            ("wheel_base_m", self.wheel_base_m),
+        ] {
+            if !is_positive(value) {
+                report.error(name, format!("must be positive, got {value}"));
+            }
+        }
+        match self.veh_override_kg {
+            Some(kg) if !is_positive(kg) => {
+                report.error("veh_override_kg", format!("must be positive, got {kg}"))
+            }
+            None if !is_positive(self.glider_kg) => report.error(
+                "glider_kg",
+                format!(
+                    "must be positive if `veh_override_kg` is not provided, got {}",
+                    self.glider_kg
+                ),
+            ),
+            _ => {}
+        }
+
+        // road load
+        if self.drag_coef == 0.0 || self.frontal_area_m2 == 0.0 {
+            report.warning(
+                "drag_coef",
+                "aerodynamic drag is zero because `drag_coef` or `frontal_area_m2` is zero".into(),
+            );
+        }
+        if self.wheel_rr_coef == 0.0 {
+            report.warning("wheel_rr_coef", "rolling resistance is zero".into());
+        }
+
+        // powertrain consistency
+        if self.scenario_name != "Template Vehicle for setting up data types" {
+            match self.veh_pt_type {
+                PowertrainType::BEV => {
+                    for (name, value) in [
+                        ("fs_max_kw", self.fs_max_kw),
+                        ("fs_kwh", self.fs_kwh),
+                        ("fc_max_kw", self.fc_max_kw),
+                    ] {
+                        if value != 0.0 {
+                            report.error(
+                                name,
+                                format!("must be zero for {:?} powertrain type", self.veh_pt_type),
+                            );
+                        }
+                    }
+                }
+                PowertrainType::Conv if !self.stop_start => {
+                    for (name, value) in [
+                        ("mc_max_kw", self.mc_max_kw),
+                        ("ess_max_kw", self.ess_max_kw),
+                        ("ess_max_kwh", self.ess_max_kwh),
+                    ] {
+                        if value != 0.0 {
+                            report.error(
+                                name,
+                                format!(
+                                    "must be zero for {:?} powertrain type without `stop_start`",
+                                    self.veh_pt_type
+                                ),
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+            if self.veh_pt_type != PowertrainType::BEV && !is_positive(self.fc_max_kw) {
+                report.error(
+                    "fc_max_kw",
+                    format!(
+                        "must be positive for {:?} powertrain type",
+                        self.veh_pt_type
+                    ),
+                );
+            }
+        }
+
+        // nested components
+        if self.transmission.is_none()
+            && (self.fc_eff_map_2d.is_some() || self.mc_eff_map_2d.is_some())
+        {
+            report.error(
+                "transmission",
+                "must be provided to use `fc_eff_map_2d` or `mc_eff_map_2d`".into(),
+            );
+        }
+        if self.fc_eff_type == FcEffType::H2FC && self.fc_eff_map_2d.is_some() {
+            report.error(
+                "fc_eff_map_2d",
+                format!("not supported for {:?}", FcEffType::H2FC),
+            );
+        }
+        report.check_component("ess_circuit", self.ess_circuit.as_ref());
+        report.check_component("transmission", self.transmission.as_ref());
+        report.check_component("fc_eff_map_2d", self.fc_eff_map_2d.as_ref());
+        report.check_component("mc_eff_map_2d", self.mc_eff_map_2d.as_ref());
+        report.check_component("emissions", self.emissions.as_ref());
+        report.check_component("fuel", self.fuel.as_ref());
+        report.check_component("secondary_fuel", self.secondary_fuel.as_ref());
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_all_issues() {
+        let veh = RustVehicle::mock_vehicle();
+        let report = veh.validate();
+        assert!(report.is_valid(), "{report}");
+
+        let mut veh = RustVehicle::mock_vehicle();
+        veh.min_soc = 0.9;
+        veh.max_soc = 0.1;
+        veh.fc_pwr_out_perc[3] = veh.fc_pwr_out_perc[2];
+        veh.mc_eff_map = veh.mc_eff_map.slice(s![1..]).to_owned();
+        veh.fc_eff_map[5] = 1.2;
+        veh.wheel_radius_m = 0.0;
+        veh.drag_coef = 0.0;
+        let report = veh.validate();
+        let error_paths: Vec<&str> = report.errors().map(|issue| issue.path.as_str()).collect();
+        for path in [
+            "min_soc",
+            "fc_pwr_out_perc[3]",
+            "mc_eff_map",
+            "fc_eff_map[5]",
+            "wheel_radius_m",
+        ] {
+            assert!(error_paths.contains(&path), "{path} not in {error_paths:?}");
+        }
+        assert_eq!(report.warnings().next().unwrap().path, "drag_coef");
+
+        let err = veh.set_derived().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FastSimError>(),
+            Some(FastSimError::InvalidVehicle(_))
+        ));
+        // the error carries the full report rather than stopping at the first issue
+        let msg = format!("{err:#}");
+        for path in error_paths {
+            assert!(msg.contains(path), "{path} not in {msg}");
+        }
+    }
+
+    #[test]
+    fn test_validate_resource_vehicles() {
+        let mut vehs: Vec<(String, RustVehicle)> = RustVehicle::list_resources()
+            .into_iter()
+            .map(|veh_file| {
+                let veh = RustVehicle::from_resource(&veh_file, true).unwrap();
+                (veh_file, veh)
+            })
+            .collect();
+        for entry in std::fs::read_dir(resources_path().join("vehdb")).unwrap() {
+            let veh_file = entry.unwrap().path();
+            if veh_file.extension().and_then(OsStr::to_str) == Some("yaml") {
+                let veh = RustVehicle::from_file(&veh_file, true).unwrap();
+                vehs.push((veh_file.display().to_string(), veh));
+            }
+        }
+        assert!(!vehs.is_empty());
+        for (veh_file, veh) in vehs {
+            let report = veh.validate();
+            assert!(report.is_valid(), "{veh_file}:\n{report}");
+        }
+    }
+}
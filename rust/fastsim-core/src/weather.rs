@@ -0,0 +1,663 @@
+//! Module containing a time- or distance-indexed ambient weather profile
+//! providing temperature, wind, solar load, humidity, and air pressure to
+//! [RustSimDrive](crate::simdrive::RustSimDrive) and
+//! [SimDriveHot](crate::thermal::SimDriveHot).
+
+use crate::air::{get_std_pressure, R_AIR};
+use crate::imports::*;
+use crate::proc_macros::add_pyo3_api;
+#[cfg(feature = "pyo3")]
+use crate::pyo3imports::*;
+
+/// gas constant of water vapor \[J/(kg*K)\]
+pub const R_VAPOR: f64 = 461.5;
+/// elevation assumed when neither `pressure_kpa` nor `elevation_m` is provided \[m\]
+const DEFAULT_ELEVATION_M: f64 = 180.0;
+/// duration of each row of a TMY-style file \[s\]
+const TMY_STEP_S: f64 = 3600.0;
+
+#[add_pyo3_api(
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        amb_te_deg_c,
+        time_s=None,
+        dist_m=None,
+        wind_speed_mps=None,
+        wind_from_deg=None,
+        heading_deg=None,
+        solar_w_per_m2=None,
+        rel_humidity=None,
+        pressure_kpa=None,
+        elevation_m=None,
+    ))]
+    pub fn __new__(
+        amb_te_deg_c: Vec<f64>,
+        time_s: Option<Vec<f64>>,
+        dist_m: Option<Vec<f64>>,
+        wind_speed_mps: Option<Vec<f64>>,
+        wind_from_deg: Option<Vec<f64>>,
+        heading_deg: Option<Vec<f64>>,
+        solar_w_per_m2: Option<Vec<f64>>,
+        rel_humidity: Option<Vec<f64>>,
+        pressure_kpa: Option<Vec<f64>>,
+        elevation_m: Option<Vec<f64>>,
+    ) -> anyhow::Result<Self> {
+        let mut weather = Self {
+            time_s: Array::from_vec(time_s.unwrap_or_default()),
+            dist_m: Array::from_vec(dist_m.unwrap_or_default()),
+            amb_te_deg_c: Array::from_vec(amb_te_deg_c),
+            wind_speed_mps: Array::from_vec(wind_speed_mps.unwrap_or_default()),
+            wind_from_deg: Array::from_vec(wind_from_deg.unwrap_or_default()),
+            heading_deg: Array::from_vec(heading_deg.unwrap_or_default()),
+            solar_w_per_m2: Array::from_vec(solar_w_per_m2.unwrap_or_default()),
+            rel_humidity: Array::from_vec(rel_humidity.unwrap_or_default()),
+            pressure_kpa: Array::from_vec(pressure_kpa.unwrap_or_default()),
+            elevation_m: Array::from_vec(elevation_m.unwrap_or_default()),
+            orphaned: false,
+        };
+        weather.init()?;
+        Ok(weather)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_csv")]
+    pub fn from_csv_py(filepath: &Bound<PyAny>) -> anyhow::Result<Self> {
+        Self::from_csv_file(PathBuf::extract_bound(filepath)?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_tmy")]
+    #[pyo3(signature = (filepath, start_hour=0, n_hours=None))]
+    pub fn from_tmy_py(
+        filepath: &Bound<PyAny>,
+        start_hour: usize,
+        n_hours: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        Self::from_tmy_file(PathBuf::extract_bound(filepath)?, start_hour, n_hours)
+    }
+
+    #[pyo3(name = "amb_te_deg_c_at")]
+    #[pyo3(signature = (time_s=0.0, dist_m=0.0))]
+    pub fn amb_te_deg_c_at_py(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.amb_te_deg_c_at(time_s, dist_m)
+    }
+
+    #[pyo3(name = "headwind_mps_at")]
+    #[pyo3(signature = (time_s=0.0, dist_m=0.0))]
+    pub fn headwind_mps_at_py(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.headwind_mps_at(time_s, dist_m)
+    }
+
+    #[pyo3(name = "solar_w_per_m2_at")]
+    #[pyo3(signature = (time_s=0.0, dist_m=0.0))]
+    pub fn solar_w_per_m2_at_py(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.solar_w_per_m2_at(time_s, dist_m)
+    }
+
+    #[pyo3(name = "air_density_kg_per_m3_at")]
+    #[pyo3(signature = (time_s=0.0, dist_m=0.0))]
+    pub fn air_density_kg_per_m3_at_py(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.air_density_kg_per_m3_at(time_s, dist_m)
+    }
+)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+/// Ambient weather indexed by either time (e.g. a measured or typical
+/// meteorological year series) or distance (e.g. conditions along a route).
+/// Values are linearly interpolated between samples and held constant
+/// beyond the first and last sample.  Every field other than the index and
+/// `amb_te_deg_c` may be left empty, in which case it has no effect.
+pub struct WeatherProfile {
+    /// times at which weather is specified \[s\], must be empty if `dist_m` is used
+    #[serde(default)]
+    pub time_s: Array1<f64>,
+    /// distances at which weather is specified \[m\], must be empty if `time_s` is used
+    #[serde(default)]
+    pub dist_m: Array1<f64>,
+    /// ambient (dry bulb) air temperature \[°C\]
+    pub amb_te_deg_c: Array1<f64>,
+    /// wind speed \[m/s\]
+    #[serde(default)]
+    pub wind_speed_mps: Array1<f64>,
+    /// direction the wind is blowing from \[deg clockwise from north\]
+    #[serde(default)]
+    pub wind_from_deg: Array1<f64>,
+    /// direction of vehicle travel \[deg clockwise from north\], north if empty
+    #[serde(default)]
+    pub heading_deg: Array1<f64>,
+    /// global horizontal solar irradiance \[W/m**2\]
+    #[serde(default)]
+    pub solar_w_per_m2: Array1<f64>,
+    /// relative humidity \[-\] between 0 and 1, dry air if empty
+    #[serde(default)]
+    pub rel_humidity: Array1<f64>,
+    /// ambient air pressure \[kPa\], derived from `elevation_m` if empty
+    #[serde(default)]
+    pub pressure_kpa: Array1<f64>,
+    /// elevation above sea level \[m\], used only if `pressure_kpa` is empty,
+    /// defaults to 180 m if also empty
+    #[serde(default)]
+    pub elevation_m: Array1<f64>,
+    #[serde(skip)]
+    pub orphaned: bool,
+}
+
+impl SerdeAPI for WeatherProfile {
+    fn init(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.time_s.is_empty() != self.dist_m.is_empty(),
+            "{}\nexactly one of `time_s` and `dist_m` must be provided",
+            format_dbg!((self.time_s.len(), self.dist_m.len()))
+        );
+        let n = self.index().len();
+        ensure!(
+            self.index().windows(2).into_iter().all(|w| w[0] < w[1]),
+            "{}\n`time_s` or `dist_m` must be strictly increasing",
+            format_dbg!()
+        );
+        ensure!(
+            self.amb_te_deg_c.len() == n,
+            "{}\n`amb_te_deg_c` must have the same length as `time_s` or `dist_m`",
+            format_dbg!((self.amb_te_deg_c.len(), n))
+        );
+        for (name, arr) in [
+            ("wind_speed_mps", &self.wind_speed_mps),
+            ("wind_from_deg", &self.wind_from_deg),
+            ("heading_deg", &self.heading_deg),
+            ("solar_w_per_m2", &self.solar_w_per_m2),
+            ("rel_humidity", &self.rel_humidity),
+            ("pressure_kpa", &self.pressure_kpa),
+            ("elevation_m", &self.elevation_m),
+        ] {
+            ensure!(
+                arr.is_empty() || arr.len() == n,
+                "{}\n`{name}` must be empty or have the same length as `time_s` or `dist_m`",
+                format_dbg!((arr.len(), n))
+            );
+        }
+        ensure!(
+            self.wind_speed_mps.is_empty() == self.wind_from_deg.is_empty(),
+            "{}\n`wind_speed_mps` and `wind_from_deg` must be provided together",
+            format_dbg!((self.wind_speed_mps.len(), self.wind_from_deg.len()))
+        );
+        ensure!(
+            self.wind_speed_mps.iter().all(|v| *v >= 0.0),
+            "{}\n`wind_speed_mps` must be non-negative",
+            format_dbg!()
+        );
+        ensure!(
+            self.solar_w_per_m2.iter().all(|q| *q >= 0.0),
+            "{}\n`solar_w_per_m2` must be non-negative",
+            format_dbg!()
+        );
+        ensure!(
+            self.rel_humidity.iter().all(|rh| (0.0..=1.0).contains(rh)),
+            "{}\n`rel_humidity` must be between 0 and 1",
+            format_dbg!()
+        );
+        ensure!(
+            self.pressure_kpa.iter().all(|p| *p > 0.0),
+            "{}\n`pressure_kpa` must be positive",
+            format_dbg!()
+        );
+        Ok(())
+    }
+}
+
+/// Returns the indices of the samples bracketing `x` in strictly increasing
+/// `xs` and the interpolation fraction between them, clamped to the ends
+fn bracket(xs: &Array1<f64>, x: f64) -> (usize, usize, f64) {
+    let n = xs.len();
+    if x <= xs[0] {
+        return (0, 0, 0.0);
+    }
+    if x >= xs[n - 1] {
+        return (n - 1, n - 1, 0.0);
+    }
+    // `xs` is strictly increasing, as checked by `init`
+    let j = xs
+        .as_slice()
+        .expect("`Array1` built from a `Vec` is contiguous")
+        .partition_point(|xj| *xj <= x);
+    let i = j - 1;
+    (i, j, (x - xs[i]) / (xs[j] - xs[i]))
+}
+
+/// Density of humid air \[kg/m**3\] at temperature `te_deg_c` \[°C\], pressure
+/// `pressure_pa` \[Pa\], and relative humidity `rel_humidity` \[-\]
+pub fn get_humid_air_density(te_deg_c: f64, pressure_pa: f64, rel_humidity: f64) -> f64 {
+    // Tetens equation for saturation vapor pressure [Pa]
+    let p_vapor = rel_humidity * 610.78 * (17.27 * te_deg_c / (te_deg_c + 237.3)).exp();
+    let te_k = te_deg_c + 273.15;
+    (pressure_pa - p_vapor) / (R_AIR * te_k) + p_vapor / (R_VAPOR * te_k)
+}
+
+impl WeatherProfile {
+    /// Array by which the profile is indexed, either `time_s` or `dist_m`
+    fn index(&self) -> &Array1<f64> {
+        if self.time_s.is_empty() {
+            &self.dist_m
+        } else {
+            &self.time_s
+        }
+    }
+
+    /// Bracketing samples and interpolation fraction at the given time \[s\]
+    /// and distance \[m\], only one of which is used depending on the index
+    fn bracket_at(&self, time_s: f64, dist_m: f64) -> (usize, usize, f64) {
+        if self.time_s.is_empty() {
+            bracket(&self.dist_m, dist_m)
+        } else {
+            bracket(&self.time_s, time_s)
+        }
+    }
+
+    /// Interpolates `ys` at the given time \[s\] and distance \[m\], returning
+    /// `default` if `ys` is empty
+    fn interp_at(&self, ys: &Array1<f64>, time_s: f64, dist_m: f64, default: f64) -> f64 {
+        if ys.is_empty() {
+            return default;
+        }
+        let (i, j, frac) = self.bracket_at(time_s, dist_m);
+        ys[i] + frac * (ys[j] - ys[i])
+    }
+
+    /// Ambient air temperature \[°C\]
+    pub fn amb_te_deg_c_at(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.interp_at(&self.amb_te_deg_c, time_s, dist_m, 0.0)
+    }
+
+    /// Wind speed component opposing vehicle travel \[m/s\], negative for a
+    /// tailwind
+    pub fn headwind_mps_at(&self, time_s: f64, dist_m: f64) -> f64 {
+        if self.wind_speed_mps.is_empty() {
+            return 0.0;
+        }
+        let headwind = |k: usize| {
+            let heading_deg = self.heading_deg.get(k).copied().unwrap_or_default();
+            self.wind_speed_mps[k] * (self.wind_from_deg[k] - heading_deg).to_radians().cos()
+        };
+        let (i, j, frac) = self.bracket_at(time_s, dist_m);
+        headwind(i) + frac * (headwind(j) - headwind(i))
+    }
+
+    /// Global horizontal solar irradiance \[W/m**2\]
+    pub fn solar_w_per_m2_at(&self, time_s: f64, dist_m: f64) -> f64 {
+        self.interp_at(&self.solar_w_per_m2, time_s, dist_m, 0.0)
+    }
+
+    /// Ambient air pressure \[Pa\]
+    pub fn pressure_pa_at(&self, time_s: f64, dist_m: f64) -> f64 {
+        if self.pressure_kpa.is_empty() {
+            get_std_pressure(self.interp_at(&self.elevation_m, time_s, dist_m, DEFAULT_ELEVATION_M))
+        } else {
+            self.interp_at(&self.pressure_kpa, time_s, dist_m, 0.0) * 1e3
+        }
+    }
+
+    /// Density of ambient (humid) air \[kg/m**3\]
+    pub fn air_density_kg_per_m3_at(&self, time_s: f64, dist_m: f64) -> f64 {
+        get_humid_air_density(
+            self.amb_te_deg_c_at(time_s, dist_m),
+            self.pressure_pa_at(time_s, dist_m),
+            self.interp_at(&self.rel_humidity, time_s, dist_m, 0.0),
+        )
+    }
+
+    /// Load from a CSV file whose column headers are field names of
+    /// [WeatherProfile], e.g. `time_s,amb_te_deg_c,solar_w_per_m2`
+    pub fn from_csv_file<P: AsRef<Path>>(filepath: P) -> anyhow::Result<Self> {
+        let filepath = filepath.as_ref();
+        let contents = std::fs::read_to_string(filepath)
+            .with_context(|| format!("Could not read weather file: {filepath:?}"))?;
+        Self::from_csv_str(contents)
+    }
+
+    /// Load from a CSV string whose column headers are field names of
+    /// [WeatherProfile]
+    pub fn from_csv_str<S: AsRef<str>>(csv_str: S) -> anyhow::Result<Self> {
+        let mut rdr = csv::Reader::from_reader(csv_str.as_ref().as_bytes());
+        let headers: Vec<String> = rdr
+            .headers()
+            .map_err(FastSimError::format)?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect();
+        let mut cols: Vec<Vec<f64>> = vec![vec![]; headers.len()];
+        for record in rdr.records() {
+            let record = record.map_err(FastSimError::format)?;
+            for (col, (header, val)) in cols.iter_mut().zip(headers.iter().zip(record.iter())) {
+                col.push(val.trim().parse().with_context(|| {
+                    format!("Could not parse `{header}` value {val:?} as a number")
+                })?);
+            }
+        }
+        let mut weather = Self::default();
+        for (header, col) in headers.iter().zip(cols) {
+            let col = Array::from_vec(col);
+            match header.as_str() {
+                "time_s" => weather.time_s = col,
+                "dist_m" => weather.dist_m = col,
+                "amb_te_deg_c" => weather.amb_te_deg_c = col,
+                "wind_speed_mps" => weather.wind_speed_mps = col,
+                "wind_from_deg" => weather.wind_from_deg = col,
+                "heading_deg" => weather.heading_deg = col,
+                "solar_w_per_m2" => weather.solar_w_per_m2 = col,
+                "rel_humidity" => weather.rel_humidity = col,
+                "pressure_kpa" => weather.pressure_kpa = col,
+                "elevation_m" => weather.elevation_m = col,
+                _ => bail!("{}\nunrecognized weather column `{header}`", format_dbg!()),
+            }
+        }
+        weather.init()?;
+        Ok(weather)
+    }
+
+    /// Load hourly weather from a typical meteorological year (TMY3) or
+    /// NSRDB-style CSV file, starting at row `start_hour` and including
+    /// `n_hours` rows (all remaining rows if `None`)
+    pub fn from_tmy_file<P: AsRef<Path>>(
+        filepath: P,
+        start_hour: usize,
+        n_hours: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        let filepath = filepath.as_ref();
+        let contents = std::fs::read_to_string(filepath)
+            .with_context(|| format!("Could not read weather file: {filepath:?}"))?;
+        Self::from_tmy_str(contents, start_hour, n_hours)
+    }
+
+    /// Load hourly weather from a TMY3 or NSRDB-style CSV string.  Metadata
+    /// lines preceding the column header line are skipped.  Recognized
+    /// columns (units in parentheses are ignored) are dry bulb temperature
+    /// \[°C\], GHI \[W/m**2\], relative humidity \[%\], pressure \[mbar\], and wind
+    /// direction \[deg\] and speed \[m/s\].  The vehicle heading is taken as north.
+    pub fn from_tmy_str<S: AsRef<str>>(
+        tmy_str: S,
+        start_hour: usize,
+        n_hours: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        const TE_COLS: [&str; 2] = ["Dry-bulb", "Temperature"];
+        const SOLAR_COLS: [&str; 1] = ["GHI"];
+        const RH_COLS: [&str; 2] = ["RHum", "Relative Humidity"];
+        const PRESSURE_COLS: [&str; 1] = ["Pressure"];
+        const WIND_FROM_COLS: [&str; 2] = ["Wdir", "Wind Direction"];
+        const WIND_SPEED_COLS: [&str; 2] = ["Wspd", "Wind Speed"];
+
+        // column name without trailing units, e.g. `Dry-bulb (C)` -> `Dry-bulb`
+        let strip_units = |h: &str| h.split(" (").next().unwrap_or_default().trim().to_string();
+        let tmy_str = tmy_str.as_ref();
+        let header_start = tmy_str
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some((start, line.trim_end()))
+            })
+            .find(|(_, line)| {
+                line.split(',')
+                    .any(|h| TE_COLS.contains(&strip_units(h).as_str()))
+            })
+            .map(|(start, _)| start)
+            .with_context(|| {
+                format!(
+                    "{}\ncould not find a header line with a temperature column, one of {TE_COLS:?}",
+                    format_dbg!()
+                )
+            })?;
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(&tmy_str.as_bytes()[header_start..]);
+        let headers: Vec<String> = rdr
+            .headers()
+            .map_err(FastSimError::format)?
+            .iter()
+            .map(strip_units)
+            .collect();
+        let find_col = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+        let te_col = find_col(&TE_COLS).with_context(|| format_dbg!())?;
+        let solar_col = find_col(&SOLAR_COLS);
+        let rh_col = find_col(&RH_COLS);
+        let pressure_col = find_col(&PRESSURE_COLS);
+        let wind_cols = find_col(&WIND_SPEED_COLS).zip(find_col(&WIND_FROM_COLS));
+
+        let mut weather = Self::default();
+        let mut time_s = vec![];
+        let mut amb_te_deg_c = vec![];
+        let mut solar_w_per_m2 = vec![];
+        let mut rel_humidity = vec![];
+        let mut pressure_kpa = vec![];
+        let mut wind_speed_mps = vec![];
+        let mut wind_from_deg = vec![];
+        for (k, record) in rdr
+            .records()
+            .skip(start_hour)
+            .take(n_hours.unwrap_or(usize::MAX))
+            .enumerate()
+        {
+            let record = record.map_err(FastSimError::format)?;
+            let get = |col: usize| -> anyhow::Result<f64> {
+                let val = record.get(col).with_context(|| {
+                    format!(
+                        "{}\nrow {} is missing column {col}",
+                        format_dbg!(),
+                        start_hour + k
+                    )
+                })?;
+                val.trim().parse().with_context(|| {
+                    format!(
+                        "Could not parse `{}` value {val:?} as a number",
+                        headers[col]
+                    )
+                })
+            };
+            time_s.push(k as f64 * TMY_STEP_S);
+            amb_te_deg_c.push(get(te_col)?);
+            if let Some(col) = solar_col {
+                solar_w_per_m2.push(get(col)?.max(0.0));
+            }
+            if let Some(col) = rh_col {
+                rel_humidity.push((get(col)? / 100.0).clamp(0.0, 1.0));
+            }
+            if let Some(col) = pressure_col {
+                // mbar -> kPa
+                pressure_kpa.push(get(col)? / 10.0);
+            }
+            if let Some((speed_col, from_col)) = wind_cols {
+                wind_speed_mps.push(get(speed_col)?);
+                wind_from_deg.push(get(from_col)?);
+            }
+        }
+        ensure!(
+            !time_s.is_empty(),
+            "{}\nno weather rows selected",
+            format_dbg!((start_hour, n_hours))
+        );
+        weather.time_s = Array::from_vec(time_s);
+        weather.amb_te_deg_c = Array::from_vec(amb_te_deg_c);
+        weather.solar_w_per_m2 = Array::from_vec(solar_w_per_m2);
+        weather.rel_humidity = Array::from_vec(rel_humidity);
+        weather.pressure_kpa = Array::from_vec(pressure_kpa);
+        weather.wind_speed_mps = Array::from_vec(wind_speed_mps);
+        weather.wind_from_deg = Array::from_vec(wind_from_deg);
+        weather.init()?;
+        Ok(weather)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air::AirProperties;
+    use crate::cycle::RustCycle;
+    use crate::simdrive::RustSimDrive;
+    use crate::thermal::{SimDriveHot, ThermalState};
+    use crate::vehicle::RustVehicle;
+    use crate::vehicle_thermal::{CabinHvacModelTypes, HVACModel, VehicleThermal};
+
+    const TMY_STR: &str = "\
+725650,\"DENVER INTL AP\",CO,-7.0,39.833,-104.650,1650
+Date (MM/DD/YYYY),Time (HH:MM),GHI (W/m^2),Dry-bulb (C),RHum (%),Pressure (mbar),Wdir (degrees),Wspd (m/s)
+07/01/1988,01:00,0,18.0,50,835,180,2.0
+07/01/1988,02:00,100,20.0,40,836,90,4.0
+07/01/1988,03:00,500,24.0,30,837,0,6.0
+";
+
+    #[test]
+    fn test_weather_csv_and_validation() {
+        let weather = WeatherProfile::from_csv_str(
+            "time_s,amb_te_deg_c,solar_w_per_m2\n0,10,0\n100,20,800\n",
+        )
+        .unwrap();
+        assert_eq!(weather.amb_te_deg_c_at(50.0, 0.0), 15.0);
+        assert_eq!(weather.amb_te_deg_c_at(-1.0, 0.0), 10.0);
+        assert_eq!(weather.solar_w_per_m2_at(1e3, 0.0), 800.0);
+        assert_eq!(weather.headwind_mps_at(50.0, 0.0), 0.0);
+        assert!(weather.pressure_kpa.is_empty());
+
+        assert!(WeatherProfile::from_csv_str("time_s,amb_te_deg_c,foo\n0,10,1\n").is_err());
+        assert!(WeatherProfile::from_csv_str("amb_te_deg_c\n10\n").is_err());
+        assert!(WeatherProfile::from_csv_str("time_s,dist_m,amb_te_deg_c\n0,0,10\n").is_err());
+        let mut weather = weather;
+        weather.rel_humidity = array![0.5];
+        assert!(weather.init().is_err());
+    }
+
+    #[test]
+    fn test_weather_tmy() {
+        let weather = WeatherProfile::from_tmy_str(TMY_STR, 1, None).unwrap();
+        assert_eq!(weather.time_s, array![0.0, 3600.0]);
+        assert_eq!(weather.amb_te_deg_c, array![20.0, 24.0]);
+        assert_eq!(weather.rel_humidity, array![0.4, 0.3]);
+        assert_eq!(weather.pressure_kpa, array![83.6, 83.7]);
+        assert_eq!(weather.solar_w_per_m2_at(1800.0, 0.0), 300.0);
+        // wind from the east is a crosswind for the default northward heading
+        assert!(weather.headwind_mps_at(0.0, 0.0).abs() < 1e-12);
+        // wind from the north is a headwind
+        assert!((weather.headwind_mps_at(3600.0, 0.0) - 6.0).abs() < 1e-12);
+        // Denver air is much less dense than the sea level default
+        assert!(weather.air_density_kg_per_m3_at(0.0, 0.0) < 1.0);
+        assert!(WeatherProfile::from_tmy_str(TMY_STR, 3, None).is_err());
+        assert!(WeatherProfile::from_tmy_str("a,b\n1,2\n", 0, None).is_err());
+    }
+
+    #[test]
+    fn test_weather_air_density() {
+        let mut weather = WeatherProfile {
+            dist_m: array![0.0, 1e3],
+            amb_te_deg_c: array![25.0, 25.0],
+            elevation_m: array![0.0, 1600.0],
+            wind_speed_mps: array![5.0, 5.0],
+            wind_from_deg: array![90.0, 90.0],
+            heading_deg: array![270.0, 90.0],
+            ..Default::default()
+        };
+        weather.init().unwrap();
+        let air = AirProperties::default();
+        for (dist_m, h) in [(0.0, 0.0), (500.0, 800.0), (1e3, 1600.0)] {
+            let rho = weather.air_density_kg_per_m3_at(0.0, dist_m);
+            assert!((rho - air.get_rho(25.0, Some(h))).abs() / rho < 1e-3);
+        }
+        // heading west into an easterly wind is a tailwind, heading east a headwind
+        assert!((weather.headwind_mps_at(0.0, 0.0) + 5.0).abs() < 1e-12);
+        assert!((weather.headwind_mps_at(0.0, 1e3) - 5.0).abs() < 1e-12);
+
+        // humid air is less dense than dry air
+        weather.rel_humidity = array![1.0, 1.0];
+        assert!(weather.air_density_kg_per_m3_at(0.0, 0.0) < air.get_rho(25.0, Some(0.0)));
+    }
+
+    #[test]
+    fn test_sim_drive_with_weather() {
+        let veh = RustVehicle::mock_vehicle();
+        let cyc = RustCycle::from_resource("udds.csv", false).unwrap();
+        let still = WeatherProfile {
+            time_s: array![0.0],
+            amb_te_deg_c: array![30.0],
+            ..Default::default()
+        };
+        let windy = WeatherProfile {
+            wind_speed_mps: array![8.0],
+            wind_from_deg: array![0.0],
+            ..still.clone()
+        };
+
+        let mut sd = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd.weather = Some(still.clone());
+        sd.sim_drive(None, None).unwrap();
+        let mut sd_windy = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_windy.weather = Some(windy);
+        sd_windy.sim_drive(None, None).unwrap();
+        assert!(sd_windy.drag_kj > 1.2 * sd.drag_kj);
+        assert!(sd_windy.fs_kwh_out_ach.sum() > sd.fs_kwh_out_ach.sum());
+
+        // distance-indexed weather is evaluated at the distance traveled
+        // before each step
+        let sea_level = WeatherProfile {
+            elevation_m: array![0.0],
+            ..still.clone()
+        };
+        let climbing = WeatherProfile {
+            time_s: array![],
+            dist_m: array![0.0, 12e3],
+            amb_te_deg_c: array![30.0, 30.0],
+            elevation_m: array![0.0, 2e3],
+            ..Default::default()
+        };
+        let mut sd_sea_level = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_sea_level.weather = Some(sea_level.clone());
+        sd_sea_level.sim_drive(None, None).unwrap();
+        let mut sd_climbing = RustSimDrive::new(cyc.clone(), veh.clone());
+        sd_climbing.weather = Some(climbing.clone());
+        sd_climbing.sim_drive(None, None).unwrap();
+        let rho_sea_level = sea_level.air_density_kg_per_m3_at(0.0, 0.0);
+        for i in 1..cyc.len() {
+            if sd_sea_level.drag_kw[i] > 1e-3 {
+                let dist_m = sd_climbing.dist_m.slice(s![0..i]).sum();
+                let rho_ratio = climbing.air_density_kg_per_m3_at(0.0, dist_m) / rho_sea_level;
+                let drag_ratio = sd_climbing.drag_kw[i] / sd_sea_level.drag_kw[i];
+                assert!((drag_ratio - rho_ratio).abs() < 1e-9, "{}", format_dbg!(i));
+            }
+        }
+
+        // solar load warms an unconditioned cabin
+        let mut hvac_model = HVACModel::default();
+        hvac_model.te_deadband_deg_c = 1e3;
+        let vehthrm = VehicleThermal {
+            cabin_hvac_model: CabinHvacModelTypes::Internal(hvac_model),
+            ..Default::default()
+        };
+        let init_state = ThermalState::new(Some(30.0), None, Some(30.0), None, None, None);
+        let mut sdh = SimDriveHot::new(
+            cyc.clone(),
+            veh.clone(),
+            vehthrm.clone(),
+            Some(init_state.clone()),
+            None,
+        )
+        .with_weather(still.clone())
+        .unwrap();
+        sdh.sim_drive(None, None).unwrap();
+        let sunny = WeatherProfile {
+            solar_w_per_m2: array![1000.0],
+            ..still.clone()
+        };
+        let mut sdh_sunny = SimDriveHot::new(cyc.clone(), veh, vehthrm, Some(init_state), None)
+            .with_weather(sunny)
+            .unwrap();
+        sdh_sunny.sim_drive(None, None).unwrap();
+        assert!((sdh.state.cab_te_deg_c - 30.0).abs() < 0.5);
+        assert!(sdh_sunny.state.cab_te_deg_c > sdh.state.cab_te_deg_c + 1.0);
+        assert!((sdh_sunny.state.cab_qdot_solar_kw - 1.0).abs() < 1e-12);
+
+        assert!(SimDriveHot::new(
+            cyc.clone(),
+            RustVehicle::mock_vehicle(),
+            VehicleThermal::default(),
+            None,
+            Some(cyc.mps.mapv(|_| 20.0))
+        )
+        .with_weather(still)
+        .is_err());
+    }
+}
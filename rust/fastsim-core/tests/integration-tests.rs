@@ -96,3 +96,66 @@ fn test_from_url() {
     comparison_vehicle.doc = Some("Vehicle from https://raw.githubusercontent.com/NREL/fastsim-vehicles/main/assets/2022_Tesla_Model_Y_RWD_example.yaml".to_owned());
     assert_eq!(vehicle, comparison_vehicle);
 }
+
+#[test]
+fn test_cold_weather_bev_hvac() {
+    use fastsim_core::thermal::{SimDriveHot, ThermalState};
+    use fastsim_core::vehicle_thermal::*;
+
+    let veh = vehicle::RustVehicle::from_yaml(REFERENCE_VEHICLE, false).unwrap();
+    let cyc = cycle::RustCycle::from_resource("udds.csv", false).unwrap();
+    let amb_te_deg_c = -7.0;
+    let run = |components: Option<HvacComponents>| {
+        let mut hvac_model = HVACModel::default();
+        hvac_model.use_fc_waste_heat = false;
+        let vehthrm = VehicleThermal {
+            cabin_hvac_model: CabinHvacModelTypes::Internal(hvac_model),
+            cabin_hvac_components: components,
+            ..Default::default()
+        };
+        let init_state = ThermalState::new(Some(amb_te_deg_c), None, None, None, None, None);
+        let mut sdh = SimDriveHot::new(cyc.clone(), veh.clone(), vehthrm, Some(init_state), None);
+        sdh.sim_drive(None, None).unwrap();
+        sdh
+    };
+
+    let mut sd_no_hvac = simdrive::RustSimDrive::new(cyc.clone(), veh.clone());
+    sd_no_hvac.sim_drive(None, None).unwrap();
+    let sdh_ptc = run(Some(HvacComponents {
+        ptc_heater: Some(PtcHeater::default()),
+        ..Default::default()
+    }));
+    let sdh_heat_pump = run(Some(HvacComponents {
+        ptc_heater: Some(PtcHeater::default()),
+        heat_pump: Some(HeatPump::default()),
+        ..Default::default()
+    }));
+
+    // HVAC electric load is carried by the traction battery as aux load
+    let ptc_heater_kwh = sdh_ptc
+        .history
+        .cab_ptc_heater_pwr_kw
+        .iter()
+        .zip(cyc.dt_s().iter())
+        .map(|(kw, dt_s)| kw * dt_s / 3600.0)
+        .sum::<f64>();
+    assert!(ptc_heater_kwh > 0.1);
+    assert!(sdh_ptc.sd.aux_in_kw.iter().any(|kw| *kw > veh.aux_kw + 1.0));
+    assert!(sdh_ptc
+        .history
+        .cab_heat_pump_pwr_kw
+        .iter()
+        .all(|kw| *kw == 0.0));
+    assert!(sdh_heat_pump
+        .history
+        .cab_heat_pump_pwr_kw
+        .iter()
+        .any(|kw| *kw > 0.0));
+    assert!(sdh_ptc.state.cab_te_deg_c > amb_te_deg_c + 10.0);
+
+    // cold-weather consumption is worst with resistive heating only
+    let kwh_per_mi_ptc = sdh_ptc.sd.electric_kwh_per_mi;
+    let kwh_per_mi_heat_pump = sdh_heat_pump.sd.electric_kwh_per_mi;
+    assert!(kwh_per_mi_ptc > kwh_per_mi_heat_pump);
+    assert!(kwh_per_mi_heat_pump > sd_no_hvac.electric_kwh_per_mi);
+}
@@ -16,7 +16,16 @@ fn fastsimrust(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     #[cfg(feature = "logging")]
     pyo3_log::init();
     m.add_class::<cycle::RustCycle>()?;
+    m.add_class::<route::RustRoute>()?;
     m.add_class::<vehicle::RustVehicle>()?;
+    m.add_class::<vehicle_validation::ValidationIssue>()?;
+    m.add_class::<vehicle_validation::ValidationReport>()?;
+    m.add_class::<ess_circuit::EssCircuitModel>()?;
+    m.add_class::<transmission::Transmission>()?;
+    m.add_class::<speed_torque_map::SpeedTorqueEffMap>()?;
+    m.add_class::<emissions::EmissionsModel>()?;
+    m.add_class::<fuel::Fuel>()?;
+    m.add_class::<fuel::SecondaryFuel>()?;
     m.add_class::<params::RustPhysicalProperties>()?;
     m.add_class::<params::AdjCoef>()?;
     m.add_class::<params::RustLongParams>()?;
@@ -26,12 +35,52 @@ fn fastsimrust(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<utils::Pyo3VecF64>()?;
     m.add_class::<simdrive::RustSimDriveParams>()?;
     m.add_class::<simdrive::RustSimDrive>()?;
+    m.add_class::<simdrive::diagnostics::SimDiagnostics>()?;
+    m.add_class::<simdrive::diagnostics::SimFault>()?;
+    m.add_class::<simdrive::energy_breakdown::EnergyBreakdown>()?;
+    m.add_class::<simdrive::energy_breakdown::EnergyFlow>()?;
+    m.add_class::<simdrive::simdrive_stream::SimDriveStream>()?;
+    m.add_class::<simdrive::simdrive_stream::SimDriveStreamState>()?;
+    m.add_class::<simdrive::sweep::SweepParam>()?;
+    m.add_class::<simdrive::sweep::ParameterSweep>()?;
+    m.add_class::<simdrive::sweep::SweepResults>()?;
+    m.add_class::<simdrive::sensitivity::SensitivityAnalysis>()?;
+    m.add_class::<simdrive::sensitivity::SensitivityReport>()?;
+    m.add_class::<simdrive::sensitivity::OutputSensitivity>()?;
+    m.add_class::<simdrive::sensitivity::ParamSensitivity>()?;
+    m.add_class::<simdrive::platoon::Platoon>()?;
+    m.add_class::<simdrive::platoon::PlatoonMember>()?;
+    m.add_class::<simdrive::platoon::PlatoonResults>()?;
+    m.add_class::<simdrive::eco_cruise::PredictiveEcoCruise>()?;
+    m.add_class::<simdrive::eco_cruise::EcoCruiseResults>()?;
+    m.add_class::<simdrive::spat::TrafficSignal>()?;
+    m.add_class::<simdrive::spat::SpatPlanner>()?;
+    m.add_class::<simdrive::spat::SignalPassage>()?;
+    m.add_class::<simdrive::spat::SpatPlan>()?;
+    m.add_class::<simdrive::spat::SpatResults>()?;
+    m.add_class::<calibration::CalibrationParam>()?;
+    m.add_class::<calibration::CalibrationObjective>()?;
+    m.add_class::<calibration::ObjectiveFit>()?;
+    m.add_class::<calibration::Calibration>()?;
+    m.add_class::<calibration::CalibrationResult>()?;
+    m.add_class::<simdrive::energy_management::EcmsEnergyManagement>()?;
+    m.add_class::<simdrive::energy_management::ThermostatEnergyManagement>()?;
+    m.add_class::<battery_aging::BatteryAgingModel>()?;
+    m.add_class::<battery_aging::BatteryAgingState>()?;
+    m.add_class::<battery_aging::BatteryAgingStateHistoryVec>()?;
+    m.add_class::<battery_aging::FleetLife>()?;
     m.add_class::<thermal::SimDriveHot>()?;
     m.add_class::<vehicle_thermal::VehicleThermal>()?;
     m.add_class::<thermal::ThermalState>()?;
     m.add_class::<vehicle_thermal::HVACModel>()?;
+    m.add_class::<vehicle_thermal::PtcHeater>()?;
+    m.add_class::<vehicle_thermal::HeatPump>()?;
+    m.add_class::<vehicle_thermal::AcCompressor>()?;
+    m.add_class::<vehicle_thermal::HvacComponents>()?;
+    m.add_class::<weather::WeatherProfile>()?;
 
     cycle::register(py, m)?;
+    error::pyerr::register(py, m)?;
 
     // Features
     #[cfg(feature = "default")]